@@ -1,7 +1,9 @@
 
 use std::cmp::Ordering;
+use std::mem;
+use std::ops::{Deref, DerefMut};
 
-struct BinaryHeap<T, I> {
+pub struct BinaryHeap<T, I> {
   data: Vec<T>,
   comparator: I
 }
@@ -9,11 +11,20 @@ struct BinaryHeap<T, I> {
 #[inline] fn parent(i: usize) -> usize {  (i - 1) / 2 }
 #[inline] fn left_child(i: usize) -> usize { i * 2 + 1 }
 
-type DefaultCmp<T> = fn(&T, &T) -> Ordering;
+pub type DefaultCmp<T> = fn(&T, &T) -> Ordering;
 impl<T: Ord> BinaryHeap<T, DefaultCmp<T>> {
   fn comparator(a: &T, b: &T) -> Ordering {
     a.cmp(b)
   }
+  pub fn new() -> BinaryHeap<T, DefaultCmp<T>> {
+    BinaryHeap {
+      data: vec![],
+      comparator: Self::comparator as DefaultCmp<T>,
+    }
+  }
+}
+
+impl<T: Ord> From<Vec<T>> for BinaryHeap<T, DefaultCmp<T>> {
   fn from(data: Vec<T>) -> BinaryHeap<T, DefaultCmp<T>> {
     let mut ans = BinaryHeap {
       data,
@@ -22,26 +33,23 @@ impl<T: Ord> BinaryHeap<T, DefaultCmp<T>> {
     ans.build_heap();
     ans
   }
-  fn new() -> BinaryHeap<T, DefaultCmp<T>> {
-    fn comparator<T: Ord>(a: &T, b: &T) -> Ordering {
-      a.cmp(b)
-    }
-    BinaryHeap {
-      data: vec![],
-      comparator: Self::comparator as DefaultCmp<T>,
-    }
+}
+
+impl<T: Ord> Default for BinaryHeap<T, DefaultCmp<T>> {
+  fn default() -> Self {
+    Self::new()
   }
 }
 
 impl<T, I> BinaryHeap<T, I> where I: FnMut(&T, &T) -> Ordering {
-    fn with_comparator(comparator: I) -> BinaryHeap<T, I> {
+    pub fn with_comparator(comparator: I) -> BinaryHeap<T, I> {
       BinaryHeap {
         data: vec![],
         comparator,
       }
     }
 
-    fn from_with_comparator(data: Vec<T>, comparator: I) -> BinaryHeap<T, I> {
+    pub fn from_with_comparator(data: Vec<T>, comparator: I) -> BinaryHeap<T, I> {
       let mut ans = BinaryHeap {
         data,
         comparator,
@@ -50,11 +58,11 @@ impl<T, I> BinaryHeap<T, I> where I: FnMut(&T, &T) -> Ordering {
       ans
     }
 
-    fn is_empty(&self) -> bool {
+    pub fn is_empty(&self) -> bool {
       self.data.is_empty()
     }
 
-    fn len(&self) -> usize {
+    pub fn len(&self) -> usize {
       self.data.len()
     }
 
@@ -107,12 +115,12 @@ impl<T, I> BinaryHeap<T, I> where I: FnMut(&T, &T) -> Ordering {
       i < self.data.len()
     }
 
-    fn push(&mut self, v: T) {
+    pub fn push(&mut self, v: T) {
       self.data.push(v);
       self.sift_up(self.data.len() - 1);
     }
 
-    fn pop(&mut self) -> Option<T> {
+    pub fn pop(&mut self) -> Option<T> {
       if self.data.is_empty() {
         return None;
       }
@@ -120,6 +128,113 @@ impl<T, I> BinaryHeap<T, I> where I: FnMut(&T, &T) -> Ordering {
       self.sift_down(0);
       Some(ans)
     }
+
+    pub fn peek(&self) -> Option<&T> {
+      self.data.first()
+    }
+
+    // the root may be changed through the guard, it is sifted back down when the guard drops
+    pub fn peek_mut(&mut self) -> Option<PeekMut<'_, T, I>> {
+      if self.data.is_empty() {
+        None
+      } else {
+        Some(PeekMut { heap: self })
+      }
+    }
+}
+
+pub struct PeekMut<'a, T, I> where I: FnMut(&T, &T) -> Ordering {
+  heap: &'a mut BinaryHeap<T, I>,
+}
+
+impl<'a, T, I> PeekMut<'a, T, I> where I: FnMut(&T, &T) -> Ordering {
+  // removes the peeked value, an associated function so it won't shadow methods of T
+  pub fn pop(this: PeekMut<'a, T, I>) -> T {
+    let ans = this.heap.pop().unwrap();
+    mem::forget(this); // the heap is already restored, skip the sift in drop
+    ans
+  }
+}
+
+impl<'a, T, I> Deref for PeekMut<'a, T, I> where I: FnMut(&T, &T) -> Ordering {
+  type Target = T;
+  fn deref(&self) -> &T {
+    &self.heap.data[0]
+  }
+}
+
+impl<'a, T, I> DerefMut for PeekMut<'a, T, I> where I: FnMut(&T, &T) -> Ordering {
+  fn deref_mut(&mut self) -> &mut T {
+    &mut self.heap.data[0]
+  }
+}
+
+impl<'a, T, I> Drop for PeekMut<'a, T, I> where I: FnMut(&T, &T) -> Ordering {
+  fn drop(&mut self) {
+    self.heap.sift_down(0);
+  }
+}
+
+// wraps every element with its insertion sequence number so that elements comparing
+// equal pop first-in-first-out, which keeps replays of simulations deterministic
+pub struct StableBinaryHeap<T, I> {
+  heap: BinaryHeap<Stamped<T>, I>,
+  seq: u64,
+}
+
+type Stamped<T> = (T, u64);
+pub type DefaultStableCmp<T> = fn(&Stamped<T>, &Stamped<T>) -> Ordering;
+impl<T: Ord> StableBinaryHeap<T, DefaultStableCmp<T>> {
+  fn comparator(a: &Stamped<T>, b: &Stamped<T>) -> Ordering {
+    a.0.cmp(&b.0).then_with(|| b.1.cmp(&a.1)) // the smaller sequence number is "larger"
+  }
+  pub fn new() -> StableBinaryHeap<T, DefaultStableCmp<T>> {
+    StableBinaryHeap {
+      heap: BinaryHeap::with_comparator(Self::comparator as DefaultStableCmp<T>),
+      seq: 0,
+    }
+  }
+}
+
+impl<T: Ord> Default for StableBinaryHeap<T, DefaultStableCmp<T>> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T> StableBinaryHeap<T, DefaultStableCmp<T>> {
+  pub fn with_comparator<C>(mut comparator: C) -> StableBinaryHeap<T, impl FnMut(&Stamped<T>, &Stamped<T>) -> Ordering>
+  where C: FnMut(&T, &T) -> Ordering {
+    StableBinaryHeap {
+      heap: BinaryHeap::with_comparator(move |a: &Stamped<T>, b: &Stamped<T>| {
+        comparator(&a.0, &b.0).then_with(|| b.1.cmp(&a.1))
+      }),
+      seq: 0,
+    }
+  }
+}
+
+impl<T, I> StableBinaryHeap<T, I> where I: FnMut(&Stamped<T>, &Stamped<T>) -> Ordering {
+  pub fn is_empty(&self) -> bool {
+    self.heap.is_empty()
+  }
+
+  pub fn len(&self) -> usize {
+    self.heap.len()
+  }
+
+  pub fn push(&mut self, v: T) {
+    self.heap.push((v, self.seq));
+    self.seq += 1;
+  }
+
+  pub fn pop(&mut self) -> Option<T> {
+    self.heap.pop().map(|(v, _)| v)
+  }
+
+  pub fn peek(&self) -> Option<&T> {
+    self.heap.peek().map(|(v, _)| v)
+  }
 }
 
 #[cfg(test)]
@@ -147,4 +262,43 @@ mod tests {
     }
     assert_eq!(None, pq.pop());
   }
+
+  #[test]
+  fn test_peek_mut() {
+    let mut pq: BinaryHeap<i32, _> = BinaryHeap::from(vec![2,1,6,3,9,7,4,8,5]);
+    assert_eq!(Some(&9), pq.peek());
+    {
+      let mut top = pq.peek_mut().unwrap();
+      *top = 0; // the root sinks back into place
+    }
+    assert_eq!(Some(&8), pq.peek());
+    if let Some(mut top) = pq.peek_mut() {
+      *top -= 1; // still the largest, stays at the root
+    }
+    assert_eq!(Some(&7), pq.peek());
+    assert_eq!(7, PeekMut::pop(pq.peek_mut().unwrap()));
+    for i in (0..=7).rev() {
+      assert_eq!(Some(i), pq.pop());
+    }
+    assert!(pq.peek_mut().is_none());
+  }
+
+  #[test]
+  fn test_stable_fifo_ties() {
+    let mut pq = StableBinaryHeap::with_comparator(|a: &(i32, char), b: &(i32, char)| a.0.cmp(&b.0));
+    for (i, c) in "abcdefgh".chars().enumerate() {
+      pq.push(((i % 3) as i32, c));
+    }
+    let order: String = std::iter::from_fn(|| pq.pop()).map(|(_, c)| c).collect();
+    assert_eq!("cfbehadg", order);
+
+    let mut pq = StableBinaryHeap::new();
+    for v in [1, 3, 3, 2, 1] {
+      pq.push(v);
+    }
+    assert_eq!(Some(&3), pq.peek());
+    assert_eq!(5, pq.len());
+    let order: Vec<_> = std::iter::from_fn(|| pq.pop()).collect();
+    assert_eq!(vec![3, 3, 2, 1, 1], order);
+  }
 }
\ No newline at end of file
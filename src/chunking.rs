@@ -0,0 +1,223 @@
+// Content-defined chunking for deduplication: byte streams are cut where the content says so,
+// not at fixed offsets, so an insertion only changes the chunks around it and the rest of the
+// stream still splits into the same chunks as before.
+//
+// Cut points come from a Gear rolling hash, h = (h << 1) + gear[byte], whose top bits depend on
+// the last 64 bytes only. Chunking follows FastCDC: nothing is cut before min bytes, up to avg a
+// cut needs more zero bits than after it, which pulls sizes towards avg, and a chunk is cut at
+// max regardless. Each chunk also gets a Rabin fingerprint to tell chunks apart.
+
+// x^64 + x^62 + x^57 + ..., the CRC-64/ECMA-182 polynomial without its leading term
+const POLY: u64 = 0x42f0_e1eb_a9ea_3693;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Chunk {
+  pub offset: usize,
+  pub len: usize,
+  pub fingerprint: u64,
+}
+
+// Rabin fingerprint: the bytes read as a polynomial over GF(2), most significant bit first,
+// times x^64 mod POLY; byte at a time through a table, as a CRC without the final xor
+pub struct Rabin {
+  table: [u64; 256],
+}
+
+impl Rabin {
+  pub fn new() -> Rabin {
+    let mut table = [0; 256];
+    for (b, entry) in table.iter_mut().enumerate() {
+      let mut r = (b as u64) << 56;
+      for _ in 0..8 {
+        r = if r >> 63 == 1 { r << 1 ^ POLY } else { r << 1 };
+      }
+      *entry = r;
+    }
+    Rabin { table }
+  }
+
+  // the fingerprint of s followed by byte, given the fingerprint of s
+  pub fn push(&self, fingerprint: u64, byte: u8) -> u64 {
+    self.table[(fingerprint >> 56) as usize ^ byte as usize] ^ fingerprint << 8
+  }
+
+  pub fn fingerprint(&self, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0, |f, &b| self.push(f, b))
+  }
+}
+
+impl Default for Rabin {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+// streaming chunker: feed bytes in pieces of any size, chunks come out as they are cut
+pub struct Chunker {
+  min: usize,
+  avg: usize,
+  max: usize,
+  mask_small: u64, // before avg
+  mask_large: u64, // from avg on
+  gear: [u64; 256],
+  rabin: Rabin,
+  // the chunk in progress
+  offset: usize,
+  len: usize,
+  hash: u64,
+  fingerprint: u64,
+}
+
+impl Chunker {
+  // min <= avg <= max, avg a power of two
+  pub fn new(min: usize, avg: usize, max: usize) -> Chunker {
+    assert!(min <= avg && avg <= max && avg.is_power_of_two() && avg >= 4);
+    let bits = avg.trailing_zeros();
+    // splitmix64, so the table is the same everywhere
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    let mut gear = [0; 256];
+    for g in gear.iter_mut() {
+      state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+      let mut z = state;
+      z = (z ^ z >> 30).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+      z = (z ^ z >> 27).wrapping_mul(0x94d0_49bb_1331_11eb);
+      *g = z ^ z >> 31;
+    }
+    Chunker {
+      min,
+      avg,
+      max,
+      mask_small: !0 << (64 - (bits + 1)),
+      mask_large: !0 << (64 - (bits - 1)),
+      gear,
+      rabin: Rabin::new(),
+      offset: 0,
+      len: 0,
+      hash: 0,
+      fingerprint: 0,
+    }
+  }
+
+  fn cut(&mut self) -> Chunk {
+    let chunk = Chunk { offset: self.offset, len: self.len, fingerprint: self.fingerprint };
+    self.offset += self.len;
+    self.len = 0;
+    self.hash = 0;
+    self.fingerprint = 0;
+    chunk
+  }
+
+  // reads the next bytes of the stream, returning the chunks they complete
+  pub fn feed(&mut self, bytes: &[u8]) -> Vec<Chunk> {
+    let mut chunks = vec![];
+    for &b in bytes {
+      self.fingerprint = self.rabin.push(self.fingerprint, b);
+      self.len += 1;
+      if self.len == self.max {
+        chunks.push(self.cut());
+      } else if self.len > self.min {
+        self.hash = (self.hash << 1).wrapping_add(self.gear[b as usize]);
+        let mask = if self.len < self.avg { self.mask_small } else { self.mask_large };
+        if self.hash & mask == 0 {
+          chunks.push(self.cut());
+        }
+      }
+    }
+    chunks
+  }
+
+  // ends the stream, returning the last chunk unless it is empty
+  pub fn finish(&mut self) -> Option<Chunk> {
+    if self.len == 0 { None } else { Some(self.cut()) }
+  }
+}
+
+// all chunks of data at once
+pub fn chunks(data: &[u8], min: usize, avg: usize, max: usize) -> Vec<Chunk> {
+  let mut chunker = Chunker::new(min, avg, max);
+  let mut chunks = chunker.feed(data);
+  chunks.extend(chunker.finish());
+  chunks
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::xorshift;
+  use std::collections::HashSet;
+
+  fn random_bytes(n: usize, seed: u64) -> Vec<u8> {
+    let mut next = xorshift(seed);
+    (0..n).map(|_| (next() >> 24) as u8).collect()
+  }
+
+  #[test]
+  fn test_rabin() {
+    // the definition, one bit at a time: r = r x + bit x^64 mod POLY, and x^64 = POLY mod POLY
+    let slow = |bytes: &[u8]| {
+      let mut r = 0u64;
+      for &b in bytes {
+        for i in (0..8).rev() {
+          let top = r >> 63 ^ (b >> i & 1) as u64;
+          r <<= 1;
+          if top == 1 {
+            r ^= POLY;
+          }
+        }
+      }
+      r
+    };
+    let rabin = Rabin::new();
+    for seed in 1..20 {
+      let bytes = random_bytes(seed as usize * 7, seed);
+      assert_eq!(slow(&bytes), rabin.fingerprint(&bytes));
+    }
+    assert_eq!(0, rabin.fingerprint(&[0, 0, 0]));
+  }
+
+  #[test]
+  fn test_chunk_sizes_and_streaming() {
+    let data = random_bytes(1 << 18, 103);
+    let (min, avg, max) = (1024, 4096, 16384);
+    let all = chunks(&data, min, avg, max);
+    assert_eq!(0, all[0].offset);
+    assert!(all.windows(2).all(|w| w[0].offset + w[0].len == w[1].offset));
+    assert_eq!(data.len(), all.iter().map(|c| c.len).sum::<usize>());
+    assert!(all[..all.len() - 1].iter().all(|c| min < c.len && c.len <= max));
+    let rabin = Rabin::new();
+    assert!(all.iter().all(|c| c.fingerprint == rabin.fingerprint(&data[c.offset..c.offset + c.len])));
+    let mean = data.len() / all.len();
+    assert!(avg / 2 < mean && mean < avg * 2, "mean chunk size {}", mean);
+
+    // the same chunks whatever the pieces the stream arrives in
+    let mut chunker = Chunker::new(min, avg, max);
+    let mut streamed = vec![];
+    let mut at = 0;
+    let mut step = 1;
+    while at < data.len() {
+      let end = (at + step).min(data.len());
+      streamed.extend(chunker.feed(&data[at..end]));
+      at = end;
+      step = step * 3 % 10007;
+    }
+    streamed.extend(chunker.finish());
+    assert_eq!(all, streamed);
+    assert_eq!(None, chunker.finish());
+
+    // fixed size when min reaches max
+    let fixed = chunks(&data[..10000], 4096, 4096, 4096);
+    assert_eq!(vec![4096, 4096, 1808], fixed.iter().map(|c| c.len).collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn test_insertion_keeps_most_chunks() {
+    let data = random_bytes(1 << 18, 107);
+    let mut edited = data[..100_000].to_vec();
+    edited.extend_from_slice(b"a few inserted bytes");
+    edited.extend_from_slice(&data[100_000..]);
+    let before: HashSet<u64> = chunks(&data, 1024, 4096, 16384).iter().map(|c| c.fingerprint).collect();
+    let after = chunks(&edited, 1024, 4096, 16384);
+    let changed = after.iter().filter(|c| !before.contains(&c.fingerprint)).count();
+    assert!(changed <= 3, "{} of {} chunks changed", changed, after.len());
+  }
+}
@@ -0,0 +1,89 @@
+use crate::string::suffix_array::SuffixArray;
+
+// Burrows-Wheeler transform with an implicit end marker smaller than every byte: the rows are
+// the suffixes of data$ in sorted order and the output is the byte before each, which is the
+// last column of the sorted rotations. Equal contexts sort together, so the output has long runs
+// of few distinct bytes. The marker's own row is left out of the output and its index returned
+// instead, which is all the inverse needs.
+
+// the last column without the marker, and the row where the marker was, in 0..=n
+pub fn bwt(data: &[u8]) -> (Vec<u8>, usize) {
+  let n = data.len();
+  let sa = SuffixArray::new(data);
+  let mut last = Vec::with_capacity(n);
+  let mut primary = 0;
+  // row 0 is the suffix $ alone, preceded by the last byte
+  if n > 0 {
+    last.push(data[n - 1]);
+  }
+  for (row, &s) in sa.sa().iter().enumerate() {
+    if s == 0 {
+      primary = row + 1;
+    } else {
+      last.push(data[s - 1]);
+    }
+  }
+  (last, primary)
+}
+
+// walks the LF mapping back from the marker's row: row i's last byte is the byte before the row,
+// and the row of the suffix starting there is its rank among the rows' first bytes
+pub fn inverse_bwt(last: &[u8], primary: usize) -> Vec<u8> {
+  let n = last.len();
+  assert!(primary <= n && (primary > 0 || n == 0));
+  // where the bytes' first rows start, after the marker's row 0
+  let mut start = [0; 257];
+  for &b in last {
+    start[b as usize + 1] += 1;
+  }
+  start[0] = 1;
+  for b in 1..257 {
+    start[b] += start[b - 1];
+  }
+  // lf[i] for the rows other than primary, indexed by their position in last
+  let mut seen = [0; 256];
+  let lf: Vec<usize> = last.iter().map(|&b| {
+    seen[b as usize] += 1;
+    start[b as usize] + seen[b as usize] - 1
+  }).collect();
+  let mut out = vec![0; n];
+  let mut row = 0;
+  for k in (0..n).rev() {
+    // row 0 holds $, whose predecessor is the last byte
+    let i = if row < primary { row } else { row - 1 };
+    out[k] = last[i];
+    row = lf[i];
+  }
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_banana() {
+    // $ a$ ana$ anana$ banana$ na$ nana$, the bytes before: a n n b $ a a
+    let (last, primary) = bwt(b"banana");
+    assert_eq!(b"annbaa".to_vec(), last);
+    assert_eq!(4, primary);
+    assert_eq!(b"banana".to_vec(), inverse_bwt(&last, primary));
+  }
+
+  #[test]
+  fn test_round_trip() {
+    let mut rand = rand_gen(149);
+    for n in 0..300 {
+      let sigma = 1 + rand(256);
+      let data: Vec<u8> = (0..n).map(|_| rand(sigma) as u8).collect();
+      let (last, primary) = bwt(&data);
+      let mut sorted = last.clone();
+      sorted.sort_unstable();
+      let mut expected = data.clone();
+      expected.sort_unstable();
+      assert_eq!(expected, sorted);
+      assert_eq!(data, inverse_bwt(&last, primary));
+    }
+  }
+}
@@ -0,0 +1,99 @@
+use super::bwt::{bwt, inverse_bwt};
+use super::mtf::{mtf_decode, mtf_encode};
+use super::range_coder::{AdaptiveModel, RangeDecoder, RangeEncoder, StaticModel};
+use super::rle::{rle_decode, rle_encode};
+
+// A bzip2-like compressor from the pieces of this module: each block goes through the BWT,
+// move-to-front and zero-run coding, and the symbols are range coded with an adaptive order-0
+// model. bzip2 itself uses Huffman tables chosen per 50 symbols instead, and sorts rotations
+// rather than suffixes.
+//
+// The stream is one range-coded sequence: per block a flag, the three bytes of the BWT's primary
+// index and the block's symbols up to END_OF_BLOCK, then a final flag of 0.
+
+pub const BLOCK_SIZE: usize = 1 << 18;
+const END_OF_BLOCK: usize = 257;
+
+pub fn compress(data: &[u8]) -> Vec<u8> {
+  let mut encoder = RangeEncoder::new();
+  let mut flag = StaticModel::new(&[1, 1]);
+  let mut byte = StaticModel::new(&[1; 256]);
+  let mut symbols = AdaptiveModel::new(END_OF_BLOCK + 1);
+  for block in data.chunks(BLOCK_SIZE) {
+    encoder.encode(&mut flag, 1);
+    let (last, primary) = bwt(block);
+    for shift in [16, 8, 0] {
+      encoder.encode(&mut byte, primary >> shift & 0xff);
+    }
+    for s in rle_encode(&mtf_encode(&last)) {
+      encoder.encode(&mut symbols, s as usize);
+    }
+    encoder.encode(&mut symbols, END_OF_BLOCK);
+  }
+  encoder.encode(&mut flag, 0);
+  encoder.finish()
+}
+
+pub fn decompress(input: &[u8]) -> Result<Vec<u8>, String> {
+  let mut decoder = RangeDecoder::new(input);
+  let mut flag = StaticModel::new(&[1, 1]);
+  let mut byte = StaticModel::new(&[1; 256]);
+  let mut symbols = AdaptiveModel::new(END_OF_BLOCK + 1);
+  let mut out = vec![];
+  while decoder.decode(&mut flag) == 1 {
+    let primary = (0..3).fold(0, |p, _| p << 8 | decoder.decode(&mut byte));
+    let mut block = vec![];
+    loop {
+      if decoder.overrun() {
+        return Err(format!("no end of block in {} bytes", input.len()));
+      }
+      match decoder.decode(&mut symbols) {
+        END_OF_BLOCK => break,
+        s => block.push(s as u16),
+      }
+    }
+    let last = mtf_decode(&rle_decode(&block)?);
+    if last.len() > BLOCK_SIZE || primary > last.len() || (primary == 0) != last.is_empty() {
+      return Err(format!("primary index {} for a block of {} bytes", primary, last.len()));
+    }
+    out.extend(inverse_bwt(&last, primary));
+  }
+  if decoder.overrun() {
+    return Err(format!("no end of stream in {} bytes", input.len()));
+  }
+  Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+  use crate::compression::range_coder;
+
+  #[test]
+  fn test_round_trip() {
+    let mut rand = rand_gen(151);
+    for n in [0, 1, 2, 100, 5000] {
+      let data: Vec<u8> = (0..n).map(|_| rand(256) as u8).collect();
+      assert_eq!(Ok(data.clone()), decompress(&compress(&data)));
+    }
+    // several blocks, the last a short one
+    let words = ["lorem", "ipsum", "dolor", "sit", "amet", "consectetur", "adipiscing", "elit"];
+    let mut text = vec![];
+    while text.len() < 2 * BLOCK_SIZE + 1000 {
+      text.extend_from_slice(words[rand(words.len() as u64) as usize].as_bytes());
+      text.push(b' ');
+    }
+    assert_eq!(Ok(text.clone()), decompress(&compress(&text)));
+    assert_eq!(Ok(vec![0; 300_000]), decompress(&compress(&vec![0; 300_000])));
+  }
+
+  #[test]
+  fn test_beats_order_zero() {
+    let text = b"She sells sea shells by the sea shore. The shells she sells are surely seashells. ".repeat(300);
+    let packed = compress(&text);
+    assert!(packed.len() * 5 < range_coder::compress(&text).len(), "{} bytes", packed.len());
+    assert!(packed.len() < 200, "{} bytes", packed.len());
+    assert!(decompress(&packed[..packed.len() / 2]).is_err());
+  }
+}
@@ -0,0 +1,5 @@
+pub mod range_coder;
+pub mod bwt;
+pub mod mtf;
+pub mod rle;
+pub mod bzip_lite;
\ No newline at end of file
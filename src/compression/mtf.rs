@@ -0,0 +1,34 @@
+// Move-to-front: each byte becomes its position in a list of all bytes, and moves to the front
+// of the list. A byte seen recently gets a small number, so the runs a BWT leaves become runs of
+// zeros and the output is dominated by a few small values.
+
+pub fn mtf_encode(data: &[u8]) -> Vec<u8> {
+  let mut list: Vec<u8> = (0..=255).collect();
+  data.iter().map(|&b| {
+    let i = list.iter().position(|&x| x == b).unwrap();
+    list[..=i].rotate_right(1);
+    i as u8
+  }).collect()
+}
+
+pub fn mtf_decode(codes: &[u8]) -> Vec<u8> {
+  let mut list: Vec<u8> = (0..=255).collect();
+  codes.iter().map(|&i| {
+    let b = list[i as usize];
+    list[..=i as usize].rotate_right(1);
+    b
+  }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_mtf() {
+    assert_eq!(vec![98, 0, 0, 98, 0, 0, 99], mtf_encode(b"bbbaaac"));
+    let data: Vec<u8> = (0..2000u32).map(|i| (i * i % 251) as u8).chain(b"zzzzzzyyyyyz".iter().copied()).collect();
+    assert_eq!(data, mtf_decode(&mtf_encode(&data)));
+    assert!(mtf_encode(&[]).is_empty());
+  }
+}
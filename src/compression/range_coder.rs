@@ -0,0 +1,310 @@
+// Range coder, the byte-oriented form of arithmetic coding, as in LZMA: a 32-bit range is
+// narrowed to each symbol's share of its model's total, and whenever the top byte of the range
+// is settled it is shifted out. A carry into bytes already shifted out is handled by holding back
+// the last byte and any run of 0xff after it until the carry is known.
+//
+// Models say how the total splits among symbols and may learn as they go; the decoder must see
+// the same model states as the encoder, so both update theirs after each symbol.
+
+const TOP: u32 = 1 << 24;
+// totals above this would leave too little of the range for a symbol of frequency 1
+pub const MAX_TOTAL: u32 = 1 << 16;
+
+pub trait Model {
+  // sum of all frequencies, at most MAX_TOTAL
+  fn total(&self) -> u32;
+  // the cumulative frequency below symbol and its own, which must be positive
+  fn interval(&self, symbol: usize) -> (u32, u32);
+  // the symbol whose interval holds target < total
+  fn symbol_at(&self, target: u32) -> usize;
+  // called after each symbol coded with the model
+  fn update(&mut self, _symbol: usize) {}
+}
+
+// fixed frequencies, zero for symbols that never occur
+pub struct StaticModel {
+  cumulative: Vec<u32>, // cumulative[s] = sum of the frequencies below s
+}
+
+impl StaticModel {
+  pub fn new(freqs: &[u32]) -> StaticModel {
+    let mut cumulative = vec![0];
+    for &f in freqs {
+      cumulative.push(cumulative.last().unwrap() + f);
+    }
+    assert!(0 < cumulative[freqs.len()] && cumulative[freqs.len()] <= MAX_TOTAL);
+    StaticModel { cumulative }
+  }
+}
+
+impl Model for StaticModel {
+  fn total(&self) -> u32 {
+    *self.cumulative.last().unwrap()
+  }
+
+  fn interval(&self, symbol: usize) -> (u32, u32) {
+    (self.cumulative[symbol], self.cumulative[symbol + 1] - self.cumulative[symbol])
+  }
+
+  fn symbol_at(&self, target: u32) -> usize {
+    self.cumulative.partition_point(|&c| c <= target) - 1
+  }
+}
+
+// order-0 adaptive model: every symbol starts at frequency 1 and gains INCREMENT each time it is
+// seen; the counts are halved when the total gets too large, which also lets the model follow
+// drifting statistics
+pub struct AdaptiveModel {
+  freqs: Vec<u32>,
+  total: u32,
+}
+
+impl AdaptiveModel {
+  const INCREMENT: u32 = 32;
+
+  pub fn new(symbols: usize) -> AdaptiveModel {
+    assert!(0 < symbols && symbols as u32 <= MAX_TOTAL / 2);
+    AdaptiveModel { freqs: vec![1; symbols], total: symbols as u32 }
+  }
+}
+
+impl Model for AdaptiveModel {
+  fn total(&self) -> u32 {
+    self.total
+  }
+
+  fn interval(&self, symbol: usize) -> (u32, u32) {
+    (self.freqs[..symbol].iter().sum(), self.freqs[symbol])
+  }
+
+  fn symbol_at(&self, mut target: u32) -> usize {
+    for (s, &f) in self.freqs.iter().enumerate() {
+      if target < f {
+        return s;
+      }
+      target -= f;
+    }
+    unreachable!()
+  }
+
+  fn update(&mut self, symbol: usize) {
+    self.freqs[symbol] += Self::INCREMENT;
+    self.total += Self::INCREMENT;
+    if self.total > MAX_TOTAL {
+      self.total = 0;
+      for f in self.freqs.iter_mut() {
+        *f = f.div_ceil(2);
+        self.total += *f;
+      }
+    }
+  }
+}
+
+pub struct RangeEncoder {
+  low: u64, // 33 bits, the top one a pending carry
+  range: u32,
+  cache: u8, // the byte held back
+  pending: usize, // the held byte and the 0xff bytes after it
+  out: Vec<u8>,
+}
+
+impl RangeEncoder {
+  pub fn new() -> RangeEncoder {
+    RangeEncoder { low: 0, range: u32::MAX, cache: 0, pending: 1, out: vec![] }
+  }
+
+  fn shift_low(&mut self) {
+    if self.low < 0xff00_0000 || self.low >= 1 << 32 {
+      let carry = (self.low >> 32) as u8;
+      let mut byte = self.cache;
+      while self.pending > 0 {
+        self.out.push(byte.wrapping_add(carry));
+        byte = 0xff;
+        self.pending -= 1;
+      }
+      self.cache = (self.low >> 24) as u8;
+    }
+    self.pending += 1;
+    self.low = (self.low & 0x00ff_ffff) << 8;
+  }
+
+  pub fn encode(&mut self, model: &mut impl Model, symbol: usize) {
+    let (cumulative, freq) = model.interval(symbol);
+    debug_assert!(freq > 0);
+    let r = self.range / model.total();
+    self.low += (r * cumulative) as u64;
+    self.range = r * freq;
+    while self.range < TOP {
+      self.range <<= 8;
+      self.shift_low();
+    }
+    model.update(symbol);
+  }
+
+  // flushes low, which pins down the final interval
+  pub fn finish(mut self) -> Vec<u8> {
+    for _ in 0..5 {
+      self.shift_low();
+    }
+    self.out
+  }
+}
+
+impl Default for RangeEncoder {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+pub struct RangeDecoder<'a> {
+  input: &'a [u8],
+  at: usize,
+  code: u32, // the input's offset from the bottom of the range
+  range: u32,
+}
+
+impl<'a> RangeDecoder<'a> {
+  pub fn new(input: &'a [u8]) -> RangeDecoder<'a> {
+    let mut decoder = RangeDecoder { input, at: 0, code: 0, range: u32::MAX };
+    // the first byte is the encoder's initial cache, always 0
+    for _ in 0..5 {
+      decoder.code = decoder.code << 8 | decoder.next_byte() as u32;
+    }
+    decoder
+  }
+
+  // reading past the end yields zeros, as the encoder's flush would have
+  fn next_byte(&mut self) -> u8 {
+    let b = self.input.get(self.at).copied().unwrap_or(0);
+    self.at += 1;
+    b
+  }
+
+  // whether the decoder has run more than a flush past the end of its input, so that the
+  // input cannot have come from the encoder
+  pub fn overrun(&self) -> bool {
+    self.at > self.input.len() + 4
+  }
+
+  pub fn decode(&mut self, model: &mut impl Model) -> usize {
+    let total = model.total();
+    let r = self.range / total;
+    let symbol = model.symbol_at((self.code / r).min(total - 1));
+    let (cumulative, freq) = model.interval(symbol);
+    self.code -= r * cumulative;
+    self.range = r * freq;
+    while self.range < TOP {
+      self.range <<= 8;
+      self.code = self.code << 8 | self.next_byte() as u32;
+    }
+    model.update(symbol);
+    symbol
+  }
+}
+
+// one byte after another through an adaptive order-0 model, ended by a symbol of its own
+pub fn compress(data: &[u8]) -> Vec<u8> {
+  let mut model = AdaptiveModel::new(257);
+  let mut encoder = RangeEncoder::new();
+  for &b in data {
+    encoder.encode(&mut model, b as usize);
+  }
+  encoder.encode(&mut model, 256);
+  encoder.finish()
+}
+
+pub fn decompress(input: &[u8]) -> Result<Vec<u8>, String> {
+  let mut model = AdaptiveModel::new(257);
+  let mut decoder = RangeDecoder::new(input);
+  let mut out = vec![];
+  loop {
+    match decoder.decode(&mut model) {
+      256 => return Ok(out),
+      b => out.push(b as u8),
+    }
+    if decoder.overrun() {
+      return Err(format!("no end of stream in {} bytes", input.len()));
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::xorshift;
+
+  fn random_bytes(n: usize, seed: u64, skew: u32) -> Vec<u8> {
+    let mut next = xorshift(seed);
+    (0..n)
+      .map(|_| {
+        let word = next();
+        // the fewest trailing ones of skew draws, geometric-ish for skew > 1
+        (0..skew).map(|i| (word >> (16 * i)).trailing_ones()).min().unwrap() as u8
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_round_trip() {
+    for (n, skew) in [(0, 1), (1, 1), (10, 1), (1000, 1), (100_000, 1), (100_000, 3)] {
+      let data = random_bytes(n, 131 + n as u64, skew);
+      let packed = compress(&data);
+      assert_eq!(Ok(data), decompress(&packed));
+    }
+    let text = b"it was the best of times, it was the worst of times".repeat(50);
+    assert_eq!(Ok(text.clone()), decompress(&compress(&text)));
+    let all: Vec<u8> = (0..=255).cycle().take(5000).collect();
+    assert_eq!(Ok(all.clone()), decompress(&compress(&all)));
+    assert!(decompress(&[]).is_err());
+  }
+
+  #[test]
+  fn test_close_to_entropy() {
+    let data = random_bytes(200_000, 137, 1);
+    let mut counts = [0f64; 256];
+    for &b in &data {
+      counts[b as usize] += 1.0;
+    }
+    let n = data.len() as f64;
+    let entropy: f64 = counts.iter().filter(|&&c| c > 0.0).map(|&c| -c * (c / n).log2()).sum::<f64>() / 8.0;
+    let packed = compress(&data).len() as f64;
+    assert!(packed < entropy * 1.01 + 100.0, "{} bytes against {} of entropy", packed, entropy);
+    assert!(packed < n / 3.0);
+  }
+
+  #[test]
+  fn test_pluggable_models() {
+    // a fixed model for a known source
+    let freqs = [50, 30, 15, 4, 1];
+    let symbols: Vec<usize> = random_bytes(10_000, 139, 1).iter().map(|&b| (b as usize).min(4)).collect();
+    let mut encoder = RangeEncoder::new();
+    let mut model = StaticModel::new(&freqs);
+    for &s in &symbols {
+      encoder.encode(&mut model, s);
+    }
+    let packed = encoder.finish();
+    let mut decoder = RangeDecoder::new(&packed);
+    assert_eq!(symbols, (0..symbols.len()).map(|_| decoder.decode(&mut model)).collect::<Vec<_>>());
+    assert_eq!(2, model.symbol_at(80));
+    assert_eq!((95, 4), model.interval(3));
+
+    // order 1, an adaptive model per previous byte, beats order 0 on text
+    let text = b"abracadabra, the cat sat on the mat with a hat; abracadabra! ".repeat(200);
+    let mut contexts: Vec<AdaptiveModel> = (0..256).map(|_| AdaptiveModel::new(256)).collect();
+    let mut encoder = RangeEncoder::new();
+    let mut prev = 0;
+    for &b in &text {
+      encoder.encode(&mut contexts[prev], b as usize);
+      prev = b as usize;
+    }
+    let packed = encoder.finish();
+    assert!(packed.len() < compress(&text).len() / 2);
+    let mut contexts: Vec<AdaptiveModel> = (0..256).map(|_| AdaptiveModel::new(256)).collect();
+    let mut decoder = RangeDecoder::new(&packed);
+    let mut prev = 0;
+    for &b in &text {
+      prev = decoder.decode(&mut contexts[prev]);
+      assert_eq!(b as usize, prev);
+    }
+  }
+}
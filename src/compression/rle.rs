@@ -0,0 +1,77 @@
+// Zero-run coding as bzip2 does after move-to-front: a run of zeros is written as its length in
+// bijective base 2, least significant digit first, with the digits RUN_A = 1 and RUN_B = 2, so a
+// run of length r takes about log2 r symbols and needs no separate length field. Any other value
+// v becomes v + 1, giving an alphabet of 257 symbols.
+
+pub const RUN_A: u16 = 0;
+pub const RUN_B: u16 = 1;
+
+pub fn rle_encode(codes: &[u8]) -> Vec<u16> {
+  let mut out = vec![];
+  let mut run = 0;
+  for &c in codes.iter().chain(std::iter::once(&1)) {
+    if c == 0 {
+      run += 1;
+      continue;
+    }
+    while run > 0 {
+      if run % 2 == 1 {
+        out.push(RUN_A);
+        run = (run - 1) / 2;
+      } else {
+        out.push(RUN_B);
+        run = (run - 2) / 2;
+      }
+    }
+    out.push(c as u16 + 1);
+  }
+  out.pop(); // the sentinel that flushed the last run
+  out
+}
+
+pub fn rle_decode(symbols: &[u16]) -> Result<Vec<u8>, String> {
+  let mut out = vec![];
+  let mut run = 0;
+  let mut weight = 1;
+  for &s in symbols {
+    match s {
+      RUN_A | RUN_B => {
+        run += weight * (s as usize + 1);
+        weight *= 2;
+      },
+      2..=256 => {
+        out.resize(out.len() + run, 0);
+        run = 0;
+        weight = 1;
+        out.push((s - 1) as u8);
+      },
+      _ => return Err(format!("symbol {} out of range", s)),
+    }
+  }
+  out.resize(out.len() + run, 0);
+  Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_runs() {
+    // 1 = A, 2 = B, 3 = AA, 4 = BA, 5 = AB, 6 = BB, 7 = AAA
+    let runs = [vec![0], vec![1], vec![0, 0], vec![1, 0], vec![0, 1], vec![1, 1], vec![0, 0, 0]];
+    for (r, digits) in runs.iter().enumerate() {
+      let mut codes = vec![0; r + 1];
+      codes.push(7);
+      let mut expected = digits.clone();
+      expected.push(8);
+      assert_eq!(expected, rle_encode(&codes));
+      assert_eq!(Ok(codes), rle_decode(&expected));
+    }
+    for codes in [vec![], vec![0; 1000], vec![255, 0, 0, 3, 0], (0..=255).collect()] {
+      assert_eq!(Ok(codes.clone()), rle_decode(&rle_encode(&codes)));
+    }
+    assert_eq!(9, rle_encode(&[0; 1000]).len());
+    assert!(rle_decode(&[257]).is_err());
+  }
+}
@@ -0,0 +1,260 @@
+use std::cell::UnsafeCell;
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{fence, AtomicIsize, AtomicPtr, Ordering};
+use std::sync::Arc;
+
+use super::epoch;
+use super::queue::CachePadded;
+
+const MIN_CAPACITY: usize = 16;
+
+struct Buffer<T> {
+  slots: Box<[UnsafeCell<MaybeUninit<T>>]>, // a power of two of them
+}
+
+impl<T> Buffer<T> {
+  fn alloc(capacity: usize) -> *mut Buffer<T> {
+    let slots = (0..capacity).map(|_| UnsafeCell::new(MaybeUninit::uninit())).collect();
+    Box::into_raw(Box::new(Buffer { slots }))
+  }
+
+  fn capacity(&self) -> usize {
+    self.slots.len()
+  }
+
+  unsafe fn write(&self, i: isize, value: T) {
+    (*self.slots[i as usize & (self.capacity() - 1)].get()).write(value);
+  }
+
+  // a bitwise copy, owned only once the caller has won the slot
+  unsafe fn read(&self, i: isize) -> MaybeUninit<T> {
+    std::ptr::read(self.slots[i as usize & (self.capacity() - 1)].get())
+  }
+}
+
+struct Inner<T> {
+  top: CachePadded<AtomicIsize>, // next to steal
+  bottom: CachePadded<AtomicIsize>, // next to push
+  // a thief may still be reading a buffer the owner outgrew, so old ones are freed through the
+  // epoch collector
+  buffer: AtomicPtr<Buffer<T>>,
+}
+
+impl<T> Drop for Inner<T> {
+  fn drop(&mut self) {
+    let (top, bottom) = (*self.top.0.get_mut(), *self.bottom.0.get_mut());
+    let buffer = *self.buffer.get_mut();
+    unsafe {
+      for i in top..bottom {
+        (*buffer).read(i).assume_init_drop();
+      }
+      drop(Box::from_raw(buffer));
+    }
+  }
+}
+
+// Chase-Lev work-stealing deque, with the memory orderings of Le, Pop, Cohen & Zappa Nardelli:
+// the owning worker pushes and pops at the bottom without locks or, mostly, CAS; thieves take
+// from the top with one CAS each, and only a pop of the last element races them for it. The
+// circular buffer doubles when full.
+pub struct Worker<T> {
+  inner: Arc<Inner<T>>,
+  _not_sync: PhantomData<*mut ()>, // one owner thread at a time
+}
+
+pub struct Stealer<T> {
+  inner: Arc<Inner<T>>,
+}
+
+unsafe impl<T: Send> Send for Worker<T> {}
+unsafe impl<T: Send> Send for Stealer<T> {}
+unsafe impl<T: Send> Sync for Stealer<T> {}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Steal<T> {
+  Empty,
+  Success(T),
+  Retry, // lost a race to another thief or the owner
+}
+
+// a deque's owner end and a stealer end, which can be cloned for every thief
+pub fn deque<T>() -> (Worker<T>, Stealer<T>) {
+  let inner = Arc::new(Inner {
+    top: CachePadded(AtomicIsize::new(0)),
+    bottom: CachePadded(AtomicIsize::new(0)),
+    buffer: AtomicPtr::new(Buffer::alloc(MIN_CAPACITY)),
+  });
+  (Worker { inner: inner.clone(), _not_sync: PhantomData }, Stealer { inner })
+}
+
+impl<T> Worker<T> {
+  pub fn len(&self) -> usize {
+    let b = self.inner.bottom.load(Ordering::Relaxed);
+    let t = self.inner.top.load(Ordering::Relaxed);
+    (b - t).max(0) as usize
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  pub fn push(&self, value: T) {
+    let inner = &*self.inner;
+    let b = inner.bottom.load(Ordering::Relaxed);
+    let t = inner.top.load(Ordering::Acquire);
+    let mut buffer = inner.buffer.load(Ordering::Relaxed);
+    unsafe {
+      if b - t >= (*buffer).capacity() as isize {
+        let grown = Buffer::alloc(2 * (*buffer).capacity());
+        for i in t..b {
+          (*grown).write(i, (*buffer).read(i).assume_init());
+        }
+        inner.buffer.store(grown, Ordering::Release);
+        epoch::pin().defer_destroy_raw(buffer);
+        buffer = grown;
+      }
+      (*buffer).write(b, value);
+    }
+    fence(Ordering::Release);
+    inner.bottom.store(b + 1, Ordering::Relaxed);
+  }
+
+  // the most recently pushed element still there
+  pub fn pop(&self) -> Option<T> {
+    let inner = &*self.inner;
+    let b = inner.bottom.load(Ordering::Relaxed) - 1;
+    let buffer = inner.buffer.load(Ordering::Relaxed);
+    inner.bottom.store(b, Ordering::Relaxed);
+    fence(Ordering::SeqCst);
+    let t = inner.top.load(Ordering::Relaxed);
+    if t > b {
+      inner.bottom.store(b + 1, Ordering::Relaxed);
+      return None;
+    }
+    let value = unsafe { (*buffer).read(b) };
+    if t == b {
+      // the last one: whoever moves top gets it
+      let won = inner.top.compare_exchange(t, t + 1, Ordering::SeqCst, Ordering::Relaxed).is_ok();
+      inner.bottom.store(b + 1, Ordering::Relaxed);
+      if !won {
+        return None;
+      }
+    }
+    Some(unsafe { value.assume_init() })
+  }
+}
+
+impl<T> Stealer<T> {
+  pub fn is_empty(&self) -> bool {
+    let t = self.inner.top.load(Ordering::Acquire);
+    let b = self.inner.bottom.load(Ordering::Acquire);
+    b <= t
+  }
+
+  // the least recently pushed element still there
+  pub fn steal(&self) -> Steal<T> {
+    let inner = &*self.inner;
+    let _guard = epoch::pin();
+    let t = inner.top.load(Ordering::Acquire);
+    fence(Ordering::SeqCst);
+    let b = inner.bottom.load(Ordering::Acquire);
+    if t >= b {
+      return Steal::Empty;
+    }
+    let buffer = inner.buffer.load(Ordering::Acquire);
+    let value = unsafe { (*buffer).read(t) };
+    if inner.top.compare_exchange(t, t + 1, Ordering::SeqCst, Ordering::Relaxed).is_err() {
+      return Steal::Retry;
+    }
+    Steal::Success(unsafe { value.assume_init() })
+  }
+}
+
+impl<T> Clone for Stealer<T> {
+  fn clone(&self) -> Self {
+    Stealer { inner: self.inner.clone() }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::atomic::{AtomicBool, AtomicUsize};
+  use std::thread;
+
+  #[test]
+  fn test_ends() {
+    let (worker, stealer) = deque();
+    assert_eq!(None, worker.pop());
+    assert_eq!(Steal::Empty, stealer.steal());
+    for i in 0..100 {
+      worker.push(i);
+    }
+    assert_eq!(100, worker.len());
+    assert_eq!(Some(99), worker.pop());
+    assert_eq!(Steal::Success(0), stealer.steal());
+    assert_eq!(Steal::Success(1), stealer.clone().steal());
+    assert_eq!(Some(98), worker.pop());
+    let mut rest = vec![];
+    while let Some(x) = worker.pop() {
+      rest.push(x);
+    }
+    assert_eq!((2..98).rev().collect::<Vec<_>>(), rest);
+    assert!(worker.is_empty() && stealer.is_empty());
+  }
+
+  #[test]
+  fn test_owner_and_thieves() {
+    const N: usize = 200_000;
+    let (worker, stealer) = deque();
+    let done = AtomicBool::new(false);
+    let sum = AtomicUsize::new(0);
+    let taken = AtomicUsize::new(0);
+    thread::scope(|s| {
+      for _ in 0..3 {
+        let (stealer, done, sum, taken) = (stealer.clone(), &done, &sum, &taken);
+        s.spawn(move || {
+          while !done.load(Ordering::Acquire) || !stealer.is_empty() {
+            if let Steal::Success(x) = stealer.steal() {
+              sum.fetch_add(x, Ordering::Relaxed);
+              taken.fetch_add(1, Ordering::Relaxed);
+            }
+          }
+        });
+      }
+      for i in 1..=N {
+        worker.push(i);
+        // pops now and then, racing the thieves for what is left
+        if i % 3 == 0 {
+          if let Some(x) = worker.pop() {
+            sum.fetch_add(x, Ordering::Relaxed);
+            taken.fetch_add(1, Ordering::Relaxed);
+          }
+        }
+      }
+      while let Some(x) = worker.pop() {
+        sum.fetch_add(x, Ordering::Relaxed);
+        taken.fetch_add(1, Ordering::Relaxed);
+      }
+      done.store(true, Ordering::Release);
+    });
+    assert_eq!(N, taken.into_inner());
+    assert_eq!(N * (N + 1) / 2, sum.into_inner());
+  }
+
+  #[test]
+  fn test_drops() {
+    let token = Arc::new(());
+    {
+      let (worker, stealer) = deque();
+      for _ in 0..50 {
+        worker.push(token.clone());
+      }
+      drop(worker.pop());
+      drop(stealer.steal());
+      assert_eq!(49, Arc::strong_count(&token));
+    }
+    assert_eq!(1, Arc::strong_count(&token));
+  }
+}
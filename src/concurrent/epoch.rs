@@ -0,0 +1,224 @@
+use std::cell::{Cell, RefCell};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+// Epoch-based reclamation (Fraser): memory unlinked from a lock-free structure is freed only once
+// no thread can still hold a pointer into it. Threads pin themselves while they touch shared
+// nodes, announcing the global epoch they saw; the epoch advances only when every pinned thread
+// has seen the current one. Garbage deferred in epoch e is therefore unreachable once the epoch
+// reaches e + 2, as every thread pinned back then has unpinned since.
+//
+// One global collector; a thread registers on its first pin and leaves when it exits.
+
+// how many unpins between attempts to advance and collect
+const COLLECT_EVERY: usize = 64;
+
+enum Deferred {
+  Destroy(*mut u8, unsafe fn(*mut u8)),
+  Call(Box<dyn FnOnce() + Send>),
+}
+
+// the pointers in Destroy are owned by the garbage list alone
+unsafe impl Send for Deferred {}
+
+impl Deferred {
+  fn run(self) {
+    match self {
+      Deferred::Destroy(ptr, drop_fn) => unsafe { drop_fn(ptr) },
+      Deferred::Call(f) => f(),
+    }
+  }
+}
+
+unsafe fn drop_box<T>(ptr: *mut u8) {
+  drop(Box::from_raw(ptr as *mut T));
+}
+
+struct Collector {
+  epoch: AtomicUsize,
+  // each thread's state: 0 unpinned, epoch << 1 | 1 pinned in that epoch
+  participants: Mutex<Vec<Arc<AtomicUsize>>>,
+  garbage: Mutex<Vec<(usize, Deferred)>>,
+}
+
+fn collector() -> &'static Collector {
+  static COLLECTOR: OnceLock<Collector> = OnceLock::new();
+  COLLECTOR.get_or_init(|| Collector { epoch: AtomicUsize::new(0), participants: Mutex::new(vec![]), garbage: Mutex::new(vec![]) })
+}
+
+impl Collector {
+  // advances the epoch if every pinned thread has caught up with it
+  fn try_advance(&self) -> usize {
+    let epoch = self.epoch.load(Ordering::SeqCst);
+    let lagging = self.participants.lock().unwrap().iter().any(|p| {
+      let state = p.load(Ordering::SeqCst);
+      state & 1 == 1 && state >> 1 != epoch
+    });
+    if lagging {
+      return epoch;
+    }
+    match self.epoch.compare_exchange(epoch, epoch + 1, Ordering::SeqCst, Ordering::SeqCst) {
+      Ok(_) => epoch + 1,
+      Err(now) => now,
+    }
+  }
+
+  fn collect(&self) {
+    let epoch = self.try_advance();
+    let ready: Vec<Deferred> = {
+      let mut garbage = self.garbage.lock().unwrap();
+      let (ready, waiting) = std::mem::take(&mut *garbage).into_iter().partition(|&(e, _)| e + 2 <= epoch);
+      *garbage = waiting;
+      ready.into_iter().map(|(_, d)| d).collect()
+    };
+    // outside the lock, as destructors may defer more
+    for d in ready {
+      d.run();
+    }
+  }
+}
+
+struct Local {
+  state: Arc<AtomicUsize>,
+  pins: Cell<usize>, // nesting depth
+  unpins: Cell<usize>,
+}
+
+impl Drop for Local {
+  fn drop(&mut self) {
+    let state = self.state.clone();
+    collector().participants.lock().unwrap().retain(|p| !Arc::ptr_eq(p, &state));
+  }
+}
+
+thread_local! {
+  static LOCAL: RefCell<Option<Local>> = const { RefCell::new(None) };
+}
+
+fn with_local<R>(f: impl FnOnce(&Local) -> R) -> R {
+  LOCAL.with(|local| {
+    let mut local = local.borrow_mut();
+    let local = local.get_or_insert_with(|| {
+      let state = Arc::new(AtomicUsize::new(0));
+      collector().participants.lock().unwrap().push(state.clone());
+      Local { state, pins: Cell::new(0), unpins: Cell::new(0) }
+    });
+    f(local)
+  })
+}
+
+// Proof that the current thread is pinned: shared nodes read while it lives stay allocated.
+// Not Send, as the pin belongs to the thread.
+pub struct Guard {
+  _not_send: std::marker::PhantomData<*mut ()>,
+}
+
+pub fn pin() -> Guard {
+  with_local(|local| {
+    if local.pins.get() == 0 {
+      let epoch = collector().epoch.load(Ordering::SeqCst);
+      local.state.store(epoch << 1 | 1, Ordering::SeqCst);
+      // reads of shared pointers must not move above the announcement
+      std::sync::atomic::fence(Ordering::SeqCst);
+    }
+    local.pins.set(local.pins.get() + 1);
+  });
+  Guard { _not_send: std::marker::PhantomData }
+}
+
+impl Guard {
+  fn defer_deferred(&self, d: Deferred) {
+    let epoch = collector().epoch.load(Ordering::SeqCst);
+    collector().garbage.lock().unwrap().push((epoch, d));
+  }
+
+  // drops boxed once no thread pinned now can still be looking at it
+  pub fn defer_destroy<T: Send + 'static>(&self, boxed: Box<T>) {
+    unsafe { self.defer_destroy_raw(Box::into_raw(boxed)) };
+  }
+
+  // the same for what the structures here unlink: ptr came from Box::into_raw, threads pinning
+  // from now on cannot reach it, and it is deferred once. T need not be 'static as long as
+  // dropping it frees memory only, like the queue's emptied nodes and the deque's old buffers.
+  pub(crate) unsafe fn defer_destroy_raw<T>(&self, ptr: *mut T) {
+    self.defer_deferred(Deferred::Destroy(ptr as *mut u8, drop_box::<T>));
+  }
+
+  // runs f once every thread pinned now has unpinned
+  pub fn defer(&self, f: impl FnOnce() + Send + 'static) {
+    self.defer_deferred(Deferred::Call(Box::new(f)));
+  }
+
+  // tries to advance the epoch and run what is due, rather than waiting for the next periodic
+  // collection
+  pub fn flush(&self) {
+    collector().collect();
+  }
+}
+
+impl Drop for Guard {
+  fn drop(&mut self) {
+    let collect = with_local(|local| {
+      local.pins.set(local.pins.get() - 1);
+      if local.pins.get() > 0 {
+        return false;
+      }
+      local.state.store(0, Ordering::SeqCst);
+      local.unpins.set(local.unpins.get() + 1);
+      local.unpins.get() % COLLECT_EVERY == 0
+    });
+    if collect {
+      collector().collect();
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::atomic::AtomicBool;
+  use std::sync::mpsc;
+  use std::thread;
+
+  #[test]
+  fn test_deferred_waits_for_pinned_threads() {
+    let ran = Arc::new(AtomicBool::new(false));
+    let (pinned_tx, pinned_rx) = mpsc::channel();
+    let (release_tx, release_rx) = mpsc::channel::<()>();
+    let other = thread::spawn(move || {
+      let _guard = pin();
+      pinned_tx.send(()).unwrap();
+      release_rx.recv().unwrap();
+    });
+    pinned_rx.recv().unwrap();
+    {
+      let guard = pin();
+      let ran = ran.clone();
+      guard.defer(move || ran.store(true, Ordering::SeqCst));
+    }
+    // the other thread pins an old epoch, which holds the epoch back at most one step
+    for _ in 0..10 {
+      pin().flush();
+    }
+    assert!(!ran.load(Ordering::SeqCst));
+    release_tx.send(()).unwrap();
+    other.join().unwrap();
+    // other tests may hold the epoch back for a while too
+    while !ran.load(Ordering::SeqCst) {
+      pin().flush();
+      thread::yield_now();
+    }
+  }
+
+  #[test]
+  fn test_defer_destroy() {
+    let token = Arc::new(());
+    let guard = pin();
+    guard.defer_destroy(Box::new(token.clone()));
+    drop(guard);
+    while Arc::strong_count(&token) > 1 {
+      pin().flush();
+      thread::yield_now();
+    }
+  }
+}
@@ -0,0 +1,4 @@
+pub mod queue;
+pub mod priority_queue;
+pub mod deque;
+pub mod epoch;
\ No newline at end of file
@@ -0,0 +1,187 @@
+use std::cell::Cell;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::thread;
+
+use super::queue::CachePadded;
+use crate::binary_heap::{BinaryHeap, DefaultCmp};
+
+type Shard<T> = CachePadded<Mutex<BinaryHeap<T, DefaultCmp<T>>>>;
+
+thread_local! {
+  // xorshift state per thread, seeded apart
+  static RNG: Cell<u64> = Cell::new(RandomState::new().build_hasher().finish() | 1);
+}
+
+fn random(m: usize) -> usize {
+  RNG.with(|rng| {
+    let mut x = rng.get();
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    rng.set(x);
+    (x % m as u64) as usize
+  })
+}
+
+// Concurrent max-priority queue as a MultiQueue (Rihani, Sanders & Dementiev): several binary
+// heaps behind their own locks. A push goes to a random heap whose lock is free; a pop looks at
+// the tops of two random heaps and takes the larger. Threads rarely meet on a lock, at the price
+// of relaxed order: a pop returns one of the larger elements, close to the maximum in rank on
+// average, rather than the maximum itself. That is what parallel branch and bound or best-first
+// search can use, where a strict queue would serialize the workers.
+pub struct ConcurrentPriorityQueue<T> {
+  shards: Vec<Shard<T>>,
+  len: AtomicUsize,
+}
+
+impl<T: Ord> ConcurrentPriorityQueue<T> {
+  // two heaps per thread of the machine
+  pub fn new() -> ConcurrentPriorityQueue<T> {
+    let threads = thread::available_parallelism().map_or(4, |n| n.get());
+    Self::with_shards(2 * threads)
+  }
+
+  // one shard makes it an exact, fully serialized queue
+  pub fn with_shards(shards: usize) -> ConcurrentPriorityQueue<T> {
+    assert!(shards > 0);
+    ConcurrentPriorityQueue {
+      shards: (0..shards).map(|_| CachePadded(Mutex::new(BinaryHeap::new()))).collect(),
+      len: AtomicUsize::new(0),
+    }
+  }
+
+  // a snapshot, possibly stale by the time it is read
+  pub fn len(&self) -> usize {
+    self.len.load(Ordering::Relaxed)
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  fn lock(&self, i: usize) -> MutexGuard<'_, BinaryHeap<T, DefaultCmp<T>>> {
+    self.shards[i].lock().unwrap()
+  }
+
+  pub fn push(&self, value: T) {
+    let n = self.shards.len();
+    let mut i = random(n);
+    // a few tries for a free lock before waiting on one
+    for _ in 0..n {
+      if let Ok(mut heap) = self.shards[i].try_lock() {
+        heap.push(value);
+        self.len.fetch_add(1, Ordering::Relaxed);
+        return;
+      }
+      i = random(n);
+    }
+    self.lock(i).push(value);
+    self.len.fetch_add(1, Ordering::Relaxed);
+  }
+
+  // one of the larger elements, or None if every heap was empty when looked at
+  pub fn try_pop(&self) -> Option<T> {
+    let n = self.shards.len();
+    while !self.is_empty() {
+      let (i, j) = (random(n), random(n));
+      let value = if i == j {
+        self.lock(i).pop()
+      } else {
+        // locked in index order, so two pops cannot wait on each other
+        let (mut a, mut b) = (self.lock(i.min(j)), self.lock(i.max(j)));
+        match (a.peek(), b.peek()) {
+          (Some(x), Some(y)) if x >= y => a.pop(),
+          (Some(_), None) => a.pop(),
+          (_, Some(_)) => b.pop(),
+          (None, None) => None,
+        }
+      };
+      if value.is_some() {
+        self.len.fetch_sub(1, Ordering::Relaxed);
+        return value;
+      }
+      // both empty: sweep every heap once rather than keep guessing
+      let start = random(n);
+      for k in 0..n {
+        if let Some(value) = self.lock((start + k) % n).pop() {
+          self.len.fetch_sub(1, Ordering::Relaxed);
+          return Some(value);
+        }
+      }
+    }
+    None
+  }
+}
+
+impl<T: Ord> Default for ConcurrentPriorityQueue<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::collections::BTreeSet;
+
+  #[test]
+  fn test_single_shard_is_exact() {
+    let q = ConcurrentPriorityQueue::with_shards(1);
+    for x in [5, 1, 4, 1, 5, 9, 2, 6] {
+      q.push(x);
+    }
+    assert_eq!(8, q.len());
+    assert_eq!(vec![9, 6, 5, 5, 4, 2, 1, 1], (0..8).map(|_| q.try_pop().unwrap()).collect::<Vec<_>>());
+    assert_eq!(None, q.try_pop());
+  }
+
+  #[test]
+  fn test_rank_error() {
+    let q = ConcurrentPriorityQueue::with_shards(8);
+    let n = 1u32 << 14;
+    for x in 0..n {
+      q.push(x.wrapping_mul(2_654_435_761) % n);
+    }
+    let mut remaining: BTreeSet<u32> = (0..n).collect();
+    let mut total_rank = 0;
+    while let Some(x) = q.try_pop() {
+      total_rank += remaining.range(x + 1..).count();
+      assert!(remaining.remove(&x));
+    }
+    assert!(remaining.is_empty());
+    // how many larger elements were still in the queue, on average
+    let mean = total_rank as f64 / n as f64;
+    assert!(mean < 16.0, "mean rank {}", mean);
+  }
+
+  #[test]
+  fn test_threads() {
+    let q = ConcurrentPriorityQueue::with_shards(8);
+    let popped = Mutex::new(vec![]);
+    thread::scope(|s| {
+      for t in 0..4u64 {
+        let (q, popped) = (&q, &popped);
+        s.spawn(move || {
+          let mut mine = vec![];
+          for i in 0..10_000 {
+            q.push(t * 10_000 + i);
+            if i % 3 == 0 {
+              mine.extend(q.try_pop());
+            }
+          }
+          popped.lock().unwrap().extend(mine);
+        });
+      }
+    });
+    let mut all = popped.into_inner().unwrap();
+    while let Some(x) = q.try_pop() {
+      all.push(x);
+    }
+    all.sort_unstable();
+    assert_eq!((0..40_000).collect::<Vec<_>>(), all);
+    assert!(q.is_empty());
+  }
+}
@@ -0,0 +1,394 @@
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::ops::Deref;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use super::epoch;
+
+// keeps hot atomics on cache lines of their own, so producers and consumers do not false-share
+#[repr(align(64))]
+pub(crate) struct CachePadded<T>(pub(crate) T);
+
+impl<T> Deref for CachePadded<T> {
+  type Target = T;
+  fn deref(&self) -> &T {
+    &self.0
+  }
+}
+
+struct Slot<T> {
+  // stamp = position when the slot is free for the push at that position, position + 1 once it
+  // holds that push's value
+  stamp: AtomicUsize,
+  value: UnsafeCell<MaybeUninit<T>>,
+}
+
+// Bounded multi-producer multi-consumer queue on a ring buffer (Vyukov). Every slot carries a
+// stamp telling which lap it is ready for, so a producer claims a position with one CAS on the
+// tail and then owns the slot until it publishes the stamp; consumers do the same on the head.
+// Lock-free in practice: a thread stalled between its CAS and its stamp holds up only the
+// threads that reach that slot.
+pub struct ArrayQueue<T> {
+  slots: Box<[Slot<T>]>,
+  mask: usize,
+  head: CachePadded<AtomicUsize>,
+  tail: CachePadded<AtomicUsize>,
+}
+
+unsafe impl<T: Send> Send for ArrayQueue<T> {}
+unsafe impl<T: Send> Sync for ArrayQueue<T> {}
+
+impl<T> ArrayQueue<T> {
+  // capacity is rounded up to a power of two
+  pub fn new(capacity: usize) -> ArrayQueue<T> {
+    let capacity = capacity.max(2).next_power_of_two();
+    ArrayQueue {
+      slots: (0..capacity).map(|i| Slot { stamp: AtomicUsize::new(i), value: UnsafeCell::new(MaybeUninit::uninit()) }).collect(),
+      mask: capacity - 1,
+      head: CachePadded(AtomicUsize::new(0)),
+      tail: CachePadded(AtomicUsize::new(0)),
+    }
+  }
+
+  pub fn capacity(&self) -> usize {
+    self.slots.len()
+  }
+
+  // hands the value back if the queue is full
+  pub fn push(&self, value: T) -> Result<(), T> {
+    let mut pos = self.tail.load(Ordering::Relaxed);
+    loop {
+      let slot = &self.slots[pos & self.mask];
+      let stamp = slot.stamp.load(Ordering::Acquire);
+      if stamp == pos {
+        match self.tail.compare_exchange_weak(pos, pos + 1, Ordering::Relaxed, Ordering::Relaxed) {
+          Ok(_) => {
+            unsafe { (*slot.value.get()).write(value) };
+            slot.stamp.store(pos + 1, Ordering::Release);
+            return Ok(());
+          },
+          Err(now) => pos = now,
+        }
+      } else if stamp < pos {
+        // the slot still holds the value from a lap ago
+        return Err(value);
+      } else {
+        pos = self.tail.load(Ordering::Relaxed);
+      }
+    }
+  }
+
+  pub fn pop(&self) -> Option<T> {
+    let mut pos = self.head.load(Ordering::Relaxed);
+    loop {
+      let slot = &self.slots[pos & self.mask];
+      let stamp = slot.stamp.load(Ordering::Acquire);
+      if stamp == pos + 1 {
+        match self.head.compare_exchange_weak(pos, pos + 1, Ordering::Relaxed, Ordering::Relaxed) {
+          Ok(_) => {
+            let value = unsafe { (*slot.value.get()).assume_init_read() };
+            // free for the push one lap later
+            slot.stamp.store(pos + self.slots.len(), Ordering::Release);
+            return Some(value);
+          },
+          Err(now) => pos = now,
+        }
+      } else if stamp < pos + 1 {
+        // not written yet: empty
+        return None;
+      } else {
+        pos = self.head.load(Ordering::Relaxed);
+      }
+    }
+  }
+
+  // a snapshot, possibly stale by the time it is read
+  pub fn len(&self) -> usize {
+    loop {
+      let tail = self.tail.load(Ordering::SeqCst);
+      let head = self.head.load(Ordering::SeqCst);
+      if self.tail.load(Ordering::SeqCst) == tail {
+        return tail.saturating_sub(head);
+      }
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+}
+
+impl<T> Drop for ArrayQueue<T> {
+  fn drop(&mut self) {
+    while self.pop().is_some() {}
+  }
+}
+
+struct Node<T> {
+  value: Option<T>,
+  next: AtomicPtr<Node<T>>,
+}
+
+// Unbounded FIFO queue, the two-lock algorithm from Michael & Scott's paper: a singly linked
+// list with a dummy head, one lock for the head and one for the tail, so a producer and a
+// consumer never wait for each other. The head lock doubles as memory reclamation: no one can
+// be reading a node once pop has unlinked it. MsQueue below is their lock-free variant.
+pub struct LinkedQueue<T> {
+  head: CachePadded<Mutex<*mut Node<T>>>, // the dummy; its successor holds the front value
+  tail: CachePadded<Mutex<*mut Node<T>>>,
+}
+
+unsafe impl<T: Send> Send for LinkedQueue<T> {}
+unsafe impl<T: Send> Sync for LinkedQueue<T> {}
+
+impl<T> LinkedQueue<T> {
+  pub fn new() -> LinkedQueue<T> {
+    let dummy = Box::into_raw(Box::new(Node { value: None, next: AtomicPtr::new(ptr::null_mut()) }));
+    LinkedQueue { head: CachePadded(Mutex::new(dummy)), tail: CachePadded(Mutex::new(dummy)) }
+  }
+
+  pub fn push(&self, value: T) {
+    let node = Box::into_raw(Box::new(Node { value: Some(value), next: AtomicPtr::new(ptr::null_mut()) }));
+    let mut tail = self.tail.lock().unwrap();
+    // the old tail stays alive: pop frees a node only once it has a successor and is not the
+    // last node, and the link is the last thing done to it here
+    unsafe { (**tail).next.store(node, Ordering::Release) };
+    *tail = node;
+  }
+
+  pub fn pop(&self) -> Option<T> {
+    let mut head = self.head.lock().unwrap();
+    let dummy = *head;
+    let next = unsafe { (*dummy).next.load(Ordering::Acquire) };
+    if next.is_null() {
+      return None;
+    }
+    // next becomes the dummy, its value moves out
+    let value = unsafe { (*next).value.take() };
+    *head = next;
+    drop(head);
+    drop(unsafe { Box::from_raw(dummy) });
+    value
+  }
+
+  pub fn is_empty(&self) -> bool {
+    let head = self.head.lock().unwrap();
+    unsafe { (**head).next.load(Ordering::Acquire).is_null() }
+  }
+}
+
+impl<T> Default for LinkedQueue<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T> Drop for LinkedQueue<T> {
+  fn drop(&mut self) {
+    let mut node = *self.head.0.get_mut().unwrap();
+    while !node.is_null() {
+      let boxed = unsafe { Box::from_raw(node) };
+      node = boxed.next.load(Ordering::Relaxed);
+    }
+  }
+}
+
+struct MsNode<T> {
+  value: MaybeUninit<T>, // moved out by the pop that makes the node the dummy
+  next: AtomicPtr<MsNode<T>>,
+}
+
+impl<T> MsNode<T> {
+  fn alloc(value: MaybeUninit<T>) -> *mut MsNode<T> {
+    Box::into_raw(Box::new(MsNode { value, next: AtomicPtr::new(ptr::null_mut()) }))
+  }
+}
+
+// Unbounded lock-free FIFO queue, the non-blocking algorithm from the same paper: a push links
+// its node after the last with a CAS and then swings the tail, which any thread finding the tail
+// behind helps along; a pop swings the head past the dummy. A popped dummy may still be read by
+// threads that loaded the head before, so it is freed through the epoch collector.
+pub struct MsQueue<T> {
+  head: CachePadded<AtomicPtr<MsNode<T>>>, // the dummy; its successor holds the front value
+  tail: CachePadded<AtomicPtr<MsNode<T>>>, // the last node or, for a moment, the one before
+}
+
+unsafe impl<T: Send> Send for MsQueue<T> {}
+unsafe impl<T: Send> Sync for MsQueue<T> {}
+
+impl<T> MsQueue<T> {
+  pub fn new() -> MsQueue<T> {
+    let dummy = MsNode::alloc(MaybeUninit::uninit());
+    MsQueue { head: CachePadded(AtomicPtr::new(dummy)), tail: CachePadded(AtomicPtr::new(dummy)) }
+  }
+
+  pub fn push(&self, value: T) {
+    let node = MsNode::alloc(MaybeUninit::new(value));
+    let _guard = epoch::pin();
+    loop {
+      let tail = self.tail.load(Ordering::Acquire);
+      let next = unsafe { (*tail).next.load(Ordering::Acquire) };
+      if !next.is_null() {
+        let _ = self.tail.compare_exchange(tail, next, Ordering::Release, Ordering::Relaxed);
+      } else if unsafe { (*tail).next.compare_exchange(next, node, Ordering::Release, Ordering::Relaxed) }.is_ok() {
+        let _ = self.tail.compare_exchange(tail, node, Ordering::Release, Ordering::Relaxed);
+        return;
+      }
+    }
+  }
+
+  pub fn pop(&self) -> Option<T> {
+    let guard = epoch::pin();
+    loop {
+      let head = self.head.load(Ordering::Acquire);
+      let next = unsafe { (*head).next.load(Ordering::Acquire) };
+      if next.is_null() {
+        return None;
+      }
+      // the tail must not be left pointing at the node about to be unlinked
+      let tail = self.tail.load(Ordering::Acquire);
+      if tail == head {
+        let _ = self.tail.compare_exchange(tail, next, Ordering::Release, Ordering::Relaxed);
+      }
+      if self.head.compare_exchange(head, next, Ordering::AcqRel, Ordering::Relaxed).is_ok() {
+        // next becomes the dummy, and only the winner of the CAS takes its value
+        let value = unsafe { (*next).value.assume_init_read() };
+        unsafe { guard.defer_destroy_raw(head) };
+        return Some(value);
+      }
+    }
+  }
+
+  // a snapshot, possibly stale by the time it is read
+  pub fn is_empty(&self) -> bool {
+    let _guard = epoch::pin();
+    let head = self.head.load(Ordering::Acquire);
+    unsafe { (*head).next.load(Ordering::Acquire).is_null() }
+  }
+}
+
+impl<T> Default for MsQueue<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T> Drop for MsQueue<T> {
+  fn drop(&mut self) {
+    while self.pop().is_some() {}
+    drop(unsafe { Box::from_raw(*self.head.0.get_mut()) });
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::Arc;
+  use std::thread;
+
+  // producers push (producer, sequence number), consumers collect; every item must arrive once
+  // and each producer's items in order
+  fn stress(push: impl Fn(u64) + Sync, pop: impl Fn() -> Option<u64> + Sync) {
+    const PRODUCERS: u64 = 4;
+    const PER_PRODUCER: u64 = 20_000;
+    let received = Mutex::new(vec![]);
+    let remaining = AtomicUsize::new((PRODUCERS * PER_PRODUCER) as usize);
+    thread::scope(|s| {
+      for p in 0..PRODUCERS {
+        let push = &push;
+        s.spawn(move || (0..PER_PRODUCER).for_each(|i| push(p << 32 | i)));
+      }
+      for _ in 0..4 {
+        s.spawn(|| {
+          let mut mine = vec![];
+          while remaining.load(Ordering::Relaxed) > 0 {
+            match pop() {
+              Some(x) => {
+                mine.push(x);
+                remaining.fetch_sub(1, Ordering::Relaxed);
+              },
+              None => thread::yield_now(),
+            }
+          }
+          let mut last = vec![None; PRODUCERS as usize];
+          for &x in &mine {
+            let (p, i) = ((x >> 32) as usize, x & 0xffff_ffff);
+            assert!(last[p] < Some(i));
+            last[p] = Some(i);
+          }
+          received.lock().unwrap().extend(mine);
+        });
+      }
+    });
+    let mut received = received.into_inner().unwrap();
+    received.sort_unstable();
+    let expected: Vec<u64> = (0..PRODUCERS).flat_map(|p| (0..PER_PRODUCER).map(move |i| p << 32 | i)).collect();
+    assert_eq!(expected, received);
+  }
+
+  #[test]
+  fn test_array_queue() {
+    let q = ArrayQueue::new(3);
+    assert_eq!(4, q.capacity());
+    assert_eq!(None, q.pop());
+    for i in 0..4 {
+      assert_eq!(Ok(()), q.push(i));
+    }
+    assert_eq!(Err(4), q.push(4));
+    assert_eq!(4, q.len());
+    assert_eq!(Some(0), q.pop());
+    assert_eq!(Ok(()), q.push(4));
+    assert_eq!(vec![1, 2, 3, 4], (0..4).map(|_| q.pop().unwrap()).collect::<Vec<_>>());
+    assert!(q.is_empty());
+    let q = ArrayQueue::new(64);
+    stress(|x| while q.push(x).is_err() { thread::yield_now() }, || q.pop());
+  }
+
+  #[test]
+  fn test_linked_queue() {
+    let q = LinkedQueue::new();
+    assert!(q.is_empty() && q.pop().is_none());
+    q.push(1);
+    q.push(2);
+    assert_eq!(Some(1), q.pop());
+    q.push(3);
+    assert_eq!(vec![Some(2), Some(3), None], (0..3).map(|_| q.pop()).collect::<Vec<_>>());
+    let q = LinkedQueue::new();
+    stress(|x| q.push(x), || q.pop());
+  }
+
+  #[test]
+  fn test_ms_queue() {
+    let q = MsQueue::new();
+    assert!(q.is_empty() && q.pop().is_none());
+    q.push(1);
+    q.push(2);
+    assert_eq!(Some(1), q.pop());
+    q.push(3);
+    assert_eq!(vec![Some(2), Some(3), None], (0..3).map(|_| q.pop()).collect::<Vec<_>>());
+    let q = MsQueue::new();
+    stress(|x| q.push(x), || q.pop());
+  }
+
+  #[test]
+  fn test_drops() {
+    let token = Arc::new(());
+    {
+      let a = ArrayQueue::new(8);
+      let l = LinkedQueue::new();
+      let m = MsQueue::new();
+      for _ in 0..5 {
+        a.push(token.clone()).unwrap();
+        l.push(token.clone());
+        m.push(token.clone());
+      }
+      drop(a.pop());
+      drop(l.pop());
+      drop(m.pop());
+      assert_eq!(13, Arc::strong_count(&token));
+    }
+    assert_eq!(1, Arc::strong_count(&token));
+  }
+}
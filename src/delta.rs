@@ -0,0 +1,112 @@
+use crate::string::suffix_array::SuffixArray;
+
+// Binary delta encoding in the spirit of bsdiff, without its approximate matches: the new
+// version is described as copies of ranges of the old one and literal inserts. Matches are found
+// with a suffix array over the old data, greedily taking the longest match at each position.
+
+// matches shorter than this are cheaper as literals
+const MIN_MATCH: usize = 8;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeltaOp {
+  Copy { offset: usize, len: usize },
+  Insert(Vec<u8>),
+}
+
+// a script turning old into new, O(n m log n) worst case for match lengths m
+pub fn diff(old: &[u8], new: &[u8]) -> Vec<DeltaOp> {
+  let sa = SuffixArray::new(old);
+  let mut ops = vec![];
+  let mut literal = vec![];
+  let mut i = 0;
+  while i < new.len() {
+    let (offset, len) = sa.longest_match(&new[i..]);
+    if len >= MIN_MATCH {
+      if !literal.is_empty() {
+        ops.push(DeltaOp::Insert(std::mem::take(&mut literal)));
+      }
+      ops.push(DeltaOp::Copy { offset, len });
+      i += len;
+    } else {
+      literal.push(new[i]);
+      i += 1;
+    }
+  }
+  if !literal.is_empty() {
+    ops.push(DeltaOp::Insert(literal));
+  }
+  ops
+}
+
+// rebuilds the new data, or fails on a copy outside old
+pub fn apply_patch(old: &[u8], ops: &[DeltaOp]) -> Result<Vec<u8>, String> {
+  let mut out = vec![];
+  for op in ops {
+    match op {
+      DeltaOp::Copy { offset, len } => {
+        let range = old.get(*offset..offset.saturating_add(*len));
+        out.extend_from_slice(range.ok_or_else(|| format!("copy of {} bytes at {} outside {} bytes", len, offset, old.len()))?);
+      },
+      DeltaOp::Insert(bytes) => out.extend_from_slice(bytes),
+    }
+  }
+  Ok(out)
+}
+
+// bytes the script carries itself, the part a patch cannot take from old
+pub fn literal_len(ops: &[DeltaOp]) -> usize {
+  ops.iter().map(|op| if let DeltaOp::Insert(bytes) = op { bytes.len() } else { 0 }).sum()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_edit_script() {
+    let old = b"the quick brown fox jumps over the lazy dog".to_vec();
+    let new = b"the quick red fox jumps over the lazy dog again".to_vec();
+    let ops = diff(&old, &new);
+    assert_eq!(Ok(new.clone()), apply_patch(&old, &ops));
+    assert_eq!(
+      vec![
+        DeltaOp::Copy { offset: 0, len: 10 },
+        DeltaOp::Insert(b"red".to_vec()),
+        DeltaOp::Copy { offset: 15, len: 28 },
+        DeltaOp::Insert(b" again".to_vec()),
+      ],
+      ops
+    );
+    assert!(apply_patch(b"short", &[DeltaOp::Copy { offset: 3, len: 5 }]).is_err());
+    assert!(apply_patch(b"abc", &[DeltaOp::Copy { offset: usize::MAX, len: 2 }]).is_err());
+    assert_eq!(vec![DeltaOp::Insert(b"abc".to_vec())], diff(b"", b"abc"));
+    assert!(diff(b"abc", b"").is_empty());
+  }
+
+  #[test]
+  fn test_random_versions() {
+    let mut rand = rand_gen(113);
+    for _ in 0..30 {
+      let old: Vec<u8> = (0..rand(3000)).map(|_| rand(256) as u8).collect();
+      // a few edits: blocks moved around, small insertions and deletions
+      let mut new = vec![];
+      let mut inserted = 0;
+      for _ in 0..rand(8) {
+        if rand(2) == 0 && !old.is_empty() {
+          let start = rand(old.len() as u64) as usize;
+          let end = (start + rand(500) as usize).min(old.len());
+          new.extend_from_slice(&old[start..end]);
+        } else {
+          let len = rand(20) as usize;
+          inserted += len;
+          new.extend((0..len).map(|_| rand(256) as u8));
+        }
+      }
+      let ops = diff(&old, &new);
+      assert_eq!(Ok(new.clone()), apply_patch(&old, &ops));
+      // copied blocks are found again, up to short ones and random bytes matching by chance
+      assert!(literal_len(&ops) <= inserted + 8 * MIN_MATCH * 2);
+    }
+  }
+}
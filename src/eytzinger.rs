@@ -0,0 +1,163 @@
+// Static search layouts for a sorted set of values, looked up far more often than built. A
+// binary search over a sorted slice jumps across it, missing the cache on nearly every step
+// until the end, and branches the way it compares, which is a coin toss to the predictor. Laid
+// out in the order a search visits them, the first levels share a few cache lines, and the step
+// to the next node is arithmetic on the comparison rather than a branch.
+
+// Eytzinger layout: the implicit binary search tree in breadth-first order, node k (counting
+// from 1) having its children at 2k and 2k + 1. A search goes down every level, whatever it
+// meets, and the answer is the last node where it went left.
+pub struct EytzingerVec<T> {
+  tree: Vec<T>, // node k at k - 1
+}
+
+impl<T: Ord + Clone> EytzingerVec<T> {
+  // O(n log n), or O(n) when already sorted; repeats are kept
+  pub fn new(mut values: Vec<T>) -> EytzingerVec<T> {
+    values.sort();
+    let mut tree = values.clone();
+    let mut next = 0;
+    fill(&values, &mut tree, 1, &mut next);
+    EytzingerVec { tree }
+  }
+
+  pub fn len(&self) -> usize {
+    self.tree.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.tree.is_empty()
+  }
+
+  // the least value not less than x, like the value at slice::partition_point(|v| v < x)
+  pub fn lower_bound(&self, x: &T) -> Option<&T> {
+    let n = self.tree.len();
+    let mut k = 1;
+    while k <= n {
+      k = 2 * k + (self.tree[k - 1] < *x) as usize;
+    }
+    // undo the right turns after the last left one, and that one
+    k >>= k.trailing_ones() + 1;
+    if k == 0 { None } else { Some(&self.tree[k - 1]) }
+  }
+
+  pub fn contains(&self, x: &T) -> bool {
+    self.lower_bound(x) == Some(x)
+  }
+}
+
+// the sorted values into the nodes of the subtree at k in order
+fn fill<T: Clone>(sorted: &[T], tree: &mut [T], k: usize, next: &mut usize) {
+  if k <= tree.len() {
+    fill(sorted, tree, 2 * k, next);
+    tree[k - 1] = sorted[*next].clone();
+    *next += 1;
+    fill(sorted, tree, 2 * k + 1, next);
+  }
+}
+
+const B: usize = 16;
+
+// Static B-tree layout (the S-tree): nodes of B values, one or two cache lines, with B + 1
+// children, node k's at k (B + 1) + 1..=k (B + 1) + B + 1, in breadth-first order. A search counts
+// the values below x in each node, which compiles to comparisons without branches, and goes
+// down that child, so only log_(B + 1) n nodes are read. The last node is padded with copies of
+// the greatest value, which come after every real value in search order.
+pub struct StaticBTree<T> {
+  n: usize,
+  nodes: Vec<T>, // node k in k B..(k + 1) B
+}
+
+impl<T: Ord + Clone> StaticBTree<T> {
+  pub fn new(mut values: Vec<T>) -> StaticBTree<T> {
+    values.sort();
+    let n = values.len();
+    let blocks = n.div_ceil(B);
+    let mut nodes = match values.last() {
+      Some(max) => vec![max.clone(); blocks * B],
+      None => vec![],
+    };
+    let mut next = 0;
+    fill_blocks(&values, &mut nodes, 0, blocks, &mut next);
+    StaticBTree { n, nodes }
+  }
+
+  pub fn len(&self) -> usize {
+    self.n
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.n == 0
+  }
+
+  // the least value not less than x
+  pub fn lower_bound(&self, x: &T) -> Option<&T> {
+    let blocks = self.nodes.len() / B;
+    let (mut k, mut found) = (0, None);
+    while k < blocks {
+      let node = &self.nodes[k * B..(k + 1) * B];
+      let i = node.iter().map(|v| (v < x) as usize).sum::<usize>();
+      if i < B {
+        found = Some(&node[i]);
+      }
+      k = k * (B + 1) + i + 1;
+    }
+    found
+  }
+
+  pub fn contains(&self, x: &T) -> bool {
+    self.lower_bound(x) == Some(x)
+  }
+}
+
+fn fill_blocks<T: Clone>(sorted: &[T], nodes: &mut [T], k: usize, blocks: usize, next: &mut usize) {
+  if k >= blocks {
+    return;
+  }
+  for i in 0..B {
+    fill_blocks(sorted, nodes, k * (B + 1) + i + 1, blocks, next);
+    if *next < sorted.len() {
+      nodes[k * B + i] = sorted[*next].clone();
+      *next += 1;
+    }
+  }
+  fill_blocks(sorted, nodes, k * (B + 1) + B + 1, blocks, next);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_small() {
+    let values = vec![9, 2, 7, 2, 5];
+    let (eytzinger, btree) = (EytzingerVec::new(values.clone()), StaticBTree::new(values));
+    for (x, expected) in [(0, Some(2)), (2, Some(2)), (3, Some(5)), (8, Some(9)), (9, Some(9)), (10, None)] {
+      assert_eq!(expected.as_ref(), eytzinger.lower_bound(&x));
+      assert_eq!(expected.as_ref(), btree.lower_bound(&x));
+    }
+    assert!(eytzinger.contains(&7) && !eytzinger.contains(&6) && btree.contains(&7) && !btree.contains(&6));
+    assert_eq!((5, 5), (eytzinger.len(), btree.len()));
+    assert!(EytzingerVec::<u8>::new(vec![]).lower_bound(&0).is_none());
+    assert!(StaticBTree::<u8>::new(vec![]).lower_bound(&0).is_none());
+  }
+
+  #[test]
+  fn test_random_against_partition_point() {
+    let mut rand = rand_gen(367);
+    for round in 0..200 {
+      let n = if round % 20 == 0 { 5000 } else { round };
+      let range = 1 + rand(3 * n as u64 + 1);
+      let mut values: Vec<u64> = (0..n).map(|_| rand(range)).collect();
+      let (eytzinger, btree) = (EytzingerVec::new(values.clone()), StaticBTree::new(values.clone()));
+      values.sort_unstable();
+      for _ in 0..50 {
+        let x = rand(range + 2);
+        let expected = values.get(values.partition_point(|&v| v < x));
+        assert_eq!(expected, eytzinger.lower_bound(&x));
+        assert_eq!(expected, btree.lower_bound(&x));
+      }
+    }
+  }
+}
@@ -0,0 +1,347 @@
+use std::ops::{Add, Range, Sub};
+
+// Fenwick tree (binary indexed tree): point additions and prefix sums, both O(log n), in n
+// slots and a few instructions per level. Slot i, counting from 1, holds the sum of the
+// lowbit(i) positions ending at i, so a prefix is the sum of the slots met clearing the lowest
+// bit of its length, and an addition at i reaches the slots met adding it. Only sums: for other
+// monoids there is the segment tree. T::default() is taken as zero.
+pub struct Fenwick<T> {
+  tree: Vec<T>, // tree[0] unused
+}
+
+impl<T: Copy + Default + Add<Output = T> + Sub<Output = T>> Fenwick<T> {
+  // n zeros
+  pub fn new(n: usize) -> Fenwick<T> {
+    Fenwick { tree: vec![T::default(); n + 1] }
+  }
+
+  // O(n), each slot passing its sum on to the next slot covering it
+  pub fn from_slice(values: &[T]) -> Fenwick<T> {
+    let n = values.len();
+    let mut tree = vec![T::default(); n + 1];
+    tree[1..].copy_from_slice(values);
+    for i in 1..=n {
+      let j = i + (i & i.wrapping_neg());
+      if j <= n {
+        tree[j] = tree[j] + tree[i];
+      }
+    }
+    Fenwick { tree }
+  }
+
+  pub fn len(&self) -> usize {
+    self.tree.len() - 1
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  // adds delta to position i
+  pub fn add(&mut self, i: usize, delta: T) {
+    assert!(i < self.len());
+    let mut i = i + 1;
+    while i < self.tree.len() {
+      self.tree[i] = self.tree[i] + delta;
+      i += i & i.wrapping_neg();
+    }
+  }
+
+  // the sum of the first i positions
+  pub fn prefix_sum(&self, i: usize) -> T {
+    assert!(i <= self.len());
+    let mut i = i;
+    let mut sum = T::default();
+    while i > 0 {
+      sum = sum + self.tree[i];
+      i &= i - 1;
+    }
+    sum
+  }
+
+  pub fn range_sum(&self, range: Range<usize>) -> T {
+    assert!(range.start <= range.end);
+    self.prefix_sum(range.end) - self.prefix_sum(range.start)
+  }
+
+  pub fn get(&self, i: usize) -> T {
+    self.range_sum(i..i + 1)
+  }
+}
+
+impl<T: Copy + Default + Add<Output = T> + Sub<Output = T> + PartialOrd> Fenwick<T> {
+  // the first position i where the sum of 0..=i reaches target, len() if the total falls short,
+  // in one descent of O(log n); the values must not be negative, so the prefix sums only grow.
+  // With counts, the position of the element of rank k is lower_bound(k + 1).
+  pub fn lower_bound(&self, target: T) -> usize {
+    let n = self.len();
+    let mut step = if n == 0 { 0 } else { 1 << (usize::BITS - 1 - n.leading_zeros()) };
+    let (mut pos, mut rest) = (0, target);
+    while step > 0 {
+      if pos + step <= n && self.tree[pos + step] < rest {
+        pos += step;
+        rest = rest - self.tree[pos];
+      }
+      step >>= 1;
+    }
+    pos
+  }
+}
+
+// The dual form: an addition to a whole range and the value at one position, both O(log n).
+// A Fenwick tree over the differences between neighbours, whose prefix sums are the values;
+// those go negative, so T must be signed.
+pub struct RangeAddFenwick<T> {
+  diff: Fenwick<T>, // one past the end, so a range may reach the last position
+}
+
+impl<T: Copy + Default + Add<Output = T> + Sub<Output = T>> RangeAddFenwick<T> {
+  pub fn new(n: usize) -> RangeAddFenwick<T> {
+    RangeAddFenwick { diff: Fenwick::new(n + 1) }
+  }
+
+  pub fn from_slice(values: &[T]) -> RangeAddFenwick<T> {
+    let mut diff = vec![T::default(); values.len() + 1];
+    for (i, &x) in values.iter().enumerate() {
+      diff[i] = diff[i] + x;
+      diff[i + 1] = diff[i + 1] - x;
+    }
+    RangeAddFenwick { diff: Fenwick::from_slice(&diff) }
+  }
+
+  pub fn len(&self) -> usize {
+    self.diff.len() - 1
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  // adds delta to every position in range
+  pub fn add_range(&mut self, range: Range<usize>, delta: T) {
+    assert!(range.start <= range.end && range.end <= self.len());
+    if range.start < range.end {
+      self.diff.add(range.start, delta);
+      self.diff.add(range.end, T::default() - delta);
+    }
+  }
+
+  pub fn get(&self, i: usize) -> T {
+    assert!(i < self.len());
+    self.diff.prefix_sum(i + 1)
+  }
+}
+
+// Fenwick tree over a grid: point additions and sums of rectangles, both O(log rows log cols).
+// A Fenwick tree over the rows whose slots are Fenwick trees over the columns, slot i holding the
+// sums of the lowbit(i) rows ending at i.
+pub struct Fenwick2D<T> {
+  cols: usize,
+  rows: Vec<Fenwick<T>>, // rows[i] is slot i + 1
+}
+
+impl<T: Copy + Default + Add<Output = T> + Sub<Output = T>> Fenwick2D<T> {
+  // a rows by cols grid of zeros
+  pub fn new(rows: usize, cols: usize) -> Fenwick2D<T> {
+    Fenwick2D { cols, rows: (0..rows).map(|_| Fenwick::new(cols)).collect() }
+  }
+
+  pub fn rows(&self) -> usize {
+    self.rows.len()
+  }
+
+  pub fn cols(&self) -> usize {
+    self.cols
+  }
+
+  // adds delta at row r, column c
+  pub fn add(&mut self, r: usize, c: usize, delta: T) {
+    assert!(r < self.rows() && c < self.cols);
+    let mut i = r + 1;
+    while i <= self.rows() {
+      self.rows[i - 1].add(c, delta);
+      i += i & i.wrapping_neg();
+    }
+  }
+
+  // the sum of the first r rows by the first c columns
+  pub fn prefix_sum(&self, r: usize, c: usize) -> T {
+    assert!(r <= self.rows() && c <= self.cols);
+    let mut i = r;
+    let mut sum = T::default();
+    while i > 0 {
+      sum = sum + self.rows[i - 1].prefix_sum(c);
+      i &= i - 1;
+    }
+    sum
+  }
+
+  pub fn rect_sum(&self, rows: Range<usize>, cols: Range<usize>) -> T {
+    assert!(rows.start <= rows.end && cols.start <= cols.end);
+    // in this order, so an unsigned T never goes below zero
+    self.prefix_sum(rows.end, cols.end) + self.prefix_sum(rows.start, cols.start) - self.prefix_sum(rows.start, cols.end) - self.prefix_sum(rows.end, cols.start)
+  }
+}
+
+// The same over a sparse set of points with any coordinates, all given up front: only they can
+// be added to, and the rectangles may be anywhere. The rows are the distinct x, compressed, and
+// the slot of a row range keeps only the y of the points in it, so n points take O(n log n)
+// space rather than a grid.
+pub struct SparseFenwick2D<T> {
+  xs: Vec<i64>,
+  ys: Vec<Vec<i64>>, // the distinct y in slot i + 1, in order
+  rows: Vec<Fenwick<T>>,
+}
+
+impl<T: Copy + Default + Add<Output = T> + Sub<Output = T>> SparseFenwick2D<T> {
+  // zeros at the given points, (x, y), repeats allowed; O(n log n)
+  pub fn new(points: &[(i64, i64)]) -> SparseFenwick2D<T> {
+    let mut xs: Vec<i64> = points.iter().map(|p| p.0).collect();
+    xs.sort_unstable();
+    xs.dedup();
+    let mut ys = vec![vec![]; xs.len()];
+    for &(x, y) in points {
+      let mut i = xs.partition_point(|&v| v < x) + 1;
+      while i <= xs.len() {
+        ys[i - 1].push(y);
+        i += i & i.wrapping_neg();
+      }
+    }
+    for column in &mut ys {
+      column.sort_unstable();
+      column.dedup();
+    }
+    let rows = ys.iter().map(|column| Fenwick::new(column.len())).collect();
+    SparseFenwick2D { xs, ys, rows }
+  }
+
+  // adds delta at (x, y), one of the points given
+  pub fn add(&mut self, x: i64, y: i64, delta: T) {
+    let mut i = self.xs.binary_search(&x).expect("not one of the points") + 1;
+    while i <= self.xs.len() {
+      let j = self.ys[i - 1].binary_search(&y).expect("not one of the points");
+      self.rows[i - 1].add(j, delta);
+      i += i & i.wrapping_neg();
+    }
+  }
+
+  // the sum over the points with x' < x and y' < y
+  pub fn prefix_sum(&self, x: i64, y: i64) -> T {
+    let mut i = self.xs.partition_point(|&v| v < x);
+    let mut sum = T::default();
+    while i > 0 {
+      sum = sum + self.rows[i - 1].prefix_sum(self.ys[i - 1].partition_point(|&v| v < y));
+      i &= i - 1;
+    }
+    sum
+  }
+
+  // the sum over the points in xs by ys
+  pub fn rect_sum(&self, xs: Range<i64>, ys: Range<i64>) -> T {
+    assert!(xs.start <= xs.end && ys.start <= ys.end);
+    // in this order, so an unsigned T never goes below zero
+    self.prefix_sum(xs.end, ys.end) + self.prefix_sum(xs.start, ys.start) - self.prefix_sum(xs.start, ys.end) - self.prefix_sum(xs.end, ys.start)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_sums() {
+    let mut tree = Fenwick::from_slice(&[5i64, 3, 8, 6, 2, 7]);
+    assert_eq!((0, 16, 31), (tree.prefix_sum(0), tree.prefix_sum(3), tree.prefix_sum(6)));
+    tree.add(2, -10);
+    assert_eq!((7, -2, 6), (tree.range_sum(1..4), tree.get(2), tree.len()));
+    let counts = Fenwick::from_slice(&[0u32, 2, 0, 1, 3]);
+    // the elements in order are 1, 1, 3, 4, 4, 4
+    let ranks: Vec<usize> = (0..7).map(|k| counts.lower_bound(k + 1)).collect();
+    assert_eq!(vec![1, 1, 3, 4, 4, 4, 5], ranks);
+    assert_eq!(0, counts.lower_bound(0));
+    let mut ranges = RangeAddFenwick::from_slice(&[1.5, 2.0, -1.0]);
+    ranges.add_range(1..3, 0.5);
+    ranges.add_range(0..0, 100.0);
+    assert_eq!((1.5, 2.5, -0.5), (ranges.get(0), ranges.get(1), ranges.get(2)));
+    assert!(Fenwick::<u8>::new(0).is_empty() && RangeAddFenwick::<u8>::new(0).is_empty());
+    assert_eq!(0, Fenwick::<u8>::new(0).lower_bound(1));
+  }
+
+  #[test]
+  fn test_random_against_naive() {
+    let mut rand = rand_gen(349);
+    for round in 0..100 {
+      let n = if round % 10 == 0 { 1000 } else { rand(50) as usize };
+      let mut counts: Vec<u64> = (0..n).map(|_| rand(5)).collect();
+      let mut tree = if round % 2 == 0 { Fenwick::from_slice(&counts) } else { Fenwick::new(n) };
+      if round % 2 == 1 {
+        counts.iter().enumerate().for_each(|(i, &c)| tree.add(i, c));
+      }
+      let mut values: Vec<i64> = (0..n).map(|_| rand(100) as i64 - 50).collect();
+      let mut ranges = RangeAddFenwick::from_slice(&values);
+      for _ in 0..100 {
+        let l = rand(n as u64 + 1) as usize;
+        let r = l + rand((n - l) as u64 + 1) as usize;
+        if l < n && rand(2) == 0 {
+          let c = rand(5);
+          tree.add(l, c);
+          counts[l] += c;
+        }
+        let d = rand(21) as i64 - 10;
+        ranges.add_range(l..r, d);
+        values[l..r].iter_mut().for_each(|x| *x += d);
+        assert_eq!(counts[l..r].iter().sum::<u64>(), tree.range_sum(l..r));
+        let target = rand(counts.iter().sum::<u64>() + 2);
+        let expected = (0..n).find(|&i| counts[..=i].iter().sum::<u64>() >= target).unwrap_or(n);
+        assert_eq!(expected, tree.lower_bound(target));
+        if n > 0 {
+          let i = rand(n as u64) as usize;
+          assert_eq!(values[i], ranges.get(i));
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn test_grid_against_naive() {
+    let mut rand = rand_gen(359);
+    for _ in 0..50 {
+      let (rows, cols) = (rand(12) as usize, rand(12) as usize);
+      let mut grid = vec![vec![0i64; cols]; rows];
+      let mut tree = Fenwick2D::new(rows, cols);
+      let points: Vec<(i64, i64)> = (0..rand(40)).map(|_| (rand(2001) as i64 - 1000, rand(2001) as i64 - 1000)).collect();
+      let mut weights = vec![0i64; points.len()];
+      let mut sparse = SparseFenwick2D::new(&points);
+      for _ in 0..100 {
+        if rows > 0 && cols > 0 {
+          let (r, c, d) = (rand(rows as u64) as usize, rand(cols as u64) as usize, rand(21) as i64 - 10);
+          grid[r][c] += d;
+          tree.add(r, c, d);
+        }
+        if !points.is_empty() {
+          let (i, d) = (rand(points.len() as u64) as usize, rand(21) as i64 - 10);
+          weights[i] += d;
+          sparse.add(points[i].0, points[i].1, d);
+        }
+        let r0 = rand(rows as u64 + 1) as usize;
+        let r1 = r0 + rand((rows - r0) as u64 + 1) as usize;
+        let c0 = rand(cols as u64 + 1) as usize;
+        let c1 = c0 + rand((cols - c0) as u64 + 1) as usize;
+        let expected: i64 = grid[r0..r1].iter().map(|row| row[c0..c1].iter().sum::<i64>()).sum();
+        assert_eq!(expected, tree.rect_sum(r0..r1, c0..c1));
+        let x0 = rand(2201) as i64 - 1100;
+        let x1 = x0 + rand(1500) as i64;
+        let y0 = rand(2201) as i64 - 1100;
+        let y1 = y0 + rand(1500) as i64;
+        let inside = |&(x, y): &(i64, i64)| x0 <= x && x < x1 && y0 <= y && y < y1;
+        let expected: i64 = points.iter().zip(&weights).filter(|(p, _)| inside(p)).map(|(_, w)| w).sum();
+        assert_eq!(expected, sparse.rect_sum(x0..x1, y0..y1));
+      }
+    }
+    let mut tree = Fenwick2D::new(3, 4);
+    tree.add(1, 2, 5u32);
+    tree.add(2, 3, 7);
+    assert_eq!((12, 5, 0, 3, 4), (tree.prefix_sum(3, 4), tree.rect_sum(0..2, 1..3), tree.rect_sum(2..3, 0..3), tree.rows(), tree.cols()));
+  }
+}
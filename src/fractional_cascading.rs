@@ -0,0 +1,125 @@
+struct Level<T> {
+  merged: Vec<T>,
+  own: Vec<usize>,  // where each merged value, and the end, falls in this level's list
+  down: Vec<usize>, // the same in the next level's merged values
+}
+
+// Fractional cascading (Chazelle & Guibas): the position of x in each of k sorted lists with one
+// binary search and O(1) per list after it, O(log n + k) rather than O(k log n). Every level
+// merges its list with every second value of the next level's merged values, at most twice the
+// total length in all, and each merged value keeps where it would go in its own list and
+// in the next level. The position found in one level is at most a step or two past the one in
+// the next level, and walking back those steps finds it.
+pub struct FractionalCascading<T> {
+  levels: Vec<Level<T>>,
+}
+
+// for each value of a, in order, how many values of b are less than it, and b.len() at the end
+fn ranks<T: Ord>(a: &[T], b: &[T]) -> Vec<usize> {
+  let mut j = 0;
+  let mut ranks = Vec::with_capacity(a.len() + 1);
+  for x in a {
+    while j < b.len() && b[j] < *x {
+      j += 1;
+    }
+    ranks.push(j);
+  }
+  ranks.push(b.len());
+  ranks
+}
+
+impl<T: Ord + Clone> FractionalCascading<T> {
+  // the lists must each be sorted; O(total length)
+  pub fn new(lists: &[Vec<T>]) -> FractionalCascading<T> {
+    let mut levels: Vec<Level<T>> = Vec::with_capacity(lists.len());
+    for list in lists.iter().rev() {
+      assert!(list.windows(2).all(|w| w[0] <= w[1]));
+      let promoted: Vec<T> = levels.last().map_or(vec![], |next| next.merged.iter().skip(1).step_by(2).cloned().collect());
+      let mut merged = Vec::with_capacity(list.len() + promoted.len());
+      let (mut i, mut j) = (0, 0);
+      while i < list.len() || j < promoted.len() {
+        if j == promoted.len() || (i < list.len() && list[i] <= promoted[j]) {
+          merged.push(list[i].clone());
+          i += 1;
+        } else {
+          merged.push(promoted[j].clone());
+          j += 1;
+        }
+      }
+      let own = ranks(&merged, list);
+      let down = levels.last().map_or(vec![], |next| ranks(&merged, &next.merged));
+      levels.push(Level { merged, own, down });
+    }
+    levels.reverse();
+    FractionalCascading { levels }
+  }
+
+  // the number of lists
+  pub fn len(&self) -> usize {
+    self.levels.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.levels.is_empty()
+  }
+
+  // for each list, the number of its values less than x, where x would go in it
+  pub fn lower_bounds(&self, x: &T) -> Vec<usize> {
+    let mut found = Vec::with_capacity(self.levels.len());
+    let mut p = match self.levels.first() {
+      Some(first) => first.merged.partition_point(|v| v < x),
+      None => return found,
+    };
+    for (l, level) in self.levels.iter().enumerate() {
+      found.push(level.own[p]);
+      if let Some(next) = self.levels.get(l + 1) {
+        p = level.down[p];
+        while p > 0 && next.merged[p - 1] >= *x {
+          p -= 1;
+        }
+      }
+    }
+    found
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_lists() {
+    let lists = vec![vec![2, 4, 6, 8], vec![], vec![1, 3, 3, 3, 9], vec![5]];
+    let cascade = FractionalCascading::new(&lists);
+    assert_eq!(4, cascade.len());
+    assert_eq!(vec![1, 0, 1, 0], cascade.lower_bounds(&3));
+    assert_eq!(vec![4, 0, 4, 1], cascade.lower_bounds(&9));
+    assert_eq!(vec![4, 0, 5, 1], cascade.lower_bounds(&10));
+    assert_eq!(vec![0, 0, 0, 0], cascade.lower_bounds(&0));
+    assert!(FractionalCascading::<u8>::new(&[]).lower_bounds(&1).is_empty());
+  }
+
+  #[test]
+  fn test_random_against_binary_search() {
+    let mut rand = rand_gen(373);
+    for round in 0..100 {
+      let k = rand(12) as usize;
+      let range = 1 + rand(200);
+      let lists: Vec<Vec<u64>> = (0..k)
+        .map(|_| {
+          let len = if round % 10 == 0 { rand(1000) } else { rand(20) };
+          let mut list: Vec<u64> = (0..len).map(|_| rand(range)).collect();
+          list.sort_unstable();
+          list
+        })
+        .collect();
+      let cascade = FractionalCascading::new(&lists);
+      for _ in 0..50 {
+        let x = rand(range + 2);
+        let expected: Vec<usize> = lists.iter().map(|list| list.partition_point(|&v| v < x)).collect();
+        assert_eq!(expected, cascade.lower_bounds(&x));
+      }
+    }
+  }
+}
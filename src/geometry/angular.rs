@@ -0,0 +1,109 @@
+use std::cmp::Ordering;
+
+use super::exact::sign_of_det2;
+use super::point::Point;
+
+// 0 for the zero vector, 1 for angles in [0, pi), 2 for [pi, 2 pi)
+fn half(x: i128, y: i128) -> u8 {
+  if x == 0 && y == 0 {
+    0
+  } else if y > 0 || y == 0 && x > 0 {
+    1
+  } else {
+    2
+  }
+}
+
+fn compare_direction((ax, ay): (i128, i128), (bx, by): (i128, i128)) -> Ordering {
+  half(ax, ay).cmp(&half(bx, by)).then_with(|| 0.cmp(&sign_of_det2(ax, ay, bx, by)))
+}
+
+// Directions ordered by their angle counterclockwise from the positive x axis, in [0, 2 pi),
+// without atan2: first by half-plane, then by the sign of the cross product, which within a half
+// plane is exact. Vectors pointing the same way are equal, and the zero vector comes first.
+pub fn compare_angle(a: Point, b: Point) -> Ordering {
+  compare_direction((a.x as i128, a.y as i128), (b.x as i128, b.y as i128))
+}
+
+// sorts points by their direction seen from center, counterclockwise from the positive x axis
+pub fn sort_around(points: &mut [Point], center: Point) {
+  points.sort_by(|&a, &b| compare_angle(a - center, b - center));
+}
+
+// the angle turned counterclockwise from u to v, as a vector with that angle
+fn turn(u: Point, v: Point) -> (i128, i128) {
+  (u.dot(v), u.cross(v))
+}
+
+// The most points seen from center within one angle: a cone with its apex at center, as wide
+// as the angle of the vector width counterclockwise from the positive x axis, edges included,
+// turned to cover as many points as possible. Points at center are always seen. An angular
+// sweep: with the other points sorted around center, a cone starting at each direction in turn
+// reaches a window of the sorted order twice over, which only moves forward. Exact as long as
+// coordinates stay below 2^62 in absolute value, so the turns between directions fit an i128.
+// O(n log n).
+pub fn max_points_in_angle(center: Point, points: &[Point], width: Point) -> usize {
+  let width = (width.x as i128, width.y as i128);
+  let mut around: Vec<Point> = points.iter().map(|&p| p - center).filter(|&p| p != Point::default()).collect();
+  let at_center = points.len() - around.len();
+  around.sort_by(|&a, &b| compare_angle(a, b));
+  let n = around.len();
+  let mut best = 0;
+  let mut j = 0;
+  for i in 0..n {
+    // cones start at the first of the points in one direction
+    if i > 0 && compare_angle(around[i - 1], around[i]) == Ordering::Equal {
+      continue;
+    }
+    j = j.max(i);
+    while j < i + n && compare_direction(turn(around[i], around[j % n]), width) != Ordering::Greater {
+      j += 1;
+    }
+    best = best.max(j - i);
+  }
+  best + at_center
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_sort_around() {
+    let mut points: Vec<Point> = [(1, 0), (0, -1), (-1, -1), (1, 1), (-1, 0), (2, 2), (0, 1), (1, -1)].iter().map(|&(x, y)| Point::new(x, y)).collect();
+    sort_around(&mut points, Point::new(0, 0));
+    let expected: Vec<Point> = [(1, 0), (1, 1), (2, 2), (0, 1), (-1, 0), (-1, -1), (0, -1), (1, -1)].iter().map(|&(x, y)| Point::new(x, y)).collect();
+    // (1, 1) and (2, 2) point the same way, in either order
+    assert_eq!(Ordering::Equal, compare_angle(points[1], points[2]));
+    assert_eq!(expected[0], points[0]);
+    assert_eq!(expected[3..], points[3..]);
+    assert_eq!(Ordering::Less, compare_angle(Point::new(0, 0), Point::new(1, 0)));
+    assert_eq!(Ordering::Greater, compare_angle(Point::new(1, -1), Point::new(-1, 0)));
+  }
+
+  #[test]
+  fn test_max_points_in_angle() {
+    let points: Vec<Point> = [(2, 1), (2, 2), (3, 3), (1, 1)].iter().map(|&(x, y)| Point::new(x, y)).collect();
+    // 90 degrees from (1, 1): everything
+    assert_eq!(4, max_points_in_angle(Point::new(1, 1), &points, Point::new(0, 1)));
+    // a zero-width cone along the diagonal
+    assert_eq!(3, max_points_in_angle(Point::new(1, 1), &points, Point::new(1, 0)));
+    assert_eq!(0, max_points_in_angle(Point::new(1, 1), &[], Point::new(0, 1)));
+
+    let mut rand = rand_gen(257);
+    for _ in 0..300 {
+      let points: Vec<Point> = (0..rand(30)).map(|_| Point::new(rand(9) as i64 - 4, rand(9) as i64 - 4)).collect();
+      let center = Point::new(rand(5) as i64 - 2, rand(5) as i64 - 2);
+      let width = Point::new(rand(9) as i64 - 4, rand(9) as i64 - 4);
+      let width = if width == Point::default() { Point::new(1, 0) } else { width };
+      let w = (width.x as i128, width.y as i128);
+      // every cone starting at a point, counted directly
+      let at_center = points.iter().filter(|&&p| p == center).count();
+      let best = points.iter().filter(|&&p| p != center).map(|&p| {
+        points.iter().filter(|&&q| q != center && compare_direction(turn(p - center, q - center), w) != Ordering::Greater).count()
+      }).max().unwrap_or(0);
+      assert_eq!(best + at_center, max_points_in_angle(center, &points, width));
+    }
+  }
+}
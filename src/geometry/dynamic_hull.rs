@@ -0,0 +1,389 @@
+use super::point::{orientation, Point};
+
+// A node of a chain: a leaf holds one point, an internal node the points of both children, all
+// of the left child's before the right child's
+struct Node {
+  size: usize,
+  max: Point, // the largest point below
+  // the bridge: where the chain of the node leaves the left child's chain and joins the right
+  // child's, or a leaf's point twice
+  bridge: (Point, Point),
+  children: Option<Box<(Node, Node)>>,
+}
+
+impl Node {
+  fn leaf(p: Point) -> Node {
+    Node { size: 1, max: p, bridge: (p, p), children: None }
+  }
+
+  fn join(left: Node, right: Node) -> Node {
+    let mut node = Node { size: 0, max: right.max, bridge: (right.max, right.max), children: Some(Box::new((left, right))) };
+    node.update();
+    node
+  }
+
+  fn update(&mut self) {
+    let (left, right) = &**self.children.as_ref().unwrap();
+    self.size = left.size + right.size;
+    self.max = right.max;
+    self.bridge = bridge(left, right);
+  }
+
+  fn min(&self) -> Point {
+    let mut u = self;
+    while let Some(children) = &u.children {
+      u = &children.0;
+    }
+    u.max
+  }
+}
+
+// the point of u's chain an upper tangent from p touches, p before every point below u; the
+// rightmost one if several lie on the tangent
+fn tangent_from_left(mut u: &Node, p: Point) -> Point {
+  while let Some(children) = &u.children {
+    let (a, b) = u.bridge;
+    // p on or above the line ab: b does better than the whole left chain, else a than the right
+    u = if orientation(a, b, p) >= 0 { &children.1 } else { &children.0 };
+  }
+  u.max
+}
+
+// The upper common tangent of the chains of two nodes, all of left's points before right's,
+// as the leftmost point of left on it and the rightmost of right. Its left end is found
+// descending in left: at a node whose bridge is cd, it lies at or before c unless d is above the
+// tangent from c to right. O(log^2 n).
+fn bridge(left: &Node, right: &Node) -> (Point, Point) {
+  let mut u = left;
+  while let Some(children) = &u.children {
+    let (c, d) = u.bridge;
+    let t = tangent_from_left(right, c);
+    u = if orientation(c, t, d) > 0 { &children.1 } else { &children.0 };
+  }
+  (u.max, tangent_from_left(right, u.max))
+}
+
+fn build(points: &[Point]) -> Node {
+  if points.len() == 1 {
+    return Node::leaf(points[0]);
+  }
+  let (left, right) = points.split_at(points.len() / 2);
+  Node::join(build(left), build(right))
+}
+
+fn collect_points(u: &Node, out: &mut Vec<Point>) {
+  match &u.children {
+    None => out.push(u.max),
+    Some(children) => {
+      collect_points(&children.0, out);
+      collect_points(&children.1, out);
+    },
+  }
+}
+
+// rebuilds u perfectly balanced if one child holds more than three quarters of its points
+fn rebalance(u: &mut Node) {
+  let (left, right) = &**u.children.as_ref().unwrap();
+  if 4 * left.size.max(right.size) > 3 * u.size && u.size > 2 {
+    let mut points = Vec::with_capacity(u.size);
+    collect_points(u, &mut points);
+    *u = build(&points);
+  }
+}
+
+fn insert(u: &mut Node, p: Point) -> bool {
+  match &mut u.children {
+    None => {
+      if u.max == p {
+        return false;
+      }
+      let (a, b) = (p.min(u.max), p.max(u.max));
+      *u = Node::join(Node::leaf(a), Node::leaf(b));
+      true
+    },
+    Some(children) => {
+      let child = if p <= children.0.max { &mut children.0 } else { &mut children.1 };
+      if !insert(child, p) {
+        return false;
+      }
+      u.update();
+      rebalance(u);
+      true
+    },
+  }
+}
+
+// u must be internal
+fn remove(u: &mut Node, p: Point) -> bool {
+  let children = u.children.as_mut().unwrap();
+  let left = p <= children.0.max;
+  let child = if left { &mut children.0 } else { &mut children.1 };
+  if child.children.is_none() {
+    if child.max != p {
+      return false;
+    }
+    // the sibling takes u's place
+    let (l, r) = *u.children.take().unwrap();
+    *u = if left { r } else { l };
+    return true;
+  }
+  if !remove(child, p) {
+    return false;
+  }
+  u.update();
+  rebalance(u);
+  true
+}
+
+// The upper chain of a point set: the hull from its smallest point to its largest over the top,
+// in a weight-balanced tree of the points in order (Overmars & van Leeuwen). Every internal node
+// keeps only the bridge joining its children's chains, so its own chain is the left one up to
+// the bridge and the right one after it, and queries follow bridges down. Points sharing an x
+// are ordered by y, as if sheared by an infinitesimal, which no orientation test can tell apart.
+struct Chain {
+  root: Option<Node>,
+}
+
+impl Chain {
+  fn insert(&mut self, p: Point) -> bool {
+    match &mut self.root {
+      None => {
+        self.root = Some(Node::leaf(p));
+        true
+      },
+      Some(root) => insert(root, p),
+    }
+  }
+
+  fn remove(&mut self, p: Point) -> bool {
+    match &mut self.root {
+      Some(root) if root.children.is_none() => {
+        if root.max != p {
+          return false;
+        }
+        self.root = None;
+        true
+      },
+      Some(root) => remove(root, p),
+      None => false,
+    }
+  }
+
+  // whether p lies on or below the chain, between its ends
+  fn below(&self, p: Point) -> bool {
+    let mut u = match &self.root {
+      Some(root) if root.min() <= p && p <= root.max => root,
+      _ => return false,
+    };
+    while let Some(children) = &u.children {
+      let (a, b) = u.bridge;
+      if p <= a {
+        u = &children.0;
+      } else if p >= b {
+        u = &children.1;
+      } else {
+        return orientation(a, b, p) <= 0;
+      }
+    }
+    u.max == p
+  }
+
+  // the chain's point maximizing the dot product with direction, where the chain holds the
+  // maximum: direction.y > 0, or direction.y == 0 and direction.x < 0
+  fn extreme(&self, direction: Point) -> Option<Point> {
+    let mut u = self.root.as_ref()?;
+    while let Some(children) = &u.children {
+      let (a, b) = u.bridge;
+      u = if direction.dot(b) > direction.dot(a) { &children.1 } else { &children.0 };
+    }
+    Some(u.max)
+  }
+
+  // the chain's vertices in order
+  fn vertices(&self) -> Vec<Point> {
+    fn walk(u: &Node, lo: Point, hi: Point, out: &mut Vec<Point>) {
+      if lo > hi {
+        return;
+      }
+      match &u.children {
+        None => {
+          if lo <= u.max && u.max <= hi {
+            out.push(u.max);
+          }
+        },
+        Some(children) => {
+          let (a, b) = u.bridge;
+          walk(&children.0, lo, hi.min(a), out);
+          walk(&children.1, lo.max(b), hi, out);
+        },
+      }
+    }
+    let mut out = vec![];
+    if let Some(root) = &self.root {
+      walk(root, root.min(), root.max, &mut out);
+    }
+    out
+  }
+}
+
+// Fully dynamic planar convex hull: points come and go, and the hull can be listed, tested for
+// containment or asked for its vertex extreme in a direction, that is where a tangent of that
+// slope touches it, the query of an online convex hull trick. The upper chain and the lower one,
+// kept as the upper chain of the points negated, are each an Overmars-van Leeuwen tree; an update
+// recomputes the O(log n) bridges above its leaf at O(log^2 n) each, O(log^3 n) in all, and a
+// query follows one path, O(log n).
+pub struct DynamicHull {
+  upper: Chain,
+  lower: Chain, // of the negated points
+  len: usize,
+}
+
+impl DynamicHull {
+  pub fn new() -> DynamicHull {
+    DynamicHull { upper: Chain { root: None }, lower: Chain { root: None }, len: 0 }
+  }
+
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  // false if p is already there
+  pub fn insert(&mut self, p: Point) -> bool {
+    if !self.upper.insert(p) {
+      return false;
+    }
+    self.lower.insert(-p);
+    self.len += 1;
+    true
+  }
+
+  // false if p was not there
+  pub fn remove(&mut self, p: Point) -> bool {
+    if !self.upper.remove(p) {
+      return false;
+    }
+    self.lower.remove(-p);
+    self.len -= 1;
+    true
+  }
+
+  // whether p lies inside the hull or on its boundary
+  pub fn contains(&self, p: Point) -> bool {
+    self.upper.below(p) && self.lower.below(-p)
+  }
+
+  // the hull's vertices counterclockwise from the smallest point, without collinear ones
+  pub fn hull(&self) -> Vec<Point> {
+    let mut hull: Vec<Point> = self.lower.vertices().into_iter().rev().map(|p| -p).collect();
+    let upper = self.upper.vertices();
+    if upper.len() > 2 {
+      hull.extend(upper[1..upper.len() - 1].iter().rev());
+    }
+    hull
+  }
+
+  // a point maximizing the dot product with direction, None if there are none
+  pub fn extreme(&self, direction: Point) -> Option<Point> {
+    if direction.y > 0 || direction.y == 0 && direction.x <= 0 {
+      self.upper.extreme(direction)
+    } else {
+      self.lower.extreme(-direction).map(|p| -p)
+    }
+  }
+}
+
+impl Default for DynamicHull {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+  use std::collections::BTreeSet;
+
+  // Andrew's monotone chain, counterclockwise from the smallest point
+  fn naive_hull(points: &BTreeSet<Point>) -> Vec<Point> {
+    let points: Vec<Point> = points.iter().copied().collect();
+    if points.len() <= 1 {
+      return points;
+    }
+    let mut hull: Vec<Point> = vec![];
+    for pass in 0..2 {
+      let start = hull.len();
+      let ordered: Vec<Point> = if pass == 0 { points.clone() } else { points.iter().rev().copied().collect() };
+      for p in ordered {
+        while hull.len() >= start + 2 && orientation(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0 {
+          hull.pop();
+        }
+        hull.push(p);
+      }
+      hull.pop();
+    }
+    hull
+  }
+
+  #[test]
+  fn test_square() {
+    let mut h = DynamicHull::new();
+    for (x, y) in [(0, 0), (2, 0), (2, 2), (0, 2), (1, 1), (1, 0)] {
+      assert!(h.insert(Point::new(x, y)));
+    }
+    assert!(!h.insert(Point::new(1, 1)));
+    assert_eq!(6, h.len());
+    assert_eq!(vec![Point::new(0, 0), Point::new(2, 0), Point::new(2, 2), Point::new(0, 2)], h.hull());
+    assert!(h.contains(Point::new(1, 2)) && h.contains(Point::new(1, 1)) && !h.contains(Point::new(3, 1)));
+    assert_eq!(Some(Point::new(2, 2)), h.extreme(Point::new(1, 1)));
+    assert!(h.remove(Point::new(2, 2)));
+    assert!(!h.remove(Point::new(2, 2)));
+    assert_eq!(vec![Point::new(0, 0), Point::new(2, 0), Point::new(0, 2)], h.hull());
+    assert!(!h.contains(Point::new(2, 2)) && h.contains(Point::new(1, 1)));
+    for p in h.hull() {
+      h.remove(p);
+    }
+    assert_eq!(vec![Point::new(1, 0), Point::new(1, 1)], h.hull());
+    assert_eq!(2, h.len());
+  }
+
+  #[test]
+  fn test_random_against_naive() {
+    let mut rand = rand_gen(251);
+    for round in 0..40 {
+      // small grids for many collinear and shared x, wide ones for general position
+      let span = if round % 2 == 0 { 6 } else { 1 << 20 };
+      let mut h = DynamicHull::new();
+      let mut points = BTreeSet::new();
+      for _ in 0..300 {
+        let p = Point::new(rand(span) as i64 - span as i64 / 2, rand(span) as i64 - span as i64 / 2);
+        if rand(3) == 0 && !points.is_empty() {
+          let q = *points.iter().nth(rand(points.len() as u64) as usize).unwrap();
+          assert!(h.remove(q) && points.remove(&q));
+        } else {
+          assert_eq!(points.insert(p), h.insert(p));
+        }
+        assert_eq!(points.len(), h.len());
+        let hull = naive_hull(&points);
+        assert_eq!(hull, h.hull());
+        let inside = |q: Point| match hull.len() {
+          0 => false,
+          1 => q == hull[0],
+          2 => orientation(hull[0], hull[1], q) == 0 && hull[0].min(hull[1]) <= q && q <= hull[0].max(hull[1]),
+          _ => (0..hull.len()).all(|i| orientation(hull[i], hull[(i + 1) % hull.len()], q) >= 0),
+        };
+        for _ in 0..5 {
+          let q = Point::new(rand(span) as i64 - span as i64 / 2, rand(span) as i64 - span as i64 / 2);
+          assert_eq!(inside(q), h.contains(q));
+          let d = Point::new(rand(7) as i64 - 3, rand(7) as i64 - 3);
+          let best = points.iter().map(|p| d.dot(*p)).max();
+          assert_eq!(best, h.extreme(d).map(|p| d.dot(p)));
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,136 @@
+use std::cmp::Ordering;
+
+// Signed integers of any size, for the rare predicates whose products outgrow an i128: a sign
+// and a magnitude in 64-bit limbs, least significant first, without leading zero limbs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Wide {
+  negative: bool,
+  limbs: Vec<u64>,
+}
+
+fn trim(mut limbs: Vec<u64>) -> Vec<u64> {
+  while limbs.last() == Some(&0) {
+    limbs.pop();
+  }
+  limbs
+}
+
+fn compare_magnitude(a: &[u64], b: &[u64]) -> Ordering {
+  a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitude(a: &[u64], b: &[u64]) -> Vec<u64> {
+  let mut sum = Vec::with_capacity(a.len().max(b.len()) + 1);
+  let mut carry = 0u128;
+  for i in 0..a.len().max(b.len()) {
+    let s = *a.get(i).unwrap_or(&0) as u128 + *b.get(i).unwrap_or(&0) as u128 + carry;
+    sum.push(s as u64);
+    carry = s >> 64;
+  }
+  sum.push(carry as u64);
+  trim(sum)
+}
+
+// a - b for a at least b
+fn sub_magnitude(a: &[u64], b: &[u64]) -> Vec<u64> {
+  let mut difference = Vec::with_capacity(a.len());
+  let mut borrow = 0;
+  for (i, &x) in a.iter().enumerate() {
+    let (d, o1) = x.overflowing_sub(*b.get(i).unwrap_or(&0));
+    let (d, o2) = d.overflowing_sub(borrow);
+    difference.push(d);
+    borrow = (o1 || o2) as u64;
+  }
+  trim(difference)
+}
+
+impl Wide {
+  pub(crate) fn new(x: i128) -> Wide {
+    let m = x.unsigned_abs();
+    Wide { negative: x < 0, limbs: trim(vec![m as u64, (m >> 64) as u64]) }
+  }
+
+  pub(crate) fn signum(&self) -> i32 {
+    if self.limbs.is_empty() {
+      0
+    } else if self.negative {
+      -1
+    } else {
+      1
+    }
+  }
+
+  fn with_sign(negative: bool, limbs: Vec<u64>) -> Wide {
+    let negative = negative && !limbs.is_empty();
+    Wide { negative, limbs }
+  }
+
+  pub(crate) fn mul(&self, other: &Wide) -> Wide {
+    let mut product = vec![0u64; self.limbs.len() + other.limbs.len()];
+    for (i, &x) in self.limbs.iter().enumerate() {
+      let mut carry = 0u128;
+      for (j, &y) in other.limbs.iter().enumerate() {
+        let p = x as u128 * y as u128 + product[i + j] as u128 + carry;
+        product[i + j] = p as u64;
+        carry = p >> 64;
+      }
+      product[i + other.limbs.len()] = carry as u64;
+    }
+    Wide::with_sign(self.negative != other.negative, trim(product))
+  }
+
+  pub(crate) fn add(&self, other: &Wide) -> Wide {
+    if self.negative == other.negative {
+      return Wide::with_sign(self.negative, add_magnitude(&self.limbs, &other.limbs));
+    }
+    match compare_magnitude(&self.limbs, &other.limbs) {
+      Ordering::Less => Wide::with_sign(other.negative, sub_magnitude(&other.limbs, &self.limbs)),
+      _ => Wide::with_sign(self.negative, sub_magnitude(&self.limbs, &other.limbs)),
+    }
+  }
+
+  pub(crate) fn sub(&self, other: &Wide) -> Wide {
+    self.add(&Wide::with_sign(!other.negative, other.limbs.clone()))
+  }
+}
+
+// the sign of a d - b c, exactly for any i128 inputs: in an i128 when the products fit, as
+// Wide ones otherwise
+pub(crate) fn sign_of_det2(a: i128, b: i128, c: i128, d: i128) -> i32 {
+  match (a.checked_mul(d), b.checked_mul(c)) {
+    (Some(ad), Some(bc)) => match ad.checked_sub(bc) {
+      Some(det) => det.signum() as i32,
+      None => ad.signum() as i32, // opposite signs, so the sign of ad
+    },
+    _ => Wide::new(a).mul(&Wide::new(d)).sub(&Wide::new(b).mul(&Wide::new(c))).signum(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::xorshift;
+
+  #[test]
+  fn test_wide() {
+    let big = Wide::new(i128::MAX);
+    let square = big.mul(&big);
+    // (2^127 - 1)^2 - (2^127 - 2) 2^127 = 1
+    let other = Wide::new(i128::MAX - 1).mul(&Wide::new(i128::MAX).add(&Wide::new(1)));
+    assert_eq!(Wide::new(1), square.sub(&other));
+    assert_eq!(-1, other.sub(&square).signum());
+    assert_eq!(0, square.sub(&square).signum());
+    assert_eq!(Wide::new(-6), Wide::new(2).mul(&Wide::new(-3)));
+    assert_eq!(Wide::new(i128::MIN), Wide::new(i128::MIN + 1).add(&Wide::new(-1)));
+    let mut next = xorshift(271);
+    let mut rand = move || next() as i64 as i128;
+    for _ in 0..1000 {
+      let (a, b, c, d) = (rand(), rand(), rand(), rand());
+      assert_eq!((a * d - b * c).signum() as i32, sign_of_det2(a, b, c, d));
+      assert_eq!(Wide::new(a * b + c), Wide::new(a).mul(&Wide::new(b)).add(&Wide::new(c)));
+      assert_eq!(Wide::new(a * b - c * d), Wide::new(a).mul(&Wide::new(b)).sub(&Wide::new(c).mul(&Wide::new(d))));
+    }
+    assert_eq!(1, sign_of_det2(i128::MAX, i128::MAX - 1, i128::MAX - 1, i128::MAX));
+    assert_eq!(0, sign_of_det2(i128::MIN, i128::MIN, i128::MIN, i128::MIN));
+  }
+}
@@ -0,0 +1,6 @@
+pub mod point;
+pub mod dynamic_hull;
+pub mod angular;
+pub mod exact;
+pub mod triangulation;
+pub mod visibility;
\ No newline at end of file
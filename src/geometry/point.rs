@@ -0,0 +1,163 @@
+use std::ops::{Add, Neg, Sub};
+
+use super::exact::{sign_of_det2, Wide};
+
+// A point with integer coordinates, ordered by x and then y. dot and cross are exact as long as
+// coordinates stay below 2^62 in absolute value, where their products fit an i128; the
+// predicates below are exact for any coordinates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Point {
+  pub x: i64,
+  pub y: i64,
+}
+
+impl Point {
+  pub fn new(x: i64, y: i64) -> Point {
+    Point { x, y }
+  }
+
+  pub fn dot(self, other: Point) -> i128 {
+    self.x as i128 * other.x as i128 + self.y as i128 * other.y as i128
+  }
+
+  pub fn cross(self, other: Point) -> i128 {
+    self.x as i128 * other.y as i128 - self.y as i128 * other.x as i128
+  }
+}
+
+impl Add for Point {
+  type Output = Point;
+  fn add(self, other: Point) -> Point {
+    Point::new(self.x + other.x, self.y + other.y)
+  }
+}
+
+impl Sub for Point {
+  type Output = Point;
+  fn sub(self, other: Point) -> Point {
+    Point::new(self.x - other.x, self.y - other.y)
+  }
+}
+
+impl Neg for Point {
+  type Output = Point;
+  fn neg(self) -> Point {
+    Point::new(-self.x, -self.y)
+  }
+}
+
+// The sign of twice the signed area of the triangle abc: 1 when a, b, c turn counterclockwise,
+// -1 when clockwise, 0 when collinear. Computed in i64 while nothing overflows, which covers
+// coordinates below 2^31, escalating to i128 and then to wide integers when a difference or a
+// product does not fit, so the answer is exact whatever the coordinates.
+pub fn orientation(a: Point, b: Point, c: Point) -> i32 {
+  let small = || {
+    let (ux, uy) = (b.x.checked_sub(a.x)?, b.y.checked_sub(a.y)?);
+    let (vx, vy) = (c.x.checked_sub(a.x)?, c.y.checked_sub(a.y)?);
+    ux.checked_mul(vy)?.checked_sub(uy.checked_mul(vx)?)
+  };
+  match small() {
+    Some(det) => det.signum() as i32,
+    None => {
+      let (ux, uy) = (b.x as i128 - a.x as i128, b.y as i128 - a.y as i128);
+      let (vx, vy) = (c.x as i128 - a.x as i128, c.y as i128 - a.y as i128);
+      sign_of_det2(ux, uy, vx, vy)
+    },
+  }
+}
+
+// The in-circle test of Delaunay triangulations: 1 when d lies strictly inside the circle
+// through a, b and c, -1 when strictly outside, 0 on it, for a, b, c counterclockwise; the
+// signs swap for a clockwise triangle. The sign of a 3x3 determinant over the coordinates
+// relative to d and their squared norms, in i128 while nothing overflows, below about 2^30,
+// and in wide integers past that, so again exact for any coordinates.
+pub fn in_circle(a: Point, b: Point, c: Point, d: Point) -> i32 {
+  let rows = [a, b, c].map(|p| (p.x as i128 - d.x as i128, p.y as i128 - d.y as i128));
+  let small = || {
+    let lift = |(x, y): (i128, i128)| x.checked_mul(x)?.checked_add(y.checked_mul(y)?);
+    let [(ax, ay), (bx, by), (cx, cy)] = rows;
+    let (al, bl, cl) = (lift(rows[0])?, lift(rows[1])?, lift(rows[2])?);
+    let minor = |p: i128, q: i128, r: i128, s: i128| p.checked_mul(s)?.checked_sub(q.checked_mul(r)?);
+    let first = ax.checked_mul(minor(by, bl, cy, cl)?)?;
+    let second = ay.checked_mul(minor(bx, bl, cx, cl)?)?;
+    let third = al.checked_mul(minor(bx, by, cx, cy)?)?;
+    first.checked_sub(second)?.checked_add(third)
+  };
+  if let Some(det) = small() {
+    return det.signum() as i32;
+  }
+  let [(ax, ay), (bx, by), (cx, cy)] = rows.map(|(x, y)| (Wide::new(x), Wide::new(y)));
+  let lift = |x: &Wide, y: &Wide| x.mul(x).add(&y.mul(y));
+  let (al, bl, cl) = (lift(&ax, &ay), lift(&bx, &by), lift(&cx, &cy));
+  let minor = |p: &Wide, q: &Wide, r: &Wide, s: &Wide| p.mul(s).sub(&q.mul(r));
+  let first = ax.mul(&minor(&by, &bl, &cy, &cl));
+  let second = ay.mul(&minor(&bx, &bl, &cx, &cl));
+  let third = al.mul(&minor(&bx, &by, &cx, &cy));
+  first.sub(&second).add(&third).signum()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_orientation() {
+    let (a, b) = (Point::new(0, 0), Point::new(4, 2));
+    assert!(orientation(a, b, Point::new(1, 3)) > 0);
+    assert!(orientation(a, b, Point::new(3, -1)) < 0);
+    assert_eq!(0, orientation(a, b, Point::new(-2, -1)));
+    let far = Point::new(-(1 << 61), 1 << 61);
+    assert_eq!(0, orientation(far, -far, Point::new(0, 0)));
+    // differences and products past an i128
+    let (min, max) = (i64::MIN, i64::MAX);
+    assert_eq!(0, orientation(Point::new(min, min), Point::new(max, max), Point::new(0, 0)));
+    assert_eq!(-1, orientation(Point::new(min, min), Point::new(max, max), Point::new(1, 0)));
+    assert_eq!(1, orientation(Point::new(min, max), Point::new(max, min), Point::new(0, 1)));
+    assert_eq!(1, orientation(Point::new(min, min), Point::new(max, min), Point::new(min, min + 1)));
+    assert_eq!(Point::new(3, 1), b - Point::new(1, 1));
+    assert_eq!(10, b.dot(Point::new(2, 1)));
+    assert!(Point::new(1, 5) < Point::new(2, 0) && Point::new(1, 5) > Point::new(1, 4));
+  }
+
+  #[test]
+  fn test_in_circle() {
+    let ccw = [Point::new(0, 0), Point::new(4, 0), Point::new(0, 4)];
+    let circle = |d: Point| in_circle(ccw[0], ccw[1], ccw[2], d);
+    assert_eq!((1, 0, -1), (circle(Point::new(1, 1)), circle(Point::new(4, 4)), circle(Point::new(5, 5))));
+    assert_eq!(-1, in_circle(ccw[0], ccw[2], ccw[1], Point::new(1, 1)));
+    // cocircular at every scale, up to where the squared norms need more than an i128
+    for shift in [10, 31, 40, 62] {
+      let r = 1i64 << shift;
+      assert_eq!(0, in_circle(Point::new(r, 0), Point::new(0, r), Point::new(-r, 0), Point::new(0, -r)));
+      assert_eq!(1, in_circle(Point::new(r, 0), Point::new(0, r), Point::new(-r, 0), Point::new(0, 1 - r)));
+      assert_eq!(-1, in_circle(Point::new(r, 0), Point::new(0, r), Point::new(-r, 0), Point::new(1, -r)));
+    }
+    let (min, max) = (i64::MIN, i64::MAX);
+    assert_eq!(1, in_circle(Point::new(min, min), Point::new(max, min), Point::new(max, max), Point::new(0, 0)));
+    assert_eq!(-1, in_circle(Point::new(min, min), Point::new(0, min), Point::new(min, 0), Point::new(max, max)));
+    // the i128 path against the wide one, on coordinates where both apply
+    let mut rand = rand_gen(277);
+    for _ in 0..1000 {
+      let span = 1u64 << (1 + rand(62));
+      let mut point = || Point::new(rand(span) as i64 - (span / 2) as i64, rand(span) as i64 - (span / 2) as i64);
+      let (a, b, c, d) = (point(), point(), point(), point());
+      let rows = [a, b, c].map(|p| (Wide::new(p.x as i128 - d.x as i128), Wide::new(p.y as i128 - d.y as i128)));
+      let lift = |(x, y): &(Wide, Wide)| x.mul(x).add(&y.mul(y));
+      let entry = |i: usize, j: usize| match j {
+        0 => rows[i].0.clone(),
+        1 => rows[i].1.clone(),
+        _ => lift(&rows[i]),
+      };
+      // the determinant by the rule of Sarrus
+      let mut det = Wide::new(0);
+      for k in 0..3 {
+        det = det.add(&entry(0, k).mul(&entry(1, (k + 1) % 3)).mul(&entry(2, (k + 2) % 3)));
+        det = det.sub(&entry(0, k).mul(&entry(1, (k + 2) % 3)).mul(&entry(2, (k + 1) % 3)));
+      }
+      assert_eq!(det.signum(), in_circle(a, b, c, d));
+      let (u, v) = (b - a, c - a);
+      assert_eq!(Wide::new(u.x as i128).mul(&Wide::new(v.y as i128)).sub(&Wide::new(u.y as i128).mul(&Wide::new(v.x as i128))).signum(), orientation(a, b, c));
+    }
+  }
+}
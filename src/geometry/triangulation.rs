@@ -0,0 +1,244 @@
+use super::point::{orientation, Point};
+
+// whether the closed segments pq and ab share a point
+fn segments_meet(p: Point, q: Point, a: Point, b: Point) -> bool {
+  let within = |s: Point, t: Point, r: Point| s.x.min(t.x) <= r.x && r.x <= s.x.max(t.x) && s.y.min(t.y) <= r.y && r.y <= s.y.max(t.y);
+  let (d1, d2) = (orientation(p, q, a), orientation(p, q, b));
+  let (d3, d4) = (orientation(a, b, p), orientation(a, b, q));
+  if d1 * d2 < 0 && d3 * d4 < 0 {
+    return true;
+  }
+  d1 == 0 && within(p, q, a) || d2 == 0 && within(p, q, b) || d3 == 0 && within(a, b, p) || d4 == 0 && within(a, b, q)
+}
+
+// the closed triangle abc, counterclockwise
+fn in_triangle(a: Point, b: Point, c: Point, p: Point) -> bool {
+  orientation(a, b, p) >= 0 && orientation(b, c, p) >= 0 && orientation(c, a, p) >= 0
+}
+
+// the vertex ids of a ring turned to the given orientation, judged at its smallest vertex, which
+// is convex
+fn oriented(points: &[Point], ids: Vec<usize>, counterclockwise: bool) -> Vec<usize> {
+  let m = ids.len();
+  let low = (0..m).min_by_key(|&i| points[ids[i]]).unwrap();
+  let turn = orientation(points[ids[(low + m - 1) % m]], points[ids[low]], points[ids[(low + 1) % m]]);
+  let mut ids = ids;
+  if (turn > 0) != counterclockwise {
+    ids.reverse();
+  }
+  ids
+}
+
+// Splices a clockwise hole into the counterclockwise ring through a bridge from its rightmost
+// vertex m (Eberly): a ring vertex to the right of m that m sees, nearest first, walked there
+// and back, so the ring stays one weakly simple polygon. Every ring vertex to the right is a
+// candidate, checked against all ring edges, O(n^2) at worst.
+fn bridge(points: &[Point], ring: &mut Vec<usize>, hole: &[usize]) {
+  let at = (0..hole.len()).max_by_key(|&i| points[hole[i]]).unwrap();
+  let m = points[hole[at]];
+  let n = ring.len();
+  let mut candidates: Vec<usize> = (0..n).filter(|&k| points[ring[k]].x > m.x).collect();
+  candidates.sort_by_key(|&k| (points[ring[k]] - m).dot(points[ring[k]] - m));
+  let sees = |k: usize| {
+    let (a, v, b) = (points[ring[(k + n - 1) % n]], points[ring[k]], points[ring[(k + 1) % n]]);
+    // m must lie strictly inside the interior angle at this copy of v
+    let inside = if orientation(a, v, b) >= 0 {
+      orientation(a, v, m) > 0 && orientation(v, b, m) > 0
+    } else {
+      orientation(a, v, m) > 0 || orientation(v, b, m) > 0
+    };
+    inside && (0..n).all(|e| {
+      let (p, q) = (points[ring[e]], points[ring[(e + 1) % n]]);
+      p == v || q == v || !segments_meet(m, v, p, q)
+    })
+  };
+  let k = candidates.into_iter().find(|&k| sees(k)).expect("a hole must lie strictly inside the polygon");
+  let walk = (0..=hole.len()).map(|i| hole[(at + i) % hole.len()]);
+  let spliced: Vec<usize> = walk.chain(std::iter::once(ring[k])).collect();
+  ring.splice(k + 1..k + 1, spliced);
+}
+
+// Ear clipping of a simple polygon with holes, given as rings of vertices in either orientation:
+// the triangles as triples of vertex indices, counting the outer ring's vertices first and then
+// each hole's in turn, every triple counterclockwise. Holes are joined to the outer ring by
+// bridges, rightmost hole first, and ears are clipped off the one ring left: a convex vertex
+// whose triangle holds no other vertex. A polygon of n vertices and h holes gives n + 2h - 2
+// triangles, fewer only for vertices lying straight between their neighbours, which are dropped
+// once no ear remains. Predicates are exact, so any coordinates work; O(n^2) for the ears plus
+// O(n^2) per hole for the bridges. Holes must lie strictly inside the outer ring and apart from
+// each other.
+pub fn triangulate_with_holes(outer: &[Point], holes: &[&[Point]]) -> Vec<[usize; 3]> {
+  let points: Vec<Point> = outer.iter().chain(holes.iter().flat_map(|h| h.iter())).copied().collect();
+  if outer.len() < 3 {
+    return vec![];
+  }
+  let mut ring = oriented(&points, (0..outer.len()).collect(), true);
+  let mut start = outer.len();
+  let mut rings = vec![];
+  for h in holes {
+    if h.len() >= 3 {
+      rings.push(oriented(&points, (start..start + h.len()).collect(), false));
+    }
+    start += h.len();
+  }
+  rings.sort_by_key(|r| std::cmp::Reverse(r.iter().map(|&i| points[i]).max()));
+  for hole in &rings {
+    bridge(&points, &mut ring, hole);
+  }
+  clip_ears(&points, &ring)
+}
+
+pub fn triangulate(polygon: &[Point]) -> Vec<[usize; 3]> {
+  triangulate_with_holes(polygon, &[])
+}
+
+fn clip_ears(points: &[Point], ring: &[usize]) -> Vec<[usize; 3]> {
+  let m = ring.len();
+  let at = |i: usize| points[ring[i]];
+  let mut prev: Vec<usize> = (0..m).map(|i| (i + m - 1) % m).collect();
+  let mut next: Vec<usize> = (0..m).map(|i| (i + 1) % m).collect();
+  let mut triangles = vec![];
+  let (mut left, mut i, mut misses) = (m, 0, 0);
+  while left > 3 {
+    if misses >= left {
+      // no ear in a whole round: drop a vertex straight between its neighbours, if any
+      let mut j = i;
+      while orientation(at(prev[j]), at(j), at(next[j])) != 0 {
+        j = next[j];
+        if j == i {
+          return triangles;
+        }
+      }
+      next[prev[j]] = next[j];
+      prev[next[j]] = prev[j];
+      left -= 1;
+      i = next[j];
+      misses = 0;
+      continue;
+    }
+    let (a, c) = (prev[i], next[i]);
+    let (pa, pb, pc) = (at(a), at(i), at(c));
+    let mut ear = orientation(pa, pb, pc) > 0;
+    let mut j = next[c];
+    while ear && j != a {
+      let q = at(j);
+      ear = q == pa || q == pb || q == pc || !in_triangle(pa, pb, pc, q);
+      j = next[j];
+    }
+    if ear {
+      triangles.push([ring[a], ring[i], ring[c]]);
+      next[a] = c;
+      prev[c] = a;
+      left -= 1;
+      misses = 0;
+      i = c;
+    } else {
+      misses += 1;
+      i = c;
+    }
+  }
+  let (a, c) = (prev[i], next[i]);
+  if orientation(at(a), at(i), at(c)) > 0 {
+    triangles.push([ring[a], ring[i], ring[c]]);
+  }
+  triangles
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+  use crate::geometry::angular::sort_around;
+
+  // twice the signed area
+  fn area(ring: &[Point]) -> i128 {
+    (0..ring.len()).map(|i| ring[i].cross(ring[(i + 1) % ring.len()])).sum()
+  }
+
+  // crossing number, for points off the boundary
+  fn inside(ring: &[Point], p: Point) -> bool {
+    let mut odd = false;
+    for i in 0..ring.len() {
+      let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+      if (a.y > p.y) != (b.y > p.y) {
+        let (a, b) = if a.y < b.y { (a, b) } else { (b, a) };
+        odd ^= orientation(a, b, p) > 0;
+      }
+    }
+    odd
+  }
+
+  fn check(outer: &[Point], holes: &[&[Point]], triangles: &[[usize; 3]]) {
+    let points: Vec<Point> = outer.iter().chain(holes.iter().flat_map(|h| h.iter())).copied().collect();
+    let mut total = 0;
+    for &[a, b, c] in triangles {
+      let (a, b, c) = (points[a], points[b], points[c]);
+      assert_eq!(1, orientation(a, b, c));
+      total += area(&[a, b, c]);
+      // the centroid, scaled by 3 with everything else
+      let scale = |r: &[Point]| r.iter().map(|&p| Point::new(3 * p.x, 3 * p.y)).collect::<Vec<_>>();
+      let g = a + b + c;
+      assert!(inside(&scale(outer), g));
+      assert!(holes.iter().all(|h| !inside(&scale(h), g)));
+    }
+    assert_eq!(area(outer).abs() - holes.iter().map(|h| area(h).abs()).sum::<i128>(), total);
+  }
+
+  #[test]
+  fn test_shapes() {
+    let p = |v: &[(i64, i64)]| v.iter().map(|&(x, y)| Point::new(x, y)).collect::<Vec<_>>();
+    let square = p(&[(0, 0), (4, 0), (4, 4), (0, 4)]);
+    assert_eq!(2, triangulate(&square).len());
+    check(&square, &[], &triangulate(&square));
+    let clockwise: Vec<Point> = square.iter().rev().copied().collect();
+    check(&clockwise, &[], &triangulate(&clockwise));
+    // straight vertices along the edges
+    let dotted = p(&[(0, 0), (2, 0), (4, 0), (4, 2), (4, 4), (2, 4), (0, 4), (0, 2)]);
+    check(&dotted, &[], &triangulate(&dotted));
+    let comb = p(&[(0, 0), (10, 0), (10, 5), (9, 5), (8, 1), (7, 5), (6, 1), (5, 5), (4, 1), (3, 5), (2, 1), (1, 5), (0, 5)]);
+    let triangles = triangulate(&comb);
+    assert_eq!(comb.len() - 2, triangles.len());
+    check(&comb, &[], &triangles);
+    let hole = p(&[(1, 1), (3, 1), (3, 3), (1, 3)]);
+    let triangles = triangulate_with_holes(&square, &[&hole]);
+    assert_eq!(8, triangles.len());
+    check(&square, &[&hole], &triangles);
+    // two holes in a row, the left one bridged through the right one
+    let frame = p(&[(0, 0), (20, 0), (20, 6), (0, 6)]);
+    let (left, right) = (p(&[(2, 2), (6, 2), (6, 4), (2, 4)]), p(&[(10, 1), (14, 1), (14, 5), (10, 5)]));
+    let triangles = triangulate_with_holes(&frame, &[&left, &right]);
+    assert_eq!(4 + 4 + 4 + 2, triangles.len());
+    check(&frame, &[&left, &right], &triangles);
+    assert!(triangulate(&square[..2]).is_empty());
+  }
+
+  #[test]
+  fn test_random_star_polygons() {
+    let mut rand = rand_gen(283);
+    // vertices at increasing angles around center, so the ring is simple
+    let mut star = |center: Point, k: u64, radius: (u64, u64)| {
+      let mut ring: Vec<Point> = (0..k)
+        .map(|i| {
+          let angle = (i as f64 + rand(1000) as f64 / 1000.0) * std::f64::consts::TAU / k as f64;
+          let r = (radius.0 + rand(radius.1 - radius.0)) as f64;
+          center + Point::new((r * angle.cos()).round() as i64, (r * angle.sin()).round() as i64)
+        })
+        .collect();
+      sort_around(&mut ring, center);
+      ring.dedup_by(|a, b| orientation(center, *a, *b) == 0);
+      ring
+    };
+    for round in 0..300 {
+      let outer = star(Point::new(0, 0), 8 + round % 30, (600, 1000));
+      let mut holes = vec![];
+      for side in [-300, 300].iter().take(round as usize % 3) {
+        holes.push(star(Point::new(*side, 0), 5 + round % 7, (50, 100)));
+      }
+      let holes: Vec<&[Point]> = holes.iter().map(|h| &h[..]).collect();
+      let triangles = triangulate_with_holes(&outer, &holes);
+      let n: usize = outer.len() + holes.iter().map(|h| h.len()).sum::<usize>();
+      assert_eq!(n + 2 * holes.len() - 2, triangles.len());
+      check(&outer, &holes, &triangles);
+    }
+  }
+}
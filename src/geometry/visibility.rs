@@ -0,0 +1,203 @@
+use super::point::{orientation, Point};
+use crate::binary_heap::BinaryHeap;
+use crate::graph::csr::Csr;
+
+// whether p lies on the closed segment ab
+fn on_segment(a: Point, b: Point, p: Point) -> bool {
+  orientation(a, b, p) == 0 && a.x.min(b.x) <= p.x && p.x <= a.x.max(b.x) && a.y.min(b.y) <= p.y && p.y <= a.y.max(b.y)
+}
+
+// whether p lies in the open interior of the polygon, by the crossing number once the boundary
+// is ruled out
+fn strictly_inside(polygon: &[Point], p: Point) -> bool {
+  let n = polygon.len();
+  let edges = || (0..n).map(|i| (polygon[i], polygon[(i + 1) % n]));
+  if edges().any(|(a, b)| on_segment(a, b, p)) {
+    return false;
+  }
+  let mut odd = false;
+  for (a, b) in edges() {
+    if (a.y > p.y) != (b.y > p.y) {
+      let (low, high) = if a.y < b.y { (a, b) } else { (b, a) };
+      odd ^= orientation(low, high, p) > 0;
+    }
+  }
+  odd
+}
+
+// Whether the segment pq keeps out of the interior of every obstacle; it may run along their
+// edges and through their vertices. pq must not cross an edge properly, and between the vertices
+// lying on it, where it touches no boundary, each piece is inside or outside as its midpoint is.
+fn visible(obstacles: &[&[Point]], p: Point, q: Point) -> bool {
+  let mut stops = vec![p, q];
+  for polygon in obstacles {
+    let n = polygon.len();
+    for i in 0..n {
+      let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+      let crosses = orientation(p, q, a) * orientation(p, q, b) < 0 && orientation(a, b, p) * orientation(a, b, q) < 0;
+      if crosses {
+        return false;
+      }
+      if on_segment(p, q, a) {
+        stops.push(a);
+      }
+    }
+  }
+  stops.sort_by_key(|&s| (s - p).dot(q - p));
+  stops.dedup();
+  // midpoints doubled, against doubled obstacles
+  let doubled: Vec<Vec<Point>> = obstacles.iter().map(|polygon| polygon.iter().map(|&a| a + a).collect()).collect();
+  stops.windows(2).all(|w| doubled.iter().all(|polygon| !strictly_inside(polygon, w[0] + w[1])))
+}
+
+// The visibility graph among polygonal obstacles: their vertices, the obstacles' in turn, then
+// the extra sites, joined when the segment between them keeps out of every obstacle's interior.
+// Obstacles are simple polygons in either orientation, and may overlap; where two only touch,
+// the seam between them is open, like any boundary. Every pair is checked against every edge,
+// O(n^3); coordinates must stay below 2^61 in absolute value, as midpoints are taken doubled.
+pub fn visibility_graph(obstacles: &[&[Point]], sites: &[Point]) -> (Vec<Point>, Csr) {
+  let points: Vec<Point> = obstacles.iter().flat_map(|polygon| polygon.iter()).chain(sites).copied().collect();
+  let mut arcs = vec![];
+  for u in 0..points.len() {
+    for v in u + 1..points.len() {
+      if points[u] != points[v] && visible(obstacles, points[u], points[v]) {
+        arcs.push((u, v));
+        arcs.push((v, u));
+      }
+    }
+  }
+  let graph = Csr::from_arcs(points.len(), &arcs);
+  (points, graph)
+}
+
+fn distance(a: Point, b: Point) -> f64 {
+  ((b - a).dot(b - a) as f64).sqrt()
+}
+
+// The Euclidean shortest path from start to goal around polygonal obstacles, as its length and
+// the points it bends at, start and goal included; None if no path keeps out of the obstacles.
+// A shortest path bends only at obstacle vertices, so it is Dijkstra over the visibility graph
+// of start, goal and the vertices.
+pub fn shortest_path(obstacles: &[&[Point]], start: Point, goal: Point) -> Option<(f64, Vec<Point>)> {
+  if start == goal {
+    return Some((0.0, vec![start]));
+  }
+  let (points, graph) = visibility_graph(obstacles, &[start, goal]);
+  let (s, t) = (points.len() - 2, points.len() - 1);
+  let mut dist = vec![f64::INFINITY; points.len()];
+  let mut from = vec![usize::MAX; points.len()];
+  let mut heap = BinaryHeap::with_comparator(|a: &(f64, usize), b: &(f64, usize)| b.0.total_cmp(&a.0));
+  dist[s] = 0.0;
+  heap.push((0.0, s));
+  while let Some((d, u)) = heap.pop() {
+    if d > dist[u] {
+      continue;
+    }
+    if u == t {
+      break;
+    }
+    for &v in graph.neighbors(u) {
+      let through = d + distance(points[u], points[v]);
+      if through < dist[v] {
+        dist[v] = through;
+        from[v] = u;
+        heap.push((through, v));
+      }
+    }
+  }
+  if dist[t].is_infinite() {
+    return None;
+  }
+  let mut path = vec![points[t]];
+  let mut v = t;
+  while v != s {
+    v = from[v];
+    path.push(points[v]);
+  }
+  path.reverse();
+  Some((dist[t], path))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  fn rectangle(x0: i64, y0: i64, x1: i64, y1: i64) -> Vec<Point> {
+    vec![Point::new(x0, y0), Point::new(x1, y0), Point::new(x1, y1), Point::new(x0, y1)]
+  }
+
+  #[test]
+  fn test_around_a_square() {
+    let (start, goal) = (Point::new(0, 2), Point::new(6, 2));
+    assert_eq!(Some((6.0, vec![start, goal])), shortest_path(&[], start, goal));
+    let square = rectangle(2, 1, 4, 3);
+    let (length, path) = shortest_path(&[&square], start, goal).unwrap();
+    assert!((length - (2.0 + 2.0 * 5f64.sqrt())).abs() < 1e-9);
+    assert_eq!(4, path.len());
+    assert!(path[1..3] == [Point::new(2, 3), Point::new(4, 3)] || path[1..3] == [Point::new(2, 1), Point::new(4, 1)]);
+    // along an edge and through a corner is fine, through the middle is not
+    assert!(visible(&[&square], Point::new(0, 3), Point::new(6, 3)));
+    assert!(!visible(&[&square], Point::new(0, -1), Point::new(6, 5)));
+    assert!(!visible(&[&square], Point::new(1, 0), Point::new(5, 4)));
+    assert!(visible(&[&square], Point::new(0, 3), Point::new(3, 6)) && visible(&[&square], Point::new(0, 1), Point::new(2, 3)));
+    assert!(!visible(&[&square], Point::new(2, 1), Point::new(4, 3)));
+    // walls that only touch leave seams to squeeze through, overlapping ones do not
+    let walls = [rectangle(-2, -2, 2, -1), rectangle(-2, 1, 2, 2), rectangle(-2, -1, -1, 1), rectangle(1, -1, 2, 1)];
+    let walls: Vec<&[Point]> = walls.iter().map(|w| &w[..]).collect();
+    assert!(shortest_path(&walls, Point::new(0, 0), Point::new(5, 5)).is_some());
+    let walls = [rectangle(-3, -3, 3, -1), rectangle(-3, 1, 3, 3), rectangle(-3, -3, -1, 3), rectangle(1, -3, 3, 3)];
+    let walls: Vec<&[Point]> = walls.iter().map(|w| &w[..]).collect();
+    assert_eq!(None, shortest_path(&walls, Point::new(0, 0), Point::new(5, 5)));
+    assert_eq!(None, shortest_path(&[&square], Point::new(3, 2), goal));
+  }
+
+  #[test]
+  fn test_random_rectangles() {
+    let mut rand = rand_gen(293);
+    for _ in 0..30 {
+      // rectangles that may touch but not overlap
+      let mut boxes: Vec<(i64, i64, i64, i64)> = vec![];
+      for _ in 0..40 {
+        let (x0, y0) = (rand(36) as i64, rand(36) as i64);
+        let (x1, y1) = (x0 + 1 + rand(6) as i64, y0 + 1 + rand(6) as i64);
+        if boxes.iter().all(|&(a0, b0, a1, b1)| x1 <= a0 || a1 <= x0 || y1 <= b0 || b1 <= y0) {
+          boxes.push((x0, y0, x1, y1));
+        }
+        if boxes.len() == 6 {
+          break;
+        }
+      }
+      let rectangles: Vec<Vec<Point>> = boxes.iter().map(|&(x0, y0, x1, y1)| rectangle(x0, y0, x1, y1)).collect();
+      let obstacles: Vec<&[Point]> = rectangles.iter().map(|r| &r[..]).collect();
+      let sites: Vec<Point> = (0..4).map(|_| Point::new(rand(44) as i64 - 2, rand(44) as i64 - 2)).collect();
+      let (points, graph) = visibility_graph(&obstacles, &sites);
+      // against samples along each segment, fine enough to land inside any box it cuts through
+      const K: i64 = 4096;
+      let blocked = |p: Point, q: Point| {
+        (0..=K).any(|k| {
+          let (x, y) = (K * p.x + k * (q.x - p.x), K * p.y + k * (q.y - p.y));
+          boxes.iter().any(|&(x0, y0, x1, y1)| K * x0 < x && x < K * x1 && K * y0 < y && y < K * y1)
+        })
+      };
+      for u in 0..points.len() {
+        for v in 0..points.len() {
+          if u != v && points[u] != points[v] {
+            assert_eq!(!blocked(points[u], points[v]), graph.neighbors(u).contains(&v));
+          }
+        }
+      }
+      let (start, goal) = (sites[0], sites[1]);
+      let inside = |p: Point| boxes.iter().any(|&(x0, y0, x1, y1)| x0 < p.x && p.x < x1 && y0 < p.y && p.y < y1);
+      match shortest_path(&obstacles, start, goal) {
+        None => assert!(inside(start) || inside(goal)),
+        Some((length, path)) => {
+          assert_eq!((start, goal), (path[0], *path.last().unwrap()));
+          assert!(path.windows(2).all(|w| !blocked(w[0], w[1])));
+          let total: f64 = path.windows(2).map(|w| distance(w[0], w[1])).sum();
+          assert!((total - length).abs() < 1e-9 && length >= distance(start, goal) - 1e-9);
+        },
+      }
+    }
+  }
+}
@@ -0,0 +1,294 @@
+use std::collections::HashSet;
+
+const NIL: usize = usize::MAX;
+
+// indices of the edges whose removal disconnects their endpoints, in increasing order. Parallel
+// edges are never bridges, self-loops never are either. Iterative Tarjan low-link, O(n + m).
+pub fn bridges(n: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+  let mut adj = vec![vec![]; n];
+  for (i, &(u, v)) in edges.iter().enumerate() {
+    adj[u].push((v, i));
+    adj[v].push((u, i));
+  }
+  let mut tin = vec![NIL; n];
+  let mut low = vec![0; n];
+  let mut timer = 0;
+  let mut found = vec![];
+  for s in 0..n {
+    if tin[s] != NIL { continue; }
+    tin[s] = timer;
+    low[s] = timer;
+    timer += 1;
+    // (vertex, edge used to enter it, next adjacency index)
+    let mut stack = vec![(s, NIL, 0)];
+    while let Some(&mut (u, via, ref mut i)) = stack.last_mut() {
+      if *i < adj[u].len() {
+        let (v, e) = adj[u][*i];
+        *i += 1;
+        if e == via { continue; }
+        if tin[v] == NIL {
+          tin[v] = timer;
+          low[v] = timer;
+          timer += 1;
+          stack.push((v, e, 0));
+        } else {
+          low[u] = low[u].min(tin[v]);
+        }
+      } else {
+        stack.pop();
+        if let Some(&(p, _, _)) = stack.last() {
+          low[p] = low[p].min(low[u]);
+          if low[u] > tin[p] {
+            found.push(via);
+          }
+        }
+      }
+    }
+  }
+  found.sort_unstable();
+  found
+}
+
+// Bridges of a graph under edge insertions, O(log n) amortized per insertion plus the union-find
+// work. Each 2-edge-connected component is contracted to one vertex with a union-find, which
+// leaves a forest whose edges are exactly the bridges. Joining two trees reroots the smaller one;
+// an edge inside a tree closes a cycle, and the forest path it spans collapses into one component.
+pub struct IncrementalBridges {
+  two_edge: Vec<usize>, // union-find over 2-edge-connected components
+  component: Vec<usize>, // union-find over connected components, only read at representatives
+  size: Vec<usize>, // component sizes at their roots
+  parent: Vec<usize>, // forest parent of a representative, any vertex of the parent component
+  visit: Vec<usize>, // scratch for finding the lowest common ancestor
+  iteration: usize,
+  edges: HashSet<(usize, usize)>,
+  bridge_count: usize,
+}
+
+impl IncrementalBridges {
+  pub fn new(n: usize) -> IncrementalBridges {
+    IncrementalBridges {
+      two_edge: (0..n).collect(),
+      component: (0..n).collect(),
+      size: vec![1; n],
+      parent: vec![NIL; n],
+      visit: vec![0; n],
+      iteration: 0,
+      edges: HashSet::new(),
+      bridge_count: 0,
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.two_edge.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.two_edge.is_empty()
+  }
+
+  fn find(set: &mut [usize], v: usize) -> usize {
+    let mut root = v;
+    while set[root] != root {
+      root = set[root];
+    }
+    let mut v = v;
+    while set[v] != root {
+      let next = set[v];
+      set[v] = root;
+      v = next;
+    }
+    root
+  }
+
+  fn find_two_edge(&mut self, v: usize) -> usize {
+    if v == NIL { NIL } else { IncrementalBridges::find(&mut self.two_edge, v) }
+  }
+
+  // component links may point at vertices merged away since, so every hop goes through the
+  // representative first
+  fn find_component(&mut self, v: usize) -> usize {
+    let mut path = vec![];
+    let mut v = self.find_two_edge(v);
+    while self.component[v] != v {
+      path.push(v);
+      v = self.find_two_edge(self.component[v]);
+    }
+    for u in path {
+      self.component[u] = v;
+    }
+    v
+  }
+
+  // reverses the forest path from v to its root so v becomes the root of its tree
+  fn make_root(&mut self, v: usize) {
+    let root = v;
+    let mut v = v;
+    let mut child = NIL;
+    while v != NIL {
+      let p = self.find_two_edge(self.parent[v]);
+      self.parent[v] = child;
+      self.component[v] = root;
+      child = v;
+      v = p;
+    }
+    self.size[root] = self.size[child];
+  }
+
+  // a and b are representatives in one tree: the path between them becomes one component
+  fn merge_path(&mut self, mut a: usize, mut b: usize) {
+    self.iteration += 1;
+    let (mut path_a, mut path_b) = (vec![], vec![]);
+    let lca = loop {
+      if a != NIL {
+        a = self.find_two_edge(a);
+        path_a.push(a);
+        if self.visit[a] == self.iteration { break a; }
+        self.visit[a] = self.iteration;
+        a = self.parent[a];
+      }
+      if b != NIL {
+        b = self.find_two_edge(b);
+        path_b.push(b);
+        if self.visit[b] == self.iteration { break b; }
+        self.visit[b] = self.iteration;
+        b = self.parent[b];
+      }
+    };
+    for path in [path_a, path_b] {
+      for v in path {
+        self.two_edge[v] = lca;
+        if v == lca { break; }
+        self.bridge_count -= 1;
+      }
+    }
+  }
+
+  // returns false for self-loops; a repeated edge is kept and closes a cycle of length two
+  pub fn add_edge(&mut self, u: usize, v: usize) -> bool {
+    if u == v {
+      return false;
+    }
+    self.edges.insert((u.min(v), u.max(v)));
+    let (mut a, mut b) = (self.find_two_edge(u), self.find_two_edge(v));
+    if a == b {
+      return true;
+    }
+    let (mut ca, mut cb) = (self.find_component(a), self.find_component(b));
+    if ca != cb {
+      self.bridge_count += 1;
+      if self.size[ca] > self.size[cb] {
+        std::mem::swap(&mut a, &mut b);
+        std::mem::swap(&mut ca, &mut cb);
+      }
+      self.make_root(a);
+      self.parent[a] = b;
+      self.component[a] = b;
+      self.size[cb] += self.size[a];
+    } else {
+      self.merge_path(a, b);
+    }
+    true
+  }
+
+  pub fn bridge_count(&self) -> usize {
+    self.bridge_count
+  }
+
+  pub fn connected(&mut self, u: usize, v: usize) -> bool {
+    self.find_component(u) == self.find_component(v)
+  }
+
+  // whether u and v stay connected after removing any single edge
+  pub fn two_edge_connected(&mut self, u: usize, v: usize) -> bool {
+    self.find_two_edge(u) == self.find_two_edge(v)
+  }
+
+  // whether the edge u-v exists and is a bridge
+  pub fn is_bridge(&mut self, u: usize, v: usize) -> bool {
+    self.edges.contains(&(u.min(v), u.max(v))) && !self.two_edge_connected(u, v)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  fn connected(n: usize, edges: &[(usize, usize)], skip: usize, s: usize, t: usize) -> bool {
+    let mut adj = vec![vec![]; n];
+    for (i, &(u, v)) in edges.iter().enumerate() {
+      if i != skip {
+        adj[u].push(v);
+        adj[v].push(u);
+      }
+    }
+    let mut seen = vec![false; n];
+    let mut stack = vec![s];
+    seen[s] = true;
+    while let Some(u) = stack.pop() {
+      for &v in &adj[u] {
+        if !seen[v] {
+          seen[v] = true;
+          stack.push(v);
+        }
+      }
+    }
+    seen[t]
+  }
+
+  #[test]
+  fn test_static_against_brute_force() {
+    let mut rand = rand_gen(29);
+    for _ in 0..200 {
+      let n = 1 + rand(12) as usize;
+      let edges: Vec<(usize, usize)> = (0..rand(2 * n as u64))
+        .map(|_| (rand(n as u64) as usize, rand(n as u64) as usize))
+        .collect();
+      let expected: Vec<usize> = (0..edges.len())
+        .filter(|&i| !connected(n, &edges, i, edges[i].0, edges[i].1))
+        .collect();
+      assert_eq!(expected, bridges(n, &edges));
+    }
+  }
+
+  #[test]
+  fn test_incremental_against_static() {
+    let mut rand = rand_gen(43);
+    for _ in 0..50 {
+      let n = 1 + rand(30) as usize;
+      let mut inc = IncrementalBridges::new(n);
+      let mut edges = vec![];
+      for _ in 0..2 * n {
+        let (u, v) = (rand(n as u64) as usize, rand(n as u64) as usize);
+        assert_eq!(u != v, inc.add_edge(u, v));
+        if u != v {
+          edges.push((u, v));
+        }
+        let found = bridges(n, &edges);
+        assert_eq!(found.len(), inc.bridge_count());
+        for (i, &(a, b)) in edges.iter().enumerate() {
+          assert_eq!(found.binary_search(&i).is_ok(), inc.is_bridge(a, b));
+        }
+        let (a, b) = (rand(n as u64) as usize, rand(n as u64) as usize);
+        assert_eq!(connected(n, &edges, NIL, a, b), inc.connected(a, b));
+      }
+    }
+  }
+
+  #[test]
+  fn test_cycle_closes_path() {
+    let mut inc = IncrementalBridges::new(5);
+    for (u, v) in [(0, 1), (1, 2), (2, 3), (3, 4)] {
+      inc.add_edge(u, v);
+    }
+    assert_eq!(4, inc.bridge_count());
+    inc.add_edge(1, 3);
+    assert_eq!(2, inc.bridge_count());
+    assert!(inc.is_bridge(0, 1) && inc.is_bridge(4, 3));
+    assert!(!inc.is_bridge(1, 2) && !inc.is_bridge(0, 2));
+    assert!(inc.two_edge_connected(1, 3));
+    inc.add_edge(0, 1);
+    assert!(!inc.is_bridge(0, 1)); // a parallel edge
+    assert_eq!(1, inc.bridge_count());
+  }
+}
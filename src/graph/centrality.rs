@@ -0,0 +1,241 @@
+use std::collections::VecDeque;
+
+// Per-vertex centrality scores for graphs given as (n, edge list).
+
+#[derive(Copy, Clone, Debug)]
+pub struct PageRankOptions {
+  pub damping: f64,
+  pub tolerance: f64, // stop once the L1 change of an iteration drops below this
+  pub max_iterations: usize,
+}
+
+impl Default for PageRankOptions {
+  fn default() -> Self {
+    PageRankOptions { damping: 0.85, tolerance: 1e-10, max_iterations: 100 }
+  }
+}
+
+// power iteration over directed edges; the rank of vertices without out-edges is spread
+// evenly over all vertices. Scores sum to 1.
+pub fn pagerank(n: usize, edges: &[(usize, usize)], options: PageRankOptions) -> Vec<f64> {
+  if n == 0 {
+    return vec![];
+  }
+  let mut out_degree = vec![0usize; n];
+  for &(u, _) in edges {
+    out_degree[u] += 1;
+  }
+  let uniform = 1.0 / n as f64;
+  let mut rank = vec![uniform; n];
+  for _ in 0..options.max_iterations {
+    let dangling: f64 = (0..n).filter(|&v| out_degree[v] == 0).map(|v| rank[v]).sum();
+    let base = (1.0 - options.damping) * uniform + options.damping * dangling * uniform;
+    let mut next = vec![base; n];
+    for &(u, v) in edges {
+      next[v] += options.damping * rank[u] / out_degree[u] as f64;
+    }
+    let change: f64 = rank.iter().zip(&next).map(|(a, b)| (a - b).abs()).sum();
+    rank = next;
+    if change < options.tolerance {
+      break;
+    }
+  }
+  rank
+}
+
+// number of incident edges per vertex, divided by n - 1 so that a vertex adjacent to every
+// other one scores 1; a self-loop counts twice
+pub fn degree_centrality(n: usize, edges: &[(usize, usize)]) -> Vec<f64> {
+  let mut degree = vec![0usize; n];
+  for &(u, v) in edges {
+    degree[u] += 1;
+    degree[v] += 1;
+  }
+  let scale = if n > 1 { 1.0 / (n - 1) as f64 } else { 1.0 };
+  degree.into_iter().map(|d| d as f64 * scale).collect()
+}
+
+// Brandes' algorithm for unweighted graphs, O(nm): for every vertex, the sum over pairs (s, t)
+// of the fraction of shortest s-t paths passing through it. Undirected graphs count every
+// unordered pair once.
+pub fn betweenness_centrality(n: usize, edges: &[(usize, usize)], directed: bool) -> Vec<f64> {
+  let mut adj = vec![vec![]; n];
+  for &(u, v) in edges {
+    adj[u].push(v);
+    if !directed {
+      adj[v].push(u);
+    }
+  }
+  let mut centrality = vec![0.0; n];
+  let mut sigma = vec![0.0f64; n]; // number of shortest paths from s
+  let mut dist = vec![usize::MAX; n];
+  let mut delta = vec![0.0f64; n]; // dependency of s on each vertex
+  let mut preds: Vec<Vec<usize>> = vec![vec![]; n];
+  for s in 0..n {
+    let mut order = vec![];
+    for v in 0..n {
+      sigma[v] = 0.0;
+      dist[v] = usize::MAX;
+      delta[v] = 0.0;
+      preds[v].clear();
+    }
+    sigma[s] = 1.0;
+    dist[s] = 0;
+    let mut queue = VecDeque::new();
+    queue.push_back(s);
+    while let Some(u) = queue.pop_front() {
+      order.push(u);
+      for &v in &adj[u] {
+        if dist[v] == usize::MAX {
+          dist[v] = dist[u] + 1;
+          queue.push_back(v);
+        }
+        if dist[v] == dist[u] + 1 {
+          sigma[v] += sigma[u];
+          preds[v].push(u);
+        }
+      }
+    }
+    // accumulate dependencies from the farthest vertices back
+    for &w in order.iter().rev() {
+      for &u in &preds[w] {
+        delta[u] += sigma[u] / sigma[w] * (1.0 + delta[w]);
+      }
+      if w != s {
+        centrality[w] += delta[w];
+      }
+    }
+  }
+  if !directed {
+    for c in centrality.iter_mut() {
+      *c /= 2.0;
+    }
+  }
+  centrality
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-9
+  }
+
+  // counts shortest paths between every pair with a Floyd-Warshall style pass
+  fn brute_force_betweenness(n: usize, edges: &[(usize, usize)], directed: bool) -> Vec<f64> {
+    let inf = usize::MAX / 4;
+    let mut dist = vec![vec![inf; n]; n];
+    let mut count = vec![vec![0.0f64; n]; n];
+    for v in 0..n {
+      dist[v][v] = 0;
+      count[v][v] = 1.0;
+    }
+    for &(u, v) in edges {
+      if u == v { continue; }
+      let pairs = if directed { vec![(u, v)] } else { vec![(u, v), (v, u)] };
+      for (a, b) in pairs {
+        if dist[a][b] > 1 {
+          dist[a][b] = 1;
+          count[a][b] = 0.0;
+        }
+        count[a][b] += 1.0;
+      }
+    }
+    // path counts by increasing length, each path counted through its second to last vertex
+    for len in 2..n {
+      for a in 0..n {
+        for b in 0..n {
+          if dist[a][b] != inf { continue; }
+          let mut paths = 0.0;
+          for m in 0..n {
+            if dist[a][m] == len - 1 && dist[m][b] == 1 {
+              paths += count[a][m] * count[m][b];
+            }
+          }
+          if paths > 0.0 {
+            dist[a][b] = len;
+            count[a][b] = paths;
+          }
+        }
+      }
+    }
+    let mut result = vec![0.0; n];
+    for s in 0..n {
+      for t in 0..n {
+        if s == t || dist[s][t] >= inf { continue; }
+        for v in (0..n).filter(|&v| v != s && v != t) {
+          if dist[s][v] + dist[v][t] == dist[s][t] {
+            result[v] += count[s][v] * count[v][t] / count[s][t];
+          }
+        }
+      }
+    }
+    if !directed {
+      for r in result.iter_mut() {
+        *r /= 2.0;
+      }
+    }
+    result
+  }
+
+  #[test]
+  fn test_betweenness_against_brute_force() {
+    let mut rand = rand_gen(13);
+    for round in 0..200 {
+      let n = 1 + rand(8) as usize;
+      let directed = round % 2 == 0;
+      // simple graphs only, an undirected edge is listed once
+      let mut edges: Vec<(usize, usize)> = (0..rand(2 * n as u64))
+        .map(|_| (rand(n as u64) as usize, rand(n as u64) as usize))
+        .filter(|&(u, v)| u != v)
+        .map(|(u, v)| if directed { (u, v) } else { (u.min(v), u.max(v)) })
+        .collect();
+      edges.sort();
+      edges.dedup();
+      let fast = betweenness_centrality(n, &edges, directed);
+      let slow = brute_force_betweenness(n, &edges, directed);
+      assert!(fast.iter().zip(&slow).all(|(&a, &b)| close(a, b)), "{:?} {:?}", fast, slow);
+    }
+  }
+
+  #[test]
+  fn test_star() {
+    let star: Vec<_> = (1..6).map(|v| (0, v)).collect();
+    let b = betweenness_centrality(6, &star, false);
+    assert!(close(10.0, b[0]));
+    assert!(b[1..].iter().all(|&x| close(0.0, x)));
+    let d = degree_centrality(6, &star);
+    assert!(close(1.0, d[0]));
+    assert!(close(0.2, d[3]));
+  }
+
+  #[test]
+  fn test_pagerank() {
+    // a directed cycle is uniform
+    let cycle = [(0, 1), (1, 2), (2, 3), (3, 0)];
+    let rank = pagerank(4, &cycle, PageRankOptions::default());
+    assert!(rank.iter().all(|&r| close(0.25, r)));
+
+    // vertex 2 is dangling; the result is a fixed point of the update and sums to 1
+    let edges = [(0, 1), (0, 2), (1, 2), (3, 2), (3, 0)];
+    let options = PageRankOptions { damping: 0.9, ..PageRankOptions::default() };
+    let rank = pagerank(4, &edges, options);
+    assert!(close(1.0, rank.iter().sum()));
+    let mut out = [0.0; 4];
+    for &(u, _) in &edges {
+      out[u] += 1.0;
+    }
+    for v in 0..4 {
+      let incoming: f64 = edges.iter().filter(|&&(_, w)| w == v).map(|&(u, _)| rank[u] / out[u]).sum();
+      let expected = 0.1 / 4.0 + 0.9 * (incoming + rank[2] / 4.0);
+      assert!(close(expected, rank[v]));
+    }
+    assert!(rank[2] > rank[1] && rank[1] > rank[3]);
+
+    // a single iteration stops early
+    let once = pagerank(4, &edges, PageRankOptions { max_iterations: 1, ..options });
+    assert!(!close(rank[2], once[2]));
+  }
+}
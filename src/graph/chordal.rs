@@ -0,0 +1,303 @@
+use std::collections::VecDeque;
+
+use super::treewidth::{from_elimination_ordering, TreeDecomposition};
+
+// Chordal graph recognition by maximum cardinality search (Tarjan & Yannakakis). MCS visits the
+// vertex with the most visited neighbors next; the graph is chordal iff the reverse visit order
+// is a perfect elimination ordering (every vertex's later neighbors form a clique).
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Chordality {
+  Chordal(Vec<usize>), // a perfect elimination ordering
+  NotChordal(Vec<usize>), // a chordless cycle of length at least 4, as a vertex sequence
+}
+
+fn adjacency(n: usize, edges: &[(usize, usize)]) -> Vec<Vec<usize>> {
+  let mut adj = vec![vec![]; n];
+  for &(u, v) in edges {
+    if u != v {
+      adj[u].push(v);
+      adj[v].push(u);
+    }
+  }
+  for list in adj.iter_mut() {
+    list.sort_unstable();
+    list.dedup();
+  }
+  adj
+}
+
+fn is_adjacent(adj: &[Vec<usize>], u: usize, v: usize) -> bool {
+  adj[u].binary_search(&v).is_ok()
+}
+
+// visit order, O(n + m) with buckets keyed by the number of visited neighbors
+pub fn maximum_cardinality_search(n: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+  let adj = adjacency(n, edges);
+  let mut weight = vec![0; n];
+  let mut visited = vec![false; n];
+  let mut buckets = vec![vec![]; n + 1];
+  buckets[0] = (0..n).rev().collect();
+  let mut order = vec![];
+  let mut top = 0;
+  while order.len() < n {
+    let v = match buckets[top].pop() {
+      Some(v) => v,
+      None => {
+        top -= 1;
+        continue;
+      },
+    };
+    if visited[v] || weight[v] != top {
+      continue;
+    }
+    visited[v] = true;
+    order.push(v);
+    for &u in &adj[v] {
+      if !visited[u] {
+        weight[u] += 1;
+        buckets[weight[u]].push(u);
+        top = top.max(weight[u]);
+      }
+    }
+  }
+  order
+}
+
+// shortest p-w path avoiding v and every other neighbor of v, closed into a cycle through v. The
+// path is induced and its inner vertices miss v, so with p and w nonadjacent the cycle is
+// chordless and at least 4 long.
+fn chordless_cycle_through(adj: &[Vec<usize>], v: usize, p: usize, w: usize) -> Vec<usize> {
+  let n = adj.len();
+  let mut prev = vec![usize::MAX; n];
+  let blocked = |u: usize| u == v || (u != p && u != w && is_adjacent(adj, v, u));
+  let mut queue = VecDeque::new();
+  prev[p] = p;
+  queue.push_back(p);
+  while let Some(u) = queue.pop_front() {
+    if u == w {
+      break;
+    }
+    for &x in &adj[u] {
+      if prev[x] == usize::MAX && !blocked(x) {
+        prev[x] = u;
+        queue.push_back(x);
+      }
+    }
+  }
+  let mut cycle = vec![v];
+  let mut x = w;
+  while x != p {
+    cycle.push(x);
+    x = prev[x];
+  }
+  cycle.push(p);
+  cycle
+}
+
+pub fn chordality(n: usize, edges: &[(usize, usize)]) -> Chordality {
+  let adj = adjacency(n, edges);
+  let mut peo = maximum_cardinality_search(n, edges);
+  peo.reverse();
+  let mut position = vec![0; n];
+  for (i, &v) in peo.iter().enumerate() {
+    position[v] = i;
+  }
+  for &v in &peo {
+    let later: Vec<usize> = adj[v].iter().cloned().filter(|&u| position[u] > position[v]).collect();
+    let p = match later.iter().cloned().min_by_key(|&u| position[u]) {
+      Some(p) => p,
+      None => continue,
+    };
+    // the later neighbors are a clique iff they are all adjacent to the earliest one. The first
+    // failure is the Tarjan-Yannakakis certificate: p and w stay connected once v and its other
+    // neighbors are removed (MCS took p, the last of them it visited, over v, so p had a visited
+    // neighbor off v's neighborhood, and so on back to w), and the shortest such path closes a
+    // chordless cycle
+    if let Some(&w) = later.iter().find(|&&w| w != p && !is_adjacent(&adj, p, w)) {
+      return Chordality::NotChordal(chordless_cycle_through(&adj, v, p, w));
+    }
+  }
+  Chordality::Chordal(peo)
+}
+
+pub fn is_chordal(n: usize, edges: &[(usize, usize)]) -> bool {
+  matches!(chordality(n, edges), Chordality::Chordal(_))
+}
+
+fn is_subset(a: &[usize], b: &[usize]) -> bool {
+  a.iter().all(|x| b.binary_search(x).is_ok())
+}
+
+// a tree over the maximal cliques of a chordal graph such that the cliques holding any vertex
+// form a subtree; None if the graph is not chordal. Components are joined by empty separators.
+pub fn clique_tree(n: usize, edges: &[(usize, usize)]) -> Option<TreeDecomposition> {
+  let peo = match chordality(n, edges) {
+    Chordality::Chordal(peo) => peo,
+    Chordality::NotChordal(_) => return None,
+  };
+  // the elimination bags are cliques; contracting every tree edge whose one side is contained
+  // in the other leaves exactly the maximal cliques
+  let td = from_elimination_ordering(n, edges, &peo);
+  let k = td.bags.len();
+  let mut tree = vec![vec![]; k];
+  for &(a, b) in &td.edges {
+    tree[a].push(b);
+    tree[b].push(a);
+  }
+  let mut alive = vec![true; k];
+  let mut changed = true;
+  while changed {
+    changed = false;
+    for a in 0..k {
+      if !alive[a] {
+        continue;
+      }
+      if let Some(&b) = tree[a].iter().find(|&&b| is_subset(&td.bags[a], &td.bags[b])) {
+        alive[a] = false;
+        let moved = std::mem::take(&mut tree[a]);
+        tree[b].retain(|&x| x != a);
+        for x in moved.into_iter().filter(|&x| x != b) {
+          for y in tree[x].iter_mut() {
+            if *y == a {
+              *y = b;
+            }
+          }
+          tree[b].push(x);
+        }
+        changed = true;
+      }
+    }
+  }
+  let mut index = vec![usize::MAX; k];
+  let mut bags = vec![];
+  for a in (0..k).filter(|&a| alive[a]) {
+    index[a] = bags.len();
+    bags.push(td.bags[a].clone());
+  }
+  let mut tree_edges = vec![];
+  for a in (0..k).filter(|&a| alive[a]) {
+    for &b in tree[a].iter().filter(|&&b| a < b) {
+      tree_edges.push((index[a], index[b]));
+    }
+  }
+  Some(TreeDecomposition { bags, edges: tree_edges })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  // chordal iff simplicial vertices can be peeled off until nothing is left
+  fn brute_force_chordal(n: usize, adj: &[Vec<usize>]) -> bool {
+    let mut alive = vec![true; n];
+    for _ in 0..n {
+      let simplicial = (0..n).find(|&v| {
+        alive[v] && {
+          let nbrs: Vec<usize> = adj[v].iter().cloned().filter(|&u| alive[u]).collect();
+          nbrs.iter().all(|&a| nbrs.iter().all(|&b| a == b || is_adjacent(adj, a, b)))
+        }
+      });
+      match simplicial {
+        Some(v) => alive[v] = false,
+        None => return false,
+      }
+    }
+    true
+  }
+
+  fn is_clique(adj: &[Vec<usize>], set: &[usize]) -> bool {
+    set.iter().all(|&a| set.iter().all(|&b| a == b || is_adjacent(adj, a, b)))
+  }
+
+  fn maximal_clique_count(n: usize, adj: &[Vec<usize>]) -> usize {
+    let sets: Vec<Vec<usize>> = (1u32..1 << n)
+      .map(|mask| (0..n).filter(|&v| mask >> v & 1 == 1).collect::<Vec<_>>())
+      .filter(|s| is_clique(adj, s))
+      .collect();
+    sets.iter().filter(|s| !sets.iter().any(|t| t.len() > s.len() && is_subset(s, t))).count()
+  }
+
+  // checks the answer against brute force, and the ordering or cycle it comes with
+  fn check(n: usize, edges: &[(usize, usize)]) {
+    let adj = adjacency(n, edges);
+    let expected = brute_force_chordal(n, &adj);
+    match chordality(n, edges) {
+      Chordality::Chordal(peo) => {
+        assert!(expected);
+        let mut position = vec![0; n];
+        for (i, &v) in peo.iter().enumerate() {
+          position[v] = i;
+        }
+        for v in 0..n {
+          let later: Vec<usize> = adj[v].iter().cloned().filter(|&u| position[u] > position[v]).collect();
+          assert!(is_clique(&adj, &later));
+        }
+        let tree = clique_tree(n, edges).unwrap();
+        assert!(tree.is_valid(n, edges));
+        assert!(tree.bags.iter().all(|b| is_clique(&adj, b)));
+        assert_eq!(maximal_clique_count(n, &adj), tree.bags.len());
+      },
+      Chordality::NotChordal(cycle) => {
+        assert!(!expected);
+        let len = cycle.len();
+        assert!(len >= 4);
+        for i in 0..len {
+          for j in i + 1..len {
+            let consecutive = j == i + 1 || (i == 0 && j == len - 1);
+            assert_eq!(consecutive, is_adjacent(&adj, cycle[i], cycle[j]));
+          }
+        }
+        assert!(clique_tree(n, edges).is_none());
+      },
+    }
+  }
+
+  #[test]
+  fn test_random_graphs() {
+    let mut rand = rand_gen(23);
+    for round in 0..500 {
+      let n = 1 + rand(9) as usize;
+      let edges: Vec<_> = if round % 2 == 0 {
+        (0..rand(3 * n as u64)).map(|_| (rand(n as u64) as usize, rand(n as u64) as usize)).collect()
+      } else {
+        // every new vertex attaches to a clique found so far, which keeps the graph chordal
+        let mut edges = vec![];
+        let mut cliques: Vec<Vec<usize>> = vec![vec![]];
+        for v in 0..n {
+          let base = cliques[rand(cliques.len() as u64) as usize].clone();
+          let mut clique: Vec<usize> = base.into_iter().filter(|_| rand(3) > 0).collect();
+          edges.extend(clique.iter().map(|&u| (u, v)));
+          clique.push(v);
+          cliques.push(clique);
+        }
+        edges
+      };
+      check(n, &edges);
+    }
+  }
+
+  #[test]
+  fn test_all_small_graphs() {
+    for n in 0..=6 {
+      let pairs: Vec<(usize, usize)> = (0..n).flat_map(|i| (i + 1..n).map(move |j| (i, j))).collect();
+      for mask in 0..1u32 << pairs.len() {
+        let edges: Vec<_> = pairs.iter().enumerate().filter(|&(k, _)| mask >> k & 1 == 1).map(|(_, &e)| e).collect();
+        check(n, &edges);
+      }
+    }
+  }
+
+  #[test]
+  fn test_known_graphs() {
+    let square = [(0, 1), (1, 2), (2, 3), (3, 0)];
+    assert!(!is_chordal(4, &square));
+    assert!(is_chordal(4, &[(0, 1), (1, 2), (2, 3), (3, 0), (0, 2)]));
+    let tree = clique_tree(5, &[(0, 1), (1, 2), (2, 0), (2, 3)]).unwrap();
+    let mut bags = tree.bags.clone();
+    bags.sort();
+    assert_eq!(vec![vec![0, 1, 2], vec![2, 3], vec![4]], bags);
+    assert_eq!(2, tree.edges.len());
+  }
+}
@@ -0,0 +1,248 @@
+use std::collections::HashSet;
+
+// Vertex coloring: greedy first-fit under a few vertex orders, DSATUR, and an exact
+// branch and bound for small graphs. Colors are 0..count; self-loops are ignored.
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum GreedyOrder {
+  Natural, // 0, 1, 2, ...
+  LargestFirst, // by decreasing degree
+  SmallestLast, // reverse of repeatedly removing a minimum degree vertex, uses at most degeneracy + 1 colors
+}
+
+#[derive(Clone, Debug)]
+pub struct Coloring {
+  pub colors: Vec<usize>,
+  pub count: usize,
+}
+
+fn adjacency(n: usize, edges: &[(usize, usize)]) -> Vec<Vec<usize>> {
+  let mut adj = vec![vec![]; n];
+  for &(u, v) in edges {
+    if u != v {
+      adj[u].push(v);
+      adj[v].push(u);
+    }
+  }
+  for list in adj.iter_mut() {
+    list.sort_unstable();
+    list.dedup();
+  }
+  adj
+}
+
+fn smallest_last_order(adj: &[Vec<usize>]) -> Vec<usize> {
+  let n = adj.len();
+  let mut degree: Vec<usize> = adj.iter().map(|a| a.len()).collect();
+  let mut buckets = vec![vec![]; n.max(1)];
+  for v in 0..n {
+    buckets[degree[v]].push(v);
+  }
+  let mut removed = vec![false; n];
+  let mut order = vec![];
+  let mut d = 0;
+  while order.len() < n {
+    let v = match buckets[d].pop() {
+      Some(v) => v,
+      None => {
+        d += 1;
+        continue;
+      },
+    };
+    if removed[v] || degree[v] != d {
+      continue;
+    }
+    removed[v] = true;
+    order.push(v);
+    for &u in &adj[v] {
+      if !removed[u] {
+        degree[u] -= 1;
+        buckets[degree[u]].push(u);
+        d = d.min(degree[u]);
+      }
+    }
+  }
+  order.reverse();
+  order
+}
+
+// first-fit: each vertex takes the smallest color unused by its colored neighbors
+fn first_fit(adj: &[Vec<usize>], order: &[usize]) -> Coloring {
+  let n = adj.len();
+  let mut colors = vec![usize::MAX; n];
+  let mut mark = vec![usize::MAX; n + 1]; // mark[c] == v means c is taken around v
+  let mut count = 0;
+  for &v in order {
+    for &u in &adj[v] {
+      if colors[u] != usize::MAX {
+        mark[colors[u]] = v;
+      }
+    }
+    let c = (0..).find(|&c| mark[c] != v).unwrap();
+    colors[v] = c;
+    count = count.max(c + 1);
+  }
+  Coloring { colors, count }
+}
+
+pub fn greedy_coloring(n: usize, edges: &[(usize, usize)], order: GreedyOrder) -> Coloring {
+  let adj = adjacency(n, edges);
+  let order = match order {
+    GreedyOrder::Natural => (0..n).collect(),
+    GreedyOrder::LargestFirst => {
+      let mut order: Vec<usize> = (0..n).collect();
+      order.sort_by_key(|&v| std::cmp::Reverse(adj[v].len()));
+      order
+    },
+    GreedyOrder::SmallestLast => smallest_last_order(&adj),
+  };
+  first_fit(&adj, &order)
+}
+
+// DSATUR: always color the vertex seeing the most distinct colors, ties by degree; O(n^2) time,
+// O(n + m) memory
+pub fn dsatur(n: usize, edges: &[(usize, usize)]) -> Coloring {
+  let adj = adjacency(n, edges);
+  let mut colors = vec![usize::MAX; n];
+  let mut seen: Vec<HashSet<usize>> = vec![HashSet::new(); n]; // the colors of each vertex's neighbors, its saturation in size
+  let mut count = 0;
+  for _ in 0..n {
+    let v = (0..n)
+      .filter(|&v| colors[v] == usize::MAX)
+      .max_by_key(|&v| (seen[v].len(), adj[v].len(), std::cmp::Reverse(v)))
+      .unwrap();
+    let c = (0..).find(|c| !seen[v].contains(c)).unwrap();
+    colors[v] = c;
+    count = count.max(c + 1);
+    for &u in &adj[v] {
+      seen[u].insert(c);
+    }
+  }
+  Coloring { colors, count }
+}
+
+struct Exact<'a> {
+  adj: &'a [Vec<usize>],
+  colors: Vec<usize>,
+  best: Vec<usize>,
+  best_count: usize,
+}
+
+impl<'a> Exact<'a> {
+  fn search(&mut self, colored: usize, used: usize) {
+    let n = self.adj.len();
+    if used >= self.best_count {
+      return;
+    }
+    if colored == n {
+      self.best = self.colors.clone();
+      self.best_count = used;
+      return;
+    }
+    // dsatur branching order: the most constrained vertex first
+    let mut pick = None;
+    let mut pick_key = (0, 0);
+    for v in (0..n).filter(|&v| self.colors[v] == usize::MAX) {
+      let mut around: Vec<usize> = self.adj[v].iter().map(|&u| self.colors[u]).filter(|&c| c != usize::MAX).collect();
+      around.sort_unstable();
+      around.dedup();
+      let key = (around.len(), self.adj[v].len());
+      if pick.is_none() || key > pick_key {
+        pick = Some((v, around));
+        pick_key = key;
+      }
+    }
+    let (v, around) = pick.unwrap();
+    // a fresh color is only tried once, every unused color is symmetric
+    for c in 0..(used + 1).min(self.best_count - 1) {
+      if around.binary_search(&c).is_err() {
+        self.colors[v] = c;
+        self.search(colored + 1, used.max(c + 1));
+        self.colors[v] = usize::MAX;
+      }
+    }
+  }
+}
+
+// optimal coloring by branch and bound seeded with DSATUR, exponential in the worst case
+pub fn exact_coloring(n: usize, edges: &[(usize, usize)]) -> Coloring {
+  let upper = dsatur(n, edges);
+  let adj = adjacency(n, edges);
+  let mut exact = Exact { adj: &adj, colors: vec![usize::MAX; n], best: upper.colors, best_count: upper.count };
+  exact.search(0, 0);
+  Coloring { colors: exact.best, count: exact.best_count }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  fn check(coloring: &Coloring, n: usize, edges: &[(usize, usize)]) {
+    assert_eq!(n, coloring.colors.len());
+    assert!(coloring.colors.iter().all(|&c| c < coloring.count));
+    assert!(edges.iter().all(|&(u, v)| u == v || coloring.colors[u] != coloring.colors[v]));
+  }
+
+  fn chromatic_number(n: usize, edges: &[(usize, usize)]) -> usize {
+    (0..=n).find(|&k| {
+      let total = (k as u64).pow(n as u32);
+      (0..total).any(|mut code| {
+        let mut colors = vec![0; n];
+        for c in colors.iter_mut() {
+          *c = (code % k as u64) as usize;
+          code /= k as u64;
+        }
+        edges.iter().all(|&(u, v)| u == v || colors[u] != colors[v])
+      })
+    }).unwrap()
+  }
+
+  #[test]
+  fn test_against_brute_force() {
+    let mut rand = rand_gen(5);
+    for _ in 0..200 {
+      let n = 1 + rand(7) as usize;
+      let edges: Vec<_> = (0..rand(3 * n as u64))
+        .map(|_| (rand(n as u64) as usize, rand(n as u64) as usize))
+        .collect();
+      let exact = exact_coloring(n, &edges);
+      check(&exact, n, &edges);
+      assert_eq!(chromatic_number(n, &edges), exact.count);
+      let heuristics = [
+        greedy_coloring(n, &edges, GreedyOrder::Natural),
+        greedy_coloring(n, &edges, GreedyOrder::LargestFirst),
+        greedy_coloring(n, &edges, GreedyOrder::SmallestLast),
+        dsatur(n, &edges),
+      ];
+      for coloring in heuristics.iter() {
+        check(coloring, n, &edges);
+        assert!(coloring.count >= exact.count);
+      }
+    }
+  }
+
+  #[test]
+  fn test_known_graphs() {
+    // crown graph: natural order first-fit needs n/2 colors on a bipartite graph
+    let k = 6;
+    let crown: Vec<_> = (0..k).flat_map(|i| (0..k).filter(move |&j| j != i).map(move |j| (2 * i, 2 * j + 1))).collect();
+    assert_eq!(k, greedy_coloring(2 * k, &crown, GreedyOrder::Natural).count);
+    assert_eq!(2, dsatur(2 * k, &crown).count);
+    assert_eq!(2, exact_coloring(2 * k, &crown).count);
+    // odd wheel needs 4 colors
+    let mut wheel: Vec<_> = (0..5).map(|i| (i, (i + 1) % 5)).collect();
+    wheel.extend((0..5).map(|i| (i, 5)));
+    assert_eq!(4, exact_coloring(6, &wheel).count);
+    // a grid is 2-degenerate, so smallest last stays within 3 colors
+    let grid: Vec<_> = (0..100).flat_map(|v| {
+      let mut e = vec![];
+      if v % 10 != 9 { e.push((v, v + 1)); }
+      if v < 90 { e.push((v, v + 10)); }
+      e
+    }).collect();
+    let coloring = greedy_coloring(100, &grid, GreedyOrder::SmallestLast);
+    check(&coloring, 100, &grid);
+    assert!(coloring.count <= 3);
+  }
+}
@@ -0,0 +1,245 @@
+// Community detection on undirected weighted graphs given as (n, [(u, v, weight)]).
+// Partitions are returned as groups: each group sorted, groups ordered by their smallest vertex,
+// the same shape a union-find's grouping produces.
+
+fn weighted_adjacency(n: usize, edges: &[(usize, usize, f64)]) -> Vec<Vec<(usize, f64)>> {
+  let mut adj = vec![vec![]; n];
+  for &(u, v, w) in edges {
+    adj[u].push((v, w));
+    if u != v {
+      adj[v].push((u, w));
+    }
+  }
+  adj
+}
+
+// relabels to groups; labels are arbitrary usize values
+fn groups_from_labels(labels: &[usize]) -> Vec<Vec<usize>> {
+  let mut index = std::collections::HashMap::new();
+  let mut groups: Vec<Vec<usize>> = vec![];
+  for (v, &label) in labels.iter().enumerate() {
+    let i = *index.entry(label).or_insert_with(|| {
+      groups.push(vec![]);
+      groups.len() - 1
+    });
+    groups[i].push(v);
+  }
+  groups
+}
+
+// asynchronous label propagation: vertices are visited in a shuffled order and each adopts the
+// label carrying the most edge weight among its neighbors, keeping its own label on ties, until
+// a full round changes nothing or max_rounds is reached. Results depend on seed.
+pub fn label_propagation(n: usize, edges: &[(usize, usize, f64)], seed: u64, max_rounds: usize) -> Vec<Vec<usize>> {
+  let adj = weighted_adjacency(n, edges);
+  let mut state = seed | 1;
+  let mut rand = move |m: usize| {
+    state ^= state << 13;
+    state ^= state >> 7;
+    state ^= state << 17;
+    (state % m as u64) as usize
+  };
+  let mut labels: Vec<usize> = (0..n).collect();
+  let mut order: Vec<usize> = (0..n).collect();
+  let mut weight = vec![0.0f64; n]; // scratch, weight per label around the current vertex
+  for _ in 0..max_rounds {
+    for i in (1..n).rev() {
+      order.swap(i, rand(i + 1));
+    }
+    let mut changed = false;
+    for &v in &order {
+      for &(u, w) in &adj[v] {
+        if u != v {
+          weight[labels[u]] += w;
+        }
+      }
+      let own = labels[v];
+      let mut best = own;
+      for &(u, _) in &adj[v] {
+        let label = labels[u];
+        if weight[label] > weight[best] || (weight[label] == weight[best] && best != own && label < best) {
+          best = label;
+        }
+      }
+      if weight[best] > weight[own] {
+        labels[v] = best;
+        changed = true;
+      }
+      for &(u, _) in &adj[v] {
+        weight[labels[u]] = 0.0;
+      }
+      weight[own] = 0.0;
+    }
+    if !changed {
+      break;
+    }
+  }
+  groups_from_labels(&labels)
+}
+
+// Newman's modularity of a partition, in [-1/2, 1]
+pub fn modularity(n: usize, edges: &[(usize, usize, f64)], groups: &[Vec<usize>]) -> f64 {
+  let mut community = vec![usize::MAX; n];
+  for (c, group) in groups.iter().enumerate() {
+    for &v in group {
+      community[v] = c;
+    }
+  }
+  let mut degree = vec![0.0; n];
+  let mut inside = vec![0.0; groups.len()];
+  let mut total_weight = 0.0;
+  for &(u, v, w) in edges {
+    degree[u] += w;
+    degree[v] += w;
+    total_weight += w;
+    if community[u] == community[v] {
+      inside[community[u]] += w;
+    }
+  }
+  if total_weight == 0.0 {
+    return 0.0;
+  }
+  let mut total = vec![0.0; groups.len()];
+  for v in 0..n {
+    total[community[v]] += degree[v];
+  }
+  let m2 = 2.0 * total_weight;
+  (0..groups.len()).map(|c| 2.0 * inside[c] / m2 - (total[c] / m2) * (total[c] / m2)).sum()
+}
+
+// the local moving phase of Louvain: starting from singletons, each vertex in turn moves to the
+// neighboring community with the largest modularity gain, until no move improves modularity.
+// No aggregation into a coarser graph follows, so this is a single level.
+pub fn modularity_communities(n: usize, edges: &[(usize, usize, f64)]) -> Vec<Vec<usize>> {
+  let adj = weighted_adjacency(n, edges);
+  let mut degree = vec![0.0f64; n];
+  let mut total_weight = 0.0;
+  for &(u, v, w) in edges {
+    degree[u] += w;
+    degree[v] += w;
+    total_weight += w;
+  }
+  let mut community: Vec<usize> = (0..n).collect();
+  if total_weight == 0.0 {
+    return groups_from_labels(&community);
+  }
+  let m2 = 2.0 * total_weight;
+  let mut total = degree.clone(); // sum of degrees per community
+  let mut link = vec![0.0f64; n]; // scratch, weight from the current vertex into each community
+  let mut improved = true;
+  while improved {
+    improved = false;
+    for v in 0..n {
+      let own = community[v];
+      let mut touched = vec![own];
+      for &(u, w) in &adj[v] {
+        if u != v {
+          if link[community[u]] == 0.0 {
+            touched.push(community[u]);
+          }
+          link[community[u]] += w;
+        }
+      }
+      total[own] -= degree[v];
+      // gain of joining c, up to terms that do not depend on c
+      let gain = |c: usize, link: &[f64], total: &[f64]| link[c] - total[c] * degree[v] / m2;
+      let mut best = own;
+      let mut best_gain = gain(own, &link, &total);
+      for &c in &touched {
+        let g = gain(c, &link, &total);
+        if g > best_gain + 1e-12 {
+          best = c;
+          best_gain = g;
+        }
+      }
+      total[best] += degree[v];
+      community[v] = best;
+      if best != own {
+        improved = true;
+      }
+      for c in touched {
+        link[c] = 0.0;
+      }
+    }
+  }
+  groups_from_labels(&community)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  fn check_groups(n: usize, groups: &[Vec<usize>]) {
+    let mut seen = vec![false; n];
+    for group in groups {
+      assert!(!group.is_empty());
+      assert!(group.windows(2).all(|w| w[0] < w[1]));
+      for &v in group {
+        assert!(!seen[v]);
+        seen[v] = true;
+      }
+    }
+    assert!(seen.iter().all(|&s| s));
+    assert!(groups.windows(2).all(|w| w[0][0] < w[1][0]));
+  }
+
+  // k cliques of size s in a ring, neighboring cliques joined by one light edge
+  fn ring_of_cliques(k: usize, s: usize) -> (usize, Vec<(usize, usize, f64)>) {
+    let mut edges = vec![];
+    for c in 0..k {
+      for i in 0..s {
+        for j in i + 1..s {
+          edges.push((c * s + i, c * s + j, 1.0));
+        }
+      }
+      edges.push((c * s, ((c + 1) % k) * s + 1, 0.5));
+    }
+    (k * s, edges)
+  }
+
+  #[test]
+  fn test_ring_of_cliques() {
+    let (n, edges) = ring_of_cliques(6, 5);
+    let expected: Vec<Vec<usize>> = (0..6).map(|c| (c * 5..c * 5 + 5).collect()).collect();
+    let louvain = modularity_communities(n, &edges);
+    check_groups(n, &louvain);
+    assert_eq!(expected, louvain);
+    for seed in 1..10 {
+      let lp = label_propagation(n, &edges, seed, 100);
+      check_groups(n, &lp);
+      assert_eq!(expected, lp);
+    }
+    let singletons: Vec<Vec<usize>> = (0..n).map(|v| vec![v]).collect();
+    assert!(modularity(n, &edges, &expected) > modularity(n, &edges, &singletons));
+    assert!((modularity(n, &edges, &[(0..n).collect()])).abs() < 1e-12);
+  }
+
+  #[test]
+  fn test_random_graphs() {
+    let mut rand = rand_gen(31);
+    for _ in 0..100 {
+      let n = 1 + rand(30) as usize;
+      let edges: Vec<(usize, usize, f64)> = (0..rand(3 * n as u64))
+        .map(|_| (rand(n as u64) as usize, rand(n as u64) as usize, 1.0 + rand(4) as f64))
+        .collect();
+      let louvain = modularity_communities(n, &edges);
+      check_groups(n, &louvain);
+      let q = modularity(n, &edges, &louvain);
+      let singletons: Vec<Vec<usize>> = (0..n).map(|v| vec![v]).collect();
+      assert!(q >= modularity(n, &edges, &singletons) - 1e-12);
+      // a local optimum: no vertex can move into a neighboring community to improve modularity
+      for &(v, u, _) in &edges {
+        for c in (0..louvain.len()).filter(|&c| louvain[c].contains(&u)) {
+          let mut moved: Vec<Vec<usize>> = louvain.iter()
+            .map(|g| g.iter().cloned().filter(|&u| u != v).collect::<Vec<_>>())
+            .collect();
+          moved[c].push(v);
+          moved.retain(|g| !g.is_empty());
+          assert!(modularity(n, &edges, &moved) <= q + 1e-9);
+        }
+      }
+      check_groups(n, &label_propagation(n, &edges, 7, 50));
+    }
+  }
+}
@@ -0,0 +1,424 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::rc::Rc;
+
+// Compressed sparse row adjacency: the neighbors of v are targets[offsets[v]..offsets[v + 1]],
+// sorted. One allocation for all edges keeps neighbor scans cache friendly.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Csr {
+  offsets: Vec<usize>,
+  targets: Vec<usize>,
+}
+
+impl Csr {
+  // directed arcs, kept as given (duplicates included)
+  pub fn from_arcs(n: usize, arcs: &[(usize, usize)]) -> Csr {
+    let mut offsets = vec![0; n + 1];
+    for &(u, _) in arcs {
+      offsets[u + 1] += 1;
+    }
+    for v in 0..n {
+      offsets[v + 1] += offsets[v];
+    }
+    let mut fill = offsets.clone();
+    let mut targets = vec![0; arcs.len()];
+    for &(u, v) in arcs {
+      targets[fill[u]] = v;
+      fill[u] += 1;
+    }
+    for v in 0..n {
+      targets[offsets[v]..offsets[v + 1]].sort_unstable();
+    }
+    Csr { offsets, targets }
+  }
+
+  pub fn len(&self) -> usize {
+    self.offsets.len() - 1
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  // number of stored arcs; an undirected edge is stored once per direction
+  pub fn arc_count(&self) -> usize {
+    self.targets.len()
+  }
+
+  pub fn neighbors(&self, v: usize) -> &[usize] {
+    &self.targets[self.offsets[v]..self.offsets[v + 1]]
+  }
+
+  pub fn degree(&self, v: usize) -> usize {
+    self.offsets[v + 1] - self.offsets[v]
+  }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Relabel {
+  Keep,
+  Bfs, // breadth-first per component from a minimum degree vertex, low degree neighbors first (Cuthill-McKee)
+  DegreeDescending, // hubs first, ties by old id
+}
+
+// Builds a Csr from an edge list, optionally cleaning it and renumbering the vertices:
+//   let (csr, new_id) = GraphBuilder::new(n).edges(&edges).remove_self_loops()
+//     .remove_parallel_edges().relabel(Relabel::Bfs).build();
+// new_id[old] is the id vertex old got in csr.
+#[derive(Clone, Debug)]
+pub struct GraphBuilder {
+  n: usize,
+  edges: Vec<(usize, usize)>,
+  directed: bool,
+  remove_self_loops: bool,
+  remove_parallel_edges: bool,
+  relabel: Relabel,
+}
+
+impl GraphBuilder {
+  pub fn new(n: usize) -> GraphBuilder {
+    GraphBuilder {
+      n,
+      edges: vec![],
+      directed: false,
+      remove_self_loops: false,
+      remove_parallel_edges: false,
+      relabel: Relabel::Keep,
+    }
+  }
+
+  pub fn edge(mut self, u: usize, v: usize) -> GraphBuilder {
+    assert!(u < self.n && v < self.n);
+    self.edges.push((u, v));
+    self
+  }
+
+  pub fn edges(mut self, edges: &[(usize, usize)]) -> GraphBuilder {
+    for &(u, v) in edges {
+      self = self.edge(u, v);
+    }
+    self
+  }
+
+  // edges are undirected unless this is called
+  pub fn directed(mut self) -> GraphBuilder {
+    self.directed = true;
+    self
+  }
+
+  pub fn remove_self_loops(mut self) -> GraphBuilder {
+    self.remove_self_loops = true;
+    self
+  }
+
+  // keeps one copy of every edge; undirected (u, v) and (v, u) are the same edge
+  pub fn remove_parallel_edges(mut self) -> GraphBuilder {
+    self.remove_parallel_edges = true;
+    self
+  }
+
+  pub fn relabel(mut self, relabel: Relabel) -> GraphBuilder {
+    self.relabel = relabel;
+    self
+  }
+
+  fn new_ids(&self, csr: &Csr) -> Vec<usize> {
+    let n = self.n;
+    let order: Vec<usize> = match self.relabel {
+      Relabel::Keep => return (0..n).collect(),
+      Relabel::DegreeDescending => {
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by_key(|&v| std::cmp::Reverse(csr.degree(v)));
+        order
+      },
+      Relabel::Bfs => {
+        let mut order = vec![];
+        let mut seen = vec![false; n];
+        let mut starts: Vec<usize> = (0..n).collect();
+        starts.sort_by_key(|&v| (csr.degree(v), v));
+        for start in starts {
+          if seen[start] { continue; }
+          seen[start] = true;
+          let mut queue = VecDeque::new();
+          queue.push_back(start);
+          while let Some(u) = queue.pop_front() {
+            order.push(u);
+            let mut next: Vec<usize> = csr.neighbors(u).iter().cloned().filter(|&v| !seen[v]).collect();
+            next.sort_by_key(|&v| (csr.degree(v), v));
+            next.dedup();
+            for v in next {
+              seen[v] = true;
+              queue.push_back(v);
+            }
+          }
+        }
+        order
+      },
+    };
+    let mut new_id = vec![0; n];
+    for (i, &v) in order.iter().enumerate() {
+      new_id[v] = i;
+    }
+    new_id
+  }
+
+  // build, frozen into a snapshot that further edits go to
+  pub fn build_snapshot(self) -> (GraphSnapshot, Vec<usize>) {
+    let directed = self.directed;
+    let (csr, new_id) = self.build();
+    (GraphSnapshot::new(csr, directed), new_id)
+  }
+
+  pub fn build(self) -> (Csr, Vec<usize>) {
+    let mut arcs: Vec<(usize, usize)> = vec![];
+    for &(u, v) in &self.edges {
+      if self.remove_self_loops && u == v {
+        continue;
+      }
+      if self.directed {
+        arcs.push((u, v));
+      } else {
+        // a self-loop appears once in its own neighbor list
+        arcs.push((u, v));
+        if u != v {
+          arcs.push((v, u));
+        }
+      }
+    }
+    if self.remove_parallel_edges {
+      arcs.sort_unstable();
+      arcs.dedup();
+    }
+    let csr = Csr::from_arcs(self.n, &arcs);
+    let new_id = self.new_ids(&csr);
+    if self.relabel == Relabel::Keep {
+      return (csr, new_id);
+    }
+    let relabeled: Vec<(usize, usize)> = arcs.iter().map(|&(u, v)| (new_id[u], new_id[v])).collect();
+    (Csr::from_arcs(self.n, &relabeled), new_id)
+  }
+}
+
+#[derive(Clone, Debug, Default)]
+struct Overlay {
+  added: HashMap<usize, Vec<usize>>, // arcs per source, sorted
+  removed: HashSet<(usize, usize)>, // arcs of the base hidden from view
+}
+
+// A graph state to branch from: a shared frozen Csr plus a small overlay of added and removed
+// edges. Cloning is O(1); the overlay is copied on the first edit after a clone, and the base
+// is never copied. freeze folds the overlay into a new Csr once it grows large.
+#[derive(Clone, Debug)]
+pub struct GraphSnapshot {
+  base: Rc<Csr>,
+  overlay: Rc<Overlay>,
+  directed: bool, // undirected edges are edited in both directions
+}
+
+impl GraphSnapshot {
+  pub fn new(csr: Csr, directed: bool) -> GraphSnapshot {
+    GraphSnapshot { base: Rc::new(csr), overlay: Rc::new(Overlay::default()), directed }
+  }
+
+  pub fn len(&self) -> usize {
+    self.base.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.base.is_empty()
+  }
+
+  fn arcs(&self, u: usize, v: usize) -> Vec<(usize, usize)> {
+    if self.directed || u == v { vec![(u, v)] } else { vec![(u, v), (v, u)] }
+  }
+
+  // adds one more copy of the edge
+  pub fn add_edge(&mut self, u: usize, v: usize) {
+    assert!(u < self.len() && v < self.len());
+    let arcs = self.arcs(u, v);
+    let overlay = Rc::make_mut(&mut self.overlay);
+    for (a, b) in arcs {
+      let targets = overlay.added.entry(a).or_default();
+      let at = targets.partition_point(|&t| t <= b);
+      targets.insert(at, b);
+    }
+  }
+
+  // removes every copy of the edge; false if there was none
+  pub fn remove_edge(&mut self, u: usize, v: usize) -> bool {
+    if !self.has_edge(u, v) {
+      return false;
+    }
+    let arcs = self.arcs(u, v);
+    let overlay = Rc::make_mut(&mut self.overlay);
+    for (a, b) in arcs {
+      if let Some(targets) = overlay.added.get_mut(&a) {
+        targets.retain(|&t| t != b);
+      }
+      overlay.removed.insert((a, b));
+    }
+    true
+  }
+
+  pub fn has_edge(&self, u: usize, v: usize) -> bool {
+    let added = self.overlay.added.get(&u).is_some_and(|targets| targets.binary_search(&v).is_ok());
+    added || (!self.overlay.removed.contains(&(u, v)) && self.base.neighbors(u).binary_search(&v).is_ok())
+  }
+
+  // sorted, parallel edges repeated, like Csr::neighbors
+  pub fn neighbors(&self, v: usize) -> Vec<usize> {
+    let base = self.base.neighbors(v).iter().cloned().filter(|&u| !self.overlay.removed.contains(&(v, u)));
+    let mut merged: Vec<usize> = base.collect();
+    if let Some(added) = self.overlay.added.get(&v) {
+      merged.extend_from_slice(added);
+      merged.sort_unstable();
+    }
+    merged
+  }
+
+  pub fn degree(&self, v: usize) -> usize {
+    self.neighbors(v).len()
+  }
+
+  // the current state as a plain Csr
+  pub fn freeze(&self) -> Csr {
+    let arcs: Vec<(usize, usize)> = (0..self.len())
+      .flat_map(|u| self.neighbors(u).into_iter().map(move |v| (u, v)))
+      .collect();
+    Csr::from_arcs(self.len(), &arcs)
+  }
+
+  // a snapshot of the same state with an empty overlay
+  pub fn compact(&self) -> GraphSnapshot {
+    GraphSnapshot::new(self.freeze(), self.directed)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_cleanup() {
+    let edges = [(0, 1), (1, 0), (1, 1), (1, 2), (1, 2), (3, 3)];
+    let (csr, new_id) = GraphBuilder::new(4).edges(&edges).build();
+    assert_eq!(vec![0, 1, 2, 3], new_id);
+    assert_eq!(&[1, 1], csr.neighbors(0));
+    assert_eq!(&[0, 0, 1, 2, 2], csr.neighbors(1));
+    let (csr, _) = GraphBuilder::new(4).edges(&edges).remove_self_loops().remove_parallel_edges().build();
+    assert_eq!(&[1], csr.neighbors(0));
+    assert_eq!(&[0, 2], csr.neighbors(1));
+    assert!(csr.neighbors(3).is_empty());
+    assert_eq!(4, csr.arc_count());
+    let (csr, _) = GraphBuilder::new(4).edges(&edges).directed().remove_parallel_edges().build();
+    assert_eq!(&[0, 1, 2], csr.neighbors(1));
+    assert_eq!(6 - 1, csr.arc_count());
+  }
+
+  #[test]
+  fn test_relabel_preserves_structure() {
+    let mut rand = rand_gen(3);
+    for _ in 0..100 {
+      let n = 1 + rand(20) as usize;
+      let edges: Vec<(usize, usize)> = (0..rand(3 * n as u64))
+        .map(|_| (rand(n as u64) as usize, rand(n as u64) as usize))
+        .collect();
+      let (plain, _) = GraphBuilder::new(n).edges(&edges).remove_parallel_edges().build();
+      for relabel in [Relabel::Bfs, Relabel::DegreeDescending] {
+        let (csr, new_id) = GraphBuilder::new(n).edges(&edges).remove_parallel_edges().relabel(relabel).build();
+        let mut sorted = new_id.clone();
+        sorted.sort_unstable();
+        assert_eq!((0..n).collect::<Vec<_>>(), sorted);
+        for v in 0..n {
+          let mut mapped: Vec<usize> = plain.neighbors(v).iter().map(|&u| new_id[u]).collect();
+          mapped.sort_unstable();
+          assert_eq!(&mapped[..], csr.neighbors(new_id[v]));
+        }
+        if relabel == Relabel::DegreeDescending {
+          assert!((1..n).all(|i| csr.degree(i - 1) >= csr.degree(i)));
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn test_bfs_order_on_path() {
+    // a path numbered in scrambled order gets consecutive ids from one end
+    let edges = [(3, 0), (0, 4), (4, 1), (1, 2)];
+    let (csr, new_id) = GraphBuilder::new(5).edges(&edges).relabel(Relabel::Bfs).build();
+    assert_eq!(vec![3, 1, 0, 4, 2], new_id);
+    for v in 0..4 {
+      assert!(csr.neighbors(v).contains(&(v + 1)));
+    }
+  }
+
+  #[test]
+  fn test_snapshots_branch() {
+    let edges = [(0, 1), (1, 2), (2, 3), (3, 0)];
+    let (base, _) = GraphBuilder::new(5).edges(&edges).build_snapshot();
+    let mut what_if = base.clone();
+    assert!(Rc::ptr_eq(&base.overlay, &what_if.overlay));
+    assert!(what_if.remove_edge(2, 1));
+    what_if.add_edge(4, 0);
+    assert!(!Rc::ptr_eq(&base.overlay, &what_if.overlay));
+    assert!(Rc::ptr_eq(&base.base, &what_if.base));
+    assert_eq!(vec![0, 2], base.neighbors(1));
+    assert_eq!(vec![0], what_if.neighbors(1));
+    assert_eq!(vec![1, 3, 4], what_if.neighbors(0));
+    assert!(!what_if.has_edge(1, 2) && what_if.has_edge(0, 4));
+    assert!(!what_if.remove_edge(1, 2));
+    let frozen = what_if.freeze();
+    assert_eq!(&[3], frozen.neighbors(2));
+    assert_eq!(what_if.neighbors(4), frozen.neighbors(4));
+    assert_eq!(8, frozen.arc_count());
+  }
+
+  #[test]
+  fn test_snapshot_against_multiset() {
+    let mut rand = rand_gen(59);
+    for round in 0..40 {
+      let n = 1 + rand(8) as usize;
+      let directed = round % 2 == 0;
+      let edges: Vec<(usize, usize)> = (0..rand(2 * n as u64))
+        .map(|_| (rand(n as u64) as usize, rand(n as u64) as usize))
+        .collect();
+      let mut builder = GraphBuilder::new(n).edges(&edges);
+      if directed {
+        builder = builder.directed();
+      }
+      let (csr, _) = builder.clone().build();
+      let (snapshot, _) = builder.build_snapshot();
+      // (snapshot, arcs as a sorted multiset) per branch
+      let mut arcs: Vec<(usize, usize)> = (0..n).flat_map(|u| csr.neighbors(u).iter().map(move |&v| (u, v))).collect();
+      arcs.sort_unstable();
+      let mut branches = vec![(snapshot, arcs)];
+      for _ in 0..60 {
+        let (mut g, mut arcs) = branches[rand(branches.len() as u64) as usize].clone();
+        let (u, v) = (rand(n as u64) as usize, rand(n as u64) as usize);
+        let pairs = if directed || u == v { vec![(u, v)] } else { vec![(u, v), (v, u)] };
+        if rand(2) == 0 {
+          g.add_edge(u, v);
+          arcs.extend(pairs);
+          arcs.sort_unstable();
+        } else {
+          let present = arcs.contains(&(u, v));
+          assert_eq!(present, g.remove_edge(u, v));
+          arcs.retain(|a| !pairs.contains(a));
+        }
+        if rand(8) == 0 {
+          g = g.compact();
+        }
+        branches.push((g, arcs));
+      }
+      for (g, arcs) in &branches {
+        for u in 0..n {
+          let expected: Vec<usize> = arcs.iter().filter(|a| a.0 == u).map(|a| a.1).collect();
+          assert_eq!(expected, g.neighbors(u));
+          for v in 0..n {
+            assert_eq!(arcs.contains(&(u, v)), g.has_edge(u, v));
+          }
+        }
+      }
+    }
+  }
+}
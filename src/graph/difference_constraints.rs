@@ -0,0 +1,103 @@
+// Systems of difference constraints x[j] - x[i] <= c over n integer unknowns, each given as
+// (i, j, c). Each is an edge i -> j of weight c, and shortest distances satisfy every one of
+// them, dist[j] <= dist[i] + c, so Bellman-Ford from a virtual source with a 0 edge to every
+// vertex (all distances starting at 0) either settles on a solution, the greatest one with
+// every x <= 0, or finds a negative cycle: constraints summing to 0 <= a negative number, which
+// no assignment meets. O(n m), stopping early after a round that changes nothing. Distances are
+// sums of at most n weights, so they are kept in i128, where they cannot overflow; the solution
+// itself may not fit in i64.
+//
+// Returns the solution, or the constraints of a negative cycle, by index, in the order they
+// follow each other.
+pub fn solve_difference_constraints(n: usize, constraints: &[(usize, usize, i64)]) -> Result<Vec<i128>, Vec<usize>> {
+  let mut dist = vec![0i128; n];
+  let mut via = vec![usize::MAX; n]; // the constraint that last lowered each unknown
+  // n + 1 vertices with the source, so n rounds settle every distance unless there is a
+  // negative cycle, which keeps lowering some unknown in the round after them
+  let mut lowered = 0;
+  for _ in 0..=n {
+    let mut changed = false;
+    for (e, &(i, j, c)) in constraints.iter().enumerate() {
+      if dist[i] + (c as i128) < dist[j] {
+        dist[j] = dist[i] + c as i128;
+        via[j] = e;
+        lowered = j;
+        changed = true;
+      }
+    }
+    if !changed {
+      return Ok(dist);
+    }
+  }
+  // following the lowering constraints back from there for n steps lands on the cycle
+  let mut v = lowered;
+  for _ in 0..n {
+    v = constraints[via[v]].0;
+  }
+  let mut cycle = vec![];
+  let mut u = v;
+  loop {
+    cycle.push(via[u]);
+    u = constraints[via[u]].0;
+    if u == v {
+      break;
+    }
+  }
+  cycle.reverse();
+  Err(cycle)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_systems() {
+    // the example of CLRS 24.4, counting the unknowns from 0
+    let constraints = [(1, 0, 0), (4, 0, -1), (4, 1, 1), (0, 2, 5), (0, 3, 4), (2, 3, -1), (2, 4, -3), (3, 4, -3)];
+    assert_eq!(Ok(vec![-5, -3, 0, -1, -4]), solve_difference_constraints(5, &constraints));
+    let constraints = [(3, 3, 0), (0, 1, 1), (1, 2, -2), (2, 0, 0)];
+    assert_eq!(Err(vec![3, 1, 2]), solve_difference_constraints(4, &constraints));
+    assert_eq!(Err(vec![0]), solve_difference_constraints(1, &[(0, 0, -1)]));
+    assert_eq!(Ok(vec![]), solve_difference_constraints(0, &[]));
+    // extreme weights, with a solution beyond i64 and a cycle summing to -1
+    let min = i64::MIN as i128;
+    assert_eq!(Ok(vec![0, min, min - 1]), solve_difference_constraints(3, &[(0, 1, i64::MIN), (1, 2, -1)]));
+    assert_eq!(Err(vec![0, 1]), solve_difference_constraints(2, &[(0, 1, i64::MIN), (1, 0, i64::MAX)]));
+  }
+
+  #[test]
+  fn test_random_against_floyd_warshall() {
+    let mut rand = rand_gen(397);
+    for _ in 0..300 {
+      let n = 1 + rand(8) as usize;
+      let constraints: Vec<(usize, usize, i64)> = (0..rand(20)).map(|_| (rand(n as u64) as usize, rand(n as u64) as usize, rand(20) as i64 - 6)).collect();
+      let mut d = vec![vec![i64::MAX / 4; n]; n];
+      for &(i, j, c) in &constraints {
+        d[i][j] = d[i][j].min(c);
+      }
+      for k in 0..n {
+        for i in 0..n {
+          for j in 0..n {
+            d[i][j] = d[i][j].min(d[i][k] + d[k][j]);
+          }
+        }
+      }
+      let negative = (0..n).any(|i| d[i][i] < 0);
+      match solve_difference_constraints(n, &constraints) {
+        Ok(x) => {
+          assert!(!negative);
+          assert!(constraints.iter().all(|&(i, j, c)| x[j] - x[i] <= c as i128));
+          assert!(x.iter().all(|&v| v <= 0));
+        },
+        Err(cycle) => {
+          assert!(negative);
+          let edges: Vec<(usize, usize, i64)> = cycle.iter().map(|&e| constraints[e]).collect();
+          assert!(edges.iter().zip(edges.iter().cycle().skip(1)).all(|(a, b)| a.1 == b.0));
+          assert!(edges.iter().map(|e| e.2).sum::<i64>() < 0);
+        },
+      }
+    }
+  }
+}
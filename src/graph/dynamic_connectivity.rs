@@ -0,0 +1,495 @@
+use std::collections::{HashMap, HashSet};
+
+// Fully dynamic connectivity (Holm, de Lichtenberg & Thorup): edge insertions and deletions in
+// O(log^2 n) amortized, connectivity queries in O(log n).
+//
+// Every edge has a level in 0..levels. F_i is a spanning forest of the edges with level >= i, so
+// F_0 spans the graph, and a tree of F_i has at most n / 2^i vertices. When a tree edge of level
+// l is deleted, levels l down to 0 are searched for a replacement; at each level the smaller of
+// the two halves pays for the search by pushing its tree edges and every non-tree edge that
+// failed as a replacement one level up. Each forest is kept as Euler tours in splay trees.
+
+const NIL: usize = usize::MAX;
+
+struct TourNode {
+  ch: [usize; 2],
+  parent: usize,
+  vertex: bool, // a vertex occurrence rather than an arc
+  size: usize, // vertex nodes in the subtree
+  has_tree: bool, // vertex with tree edges of exactly this level
+  has_nontree: bool, // vertex with non-tree edges of this level
+  any_tree: bool, // subtree aggregates of the two flags
+  any_nontree: bool,
+}
+
+// Euler tour trees for one forest: vertex v is node v, arcs are allocated after them
+struct EulerTourForest {
+  nodes: Vec<TourNode>,
+  arcs: HashMap<(usize, usize), usize>,
+  free: Vec<usize>,
+}
+
+impl EulerTourForest {
+  fn new(n: usize) -> EulerTourForest {
+    let mut forest = EulerTourForest { nodes: vec![], arcs: HashMap::new(), free: vec![] };
+    for _ in 0..n {
+      forest.new_node(true);
+    }
+    forest
+  }
+
+  fn new_node(&mut self, vertex: bool) -> usize {
+    let node = TourNode {
+      ch: [NIL, NIL],
+      parent: NIL,
+      vertex,
+      size: vertex as usize,
+      has_tree: false,
+      has_nontree: false,
+      any_tree: false,
+      any_nontree: false,
+    };
+    match self.free.pop() {
+      Some(x) => {
+        self.nodes[x] = node;
+        x
+      },
+      None => {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+      },
+    }
+  }
+
+  fn update(&mut self, x: usize) {
+    let [l, r] = self.nodes[x].ch;
+    let mut size = self.nodes[x].vertex as usize;
+    let mut any_tree = self.nodes[x].has_tree;
+    let mut any_nontree = self.nodes[x].has_nontree;
+    for c in [l, r] {
+      if c != NIL {
+        size += self.nodes[c].size;
+        any_tree |= self.nodes[c].any_tree;
+        any_nontree |= self.nodes[c].any_nontree;
+      }
+    }
+    let node = &mut self.nodes[x];
+    node.size = size;
+    node.any_tree = any_tree;
+    node.any_nontree = any_nontree;
+  }
+
+  fn side(&self, x: usize) -> usize {
+    (self.nodes[self.nodes[x].parent].ch[1] == x) as usize
+  }
+
+  fn rotate(&mut self, x: usize) {
+    let p = self.nodes[x].parent;
+    let g = self.nodes[p].parent;
+    let d = self.side(x);
+    let b = self.nodes[x].ch[d ^ 1];
+    if g != NIL {
+      let pd = self.side(p);
+      self.nodes[g].ch[pd] = x;
+    }
+    self.nodes[x].parent = g;
+    self.nodes[x].ch[d ^ 1] = p;
+    self.nodes[p].parent = x;
+    self.nodes[p].ch[d] = b;
+    if b != NIL {
+      self.nodes[b].parent = p;
+    }
+    self.update(p);
+    self.update(x);
+  }
+
+  fn splay(&mut self, x: usize) {
+    while self.nodes[x].parent != NIL {
+      let p = self.nodes[x].parent;
+      if self.nodes[p].parent != NIL {
+        if self.side(x) == self.side(p) {
+          self.rotate(p);
+        } else {
+          self.rotate(x);
+        }
+      }
+      self.rotate(x);
+    }
+  }
+
+  // the root of x's splay tree; callers splay x afterwards to pay for the walk
+  fn root(&mut self, x: usize) -> usize {
+    let mut r = x;
+    while self.nodes[r].parent != NIL {
+      r = self.nodes[r].parent;
+    }
+    r
+  }
+
+  fn connected(&mut self, u: usize, v: usize) -> bool {
+    if u == v {
+      return true;
+    }
+    let same = self.root(u) == self.root(v);
+    self.splay(u);
+    self.splay(v);
+    same
+  }
+
+  fn tree_size(&mut self, v: usize) -> usize {
+    self.splay(v);
+    self.nodes[v].size
+  }
+
+  // detaches and returns the part of x's sequence before x
+  fn cut_left(&mut self, x: usize) -> usize {
+    self.splay(x);
+    let l = self.nodes[x].ch[0];
+    if l != NIL {
+      self.nodes[l].parent = NIL;
+      self.nodes[x].ch[0] = NIL;
+      self.update(x);
+    }
+    l
+  }
+
+  // detaches and returns the part of x's sequence after x
+  fn cut_right(&mut self, x: usize) -> usize {
+    self.splay(x);
+    let r = self.nodes[x].ch[1];
+    if r != NIL {
+      self.nodes[r].parent = NIL;
+      self.nodes[x].ch[1] = NIL;
+      self.update(x);
+    }
+    r
+  }
+
+  // concatenates the sequences rooted at a and b
+  fn join(&mut self, a: usize, b: usize) -> usize {
+    if a == NIL {
+      return b;
+    }
+    if b == NIL {
+      return a;
+    }
+    let mut last = a;
+    while self.nodes[last].ch[1] != NIL {
+      last = self.nodes[last].ch[1];
+    }
+    self.splay(last);
+    self.nodes[last].ch[1] = b;
+    self.nodes[b].parent = last;
+    self.update(last);
+    last
+  }
+
+  // rotates v's tour to start at v and returns its root
+  fn reroot(&mut self, v: usize) -> usize {
+    let before = self.cut_left(v);
+    self.join(v, before)
+  }
+
+  fn link(&mut self, u: usize, v: usize) {
+    let tu = self.reroot(u);
+    let tv = self.reroot(v);
+    let uv = self.new_node(false);
+    let vu = self.new_node(false);
+    self.arcs.insert((u, v), uv);
+    self.arcs.insert((v, u), vu);
+    let left = self.join(tu, uv);
+    let left = self.join(left, tv);
+    self.join(left, vu);
+  }
+
+  fn cut(&mut self, u: usize, v: usize) {
+    let uv = self.arcs.remove(&(u, v)).unwrap();
+    let vu = self.arcs.remove(&(v, u)).unwrap();
+    // the tour reads A uv M vu B or A vu M uv B; M is one side, A B the other
+    let mut a = self.cut_left(uv);
+    let vu_first = a != NIL && self.root(vu) == self.root(a);
+    let (first, second) = if vu_first { (vu, uv) } else { (uv, vu) };
+    if vu_first {
+      self.join(a, uv);
+      a = self.cut_left(vu);
+    }
+    self.cut_right(first);
+    self.cut_left(second);
+    let b = self.cut_right(second);
+    self.join(a, b);
+    self.free.push(uv);
+    self.free.push(vu);
+  }
+
+  fn set_flags(&mut self, v: usize, has_tree: bool, has_nontree: bool) {
+    self.splay(v);
+    self.nodes[v].has_tree = has_tree;
+    self.nodes[v].has_nontree = has_nontree;
+    self.update(v);
+  }
+
+  // some vertex in v's tree carrying the requested flag
+  fn find_flagged(&mut self, v: usize, tree: bool) -> Option<usize> {
+    self.splay(v);
+    let flagged = |n: &TourNode| if tree { n.any_tree } else { n.any_nontree };
+    if !flagged(&self.nodes[v]) {
+      return None;
+    }
+    let mut x = v;
+    loop {
+      let node = &self.nodes[x];
+      if if tree { node.has_tree } else { node.has_nontree } {
+        break;
+      }
+      x = node.ch.iter().cloned().find(|&c| c != NIL && flagged(&self.nodes[c])).unwrap();
+    }
+    self.splay(x);
+    Some(x)
+  }
+}
+
+pub struct DynamicConnectivity {
+  forests: Vec<EulerTourForest>, // forests[i] is F_i
+  level: HashMap<(usize, usize), (usize, bool)>, // edge -> (level, is a tree edge)
+  tree_adj: Vec<Vec<HashSet<usize>>>, // tree edges of exactly level i around each vertex
+  nontree_adj: Vec<Vec<HashSet<usize>>>,
+  components: usize,
+}
+
+fn key(u: usize, v: usize) -> (usize, usize) {
+  (u.min(v), u.max(v))
+}
+
+impl DynamicConnectivity {
+  pub fn new(n: usize) -> DynamicConnectivity {
+    let mut levels = 1;
+    while (1 << levels) <= n {
+      levels += 1;
+    }
+    DynamicConnectivity {
+      forests: (0..levels).map(|_| EulerTourForest::new(n)).collect(),
+      level: HashMap::new(),
+      tree_adj: vec![vec![HashSet::new(); n]; levels],
+      nontree_adj: vec![vec![HashSet::new(); n]; levels],
+      components: n,
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.tree_adj[0].len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  pub fn component_count(&self) -> usize {
+    self.components
+  }
+
+  pub fn connected(&mut self, u: usize, v: usize) -> bool {
+    self.forests[0].connected(u, v)
+  }
+
+  pub fn component_size(&mut self, v: usize) -> usize {
+    self.forests[0].tree_size(v)
+  }
+
+  pub fn has_edge(&self, u: usize, v: usize) -> bool {
+    self.level.contains_key(&key(u, v))
+  }
+
+  fn refresh(&mut self, i: usize, v: usize) {
+    let has_tree = !self.tree_adj[i][v].is_empty();
+    let has_nontree = !self.nontree_adj[i][v].is_empty();
+    self.forests[i].set_flags(v, has_tree, has_nontree);
+  }
+
+  fn add_tree_edge(&mut self, i: usize, u: usize, v: usize) {
+    self.level.insert(key(u, v), (i, true));
+    self.tree_adj[i][u].insert(v);
+    self.tree_adj[i][v].insert(u);
+    self.refresh(i, u);
+    self.refresh(i, v);
+  }
+
+  fn add_nontree_edge(&mut self, i: usize, u: usize, v: usize) {
+    self.level.insert(key(u, v), (i, false));
+    self.nontree_adj[i][u].insert(v);
+    self.nontree_adj[i][v].insert(u);
+    self.refresh(i, u);
+    self.refresh(i, v);
+  }
+
+  // returns false for self-loops and edges already present
+  pub fn insert_edge(&mut self, u: usize, v: usize) -> bool {
+    if u == v || self.has_edge(u, v) {
+      return false;
+    }
+    if self.forests[0].connected(u, v) {
+      self.add_nontree_edge(0, u, v);
+    } else {
+      self.forests[0].link(u, v);
+      self.add_tree_edge(0, u, v);
+      self.components -= 1;
+    }
+    true
+  }
+
+  // returns false if the edge is not present
+  pub fn delete_edge(&mut self, u: usize, v: usize) -> bool {
+    let (l, is_tree) = match self.level.remove(&key(u, v)) {
+      Some(info) => info,
+      None => return false,
+    };
+    if !is_tree {
+      self.nontree_adj[l][u].remove(&v);
+      self.nontree_adj[l][v].remove(&u);
+      self.refresh(l, u);
+      self.refresh(l, v);
+      return true;
+    }
+    self.tree_adj[l][u].remove(&v);
+    self.tree_adj[l][v].remove(&u);
+    self.refresh(l, u);
+    self.refresh(l, v);
+    for i in 0..=l {
+      self.forests[i].cut(u, v);
+    }
+    for i in (0..=l).rev() {
+      if self.replace(i, u, v) {
+        return true;
+      }
+    }
+    self.components += 1;
+    true
+  }
+
+  // looks for a level i edge reconnecting the trees of u and v in F_i
+  fn replace(&mut self, i: usize, u: usize, v: usize) -> bool {
+    let (small, other) = if self.forests[i].tree_size(u) <= self.forests[i].tree_size(v) { (u, v) } else { (v, u) };
+    // the small side now fits a level up, so its level i tree edges move there
+    while let Some(x) = self.forests[i].find_flagged(small, true) {
+      let targets: Vec<usize> = self.tree_adj[i][x].drain().collect();
+      for y in targets {
+        self.tree_adj[i][y].remove(&x);
+        self.refresh(i, y);
+        self.forests[i + 1].link(x, y);
+        self.add_tree_edge(i + 1, x, y);
+      }
+      self.refresh(i, x);
+    }
+    while let Some(x) = self.forests[i].find_flagged(small, false) {
+      let targets: Vec<usize> = self.nontree_adj[i][x].iter().cloned().collect();
+      for y in targets {
+        self.nontree_adj[i][x].remove(&y);
+        self.nontree_adj[i][y].remove(&x);
+        self.refresh(i, x);
+        self.refresh(i, y);
+        if self.forests[i].connected(y, other) {
+          for j in 0..=i {
+            self.forests[j].link(x, y);
+          }
+          self.add_tree_edge(i, x, y);
+          return true;
+        }
+        self.add_nontree_edge(i + 1, x, y);
+      }
+    }
+    false
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  // component of u as a membership vector
+  fn brute_component(n: usize, edges: &[(usize, usize)], u: usize) -> Vec<bool> {
+    let mut adj = vec![vec![]; n];
+    for &(a, b) in edges {
+      adj[a].push(b);
+      adj[b].push(a);
+    }
+    let mut seen = vec![false; n];
+    let mut stack = vec![u];
+    seen[u] = true;
+    while let Some(x) = stack.pop() {
+      for &y in &adj[x] {
+        if !seen[y] {
+          seen[y] = true;
+          stack.push(y);
+        }
+      }
+    }
+    seen
+  }
+
+  #[test]
+  fn test_random_against_brute_force() {
+    let mut rand = rand_gen(101);
+    for round in 0..40 {
+      let n = 2 + rand(if round % 4 == 0 { 60 } else { 12 }) as usize;
+      let mut dc = DynamicConnectivity::new(n);
+      let mut edges: Vec<(usize, usize)> = vec![];
+      for _ in 0..600 {
+        let (u, v) = (rand(n as u64) as usize, rand(n as u64) as usize);
+        match rand(5) {
+          0 | 1 => {
+            let fresh = u != v && !edges.contains(&key(u, v));
+            if fresh {
+              edges.push(key(u, v));
+            }
+            assert_eq!(fresh, dc.insert_edge(u, v));
+          },
+          2 => {
+            // delete an existing edge most of the time
+            let (a, b) = if edges.is_empty() || rand(4) == 0 {
+              (u, v)
+            } else {
+              edges[rand(edges.len() as u64) as usize]
+            };
+            let present = edges.iter().position(|&e| e == key(a, b));
+            if let Some(i) = present {
+              edges.swap_remove(i);
+            }
+            assert_eq!(present.is_some(), dc.delete_edge(b, a));
+          },
+          _ => {},
+        }
+        let component = brute_component(n, &edges, u);
+        assert_eq!(component[v], dc.connected(u, v));
+        assert_eq!(component.iter().filter(|&&c| c).count(), dc.component_size(u));
+      }
+      let mut components = 0;
+      let mut seen = vec![false; n];
+      for s in 0..n {
+        if !seen[s] {
+          components += 1;
+          for (flag, c) in seen.iter_mut().zip(brute_component(n, &edges, s)) {
+            *flag |= c;
+          }
+        }
+      }
+      assert_eq!(components, dc.component_count());
+    }
+  }
+
+  #[test]
+  fn test_cycle_replacement() {
+    let mut dc = DynamicConnectivity::new(6);
+    for i in 0..6 {
+      assert!(dc.insert_edge(i, (i + 1) % 6));
+    }
+    assert!(!dc.insert_edge(1, 0));
+    assert_eq!(1, dc.component_count());
+    assert!(dc.delete_edge(2, 3)); // the cycle keeps it connected
+    assert!(dc.connected(2, 3));
+    assert!(dc.delete_edge(5, 0));
+    assert!(!dc.connected(2, 3));
+    assert_eq!(3, dc.component_size(0));
+    assert_eq!(2, dc.component_count());
+    assert!(!dc.delete_edge(5, 0));
+  }
+}
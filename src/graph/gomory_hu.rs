@@ -0,0 +1,97 @@
+use super::max_flow::MaxFlow;
+
+// Gomory-Hu tree of an undirected graph built with Gusfield's algorithm: n - 1 max-flow
+// computations on the original graph, no contraction needed.
+// the minimum u-v cut of the graph equals the lightest edge on the tree path from u to v
+pub struct GomoryHuTree {
+  parent: Vec<usize>, // parent[v] < v, vertex 0 is the root
+  weight: Vec<i64>, // weight of the tree edge (v, parent[v])
+  depth: Vec<usize>,
+}
+
+impl GomoryHuTree {
+  pub fn new(n: usize, edges: &[(usize, usize, i64)]) -> GomoryHuTree {
+    let mut mf = MaxFlow::new(n);
+    for &(u, v, c) in edges {
+      mf.add_undirected_edge(u, v, c);
+    }
+    let mut parent = vec![0; n];
+    let mut weight = vec![0; n];
+    for i in 1..n {
+      mf.reset();
+      weight[i] = mf.max_flow(i, parent[i]);
+      let side = mf.min_cut_side(i);
+      // vertices that fell on i's side of the cut now hang below i
+      for j in i + 1..n {
+        if side[j] && parent[j] == parent[i] {
+          parent[j] = i;
+        }
+      }
+    }
+    let mut depth = vec![0; n];
+    for v in 1..n {
+      depth[v] = depth[parent[v]] + 1;
+    }
+    GomoryHuTree { parent, weight, depth }
+  }
+
+  pub fn len(&self) -> usize {
+    self.parent.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.parent.is_empty()
+  }
+
+  // (v, parent, weight) for each of the n - 1 tree edges
+  pub fn tree_edges(&self) -> impl Iterator<Item = (usize, usize, i64)> + '_ {
+    (1..self.len()).map(move |v| (v, self.parent[v], self.weight[v]))
+  }
+
+  // the value of a minimum cut separating u and v, O(n) walk up the tree
+  pub fn min_cut(&self, mut u: usize, mut v: usize) -> i64 {
+    assert_ne!(u, v);
+    let mut ans = i64::MAX;
+    while u != v {
+      if self.depth[u] < self.depth[v] {
+        std::mem::swap(&mut u, &mut v);
+      }
+      ans = ans.min(self.weight[u]);
+      u = self.parent[u];
+    }
+    ans
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_against_max_flow() {
+    let mut rand = rand_gen(7);
+    for _ in 0..50 {
+      let n = 2 + rand(8) as usize;
+      let mut edges = vec![];
+      for _ in 0..rand(25) {
+        let (u, v) = (rand(n as u64) as usize, rand(n as u64) as usize);
+        if u != v {
+          edges.push((u, v, rand(10) as i64));
+        }
+      }
+      let tree = GomoryHuTree::new(n, &edges);
+      assert_eq!(n - 1, tree.tree_edges().count());
+      for s in 0..n {
+        for t in 0..n {
+          if s == t { continue; }
+          let mut mf = MaxFlow::new(n);
+          for &(u, v, c) in &edges {
+            mf.add_undirected_edge(u, v, c);
+          }
+          assert_eq!(mf.max_flow(s, t), tree.min_cut(s, t));
+        }
+      }
+    }
+  }
+}
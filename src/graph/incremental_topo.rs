@@ -0,0 +1,237 @@
+// Online cycle detection with an incrementally maintained topological order (Pearce & Kelly).
+// Inserting u -> v with u already before v costs O(1). Otherwise only the affected region is
+// searched: the vertices reachable from v and the vertices reaching u whose positions lie
+// between pos[v] and pos[u]. That region is reordered in place.
+pub struct IncrementalTopoOrder {
+  out: Vec<Vec<usize>>,
+  inc: Vec<Vec<usize>>,
+  pos: Vec<usize>, // position of each vertex in the order
+  order: Vec<usize>, // vertex at each position
+  mark: Vec<bool>, // scratch for the searches
+}
+
+impl IncrementalTopoOrder {
+  pub fn new(n: usize) -> IncrementalTopoOrder {
+    IncrementalTopoOrder {
+      out: vec![vec![]; n],
+      inc: vec![vec![]; n],
+      pos: (0..n).collect(),
+      order: (0..n).collect(),
+      mark: vec![false; n],
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.out.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.out.is_empty()
+  }
+
+  // appends a new vertex at the end of the order and returns its id
+  pub fn add_vertex(&mut self) -> usize {
+    let v = self.out.len();
+    self.out.push(vec![]);
+    self.inc.push(vec![]);
+    self.pos.push(v);
+    self.order.push(v);
+    self.mark.push(false);
+    v
+  }
+
+  // the current topological order, every edge points forward in it
+  pub fn order(&self) -> &[usize] {
+    &self.order
+  }
+
+  pub fn position(&self, v: usize) -> usize {
+    self.pos[v]
+  }
+
+  // vertices reachable from start through vertices at positions <= bound, in dfs order. Stops
+  // early and returns the path to target if it shows up. Marks are left set for the caller.
+  fn forward(&mut self, start: usize, bound: usize, target: usize) -> Result<Vec<usize>, Vec<usize>> {
+    let mut seen = vec![start];
+    let mut stack = vec![(start, 0)];
+    self.mark[start] = true;
+    while let Some(&mut (u, ref mut i)) = stack.last_mut() {
+      if u == target {
+        return Err(stack.iter().map(|&(v, _)| v).collect());
+      }
+      if *i == self.out[u].len() {
+        stack.pop();
+        continue;
+      }
+      let v = self.out[u][*i];
+      *i += 1;
+      if !self.mark[v] && self.pos[v] <= bound {
+        self.mark[v] = true;
+        seen.push(v);
+        stack.push((v, 0));
+      }
+    }
+    Ok(seen)
+  }
+
+  fn backward(&mut self, start: usize, bound: usize) -> Vec<usize> {
+    let mut seen = vec![start];
+    let mut stack = vec![start];
+    self.mark[start] = true;
+    while let Some(u) = stack.pop() {
+      for i in 0..self.inc[u].len() {
+        let v = self.inc[u][i];
+        if !self.mark[v] && self.pos[v] >= bound {
+          self.mark[v] = true;
+          seen.push(v);
+          stack.push(v);
+        }
+      }
+    }
+    seen
+  }
+
+  fn clear_marks(&mut self, vertices: &[usize]) {
+    for &v in vertices {
+      self.mark[v] = false;
+    }
+  }
+
+  // whether adding u -> v would close a cycle; the structure is left unchanged
+  pub fn would_create_cycle(&mut self, u: usize, v: usize) -> bool {
+    if u == v {
+      return true;
+    }
+    if self.pos[u] < self.pos[v] {
+      return false;
+    }
+    let bound = self.pos[u];
+    match self.forward(v, bound, u) {
+      Ok(seen) => {
+        self.clear_marks(&seen);
+        false
+      },
+      Err(_) => {
+        // the path is a subset of everything marked, which all lies in [pos[v], pos[u]]
+        let (lo, hi) = (self.pos[v], self.pos[u]);
+        for p in lo..=hi {
+          let w = self.order[p];
+          self.mark[w] = false;
+        }
+        true
+      },
+    }
+  }
+
+  // inserts u -> v and keeps the order valid; if the edge would close a cycle nothing changes
+  // and the cycle's path from v to u is returned
+  pub fn add_edge(&mut self, u: usize, v: usize) -> Result<(), Vec<usize>> {
+    if u == v {
+      return Err(vec![u]);
+    }
+    let (lo, hi) = (self.pos[v], self.pos[u]);
+    if lo < hi {
+      match self.forward(v, hi, u) {
+        Err(path) => {
+          for p in lo..=hi {
+            let w = self.order[p];
+            self.mark[w] = false;
+          }
+          return Err(path);
+        },
+        Ok(forward) => {
+          let backward = self.backward(u, lo);
+          self.clear_marks(&forward);
+          self.clear_marks(&backward);
+          self.reorder(forward, backward);
+        },
+      }
+    }
+    self.out[u].push(v);
+    self.inc[v].push(u);
+    Ok(())
+  }
+
+  // everything reaching u must now come before everything reachable from v; both groups keep
+  // their relative order and reuse the positions they occupied
+  fn reorder(&mut self, mut forward: Vec<usize>, mut backward: Vec<usize>) {
+    forward.sort_by_key(|&w| self.pos[w]);
+    backward.sort_by_key(|&w| self.pos[w]);
+    let mut slots: Vec<usize> = forward.iter().chain(&backward).map(|&w| self.pos[w]).collect();
+    slots.sort_unstable();
+    for (&w, &p) in backward.iter().chain(&forward).zip(&slots) {
+      self.pos[w] = p;
+      self.order[p] = w;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  fn reachable(adj: &[Vec<usize>], from: usize, to: usize) -> bool {
+    let mut seen = vec![false; adj.len()];
+    let mut stack = vec![from];
+    seen[from] = true;
+    while let Some(u) = stack.pop() {
+      if u == to {
+        return true;
+      }
+      for &v in &adj[u] {
+        if !seen[v] {
+          seen[v] = true;
+          stack.push(v);
+        }
+      }
+    }
+    false
+  }
+
+  #[test]
+  fn test_random_insertions() {
+    let mut rand = rand_gen(71);
+    for _ in 0..50 {
+      let n = 1 + rand(25) as usize;
+      let mut topo = IncrementalTopoOrder::new(n);
+      let mut adj = vec![vec![]; n];
+      for _ in 0..3 * n {
+        let (u, v) = (rand(n as u64) as usize, rand(n as u64) as usize);
+        let cyclic = reachable(&adj, v, u);
+        assert_eq!(cyclic, topo.would_create_cycle(u, v));
+        match topo.add_edge(u, v) {
+          Ok(()) => {
+            assert!(!cyclic);
+            adj[u].push(v);
+          },
+          Err(path) => {
+            assert!(cyclic);
+            assert_eq!(v, path[0]);
+            assert_eq!(u, *path.last().unwrap());
+            assert!(path.windows(2).all(|w| adj[w[0]].contains(&w[1])));
+          },
+        }
+        for (a, next) in adj.iter().enumerate() {
+          assert_eq!(a, topo.order()[topo.position(a)]);
+          for &b in next {
+            assert!(topo.position(a) < topo.position(b));
+          }
+        }
+        assert!(topo.mark.iter().all(|&m| !m));
+      }
+    }
+  }
+
+  #[test]
+  fn test_dependency_chain() {
+    let mut topo = IncrementalTopoOrder::new(3);
+    assert_eq!(Ok(()), topo.add_edge(2, 1));
+    assert_eq!(Ok(()), topo.add_edge(1, 0));
+    assert_eq!(&[2, 1, 0], topo.order());
+    assert_eq!(Err(vec![2, 1, 0]), topo.add_edge(0, 2));
+    let d = topo.add_vertex();
+    assert_eq!(Ok(()), topo.add_edge(d, 2));
+    assert_eq!(&[3, 2, 1, 0], topo.order());
+  }
+}
@@ -0,0 +1,248 @@
+// Maximum independent set and minimum vertex cover. Graphs with at most EXACT_LIMIT vertices are
+// solved exactly by branch and bound over u64 bitsets; larger ones fall back to heuristics
+// (min-degree greedy for independent sets, the matching 2-approximation for vertex covers).
+// A vertex cover is exactly the complement of an independent set. A vertex with a self-loop is
+// never independent, so it lands in every cover.
+
+pub const EXACT_LIMIT: usize = 64;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum SolveMode {
+  Exact,
+  Heuristic,
+}
+
+#[derive(Clone, Debug)]
+pub struct VertexSet {
+  pub vertices: Vec<usize>, // sorted
+  pub mode: SolveMode, // which solver produced the set
+}
+
+fn bit_adjacency(n: usize, edges: &[(usize, usize)]) -> Vec<u64> {
+  let mut adj = vec![0u64; n];
+  for &(u, v) in edges {
+    if u != v {
+      adj[u] |= 1 << v;
+      adj[v] |= 1 << u;
+    }
+  }
+  adj
+}
+
+fn bits(mut set: u64) -> impl Iterator<Item = usize> {
+  std::iter::from_fn(move || {
+    if set == 0 {
+      return None;
+    }
+    let v = set.trailing_zeros() as usize;
+    set &= set - 1;
+    Some(v)
+  })
+}
+
+// greedily partitions cand into cliques; an independent set takes at most one vertex of each
+fn clique_cover_bound(adj: &[u64], mut cand: u64) -> u32 {
+  let mut cliques = 0;
+  while cand != 0 {
+    let v = cand.trailing_zeros() as usize;
+    let mut common = adj[v] & cand;
+    cand &= !(1 << v);
+    while common != 0 {
+      let u = common.trailing_zeros() as usize;
+      cand &= !(1 << u);
+      common &= adj[u];
+    }
+    cliques += 1;
+  }
+  cliques
+}
+
+fn branch(adj: &[u64], mut cand: u64, mut chosen: u64, best: &mut u64) {
+  // vertices of degree at most one within cand always belong to some maximum set
+  while let Some(v) = bits(cand).find(|&v| (adj[v] & cand).count_ones() <= 1) {
+    chosen |= 1 << v;
+    cand &= !(adj[v] | 1 << v);
+  }
+  if cand == 0 {
+    if chosen.count_ones() > best.count_ones() {
+      *best = chosen;
+    }
+    return;
+  }
+  if chosen.count_ones() + clique_cover_bound(adj, cand) <= best.count_ones() {
+    return;
+  }
+  let v = bits(cand).max_by_key(|&v| (adj[v] & cand).count_ones()).unwrap();
+  branch(adj, cand & !(adj[v] | 1 << v), chosen | 1 << v, best);
+  branch(adj, cand & !(1 << v), chosen, best);
+}
+
+pub fn exact_maximum_independent_set(n: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+  assert!(n <= EXACT_LIMIT, "exact solver supports at most {} vertices", EXACT_LIMIT);
+  let adj = bit_adjacency(n, edges);
+  let mut all = if n == 64 { !0 } else { (1u64 << n) - 1 };
+  for &(u, v) in edges {
+    if u == v {
+      all &= !(1 << u);
+    }
+  }
+  let mut best = 0;
+  branch(&adj, all, 0, &mut best);
+  bits(best).collect()
+}
+
+// repeatedly takes a vertex of minimum remaining degree and drops its neighbors
+pub fn greedy_independent_set(n: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+  let mut adj = vec![vec![]; n];
+  let mut removed = vec![false; n];
+  for &(u, v) in edges {
+    if u != v {
+      adj[u].push(v);
+      adj[v].push(u);
+    } else {
+      removed[u] = true;
+    }
+  }
+  let mut degree: Vec<usize> = adj.iter().map(|a| a.len()).collect();
+  // bucket queue keyed by current degree, stale entries are skipped
+  let mut buckets = vec![vec![]; n.max(1)];
+  for v in 0..n {
+    buckets[degree[v]].push(v);
+  }
+  let mut set = vec![];
+  let mut d = 0;
+  while d < buckets.len() {
+    let v = match buckets[d].pop() {
+      Some(v) => v,
+      None => {
+        d += 1;
+        continue;
+      },
+    };
+    if removed[v] || degree[v] != d {
+      continue;
+    }
+    set.push(v);
+    removed[v] = true;
+    for &u in &adj[v] {
+      if removed[u] {
+        continue;
+      }
+      removed[u] = true;
+      for &w in &adj[u] {
+        if !removed[w] {
+          degree[w] -= 1;
+          buckets[degree[w]].push(w);
+          d = d.min(degree[w]);
+        }
+      }
+    }
+  }
+  set.sort_unstable();
+  set
+}
+
+// both endpoints of a maximal matching, at most twice the optimum
+pub fn matching_vertex_cover(n: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+  let mut covered = vec![false; n];
+  for &(u, v) in edges {
+    if !covered[u] && !covered[v] {
+      covered[u] = true;
+      covered[v] = true;
+    }
+  }
+  (0..n).filter(|&v| covered[v]).collect()
+}
+
+pub fn maximum_independent_set(n: usize, edges: &[(usize, usize)]) -> VertexSet {
+  if n <= EXACT_LIMIT {
+    VertexSet { vertices: exact_maximum_independent_set(n, edges), mode: SolveMode::Exact }
+  } else {
+    VertexSet { vertices: greedy_independent_set(n, edges), mode: SolveMode::Heuristic }
+  }
+}
+
+pub fn minimum_vertex_cover(n: usize, edges: &[(usize, usize)]) -> VertexSet {
+  if n <= EXACT_LIMIT {
+    let set = exact_maximum_independent_set(n, edges);
+    let mut in_set = vec![false; n];
+    for v in set {
+      in_set[v] = true;
+    }
+    VertexSet { vertices: (0..n).filter(|&v| !in_set[v]).collect(), mode: SolveMode::Exact }
+  } else {
+    VertexSet { vertices: matching_vertex_cover(n, edges), mode: SolveMode::Heuristic }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  fn is_independent(set: &[usize], edges: &[(usize, usize)]) -> bool {
+    edges.iter().all(|&(u, v)| !(set.contains(&u) && set.contains(&v)))
+  }
+
+  fn is_cover(set: &[usize], edges: &[(usize, usize)]) -> bool {
+    edges.iter().all(|&(u, v)| set.contains(&u) || set.contains(&v))
+  }
+
+  fn brute_force(n: usize, edges: &[(usize, usize)]) -> usize {
+    (0u32..1 << n)
+      .filter(|&mask| edges.iter().all(|&(u, v)| mask >> u & 1 == 0 || mask >> v & 1 == 0))
+      .map(|mask| mask.count_ones() as usize)
+      .max()
+      .unwrap()
+  }
+
+  #[test]
+  fn test_against_brute_force() {
+    let mut rand = rand_gen(3);
+    for _ in 0..300 {
+      let n = 1 + rand(12) as usize;
+      let edges: Vec<_> = (0..rand(3 * n as u64))
+        .map(|_| (rand(n as u64) as usize, rand(n as u64) as usize))
+        .collect();
+      let set = maximum_independent_set(n, &edges);
+      assert_eq!(SolveMode::Exact, set.mode);
+      assert!(is_independent(&set.vertices, &edges));
+      assert_eq!(brute_force(n, &edges), set.vertices.len());
+      let cover = minimum_vertex_cover(n, &edges);
+      assert!(is_cover(&cover.vertices, &edges));
+      assert_eq!(n, set.vertices.len() + cover.vertices.len());
+    }
+  }
+
+  #[test]
+  fn test_known_graphs() {
+    // petersen graph has independence number 4
+    let mut petersen = vec![];
+    for i in 0..5 {
+      petersen.push((i, (i + 1) % 5));
+      petersen.push((i, i + 5));
+      petersen.push((i + 5, (i + 2) % 5 + 5));
+    }
+    assert_eq!(4, exact_maximum_independent_set(10, &petersen).len());
+    // a 64-cycle, the largest exact instance
+    let cycle: Vec<_> = (0..64).map(|i| (i, (i + 1) % 64)).collect();
+    assert_eq!(32, exact_maximum_independent_set(64, &cycle).len());
+  }
+
+  #[test]
+  fn test_heuristics() {
+    let mut rand = rand_gen(11);
+    let n = 200;
+    let edges: Vec<_> = (0..600).map(|_| (rand(n as u64) as usize, rand(n as u64) as usize)).collect();
+    let set = maximum_independent_set(n, &edges);
+    assert_eq!(SolveMode::Heuristic, set.mode);
+    assert!(is_independent(&set.vertices, &edges));
+    let cover = minimum_vertex_cover(n, &edges);
+    assert_eq!(SolveMode::Heuristic, cover.mode);
+    assert!(is_cover(&cover.vertices, &edges));
+    // a path is solved optimally by min-degree greedy
+    let path: Vec<_> = (0..99).map(|i| (i, i + 1)).collect();
+    assert_eq!(50, greedy_independent_set(100, &path).len());
+    assert!(is_cover(&matching_vertex_cover(100, &path), &path));
+  }
+}
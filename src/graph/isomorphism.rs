@@ -0,0 +1,319 @@
+use std::collections::BTreeSet;
+
+// Canonical labeling by individualization-refinement, the scheme behind nauty in a small form:
+// color refinement splits vertices by their neighbor colors until stable, then one vertex of the
+// first non-singleton cell is individualized and the search recurses until the coloring is
+// discrete. Every leaf is a labeling, the canonical one is the leaf with the largest relabeled
+// edge list. Automorphisms found between leaves prune children in the same orbit.
+
+type Leaf = (Vec<(usize, usize)>, Vec<usize>); // (certificate, labeling)
+
+struct CanonicalSearch {
+  adj: Vec<Vec<usize>>,
+  edges: Vec<(usize, usize)>,
+  first_path: Vec<usize>,
+  first_leaf: Option<Leaf>,
+  best: Option<Leaf>,
+  generators: Vec<Vec<usize>>, // automorphisms found so far
+}
+
+// relabels the colors as their ranks among the distinct colors
+fn compress<T: Ord + Clone>(keys: &[T]) -> Vec<usize> {
+  let sorted: Vec<T> = keys.iter().cloned().collect::<BTreeSet<_>>().into_iter().collect();
+  keys.iter().map(|k| sorted.binary_search(k).unwrap()).collect()
+}
+
+fn find(parent: &mut [usize], x: usize) -> usize {
+  let mut r = x;
+  while parent[r] != r { r = parent[r]; }
+  let mut x = x;
+  while parent[x] != r {
+    let next = parent[x];
+    parent[x] = r;
+    x = next;
+  }
+  r
+}
+
+impl CanonicalSearch {
+  fn new(n: usize, edges: &[(usize, usize)]) -> CanonicalSearch {
+    let edges: Vec<(usize, usize)> = edges.iter()
+      .map(|&(u, v)| (u.min(v), u.max(v)))
+      .collect::<BTreeSet<_>>()
+      .into_iter()
+      .collect();
+    let mut adj = vec![vec![]; n];
+    for &(u, v) in &edges {
+      adj[u].push(v);
+      if u != v {
+        adj[v].push(u);
+      }
+    }
+    CanonicalSearch {
+      adj,
+      edges,
+      first_path: vec![],
+      first_leaf: None,
+      best: None,
+      generators: vec![],
+    }
+  }
+
+  // color refinement (1-dimensional Weisfeiler-Leman) to an equitable coloring
+  fn refine(&self, colors: Vec<usize>) -> Vec<usize> {
+    let mut colors = colors;
+    let mut cells = colors.iter().collect::<BTreeSet<_>>().len();
+    loop {
+      let signatures: Vec<(usize, Vec<usize>)> = (0..colors.len()).map(|v| {
+        let mut nbr: Vec<usize> = self.adj[v].iter().map(|&u| colors[u]).collect();
+        nbr.sort_unstable();
+        (colors[v], nbr)
+      }).collect();
+      let next = compress(&signatures);
+      let next_cells = next.iter().max().map_or(0, |&c| c + 1);
+      colors = next;
+      if next_cells == cells {
+        return colors;
+      }
+      cells = next_cells;
+    }
+  }
+
+  fn certificate(&self, lab: &[usize]) -> Vec<(usize, usize)> {
+    let mut cert: Vec<(usize, usize)> = self.edges.iter()
+      .map(|&(u, v)| (lab[u].min(lab[v]), lab[u].max(lab[v])))
+      .collect();
+    cert.sort_unstable();
+    cert
+  }
+
+  // whether u and w are in the same orbit of the automorphisms found so far that fix the prefix
+  fn same_orbit(&self, u: usize, w: usize, prefix: &[usize]) -> bool {
+    let mut parent: Vec<usize> = (0..self.adj.len()).collect();
+    for g in &self.generators {
+      if prefix.iter().all(|&p| g[p] == p) {
+        for (v, &gv) in g.iter().enumerate() {
+          let (a, b) = (find(&mut parent, v), find(&mut parent, gv));
+          parent[a] = b;
+        }
+      }
+    }
+    find(&mut parent, u) == find(&mut parent, w)
+  }
+
+  // returns Some(level) when the search should unwind to that level of the first path
+  fn search(&mut self, colors: Vec<usize>, prefix: &mut Vec<usize>) -> Option<usize> {
+    let colors = self.refine(colors);
+    let n = colors.len();
+    let mut size = vec![0; n];
+    for &c in &colors {
+      size[c] += 1;
+    }
+    // the first smallest non-singleton cell is an isomorphism-invariant choice
+    let target = (0..n).filter(|&c| size[c] > 1).min_by_key(|&c| (size[c], c));
+    let target = match target {
+      Some(c) => c,
+      None => return self.leaf(colors, prefix),
+    };
+    let cell: Vec<usize> = (0..n).filter(|&v| colors[v] == target).collect();
+    let mut explored: Vec<usize> = vec![];
+    for &v in &cell {
+      if explored.iter().any(|&u| self.same_orbit(u, v, prefix)) {
+        continue;
+      }
+      explored.push(v);
+      // v keeps the cell's color, the rest of the cell moves just above it
+      let individualized: Vec<usize> = (0..n)
+        .map(|u| 2 * colors[u] + (colors[u] == target && u != v) as usize)
+        .collect();
+      prefix.push(v);
+      let jump = self.search(compress(&individualized), prefix);
+      prefix.pop();
+      if let Some(level) = jump {
+        if level < prefix.len() {
+          return Some(level);
+        }
+      }
+    }
+    None
+  }
+
+  fn leaf(&mut self, lab: Vec<usize>, prefix: &[usize]) -> Option<usize> {
+    let cert = self.certificate(&lab);
+    let first = match &self.first_leaf {
+      None => {
+        self.first_path = prefix.to_vec();
+        self.first_leaf = Some((cert.clone(), lab.clone()));
+        self.best = Some((cert, lab));
+        return None;
+      },
+      Some(first) => first,
+    };
+    if cert == first.0 {
+      // the leaves are equivalent: record the automorphism and skip what remains of the
+      // subtree, it is the image of the already explored first path
+      let gamma = Self::automorphism(&lab, &first.1);
+      self.generators.push(gamma);
+      let common = prefix.iter().zip(&self.first_path).take_while(|(a, b)| a == b).count();
+      return Some(common);
+    }
+    let best = self.best.as_ref().unwrap();
+    if cert == best.0 {
+      let gamma = Self::automorphism(&lab, &best.1);
+      self.generators.push(gamma);
+    } else if cert > best.0 {
+      self.best = Some((cert, lab));
+    }
+    None
+  }
+
+  // maps each vertex to the vertex carrying the same label in the other leaf
+  fn automorphism(lab: &[usize], other: &[usize]) -> Vec<usize> {
+    let mut inv = vec![0; other.len()];
+    for (v, &l) in other.iter().enumerate() {
+      inv[l] = v;
+    }
+    lab.iter().map(|&l| inv[l]).collect()
+  }
+}
+
+// returns lab, where lab[v] is the canonical position of v, and the edges of the graph relabeled
+// by lab in sorted order; two graphs are isomorphic iff their canonical edge lists are equal
+pub fn canonical_form(n: usize, edges: &[(usize, usize)]) -> (Vec<usize>, Vec<(usize, usize)>) {
+  let mut search = CanonicalSearch::new(n, edges);
+  if n == 0 {
+    return (vec![], vec![]);
+  }
+  search.search(vec![0; n], &mut vec![]);
+  let (cert, lab) = search.best.unwrap();
+  (lab, cert)
+}
+
+// a hash of the canonical form that is stable across runs and platforms (FNV-1a)
+pub fn canonical_hash(n: usize, edges: &[(usize, usize)]) -> u64 {
+  let (_, cert) = canonical_form(n, edges);
+  let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+  let mut feed = |x: u64| {
+    for byte in x.to_le_bytes() {
+      h ^= byte as u64;
+      h = h.wrapping_mul(0x0100_0000_01b3);
+    }
+  };
+  feed(n as u64);
+  for (u, v) in cert {
+    feed(u as u64);
+    feed(v as u64);
+  }
+  h
+}
+
+// an isomorphism mapping each vertex of the first graph to a vertex of the second
+pub fn find_isomorphism(n1: usize, edges1: &[(usize, usize)], n2: usize, edges2: &[(usize, usize)]) -> Option<Vec<usize>> {
+  if n1 != n2 {
+    return None;
+  }
+  let (lab1, cert1) = canonical_form(n1, edges1);
+  let (lab2, cert2) = canonical_form(n2, edges2);
+  if cert1 != cert2 {
+    return None;
+  }
+  Some(CanonicalSearch::automorphism(&lab1, &lab2))
+}
+
+pub fn are_isomorphic(n1: usize, edges1: &[(usize, usize)], n2: usize, edges2: &[(usize, usize)]) -> bool {
+  find_isomorphism(n1, edges1, n2, edges2).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  fn permute(edges: &[(usize, usize)], perm: &[usize]) -> Vec<(usize, usize)> {
+    edges.iter().map(|&(u, v)| (perm[v], perm[u])).rev().collect()
+  }
+
+  fn shuffled(n: usize, rand: &mut impl FnMut(u64) -> u64) -> Vec<usize> {
+    let mut perm: Vec<usize> = (0..n).collect();
+    for i in (1..n).rev() {
+      perm.swap(i, rand(i as u64 + 1) as usize);
+    }
+    perm
+  }
+
+  fn check_mapping(edges1: &[(usize, usize)], edges2: &[(usize, usize)], map: &[usize]) {
+    let norm = |e: &[(usize, usize)]| e.iter().map(|&(u, v)| (u.min(v), u.max(v))).collect::<BTreeSet<_>>();
+    assert_eq!(norm(edges2), norm(&permute(edges1, map)));
+  }
+
+  #[test]
+  fn test_random_relabeling() {
+    let mut rand = rand_gen(31);
+    for _ in 0..100 {
+      let n = 1 + rand(30) as usize;
+      let edges: Vec<_> = (0..rand(3 * n as u64))
+        .map(|_| (rand(n as u64) as usize, rand(n as u64) as usize))
+        .collect();
+      let perm = shuffled(n, &mut rand);
+      let other = permute(&edges, &perm);
+      assert_eq!(canonical_hash(n, &edges), canonical_hash(n, &other));
+      let map = find_isomorphism(n, &edges, n, &other).unwrap();
+      check_mapping(&edges, &other, &map);
+    }
+  }
+
+  #[test]
+  fn test_against_brute_force() {
+    let mut rand = rand_gen(8);
+    let n = 6;
+    let perms: Vec<Vec<usize>> = {
+      let mut all = vec![];
+      let mut p: Vec<usize> = (0..n).collect();
+      fn heap(k: usize, p: &mut Vec<usize>, all: &mut Vec<Vec<usize>>) {
+        if k == 1 { all.push(p.clone()); return; }
+        for i in 0..k {
+          heap(k - 1, p, all);
+          if k.is_multiple_of(2) { p.swap(i, k - 1) } else { p.swap(0, k - 1) }
+        }
+      }
+      heap(n, &mut p, &mut all);
+      all
+    };
+    let norm = |e: &[(usize, usize)]| e.iter().map(|&(u, v)| (u.min(v), u.max(v))).collect::<BTreeSet<_>>();
+    for _ in 0..300 {
+      let m = 4 + rand(4);
+      let g1: Vec<_> = (0..m).map(|_| (rand(6) as usize, rand(6) as usize)).filter(|e| e.0 != e.1).collect();
+      let g2: Vec<_> = (0..m).map(|_| (rand(6) as usize, rand(6) as usize)).filter(|e| e.0 != e.1).collect();
+      let expected = perms.iter().any(|p| norm(&permute(&g1, p)) == norm(&g2));
+      assert_eq!(expected, are_isomorphic(n, &g1, n, &g2));
+      assert_eq!(expected, canonical_hash(n, &g1) == canonical_hash(n, &g2));
+    }
+  }
+
+  #[test]
+  fn test_regular_graphs() {
+    // color refinement alone can't tell a hexagon from two triangles
+    let c6 = [(0, 1), (1, 2), (2, 3), (3, 4), (4, 5), (5, 0)];
+    let two_c3 = [(0, 1), (1, 2), (2, 0), (3, 4), (4, 5), (5, 3)];
+    assert!(!are_isomorphic(6, &c6, 6, &two_c3));
+
+    // highly symmetric graphs stay fast thanks to automorphism pruning
+    let mut rand = rand_gen(77);
+    let k: Vec<_> = (0..30).flat_map(|i| (i + 1..30).map(move |j| (i, j))).collect();
+    let perm = shuffled(30, &mut rand);
+    assert!(are_isomorphic(30, &k, 30, &permute(&k, &perm)));
+    assert!(are_isomorphic(60, &[], 60, &[]));
+    let triangles: Vec<_> = (0..10).flat_map(|t| vec![(3 * t, 3 * t + 1), (3 * t + 1, 3 * t + 2), (3 * t + 2, 3 * t)]).collect();
+    let perm = shuffled(30, &mut rand);
+    let map = find_isomorphism(30, &triangles, 30, &permute(&triangles, &perm)).unwrap();
+    check_mapping(&triangles, &permute(&triangles, &perm), &map);
+
+    let petersen = [
+      (0, 1), (1, 2), (2, 3), (3, 4), (4, 0), (0, 5), (1, 6), (2, 7), (3, 8), (4, 9),
+      (5, 7), (7, 9), (9, 6), (6, 8), (8, 5),
+    ];
+    let perm = shuffled(10, &mut rand);
+    assert!(are_isomorphic(10, &petersen, 10, &permute(&petersen, &perm)));
+    assert!(!are_isomorphic(10, &petersen, 9, &petersen[..0]));
+  }
+}
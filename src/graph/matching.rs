@@ -0,0 +1,628 @@
+// Maximum weight matching in general graphs, Edmonds' blossom algorithm with the
+// primal-dual method, O(n^3).
+//
+// Vertices are 0..n. An edge k = (i, j, w) has two endpoints 2k (= i) and 2k+1 (= j),
+// so p ^ 1 is the other end of the edge behind endpoint p.
+// Blossoms are numbered n..2n, index b < n is a trivial blossom (a single vertex).
+// Dual variables are stored doubled so that all arithmetic stays in integers.
+
+const NONE: usize = usize::MAX;
+
+struct Blossom<'a> {
+  n: usize,
+  edges: &'a [(usize, usize, i64)],
+  endpoint: Vec<usize>,
+  neighbend: Vec<Vec<usize>>, // endpoints of incident edges, pointing away from the vertex
+  mate: Vec<usize>, // mate[v] is the remote endpoint of v's matched edge
+  label: Vec<u8>, // 0 = free, 1 = S (outer), 2 = T (inner), 5 = breadcrumb in scan_blossom
+  labelend: Vec<usize>, // the endpoint through which a labeled blossom got its label
+  inblossom: Vec<usize>, // top-level blossom containing each vertex
+  blossomparent: Vec<usize>,
+  blossomchilds: Vec<Vec<usize>>, // sub-blossoms in cyclic order, starting at the base
+  blossombase: Vec<usize>,
+  blossomendps: Vec<Vec<usize>>, // blossomendps[b][i] connects childs[i] to childs[i + 1]
+  bestedge: Vec<usize>, // least-slack edge to a different S-blossom
+  blossombestedges: Vec<Option<Vec<usize>>>,
+  unusedblossoms: Vec<usize>,
+  dualvar: Vec<i64>,
+  allowedge: Vec<bool>, // edge has zero slack
+  queue: Vec<usize>, // S-vertices still to be scanned
+}
+
+// indexes a cyclic list with a possibly negative position
+#[inline] fn at(v: &[usize], j: isize) -> usize { v[j.rem_euclid(v.len() as isize) as usize] }
+
+impl<'a> Blossom<'a> {
+  fn new(n: usize, edges: &'a [(usize, usize, i64)]) -> Blossom<'a> {
+    let maxweight = edges.iter().filter(|e| e.0 != e.1).map(|e| e.2).max().unwrap_or(0).max(0);
+    let endpoint = (0..2 * edges.len())
+      .map(|p| if p % 2 == 0 { edges[p / 2].0 } else { edges[p / 2].1 })
+      .collect();
+    let mut neighbend = vec![vec![]; n];
+    for (k, &(i, j, _)) in edges.iter().enumerate() {
+      assert!(i < n && j < n, "edge ({}, {}) out of range for {} vertices", i, j, n);
+      // a self-loop can never be matched; left out of the adjacency, it is never scanned
+      if i == j {
+        continue;
+      }
+      neighbend[i].push(2 * k + 1);
+      neighbend[j].push(2 * k);
+    }
+    Blossom {
+      n,
+      edges,
+      endpoint,
+      neighbend,
+      mate: vec![NONE; n],
+      label: vec![0; 2 * n],
+      labelend: vec![NONE; 2 * n],
+      inblossom: (0..n).collect(),
+      blossomparent: vec![NONE; 2 * n],
+      blossomchilds: vec![vec![]; 2 * n],
+      blossombase: (0..n).chain(std::iter::repeat_n(NONE, n)).collect(),
+      blossomendps: vec![vec![]; 2 * n],
+      bestedge: vec![NONE; 2 * n],
+      blossombestedges: vec![None; 2 * n],
+      unusedblossoms: (n..2 * n).collect(),
+      dualvar: std::iter::repeat_n(maxweight, n).chain(std::iter::repeat_n(0, n)).collect(),
+      allowedge: vec![false; edges.len()],
+      queue: vec![],
+    }
+  }
+
+  fn slack(&self, k: usize) -> i64 {
+    let (i, j, w) = self.edges[k];
+    self.dualvar[i] + self.dualvar[j] - 2 * w
+  }
+
+  fn blossom_leaves(&self, b: usize) -> Vec<usize> {
+    let mut leaves = vec![];
+    let mut stack = vec![b];
+    while let Some(t) = stack.pop() {
+      if t < self.n {
+        leaves.push(t);
+      } else {
+        stack.extend(self.blossomchilds[t].iter().rev());
+      }
+    }
+    leaves
+  }
+
+  // label the top-level blossom containing w with t, reached through endpoint p
+  fn assign_label(&mut self, w: usize, t: u8, p: usize) {
+    let b = self.inblossom[w];
+    self.label[w] = t;
+    self.label[b] = t;
+    self.labelend[w] = p;
+    self.labelend[b] = p;
+    self.bestedge[w] = NONE;
+    self.bestedge[b] = NONE;
+    if t == 1 {
+      let leaves = self.blossom_leaves(b);
+      self.queue.extend(leaves);
+    } else if t == 2 {
+      // the mate of a T-blossom's base becomes an S-vertex
+      let base = self.blossombase[b];
+      let mb = self.mate[base];
+      self.assign_label(self.endpoint[mb], 1, mb ^ 1);
+    }
+  }
+
+  // trace back from v and w to find either a new blossom (returns its base) or
+  // an augmenting path (returns NONE)
+  fn scan_blossom(&mut self, mut v: usize, mut w: usize) -> usize {
+    let mut path = vec![];
+    let mut base = NONE;
+    while v != NONE || w != NONE {
+      let mut b = self.inblossom[v];
+      if self.label[b] & 4 != 0 {
+        base = self.blossombase[b];
+        break;
+      }
+      path.push(b);
+      self.label[b] = 5;
+      if self.labelend[b] == NONE {
+        v = NONE; // reached a root
+      } else {
+        v = self.endpoint[self.labelend[b]];
+        b = self.inblossom[v];
+        v = self.endpoint[self.labelend[b]];
+      }
+      if w != NONE {
+        std::mem::swap(&mut v, &mut w);
+      }
+    }
+    for b in path {
+      self.label[b] = 1;
+    }
+    base
+  }
+
+  // shrink the odd cycle closed by edge k into a new blossom with the given base
+  fn add_blossom(&mut self, base: usize, k: usize) {
+    let (v, w, _) = self.edges[k];
+    let bb = self.inblossom[base];
+    let mut bv = self.inblossom[v];
+    let mut bw = self.inblossom[w];
+    let b = self.unusedblossoms.pop().unwrap();
+    self.blossombase[b] = base;
+    self.blossomparent[b] = NONE;
+    self.blossomparent[bb] = b;
+    let mut path = vec![];
+    let mut endps = vec![];
+    while bv != bb {
+      self.blossomparent[bv] = b;
+      path.push(bv);
+      endps.push(self.labelend[bv]);
+      bv = self.inblossom[self.endpoint[self.labelend[bv]]];
+    }
+    path.push(bb);
+    path.reverse();
+    endps.reverse();
+    endps.push(2 * k);
+    while bw != bb {
+      self.blossomparent[bw] = b;
+      path.push(bw);
+      endps.push(self.labelend[bw] ^ 1);
+      bw = self.inblossom[self.endpoint[self.labelend[bw]]];
+    }
+    self.label[b] = 1;
+    self.labelend[b] = self.labelend[bb];
+    self.dualvar[b] = 0;
+    for v in self.blossom_leaves_of(&path) {
+      if self.label[self.inblossom[v]] == 2 {
+        // former T-vertices become S-vertices and need scanning
+        self.queue.push(v);
+      }
+      self.inblossom[v] = b;
+    }
+    // compute the least-slack edges from the new blossom to every other S-blossom
+    let mut bestedgeto = vec![NONE; 2 * self.n];
+    for &bv in &path {
+      let nblists: Vec<Vec<usize>> = match self.blossombestedges[bv].take() {
+        Some(list) => vec![list],
+        None => self.blossom_leaves(bv).into_iter()
+          .map(|v| self.neighbend[v].iter().map(|p| p / 2).collect())
+          .collect(),
+      };
+      for nblist in nblists {
+        for k in nblist {
+          let (mut i, mut j, _) = self.edges[k];
+          if self.inblossom[j] == b {
+            std::mem::swap(&mut i, &mut j);
+          }
+          let bj = self.inblossom[j];
+          if bj != b && self.label[bj] == 1
+            && (bestedgeto[bj] == NONE || self.slack(k) < self.slack(bestedgeto[bj])) {
+            bestedgeto[bj] = k;
+          }
+        }
+      }
+      self.bestedge[bv] = NONE;
+    }
+    let best: Vec<usize> = bestedgeto.into_iter().filter(|&k| k != NONE).collect();
+    self.bestedge[b] = NONE;
+    for &k in &best {
+      if self.bestedge[b] == NONE || self.slack(k) < self.slack(self.bestedge[b]) {
+        self.bestedge[b] = k;
+      }
+    }
+    self.blossombestedges[b] = Some(best);
+    self.blossomchilds[b] = path;
+    self.blossomendps[b] = endps;
+  }
+
+  fn blossom_leaves_of(&self, childs: &[usize]) -> Vec<usize> {
+    childs.iter().flat_map(|&t| self.blossom_leaves(t)).collect()
+  }
+
+  // undo a blossom, relabeling its children if it was a T-blossom in the middle of a stage
+  fn expand_blossom(&mut self, b: usize, endstage: bool) {
+    let childs = self.blossomchilds[b].clone();
+    for &s in &childs {
+      self.blossomparent[s] = NONE;
+      if s < self.n {
+        self.inblossom[s] = s;
+      } else if endstage && self.dualvar[s] == 0 {
+        self.expand_blossom(s, endstage);
+      } else {
+        for v in self.blossom_leaves(s) {
+          self.inblossom[v] = s;
+        }
+      }
+    }
+    if !endstage && self.label[b] == 2 {
+      // the child the blossom was entered through becomes the T-labeled end of
+      // an even-length alternating path back to the base
+      let endps = self.blossomendps[b].clone();
+      let entrychild = self.inblossom[self.endpoint[self.labelend[b] ^ 1]];
+      let mut j = childs.iter().position(|&c| c == entrychild).unwrap() as isize;
+      let (jstep, endptrick): (isize, usize) = if j & 1 == 1 {
+        j -= childs.len() as isize;
+        (1, 0)
+      } else {
+        (-1, 1)
+      };
+      let mut p = self.labelend[b];
+      while j != 0 {
+        // relabel the T-sub-blossom and allow the edges along the path
+        self.label[self.endpoint[p ^ 1]] = 0;
+        let q = at(&endps, j - endptrick as isize) ^ endptrick ^ 1;
+        self.label[self.endpoint[q]] = 0;
+        self.assign_label(self.endpoint[p ^ 1], 2, p);
+        self.allowedge[at(&endps, j - endptrick as isize) / 2] = true;
+        j += jstep;
+        p = at(&endps, j - endptrick as isize) ^ endptrick;
+        self.allowedge[p / 2] = true;
+        j += jstep;
+      }
+      // the base child becomes a T-blossom without relabeling its mate
+      let bv = at(&childs, j);
+      let ep = self.endpoint[p ^ 1];
+      self.label[ep] = 2;
+      self.label[bv] = 2;
+      self.labelend[ep] = p;
+      self.labelend[bv] = p;
+      self.bestedge[bv] = NONE;
+      j += jstep;
+      // the remaining children off the path may still be reachable as T-vertices
+      while at(&childs, j) != entrychild {
+        let bv = at(&childs, j);
+        if self.label[bv] == 1 {
+          j += jstep;
+          continue;
+        }
+        if let Some(v) = self.blossom_leaves(bv).into_iter().find(|&v| self.label[v] != 0) {
+          self.label[v] = 0;
+          let mb = self.mate[self.blossombase[bv]];
+          self.label[self.endpoint[mb]] = 0;
+          self.assign_label(v, 2, self.labelend[v]);
+        }
+        j += jstep;
+      }
+    }
+    self.label[b] = 0;
+    self.labelend[b] = NONE;
+    self.blossomchilds[b] = vec![];
+    self.blossomendps[b] = vec![];
+    self.blossombase[b] = NONE;
+    self.blossombestedges[b] = None;
+    self.bestedge[b] = NONE;
+    self.unusedblossoms.push(b);
+  }
+
+  // swap matched/unmatched edges on the path from v to the base of b, making v the new base
+  fn augment_blossom(&mut self, b: usize, v: usize) {
+    let mut t = v;
+    while self.blossomparent[t] != b {
+      t = self.blossomparent[t];
+    }
+    if t >= self.n {
+      self.augment_blossom(t, v);
+    }
+    let i = self.blossomchilds[b].iter().position(|&c| c == t).unwrap();
+    let len = self.blossomchilds[b].len() as isize;
+    let mut j = i as isize;
+    let (jstep, endptrick): (isize, usize) = if i & 1 == 1 {
+      j -= len;
+      (1, 0)
+    } else {
+      (-1, 1)
+    };
+    while j != 0 {
+      j += jstep;
+      let t = at(&self.blossomchilds[b], j);
+      let p = at(&self.blossomendps[b], j - endptrick as isize) ^ endptrick;
+      if t >= self.n {
+        self.augment_blossom(t, self.endpoint[p]);
+      }
+      j += jstep;
+      let t = at(&self.blossomchilds[b], j);
+      if t >= self.n {
+        self.augment_blossom(t, self.endpoint[p ^ 1]);
+      }
+      self.mate[self.endpoint[p]] = p ^ 1;
+      self.mate[self.endpoint[p ^ 1]] = p;
+    }
+    self.blossomchilds[b].rotate_left(i);
+    self.blossomendps[b].rotate_left(i);
+    self.blossombase[b] = self.blossombase[self.blossomchilds[b][0]];
+  }
+
+  // flip the augmenting path through edge k back to the two roots
+  fn augment_matching(&mut self, k: usize) {
+    let (v, w, _) = self.edges[k];
+    for (mut s, mut p) in [(v, 2 * k + 1), (w, 2 * k)] {
+      loop {
+        let bs = self.inblossom[s];
+        if bs >= self.n {
+          self.augment_blossom(bs, s);
+        }
+        self.mate[s] = p;
+        if self.labelend[bs] == NONE {
+          break; // reached a single vertex root
+        }
+        let t = self.endpoint[self.labelend[bs]];
+        let bt = self.inblossom[t];
+        s = self.endpoint[self.labelend[bt]];
+        let j = self.endpoint[self.labelend[bt] ^ 1];
+        if bt >= self.n {
+          self.augment_blossom(bt, j);
+        }
+        self.mate[j] = self.labelend[bt];
+        p = self.labelend[bt] ^ 1;
+      }
+    }
+  }
+
+  fn solve(&mut self, max_cardinality: bool) {
+    let n = self.n;
+    for _ in 0..n {
+      // each stage either augments the matching or proves it optimal
+      self.label.iter_mut().for_each(|l| *l = 0);
+      self.bestedge.iter_mut().for_each(|e| *e = NONE);
+      self.blossombestedges[n..].iter_mut().for_each(|e| *e = None);
+      self.allowedge.iter_mut().for_each(|a| *a = false);
+      self.queue.clear();
+      for v in 0..n {
+        if self.mate[v] == NONE && self.label[self.inblossom[v]] == 0 {
+          self.assign_label(v, 1, NONE);
+        }
+      }
+      let mut augmented = false;
+      loop {
+        while let Some(v) = self.queue.pop() {
+          for pi in 0..self.neighbend[v].len() {
+            let p = self.neighbend[v][pi];
+            let k = p / 2;
+            let w = self.endpoint[p];
+            if self.inblossom[v] == self.inblossom[w] {
+              continue; // internal edge of a blossom
+            }
+            let mut kslack = 0;
+            if !self.allowedge[k] {
+              kslack = self.slack(k);
+              if kslack <= 0 {
+                self.allowedge[k] = true;
+              }
+            }
+            if self.allowedge[k] {
+              if self.label[self.inblossom[w]] == 0 {
+                self.assign_label(w, 2, p ^ 1);
+              } else if self.label[self.inblossom[w]] == 1 {
+                let base = self.scan_blossom(v, w);
+                if base != NONE {
+                  self.add_blossom(base, k);
+                } else {
+                  self.augment_matching(k);
+                  augmented = true;
+                  break;
+                }
+              } else if self.label[w] == 0 {
+                // w is inside a T-blossom but not yet reached from outside
+                self.label[w] = 2;
+                self.labelend[w] = p ^ 1;
+              }
+            } else if self.label[self.inblossom[w]] == 1 {
+              let b = self.inblossom[v];
+              if self.bestedge[b] == NONE || kslack < self.slack(self.bestedge[b]) {
+                self.bestedge[b] = k;
+              }
+            } else if self.label[w] == 0
+              && (self.bestedge[w] == NONE || kslack < self.slack(self.bestedge[w])) {
+              self.bestedge[w] = k;
+            }
+          }
+          if augmented {
+            break;
+          }
+        }
+        if augmented {
+          break;
+        }
+
+        // no tight edge left to follow, adjust the duals
+        // 1: decrease the S-vertex duals to zero, ends the algorithm
+        // 2: an S-vertex to free vertex edge becomes tight
+        // 3: an S-blossom to S-blossom edge becomes tight
+        // 4: a T-blossom dual reaches zero and it is expanded
+        let mut deltatype = 0;
+        let mut delta = 0;
+        let mut deltaedge = NONE;
+        let mut deltablossom = NONE;
+        if !max_cardinality {
+          deltatype = 1;
+          delta = *self.dualvar[..n].iter().min().unwrap();
+        }
+        for v in 0..n {
+          if self.label[self.inblossom[v]] == 0 && self.bestedge[v] != NONE {
+            let d = self.slack(self.bestedge[v]);
+            if deltatype == 0 || d < delta {
+              delta = d;
+              deltatype = 2;
+              deltaedge = self.bestedge[v];
+            }
+          }
+        }
+        for b in 0..2 * n {
+          if self.blossomparent[b] == NONE && self.label[b] == 1 && self.bestedge[b] != NONE {
+            let d = self.slack(self.bestedge[b]) / 2;
+            if deltatype == 0 || d < delta {
+              delta = d;
+              deltatype = 3;
+              deltaedge = self.bestedge[b];
+            }
+          }
+        }
+        for b in n..2 * n {
+          if self.blossombase[b] != NONE && self.blossomparent[b] == NONE && self.label[b] == 2
+            && (deltatype == 0 || self.dualvar[b] < delta) {
+            delta = self.dualvar[b];
+            deltatype = 4;
+            deltablossom = b;
+          }
+        }
+        if deltatype == 0 {
+          // only possible in max cardinality mode, no further improvement is possible
+          deltatype = 1;
+          delta = (*self.dualvar[..n].iter().min().unwrap()).max(0);
+        }
+
+        for v in 0..n {
+          match self.label[self.inblossom[v]] {
+            1 => self.dualvar[v] -= delta,
+            2 => self.dualvar[v] += delta,
+            _ => {},
+          }
+        }
+        for b in n..2 * n {
+          if self.blossombase[b] != NONE && self.blossomparent[b] == NONE {
+            match self.label[b] {
+              1 => self.dualvar[b] += delta,
+              2 => self.dualvar[b] -= delta,
+              _ => {},
+            }
+          }
+        }
+
+        match deltatype {
+          1 => break,
+          2 => {
+            self.allowedge[deltaedge] = true;
+            let (mut i, j, _) = self.edges[deltaedge];
+            if self.label[self.inblossom[i]] == 0 {
+              i = j;
+            }
+            self.queue.push(i);
+          },
+          3 => {
+            self.allowedge[deltaedge] = true;
+            let (i, _, _) = self.edges[deltaedge];
+            self.queue.push(i);
+          },
+          _ => self.expand_blossom(deltablossom, false),
+        }
+      }
+      if !augmented {
+        break;
+      }
+      // expand S-blossoms with zero dual at the end of the stage
+      for b in n..2 * n {
+        if self.blossomparent[b] == NONE && self.blossombase[b] != NONE
+          && self.label[b] == 1 && self.dualvar[b] == 0 {
+          self.expand_blossom(b, true);
+        }
+      }
+    }
+  }
+}
+
+// returns mate[v], the vertex v is matched to in a maximum weight matching of the
+// graph with vertices 0..n; with max_cardinality, the maximum weight matching among
+// all maximum cardinality matchings instead
+pub fn max_weight_matching(n: usize, edges: &[(usize, usize, i64)], max_cardinality: bool) -> Vec<Option<usize>> {
+  let mut bl = Blossom::new(n, edges);
+  bl.solve(max_cardinality);
+  bl.mate.iter().map(|&p| if p == NONE { None } else { Some(bl.endpoint[p]) }).collect()
+}
+
+pub fn max_cardinality_matching(n: usize, edges: &[(usize, usize)]) -> Vec<Option<usize>> {
+  let weighted: Vec<_> = edges.iter().map(|&(i, j)| (i, j, 1)).collect();
+  max_weight_matching(n, &weighted, true)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  fn matching_weight(edges: &[(usize, usize, i64)], mate: &[Option<usize>]) -> (usize, i64) {
+    let mut cnt = 0;
+    let mut weight = 0;
+    for (v, m) in mate.iter().enumerate() {
+      if let Some(u) = *m {
+        assert_eq!(Some(v), mate[u]);
+        if v < u {
+          cnt += 1;
+          weight += edges.iter().find(|&&(i, j, _)| (i, j) == (v, u) || (i, j) == (u, v)).unwrap().2;
+        }
+      }
+    }
+    (cnt, weight)
+  }
+
+  fn brute_force(n: usize, edges: &[(usize, usize, i64)], max_cardinality: bool) -> (usize, i64) {
+    fn go(k: usize, used: &mut Vec<bool>, edges: &[(usize, usize, i64)], cur: (usize, i64), best: &mut (usize, i64), mc: bool) {
+      let better = if mc { cur > *best } else { cur.1 > best.1 };
+      if better { *best = cur; }
+      for e in k..edges.len() {
+        let (i, j, w) = edges[e];
+        if !used[i] && !used[j] {
+          used[i] = true;
+          used[j] = true;
+          go(e + 1, used, edges, (cur.0 + 1, cur.1 + w), best, mc);
+          used[i] = false;
+          used[j] = false;
+        }
+      }
+    }
+    let mut best = (0, 0);
+    go(0, &mut vec![false; n], edges, (0, 0), &mut best, max_cardinality);
+    best
+  }
+
+  #[test]
+  fn test_small() {
+    assert_eq!(vec![Some(1), Some(0)], max_weight_matching(2, &[(0, 1, 1)], false));
+    // a heavy middle edge beats two light outer edges
+    let mate = max_weight_matching(4, &[(0, 1, 2), (1, 2, 5), (2, 3, 2)], false);
+    assert_eq!(vec![None, Some(2), Some(1), None], mate);
+    // unless cardinality comes first
+    let mate = max_weight_matching(4, &[(0, 1, 2), (1, 2, 5), (2, 3, 2)], true);
+    assert_eq!(vec![Some(1), Some(0), Some(3), Some(2)], mate);
+    // negative weights are never taken without max_cardinality
+    assert_eq!(vec![None, None], max_weight_matching(2, &[(0, 1, -1)], false));
+    // self-loops are ignored, however heavy
+    assert_eq!(vec![Some(1), Some(0), None], max_weight_matching(3, &[(0, 0, 5), (0, 1, 1), (2, 2, 9)], false));
+    assert_eq!(vec![None], max_cardinality_matching(1, &[(0, 0)]));
+  }
+
+  #[test]
+  fn test_blossom() {
+    // odd cycle 0-1-2 with a tail on both sides requires shrinking a blossom
+    let edges = [(0, 1), (1, 2), (2, 0), (0, 3), (2, 4), (4, 5)];
+    let mate = max_cardinality_matching(6, &edges);
+    let weighted: Vec<_> = edges.iter().map(|&(i, j)| (i, j, 1)).collect();
+    assert_eq!((3, 3), matching_weight(&weighted, &mate));
+
+    // nested S-blossom that gets relabeled as a T-blossom and expanded
+    let edges = [(1, 2, 19), (1, 3, 20), (1, 8, 8), (2, 3, 25), (2, 4, 18), (3, 5, 18), (4, 5, 13), (4, 7, 7), (5, 6, 7)];
+    let mate = max_weight_matching(9, &edges, false);
+    assert_eq!(vec![None, Some(8), Some(3), Some(2), Some(7), Some(6), Some(5), Some(4), Some(1)], mate);
+  }
+
+  #[test]
+  fn test_against_brute_force() {
+    let mut rand = rand_gen(12345);
+    for round in 0..500 {
+      let n = 2 + rand(7) as usize;
+      let mut edges = vec![];
+      for i in 0..n {
+        for j in i + 1..n {
+          if rand(3) != 0 {
+            edges.push((i, j, rand(20) as i64 - 3));
+          }
+        }
+      }
+      let mc = round % 2 == 0;
+      let mate = max_weight_matching(n, &edges, mc);
+      let got = matching_weight(&edges, &mate);
+      let expected = brute_force(n, &edges, mc);
+      if mc {
+        assert_eq!(expected, got);
+      } else {
+        assert_eq!(expected.1, got.1);
+      }
+    }
+  }
+}
@@ -0,0 +1,205 @@
+use std::collections::VecDeque;
+
+// Dinic's maximum flow, O(V^2 E).
+// edges are stored in pairs, edge e ^ 1 is the residual edge of e
+pub struct MaxFlow {
+  adj: Vec<Vec<usize>>,
+  to: Vec<usize>,
+  cap: Vec<i64>, // remaining capacity
+  original: Vec<i64>,
+  level: Vec<usize>,
+  next_edge: Vec<usize>, // per vertex, the first adjacent edge not yet known to be blocked
+}
+
+impl MaxFlow {
+  pub fn new(n: usize) -> MaxFlow {
+    MaxFlow {
+      adj: vec![vec![]; n],
+      to: vec![],
+      cap: vec![],
+      original: vec![],
+      level: vec![0; n],
+      next_edge: vec![0; n],
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.adj.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.adj.is_empty()
+  }
+
+  // adds a directed edge and returns its id
+  pub fn add_edge(&mut self, u: usize, v: usize, cap: i64) -> usize {
+    self.add_edge_pair(u, v, cap, 0)
+  }
+
+  // an undirected edge is a pair of edges that are each other's residual
+  pub fn add_undirected_edge(&mut self, u: usize, v: usize, cap: i64) -> usize {
+    self.add_edge_pair(u, v, cap, cap)
+  }
+
+  fn add_edge_pair(&mut self, u: usize, v: usize, cap: i64, rev_cap: i64) -> usize {
+    assert!(cap >= 0 && rev_cap >= 0);
+    let id = self.to.len();
+    self.adj[u].push(id);
+    self.to.push(v);
+    self.cap.push(cap);
+    self.original.push(cap);
+    self.adj[v].push(id + 1);
+    self.to.push(u);
+    self.cap.push(rev_cap);
+    self.original.push(rev_cap);
+    id
+  }
+
+  // the flow currently sent through edge id
+  pub fn flow(&self, id: usize) -> i64 {
+    self.original[id] - self.cap[id]
+  }
+
+  // restores every edge to its full capacity
+  pub fn reset(&mut self) {
+    self.cap.copy_from_slice(&self.original);
+  }
+
+  fn bfs(&mut self, s: usize, t: usize) -> bool {
+    self.level.iter_mut().for_each(|l| *l = usize::MAX);
+    self.level[s] = 0;
+    let mut queue = VecDeque::new();
+    queue.push_back(s);
+    while let Some(u) = queue.pop_front() {
+      for &e in &self.adj[u] {
+        let v = self.to[e];
+        if self.cap[e] > 0 && self.level[v] == usize::MAX {
+          self.level[v] = self.level[u] + 1;
+          queue.push_back(v);
+        }
+      }
+    }
+    self.level[t] != usize::MAX
+  }
+
+  // one augmenting path from s to t along the level graph, or 0 if there is none left. The path
+  // is kept as a stack of edges rather than recursion, since it can be as long as the graph
+  fn dfs(&mut self, s: usize, t: usize) -> i64 {
+    let mut path: Vec<usize> = vec![];
+    let mut u = s;
+    loop {
+      if u == t {
+        let pushed = path.iter().map(|&e| self.cap[e]).min().unwrap();
+        for &e in &path {
+          self.cap[e] -= pushed;
+          self.cap[e ^ 1] += pushed;
+        }
+        return pushed;
+      }
+      if self.next_edge[u] < self.adj[u].len() {
+        let e = self.adj[u][self.next_edge[u]];
+        let v = self.to[e];
+        if self.cap[e] > 0 && self.level[v] == self.level[u] + 1 {
+          path.push(e);
+          u = v;
+        } else {
+          self.next_edge[u] += 1;
+        }
+      } else {
+        // u is a dead end, so is the edge that led to it
+        match path.pop() {
+          Some(e) => {
+            u = self.to[e ^ 1];
+            self.next_edge[u] += 1;
+          },
+          None => return 0,
+        }
+      }
+    }
+  }
+
+  // pushes as much flow as possible from s to t on top of the current flow
+  pub fn max_flow(&mut self, s: usize, t: usize) -> i64 {
+    assert_ne!(s, t);
+    let mut total = 0;
+    while self.bfs(s, t) {
+      self.next_edge.iter_mut().for_each(|i| *i = 0);
+      loop {
+        let pushed = self.dfs(s, t);
+        if pushed == 0 { break; }
+        total += pushed;
+      }
+    }
+    total
+  }
+
+  // after max_flow, the vertices reachable from s in the residual graph form
+  // the source side of a minimum cut
+  pub fn min_cut_side(&self, s: usize) -> Vec<bool> {
+    let mut seen = vec![false; self.len()];
+    seen[s] = true;
+    let mut stack = vec![s];
+    while let Some(u) = stack.pop() {
+      for &e in &self.adj[u] {
+        let v = self.to[e];
+        if self.cap[e] > 0 && !seen[v] {
+          seen[v] = true;
+          stack.push(v);
+        }
+      }
+    }
+    seen
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_max_flow() {
+    // CLRS figure 26.1
+    let mut mf = MaxFlow::new(6);
+    let e01 = mf.add_edge(0, 1, 16);
+    mf.add_edge(0, 2, 13);
+    mf.add_edge(1, 3, 12);
+    mf.add_edge(2, 1, 4);
+    mf.add_edge(2, 4, 14);
+    mf.add_edge(3, 2, 9);
+    mf.add_edge(3, 5, 20);
+    mf.add_edge(4, 3, 7);
+    mf.add_edge(4, 5, 4);
+    assert_eq!(23, mf.max_flow(0, 5));
+    assert!(mf.flow(e01) <= 16);
+    let side = mf.min_cut_side(0);
+    assert_eq!(vec![true, true, true, false, true, false], side);
+
+    mf.reset();
+    assert_eq!(0, mf.flow(e01));
+    assert_eq!(23, mf.max_flow(0, 5));
+    assert_eq!(0, mf.max_flow(0, 5));
+  }
+
+  #[test]
+  fn test_undirected() {
+    let mut mf = MaxFlow::new(4);
+    mf.add_undirected_edge(0, 1, 3);
+    mf.add_undirected_edge(1, 2, 1);
+    mf.add_undirected_edge(2, 3, 3);
+    mf.add_undirected_edge(3, 0, 2);
+    assert_eq!(3, mf.max_flow(2, 0));
+  }
+
+  #[test]
+  fn test_long_path() {
+    // deep enough to overflow the stack with a recursive search
+    let n = 100_000;
+    let mut mf = MaxFlow::new(n);
+    for v in 0..n - 1 {
+      mf.add_edge(v, v + 1, 1 + (v % 7) as i64);
+    }
+    mf.add_edge(0, n - 1, 2);
+    assert_eq!(3, mf.max_flow(0, n - 1));
+    assert_eq!(0, mf.max_flow(0, n - 1));
+  }
+}
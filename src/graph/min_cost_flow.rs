@@ -0,0 +1,161 @@
+use std::cmp::Reverse;
+
+use crate::binary_heap::BinaryHeap;
+
+// Minimum cost flow by successive shortest paths. Potentials start from a Bellman-Ford pass,
+// so negative edge costs are fine as long as there is no negative cycle; after that every
+// augmenting path is found by Dijkstra on reduced costs, O(F (E log V)) for total flow F.
+// edges are stored in pairs, edge e ^ 1 is the residual edge of e
+pub struct MinCostFlow {
+  adj: Vec<Vec<usize>>,
+  to: Vec<usize>,
+  cap: Vec<i64>, // remaining capacity
+  original: Vec<i64>,
+  cost: Vec<i64>,
+}
+
+impl MinCostFlow {
+  pub fn new(n: usize) -> MinCostFlow {
+    MinCostFlow {
+      adj: vec![vec![]; n],
+      to: vec![],
+      cap: vec![],
+      original: vec![],
+      cost: vec![],
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.adj.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.adj.is_empty()
+  }
+
+  // adds a directed edge with a per-unit cost and returns its id
+  pub fn add_edge(&mut self, u: usize, v: usize, cap: i64, cost: i64) -> usize {
+    assert!(cap >= 0);
+    let id = self.to.len();
+    self.adj[u].push(id);
+    self.to.push(v);
+    self.cap.push(cap);
+    self.original.push(cap);
+    self.cost.push(cost);
+    self.adj[v].push(id + 1);
+    self.to.push(u);
+    self.cap.push(0);
+    self.original.push(0);
+    self.cost.push(-cost);
+    id
+  }
+
+  // the flow currently sent through edge id
+  pub fn flow(&self, id: usize) -> i64 {
+    self.original[id] - self.cap[id]
+  }
+
+  fn bellman_ford(&self, s: usize) -> Vec<i64> {
+    let n = self.adj.len();
+    let mut dist = vec![i64::MAX; n];
+    dist[s] = 0;
+    for _ in 0..n {
+      let mut changed = false;
+      for u in 0..n {
+        if dist[u] == i64::MAX { continue; }
+        for &e in &self.adj[u] {
+          if self.cap[e] > 0 && dist[u] + self.cost[e] < dist[self.to[e]] {
+            dist[self.to[e]] = dist[u] + self.cost[e];
+            changed = true;
+          }
+        }
+      }
+      if !changed { break; }
+    }
+    dist
+  }
+
+  // sends up to limit units from s to t as cheaply as possible, returns (flow, cost)
+  pub fn min_cost_flow(&mut self, s: usize, t: usize, limit: i64) -> (i64, i64) {
+    assert_ne!(s, t);
+    let n = self.adj.len();
+    let mut potential = self.bellman_ford(s);
+    let (mut flow, mut total_cost) = (0, 0);
+    while flow < limit {
+      let mut dist = vec![i64::MAX; n];
+      let mut prev_edge = vec![usize::MAX; n];
+      let mut heap = BinaryHeap::new();
+      dist[s] = 0;
+      heap.push(Reverse((0, s)));
+      while let Some(Reverse((d, u))) = heap.pop() {
+        if d > dist[u] { continue; }
+        for &e in &self.adj[u] {
+          let v = self.to[e];
+          if self.cap[e] == 0 || potential[v] == i64::MAX { continue; }
+          // reduced costs are non-negative on residual edges
+          let nd = d + self.cost[e] + potential[u] - potential[v];
+          if nd < dist[v] {
+            dist[v] = nd;
+            prev_edge[v] = e;
+            heap.push(Reverse((nd, v)));
+          }
+        }
+      }
+      if dist[t] == i64::MAX { break; }
+      for v in 0..n {
+        if dist[v] != i64::MAX {
+          potential[v] += dist[v];
+        }
+      }
+      let mut push = limit - flow;
+      let mut v = t;
+      while v != s {
+        let e = prev_edge[v];
+        push = push.min(self.cap[e]);
+        v = self.to[e ^ 1];
+      }
+      let mut v = t;
+      while v != s {
+        let e = prev_edge[v];
+        self.cap[e] -= push;
+        self.cap[e ^ 1] += push;
+        total_cost += push * self.cost[e];
+        v = self.to[e ^ 1];
+      }
+      flow += push;
+    }
+    (flow, total_cost)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_min_cost_flow() {
+    // two routes from 0 to 3: cheap with capacity 2, expensive with capacity 3
+    let mut g = MinCostFlow::new(4);
+    let cheap = g.add_edge(0, 1, 2, 1);
+    g.add_edge(1, 3, 5, 1);
+    let pricey = g.add_edge(0, 2, 3, 4);
+    g.add_edge(2, 3, 5, 1);
+    assert_eq!((3, 2 * 2 + 5), g.min_cost_flow(0, 3, 3));
+    assert_eq!(2, g.flow(cheap));
+    assert_eq!(1, g.flow(pricey));
+    assert_eq!((2, 2 * 5), g.min_cost_flow(0, 3, i64::MAX)); // continues on top
+  }
+
+  #[test]
+  fn test_negative_costs_and_rerouting() {
+    // the first path 0-1-2-3 must later be undone through the residual edge 2->1
+    let mut g = MinCostFlow::new(4);
+    g.add_edge(0, 1, 1, 1);
+    g.add_edge(0, 2, 1, 5);
+    g.add_edge(1, 2, 1, -3);
+    g.add_edge(1, 3, 1, 2);
+    g.add_edge(2, 3, 1, 1);
+    assert_eq!((1, -1), g.min_cost_flow(0, 3, 1));
+    assert_eq!((1, 10), g.min_cost_flow(0, 3, 1)); // two units cost 3 + 6 in total
+  }
+}
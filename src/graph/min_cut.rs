@@ -0,0 +1,112 @@
+// Stoer-Wagner global minimum cut of an undirected graph with non-negative weights, O(n^3).
+// returns the cut value together with the vertices on one side of the cut,
+// or None when there are fewer than two vertices to separate
+pub fn global_min_cut(n: usize, edges: &[(usize, usize, i64)]) -> Option<(i64, Vec<usize>)> {
+  if n < 2 { return None; }
+  let mut w = vec![vec![0i64; n]; n];
+  for &(u, v, c) in edges {
+    assert!(c >= 0);
+    if u != v {
+      w[u][v] += c;
+      w[v][u] += c;
+    }
+  }
+  // merged[v] holds the original vertices contracted into v
+  let mut merged: Vec<Vec<usize>> = (0..n).map(|v| vec![v]).collect();
+  let mut alive: Vec<usize> = (0..n).collect();
+  let mut best: Option<(i64, Vec<usize>)> = None;
+
+  while alive.len() > 1 {
+    // maximum adjacency ordering: repeatedly add the vertex most tightly connected to the set
+    let mut conn = vec![0i64; n];
+    let mut in_set = vec![false; n];
+    let (mut prev, mut last) = (alive[0], alive[0]);
+    for _ in 0..alive.len() {
+      let next = *alive.iter()
+        .filter(|&&v| !in_set[v])
+        .max_by_key(|&&v| conn[v])
+        .unwrap();
+      in_set[next] = true;
+      prev = last;
+      last = next;
+      for &v in &alive {
+        conn[v] += w[next][v];
+      }
+    }
+    // the cut separating the last added vertex from the rest is a minimum s-t cut
+    let cut_of_phase = conn[last];
+    if best.as_ref().is_none_or(|b| cut_of_phase < b.0) {
+      best = Some((cut_of_phase, merged[last].clone()));
+    }
+    // contract last into prev
+    let moved = std::mem::take(&mut merged[last]);
+    merged[prev].extend(moved);
+    for &v in &alive {
+      w[prev][v] += w[last][v];
+      w[v][prev] = w[prev][v];
+    }
+    w[prev][prev] = 0;
+    alive.retain(|&v| v != last);
+  }
+  best
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  fn cut_value(edges: &[(usize, usize, i64)], side: &[usize]) -> i64 {
+    edges.iter()
+      .filter(|&&(u, v, _)| side.contains(&u) != side.contains(&v))
+      .map(|e| e.2)
+      .sum()
+  }
+
+  #[test]
+  fn test_stoer_wagner_paper_example() {
+    let edges = [
+      (0, 1, 2), (0, 4, 3), (1, 2, 3), (1, 4, 2), (1, 5, 2), (2, 3, 4), (2, 6, 2),
+      (3, 6, 2), (3, 7, 2), (4, 5, 3), (5, 6, 1), (6, 7, 3),
+    ];
+    let (value, mut side) = global_min_cut(8, &edges).unwrap();
+    assert_eq!(4, value);
+    side.sort();
+    if side[0] == 0 {
+      assert_eq!(vec![0, 1, 4, 5], side);
+    } else {
+      assert_eq!(vec![2, 3, 6, 7], side);
+    }
+  }
+
+  #[test]
+  fn test_disconnected_and_trivial() {
+    assert_eq!(None, global_min_cut(1, &[]));
+    let (value, side) = global_min_cut(4, &[(0, 1, 5), (2, 3, 7)]).unwrap();
+    assert_eq!(0, value);
+    assert_eq!(0, cut_value(&[(0, 1, 5), (2, 3, 7)], &side));
+  }
+
+  #[test]
+  fn test_against_brute_force() {
+    let mut rand = rand_gen(2020);
+    for _ in 0..200 {
+      let n = 2 + rand(7) as usize;
+      let mut edges = vec![];
+      for _ in 0..rand(20) {
+        edges.push((rand(n as u64) as usize, rand(n as u64) as usize, rand(10) as i64));
+      }
+      let (value, side) = global_min_cut(n, &edges).unwrap();
+      assert!(!side.is_empty() && side.len() < n);
+      assert_eq!(value, cut_value(&edges, &side));
+      let expected = (1..(1usize << n) - 1)
+        .map(|mask| {
+          let side: Vec<usize> = (0..n).filter(|&v| mask >> v & 1 == 1).collect();
+          cut_value(&edges, &side)
+        })
+        .min()
+        .unwrap();
+      assert_eq!(expected, value);
+    }
+  }
+}
@@ -0,0 +1,19 @@
+pub mod matching;
+pub mod min_cut;
+pub mod max_flow;
+pub mod gomory_hu;
+pub mod planarity;
+pub mod isomorphism;
+pub mod treewidth;
+pub mod independent_set;
+pub mod coloring;
+pub mod chordal;
+pub mod min_cost_flow;
+pub mod transportation;
+pub mod centrality;
+pub mod community;
+pub mod csr;
+pub mod incremental_topo;
+pub mod dynamic_connectivity;
+pub mod bridges;
+pub mod difference_constraints;
\ No newline at end of file
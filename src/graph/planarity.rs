@@ -0,0 +1,693 @@
+use std::collections::{HashMap, HashSet};
+
+// Left-right planarity test (de Fraysseix, Ossona de Mendez and Rosenstiehl, in the
+// formulation of Brandes), O(n + m). When the graph is planar, the same DFS data
+// yields a combinatorial embedding: the clockwise order of neighbors around each vertex.
+
+#[derive(Clone, Copy, Default)]
+struct Interval {
+  low: Option<usize>, // edge ids
+  high: Option<usize>,
+}
+
+impl Interval {
+  fn is_empty(&self) -> bool {
+    self.low.is_none() && self.high.is_none()
+  }
+}
+
+// return edges that have to be on the same side (within an interval) or on
+// different sides (left versus right)
+#[derive(Clone, Copy, Default)]
+struct ConflictPair {
+  left: Interval,
+  right: Interval,
+}
+
+impl ConflictPair {
+  fn swap(&mut self) {
+    std::mem::swap(&mut self.left, &mut self.right);
+  }
+}
+
+const UNSEEN: usize = usize::MAX;
+
+struct LrPlanarity {
+  adj: Vec<Vec<(usize, usize)>>, // (neighbor, edge id)
+  oriented: Vec<bool>,
+  src: Vec<usize>, // edges are oriented by the first dfs, from src to dst
+  dst: Vec<usize>,
+  out: Vec<Vec<usize>>, // outgoing oriented edges of each vertex
+  height: Vec<usize>,
+  parent_edge: Vec<Option<usize>>,
+  roots: Vec<usize>,
+  lowpt: Vec<usize>,
+  lowpt2: Vec<usize>,
+  nesting_depth: Vec<i64>,
+  lowpt_edge: Vec<usize>,
+  stack: Vec<ConflictPair>,
+  stack_bottom: Vec<usize>,
+  refs: Vec<Option<usize>>,
+  side: Vec<i64>,
+}
+
+impl LrPlanarity {
+  fn new(n: usize, edges: &[(usize, usize)]) -> LrPlanarity {
+    let mut adj = vec![vec![]; n];
+    let mut seen = HashSet::new();
+    let mut m = 0;
+    for &(u, v) in edges {
+      // self loops and parallel edges don't affect planarity
+      if u == v || !seen.insert((u.min(v), u.max(v))) { continue; }
+      adj[u].push((v, m));
+      adj[v].push((u, m));
+      m += 1;
+    }
+    LrPlanarity {
+      adj,
+      oriented: vec![false; m],
+      src: vec![0; m],
+      dst: vec![0; m],
+      out: vec![vec![]; n],
+      height: vec![UNSEEN; n],
+      parent_edge: vec![None; n],
+      roots: vec![],
+      lowpt: vec![0; m],
+      lowpt2: vec![0; m],
+      nesting_depth: vec![0; m],
+      lowpt_edge: vec![0; m],
+      stack: vec![],
+      stack_bottom: vec![0; m],
+      refs: vec![None; m],
+      side: vec![1; m],
+    }
+  }
+
+  // orient the edges along a dfs and compute the lowpoints and nesting depths. The dfs keeps
+  // its path from the root, each vertex with the index of its next edge, on an explicit stack,
+  // as do the two later ones: a path can be as long as the graph.
+  fn dfs_orientation(&mut self, root: usize) {
+    let mut path = vec![(root, 0)];
+    while let Some(&(v, i)) = path.last() {
+      if i == self.adj[v].len() {
+        path.pop();
+        if let Some(&(u, _)) = path.last() {
+          // back from the tree edge u -> v
+          self.finish_orientation(u, self.parent_edge[v].unwrap());
+          path.last_mut().unwrap().1 += 1;
+        }
+        continue;
+      }
+      let (w, vw) = self.adj[v][i];
+      if self.oriented[vw] {
+        path.last_mut().unwrap().1 += 1;
+        continue;
+      }
+      self.oriented[vw] = true;
+      self.src[vw] = v;
+      self.dst[vw] = w;
+      self.out[v].push(vw);
+      self.lowpt[vw] = self.height[v];
+      self.lowpt2[vw] = self.height[v];
+      if self.height[w] == UNSEEN { // tree edge
+        self.parent_edge[w] = Some(vw);
+        self.height[w] = self.height[v] + 1;
+        path.push((w, 0));
+      } else { // back edge
+        self.lowpt[vw] = self.height[w];
+        self.finish_orientation(v, vw);
+        path.last_mut().unwrap().1 += 1;
+      }
+    }
+  }
+
+  // once the lowpoints of vw are known, its nesting depth, and its share of the lowpoints of the
+  // edge into v
+  fn finish_orientation(&mut self, v: usize, vw: usize) {
+    self.nesting_depth[vw] = 2 * self.lowpt[vw] as i64;
+    if self.lowpt2[vw] < self.height[v] { // chordal, it has two distinct return points
+      self.nesting_depth[vw] += 1;
+    }
+    if let Some(e) = self.parent_edge[v] {
+      if self.lowpt[vw] < self.lowpt[e] {
+        self.lowpt2[e] = self.lowpt[e].min(self.lowpt2[vw]);
+        self.lowpt[e] = self.lowpt[vw];
+      } else if self.lowpt[vw] > self.lowpt[e] {
+        self.lowpt2[e] = self.lowpt2[e].min(self.lowpt[vw]);
+      } else {
+        self.lowpt2[e] = self.lowpt2[e].min(self.lowpt2[vw]);
+      }
+    }
+  }
+
+  fn sort_by_nesting_depth(&mut self) {
+    for v in 0..self.out.len() {
+      let mut out = std::mem::take(&mut self.out[v]);
+      out.sort_by_key(|&e| self.nesting_depth[e]);
+      self.out[v] = out;
+    }
+  }
+
+  fn conflicting(&self, interval: &Interval, b: usize) -> bool {
+    !interval.is_empty() && interval.high.is_some_and(|h| self.lowpt[h] > self.lowpt[b])
+  }
+
+  fn lowest(&self, p: &ConflictPair) -> usize {
+    if p.left.is_empty() {
+      return self.lowpt[p.right.low.unwrap()];
+    }
+    if p.right.is_empty() {
+      return self.lowpt[p.left.low.unwrap()];
+    }
+    self.lowpt[p.left.low.unwrap()].min(self.lowpt[p.right.low.unwrap()])
+  }
+
+  fn dfs_testing(&mut self, root: usize) -> bool {
+    let mut path = vec![(root, 0)];
+    while let Some(&(v, i)) = path.last() {
+      if i == self.out[v].len() {
+        if let Some(e) = self.parent_edge[v] {
+          self.remove_back_edges(e);
+        }
+        path.pop();
+        if let Some(&(u, j)) = path.last() {
+          // back from the tree edge u -> v
+          if !self.finish_testing(u, j) {
+            return false;
+          }
+          path.last_mut().unwrap().1 += 1;
+        }
+        continue;
+      }
+      let ei = self.out[v][i];
+      let w = self.dst[ei];
+      self.stack_bottom[ei] = self.stack.len();
+      if self.parent_edge[w] == Some(ei) { // tree edge
+        path.push((w, 0));
+        continue;
+      }
+      // back edge
+      self.lowpt_edge[ei] = ei;
+      self.stack.push(ConflictPair {
+        left: Interval::default(),
+        right: Interval { low: Some(ei), high: Some(ei) },
+      });
+      if !self.finish_testing(v, i) {
+        return false;
+      }
+      path.last_mut().unwrap().1 += 1;
+    }
+    true
+  }
+
+  // integrate the return edges of the i-th outgoing edge of v
+  fn finish_testing(&mut self, v: usize, i: usize) -> bool {
+    let ei = self.out[v][i];
+    if self.lowpt[ei] < self.height[v] {
+      let e = self.parent_edge[v].unwrap(); // only a non-root vertex can have edges returning below it
+      if i == 0 {
+        self.lowpt_edge[e] = self.lowpt_edge[ei];
+      } else if !self.add_constraints(ei, e) {
+        return false;
+      }
+    }
+    true
+  }
+
+  fn add_constraints(&mut self, ei: usize, e: usize) -> bool {
+    let mut p = ConflictPair::default();
+    // merge the return edges of ei into p.right
+    loop {
+      let mut q = self.stack.pop().unwrap();
+      if !q.left.is_empty() {
+        q.swap();
+      }
+      if !q.left.is_empty() {
+        return false; // both sides are taken
+      }
+      if self.lowpt[q.right.low.unwrap()] > self.lowpt[e] {
+        // merge intervals
+        if p.right.is_empty() {
+          p.right = q.right;
+        } else {
+          self.refs[p.right.low.unwrap()] = q.right.high;
+        }
+        p.right.low = q.right.low;
+      } else {
+        // align
+        self.refs[q.right.low.unwrap()] = Some(self.lowpt_edge[e]);
+      }
+      if self.stack.len() == self.stack_bottom[ei] {
+        break;
+      }
+    }
+    // merge the conflicting return edges of the previous siblings into p.left
+    while let Some(&top) = self.stack.last() {
+      if !self.conflicting(&top.left, ei) && !self.conflicting(&top.right, ei) {
+        break;
+      }
+      let mut q = self.stack.pop().unwrap();
+      if self.conflicting(&q.right, ei) {
+        q.swap();
+      }
+      if self.conflicting(&q.right, ei) {
+        return false;
+      }
+      // merge the interval below lowpt(ei) into p.right
+      if let Some(low) = p.right.low {
+        self.refs[low] = q.right.high;
+      }
+      if q.right.low.is_some() {
+        p.right.low = q.right.low;
+      }
+      if p.left.is_empty() {
+        p.left = q.left;
+      } else if let Some(low) = p.left.low {
+        self.refs[low] = q.left.high;
+      }
+      p.left.low = q.left.low;
+    }
+    if !(p.left.is_empty() && p.right.is_empty()) {
+      self.stack.push(p);
+    }
+    true
+  }
+
+  fn remove_back_edges(&mut self, e: usize) {
+    let u = self.src[e];
+    // drop entire conflict pairs that only return to u
+    while let Some(top) = self.stack.last() {
+      if self.lowest(top) != self.height[u] { break; }
+      let p = self.stack.pop().unwrap();
+      if let Some(low) = p.left.low {
+        self.side[low] = -1;
+      }
+    }
+    // trim the intervals of one more conflict pair
+    if let Some(mut p) = self.stack.pop() {
+      while let Some(high) = p.left.high {
+        if self.dst[high] != u { break; }
+        p.left.high = self.refs[high];
+      }
+      if p.left.high.is_none() {
+        if let Some(low) = p.left.low { // just emptied
+          self.refs[low] = p.right.low;
+          self.side[low] = -1;
+          p.left.low = None;
+        }
+      }
+      while let Some(high) = p.right.high {
+        if self.dst[high] != u { break; }
+        p.right.high = self.refs[high];
+      }
+      if p.right.high.is_none() {
+        if let Some(low) = p.right.low { // just emptied
+          self.refs[low] = p.left.low;
+          self.side[low] = -1;
+          p.right.low = None;
+        }
+      }
+      self.stack.push(p);
+    }
+    // the side of e is the side of a highest return edge
+    if self.lowpt[e] < self.height[u] {
+      let top = self.stack.last().unwrap();
+      let (hl, hr) = (top.left.high, top.right.high);
+      self.refs[e] = match (hl, hr) {
+        (Some(l), Some(r)) if self.lowpt[l] > self.lowpt[r] => hl,
+        (Some(_), None) => hl,
+        _ => hr,
+      };
+    }
+  }
+
+  // resolve the relative sides along the chain of refs into absolute ones
+  fn sign(&mut self, e: usize) -> i64 {
+    let mut chain = vec![];
+    let mut cur = e;
+    while let Some(r) = self.refs[cur] {
+      chain.push(cur);
+      cur = r;
+    }
+    let mut s = self.side[cur];
+    for &c in chain.iter().rev() {
+      self.side[c] *= s;
+      self.refs[c] = None;
+      s = self.side[c];
+    }
+    self.side[e]
+  }
+
+  fn run(mut self) -> Option<PlanarEmbedding> {
+    let n = self.adj.len();
+    let m = self.oriented.len();
+    if n > 2 && m > 3 * n - 6 {
+      return None;
+    }
+    for v in 0..n {
+      if self.height[v] == UNSEEN {
+        self.height[v] = 0;
+        self.roots.push(v);
+        self.dfs_orientation(v);
+      }
+    }
+    self.sort_by_nesting_depth();
+    for i in 0..self.roots.len() {
+      if !self.dfs_testing(self.roots[i]) {
+        return None;
+      }
+    }
+    for e in 0..m {
+      self.nesting_depth[e] *= self.sign(e);
+    }
+    self.sort_by_nesting_depth();
+    let mut embedding = RotationBuilder::new(n);
+    for v in 0..n {
+      let mut prev = None;
+      for &e in &self.out[v] {
+        let w = self.dst[e];
+        embedding.add_cw(v, w, prev);
+        prev = Some(w);
+      }
+    }
+    let mut left_ref = vec![0; n];
+    let mut right_ref = vec![0; n];
+    for i in 0..self.roots.len() {
+      self.dfs_embedding(self.roots[i], &mut embedding, &mut left_ref, &mut right_ref);
+    }
+    Some(embedding.build())
+  }
+
+  // add the incoming half edges around their targets
+  fn dfs_embedding(&self, root: usize, embedding: &mut RotationBuilder, left_ref: &mut [usize], right_ref: &mut [usize]) {
+    let mut path = vec![(root, 0)];
+    while let Some(&mut (v, ref mut i)) = path.last_mut() {
+      if *i == self.out[v].len() {
+        path.pop();
+        continue;
+      }
+      let ei = self.out[v][*i];
+      *i += 1;
+      let w = self.dst[ei];
+      if self.parent_edge[w] == Some(ei) { // tree edge
+        embedding.add_first(w, v);
+        left_ref[v] = w;
+        right_ref[v] = w;
+        path.push((w, 0));
+      } else if self.side[ei] == 1 { // back edge, on the right
+        embedding.add_cw(w, v, Some(right_ref[w]));
+      } else { // back edge, on the left
+        embedding.add_ccw(w, v, left_ref[w]);
+        left_ref[w] = v;
+      }
+    }
+  }
+}
+
+// cyclic doubly linked neighbor lists used while the embedding is assembled
+struct RotationBuilder {
+  links: Vec<HashMap<usize, (usize, usize)>>, // links[v][w] = (cw, ccw) neighbors of w around v
+  first: Vec<Option<usize>>,
+}
+
+impl RotationBuilder {
+  fn new(n: usize) -> RotationBuilder {
+    RotationBuilder {
+      links: vec![HashMap::new(); n],
+      first: vec![None; n],
+    }
+  }
+
+  // insert w clockwise right after reference around v
+  fn add_cw(&mut self, v: usize, w: usize, reference: Option<usize>) {
+    match reference {
+      None => {
+        self.links[v].insert(w, (w, w));
+        self.first[v] = Some(w);
+      },
+      Some(r) => {
+        let cw_r = self.links[v][&r].0;
+        self.links[v].get_mut(&r).unwrap().0 = w;
+        self.links[v].insert(w, (cw_r, r));
+        self.links[v].get_mut(&cw_r).unwrap().1 = w;
+      },
+    }
+  }
+
+  // insert w counterclockwise right before reference around v
+  fn add_ccw(&mut self, v: usize, w: usize, reference: usize) {
+    let ccw_r = self.links[v][&reference].1;
+    self.add_cw(v, w, Some(ccw_r));
+    if self.first[v] == Some(reference) {
+      self.first[v] = Some(w);
+    }
+  }
+
+  fn add_first(&mut self, v: usize, w: usize) {
+    match self.first[v] {
+      Some(r) => self.add_ccw(v, w, r),
+      None => self.add_cw(v, w, None),
+    }
+  }
+
+  fn build(self) -> PlanarEmbedding {
+    let rotation = (0..self.links.len()).map(|v| {
+      let mut order = vec![];
+      if let Some(start) = self.first[v] {
+        let mut w = start;
+        loop {
+          order.push(w);
+          w = self.links[v][&w].0;
+          if w == start { break; }
+        }
+      }
+      order
+    }).collect();
+    PlanarEmbedding { rotation }
+  }
+}
+
+// a combinatorial embedding, the clockwise cyclic order of the neighbors of each vertex
+#[derive(Debug, Clone)]
+pub struct PlanarEmbedding {
+  rotation: Vec<Vec<usize>>,
+}
+
+impl PlanarEmbedding {
+  pub fn neighbors_cw(&self, v: usize) -> &[usize] {
+    &self.rotation[v]
+  }
+
+  // every face as the cyclic sequence of vertices on its boundary, obtained by
+  // following each half edge (v, w) with (w, x) where x comes right before v around w
+  pub fn faces(&self) -> Vec<Vec<usize>> {
+    let mut pos = HashMap::new();
+    for (v, nbrs) in self.rotation.iter().enumerate() {
+      for (i, &w) in nbrs.iter().enumerate() {
+        pos.insert((v, w), i);
+      }
+    }
+    let mut visited = HashSet::new();
+    let mut faces = vec![];
+    for (v, nbrs) in self.rotation.iter().enumerate() {
+      for &w in nbrs {
+        if visited.contains(&(v, w)) { continue; }
+        let mut face = vec![];
+        let (mut a, mut b) = (v, w);
+        while visited.insert((a, b)) {
+          face.push(a);
+          let around = &self.rotation[b];
+          let i = pos[&(b, a)];
+          let next = around[(i + around.len() - 1) % around.len()];
+          a = b;
+          b = next;
+        }
+        faces.push(face);
+      }
+    }
+    faces
+  }
+}
+
+pub fn planar_embedding(n: usize, edges: &[(usize, usize)]) -> Option<PlanarEmbedding> {
+  LrPlanarity::new(n, edges).run()
+}
+
+pub fn is_planar(n: usize, edges: &[(usize, usize)]) -> bool {
+  planar_embedding(n, edges).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  // checks that the rotation system uses every edge once in each direction and
+  // satisfies Euler's formula V - E + F = 2 for each connected component
+  fn check_embedding(n: usize, edges: &[(usize, usize)], emb: &PlanarEmbedding) {
+    let mut set = HashSet::new();
+    for &(u, v) in edges {
+      if u != v {
+        set.insert((u, v));
+        set.insert((v, u));
+      }
+    }
+    let mut half_edges = HashSet::new();
+    for v in 0..n {
+      for &w in emb.neighbors_cw(v) {
+        assert!(set.contains(&(v, w)));
+        assert!(half_edges.insert((v, w)));
+      }
+    }
+    assert_eq!(set.len(), half_edges.len());
+
+    let mut comp = (0..n).collect::<Vec<_>>();
+    fn find(comp: &mut Vec<usize>, x: usize) -> usize {
+      if comp[x] != x {
+        let r = find(comp, comp[x]);
+        comp[x] = r;
+      }
+      comp[x]
+    }
+    for &(u, v) in &set {
+      let (ru, rv) = (find(&mut comp, u), find(&mut comp, v));
+      comp[ru] = rv;
+    }
+    let mut count: HashMap<usize, (i64, i64, i64)> = HashMap::new();
+    for v in 0..n {
+      let r = find(&mut comp, v);
+      count.entry(r).or_default().0 += 1;
+    }
+    for &(u, _) in &set {
+      let r = find(&mut comp, u);
+      count.entry(r).or_default().1 += 1; // each edge counted twice
+    }
+    for face in emb.faces() {
+      let r = find(&mut comp, face[0]);
+      count.entry(r).or_default().2 += 1;
+    }
+    for (_, (v, e2, f)) in count {
+      if e2 == 0 { continue; }
+      assert_eq!(2, v - e2 / 2 + f);
+    }
+  }
+
+  // Wagner: a graph is planar iff it has neither a K5 nor a K3,3 minor. On six vertices
+  // a K3,3 minor is a K3,3 subgraph, and a K5 minor is a K5 after deleting a vertex or
+  // contracting an edge
+  fn nonplanar_on_six(adj: &[Vec<bool>]) -> bool {
+    let n = 6;
+    for mask in 0..1usize << n {
+      if mask.count_ones() == 3
+        && (0..n).all(|a| (0..n).all(|b| mask >> a & 1 == 0 || mask >> b & 1 == 1 || adj[a][b])) {
+        return true; // K3,3 between mask and its complement
+      }
+    }
+    let is_clique = |vs: &[usize]| vs.iter().all(|&a| vs.iter().all(|&b| a == b || adj[a][b]));
+    for x in 0..n {
+      let rest: Vec<usize> = (0..n).filter(|&v| v != x).collect();
+      if is_clique(&rest) {
+        return true;
+      }
+      for y in x + 1..n {
+        let others: Vec<usize> = (0..n).filter(|&v| v != x && v != y).collect();
+        if adj[x][y] && is_clique(&others) && others.iter().all(|&v| adj[x][v] || adj[y][v]) {
+          return true;
+        }
+      }
+    }
+    false
+  }
+
+  #[test]
+  fn test_kuratowski_graphs() {
+    let k5: Vec<_> = (0..5).flat_map(|i| (i + 1..5).map(move |j| (i, j))).collect();
+    assert!(!is_planar(5, &k5));
+    let k33: Vec<_> = (0..3).flat_map(|i| (3..6).map(move |j| (i, j))).collect();
+    assert!(!is_planar(6, &k33));
+    let petersen = [
+      (0, 1), (1, 2), (2, 3), (3, 4), (4, 0), (0, 5), (1, 6), (2, 7), (3, 8), (4, 9),
+      (5, 7), (7, 9), (9, 6), (6, 8), (8, 5),
+    ];
+    assert!(!is_planar(10, &petersen));
+
+    let k4 = [(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)];
+    let emb = planar_embedding(4, &k4).unwrap();
+    check_embedding(4, &k4, &emb);
+    assert_eq!(4, emb.faces().len());
+    for face in emb.faces() {
+      assert_eq!(3, face.len());
+    }
+  }
+
+  #[test]
+  fn test_all_small_graphs() {
+    let n = 6;
+    let pairs: Vec<(usize, usize)> = (0..n).flat_map(|i| (i + 1..n).map(move |j| (i, j))).collect();
+    let mut rand = rand_gen(99);
+    for _ in 0..3000 {
+      let density = 4 + rand(7);
+      let edges: Vec<_> = pairs.iter().cloned().filter(|_| rand(10) < density).collect();
+      let mut adj = vec![vec![false; n]; n];
+      for &(u, v) in &edges {
+        adj[u][v] = true;
+        adj[v][u] = true;
+      }
+      let nonplanar = nonplanar_on_six(&adj);
+      match planar_embedding(n, &edges) {
+        Some(emb) => {
+          assert!(!nonplanar, "{:?}", edges);
+          check_embedding(n, &edges, &emb);
+        },
+        None => assert!(nonplanar, "{:?}", edges),
+      }
+    }
+  }
+
+  #[test]
+  fn test_random_planar() {
+    let mut rand = rand_gen(5);
+    for _ in 0..50 {
+      // random stacked triangulation, then drop some edges and shuffle labels
+      let n = 4 + rand(60) as usize;
+      let mut edges = vec![(0, 1), (1, 2), (2, 0)];
+      let mut faces = vec![(0, 1, 2), (0, 2, 1)];
+      for v in 3..n {
+        let f = rand(faces.len() as u64) as usize;
+        let (a, b, c) = faces.swap_remove(f);
+        edges.extend([(a, v), (b, v), (c, v)]);
+        faces.extend([(a, b, v), (b, c, v), (c, a, v)]);
+      }
+      let mut label: Vec<usize> = (0..n).collect();
+      for i in (1..n).rev() {
+        label.swap(i, rand(i as u64 + 1) as usize);
+      }
+      let edges: Vec<_> = edges.into_iter()
+        .filter(|_| rand(4) != 0)
+        .map(|(u, v)| (label[u], label[v]))
+        .collect();
+      let emb = planar_embedding(n, &edges).unwrap();
+      check_embedding(n, &edges, &emb);
+    }
+  }
+  #[test]
+  fn test_long_path_and_cycle() {
+    // deep enough to overflow the stack with recursive searches
+    let n = 100_000;
+    let mut edges: Vec<(usize, usize)> = (0..n - 1).map(|v| (v, v + 1)).collect();
+    let emb = planar_embedding(n, &edges).unwrap();
+    assert_eq!(1, emb.faces().len());
+    assert!((1..n - 1).all(|v| emb.neighbors_cw(v).len() == 2));
+    edges.push((n - 1, 0));
+    let emb = planar_embedding(n, &edges).unwrap();
+    assert_eq!(2, emb.faces().len());
+    // a ladder, with back edges nested all the way down
+    let k = n / 2;
+    let ladder: Vec<_> = (0..k - 1).flat_map(|i| [(i, i + 1), (k + i, k + i + 1), (i, k + i)]).chain([(k - 1, 2 * k - 1)]).collect();
+    let emb = planar_embedding(2 * k, &ladder).unwrap();
+    assert_eq!(k, emb.faces().len());
+    // and the cycle with three chords across its last six vertices, a subdivided K3,3
+    edges.extend([(n - 1, n - 4), (n - 3, n - 6), (n - 5, n - 2)]);
+    assert!(!is_planar(n, &edges));
+  }
+}
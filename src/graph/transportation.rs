@@ -0,0 +1,118 @@
+use super::min_cost_flow::MinCostFlow;
+
+// Transportation problem on a supply/demand table: ship goods from sources with supply[i] to
+// sinks with demand[j], paying cost[i][j] per unit. Solved as a min cost flow on the complete
+// bipartite network source -> i -> j -> sink. When the totals differ, as much as possible is
+// shipped, i.e. min(total supply, total demand) units.
+
+#[derive(Clone, Debug)]
+pub struct TransportPlan {
+  pub cost: i64,
+  pub shipped: i64,
+  pub flow: Vec<Vec<i64>>, // flow[i][j] units from source i to sink j
+}
+
+pub fn solve_transportation(supply: &[i64], demand: &[i64], cost: &[Vec<i64>]) -> TransportPlan {
+  let (m, n) = (supply.len(), demand.len());
+  assert_eq!(m, cost.len());
+  let (source, sink) = (m + n, m + n + 1);
+  let mut g = MinCostFlow::new(m + n + 2);
+  for (i, &s) in supply.iter().enumerate() {
+    g.add_edge(source, i, s, 0);
+  }
+  for (j, &d) in demand.iter().enumerate() {
+    g.add_edge(m + j, sink, d, 0);
+  }
+  let ids: Vec<Vec<usize>> = cost.iter().enumerate().map(|(i, row)| {
+    assert_eq!(n, row.len());
+    row.iter().enumerate().map(|(j, &c)| g.add_edge(i, m + j, i64::MAX, c)).collect()
+  }).collect();
+  let (shipped, total) = g.min_cost_flow(source, sink, i64::MAX);
+  TransportPlan {
+    cost: total,
+    shipped,
+    flow: ids.iter().map(|row| row.iter().map(|&id| g.flow(id)).collect()).collect(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  fn check(plan: &TransportPlan, supply: &[i64], demand: &[i64], cost: &[Vec<i64>]) {
+    let mut total = 0;
+    for (i, row) in plan.flow.iter().enumerate() {
+      assert!(row.iter().sum::<i64>() <= supply[i]);
+      for (j, &f) in row.iter().enumerate() {
+        assert!(f >= 0);
+        total += f * cost[i][j];
+      }
+    }
+    for (j, &d) in demand.iter().enumerate() {
+      assert!(plan.flow.iter().map(|row| row[j]).sum::<i64>() <= d);
+    }
+    assert_eq!(total, plan.cost);
+    assert_eq!(supply.iter().sum::<i64>().min(demand.iter().sum()), plan.shipped);
+  }
+
+  // tries every way to split small integer supplies, cell by cell
+  struct BruteForce<'a> {
+    supply: Vec<i64>,
+    demand: Vec<i64>,
+    cost: &'a [Vec<i64>],
+    best: i64,
+  }
+
+  impl<'a> BruteForce<'a> {
+    fn go(&mut self, cell: usize, left: i64, acc: i64) {
+      if left == 0 {
+        self.best = self.best.min(acc);
+        return;
+      }
+      let n = self.demand.len();
+      if cell == self.supply.len() * n { return; }
+      let (i, j) = (cell / n, cell % n);
+      for f in 0..=self.supply[i].min(self.demand[j]).min(left) {
+        self.supply[i] -= f;
+        self.demand[j] -= f;
+        self.go(cell + 1, left - f, acc + f * self.cost[i][j]);
+        self.supply[i] += f;
+        self.demand[j] += f;
+      }
+    }
+  }
+
+  fn brute_force(supply: &[i64], demand: &[i64], cost: &[Vec<i64>]) -> i64 {
+    let target = supply.iter().sum::<i64>().min(demand.iter().sum());
+    let mut brute = BruteForce { supply: supply.to_vec(), demand: demand.to_vec(), cost, best: i64::MAX };
+    brute.go(0, target, 0);
+    brute.best
+  }
+
+  #[test]
+  fn test_classic_table() {
+    let supply = [20, 30, 25];
+    let demand = [10, 25, 20, 20];
+    let cost = vec![vec![8, 6, 10, 9], vec![9, 12, 13, 7], vec![14, 9, 16, 5]];
+    let plan = solve_transportation(&supply, &demand, &cost);
+    check(&plan, &supply, &demand, &cost);
+    assert_eq!(75, plan.shipped);
+    // the same table scaled down to something brute force can handle
+    assert_eq!(brute_force(&[4, 6, 5], &[2, 5, 4, 4], &cost), solve_transportation(&[4, 6, 5], &[2, 5, 4, 4], &cost).cost);
+  }
+
+  #[test]
+  fn test_random_against_brute_force() {
+    let mut rand = rand_gen(29);
+    for _ in 0..200 {
+      let (m, n) = (1 + rand(3) as usize, 1 + rand(3) as usize);
+      let supply: Vec<i64> = (0..m).map(|_| rand(4) as i64).collect();
+      let demand: Vec<i64> = (0..n).map(|_| rand(4) as i64).collect();
+      let cost: Vec<Vec<i64>> = (0..m).map(|_| (0..n).map(|_| rand(20) as i64 - 5).collect()).collect();
+      let plan = solve_transportation(&supply, &demand, &cost);
+      check(&plan, &supply, &demand, &cost);
+      assert_eq!(brute_force(&supply, &demand, &cost), plan.cost);
+    }
+  }
+}
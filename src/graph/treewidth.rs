@@ -0,0 +1,330 @@
+use std::collections::{BTreeSet, VecDeque};
+
+// Tree decompositions from greedy elimination orderings. Eliminating v makes its remaining
+// neighbors a clique; the bags {v} + N(v) at elimination time, linked to the bag of the first
+// neighbor eliminated later, form a tree decomposition whose width is the largest N(v).
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum EliminationHeuristic {
+  MinDegree, // eliminate a vertex of minimum current degree
+  MinFillIn, // eliminate a vertex whose neighborhood needs the fewest new edges
+}
+
+#[derive(Clone, Debug)]
+pub struct TreeDecomposition {
+  pub bags: Vec<Vec<usize>>, // sorted vertex lists
+  pub edges: Vec<(usize, usize)>, // tree edges between bag indices
+}
+
+impl TreeDecomposition {
+  pub fn width(&self) -> usize {
+    self.bags.iter().map(|b| b.len()).max().unwrap_or(1).max(1) - 1
+  }
+
+  // every vertex and edge is covered, and the bags holding a vertex form a connected subtree
+  pub fn is_valid(&self, n: usize, edges: &[(usize, usize)]) -> bool {
+    let k = self.bags.len();
+    if k > 0 && self.edges.len() != k - 1 {
+      return false;
+    }
+    let mut tree = vec![vec![]; k];
+    for &(a, b) in &self.edges {
+      tree[a].push(b);
+      tree[b].push(a);
+    }
+    for &(u, v) in edges {
+      if !self.bags.iter().any(|b| b.binary_search(&u).is_ok() && b.binary_search(&v).is_ok()) {
+        return false;
+      }
+    }
+    for v in 0..n {
+      let holding: Vec<usize> = (0..k).filter(|&i| self.bags[i].binary_search(&v).is_ok()).collect();
+      if holding.is_empty() {
+        return false;
+      }
+      // flood fill within the bags holding v
+      let mut seen = vec![false; k];
+      seen[holding[0]] = true;
+      let mut stack = vec![holding[0]];
+      let mut count = 1;
+      while let Some(i) = stack.pop() {
+        for &j in &tree[i] {
+          if !seen[j] && self.bags[j].binary_search(&v).is_ok() {
+            seen[j] = true;
+            count += 1;
+            stack.push(j);
+          }
+        }
+      }
+      if count != holding.len() {
+        return false;
+      }
+    }
+    true
+  }
+}
+
+fn adjacency_sets(n: usize, edges: &[(usize, usize)]) -> Vec<BTreeSet<usize>> {
+  let mut adj = vec![BTreeSet::new(); n];
+  for &(u, v) in edges {
+    if u != v {
+      adj[u].insert(v);
+      adj[v].insert(u);
+    }
+  }
+  adj
+}
+
+fn fill_in(adj: &[BTreeSet<usize>], v: usize) -> usize {
+  let nbrs: Vec<usize> = adj[v].iter().cloned().collect();
+  let mut missing = 0;
+  for i in 0..nbrs.len() {
+    for j in i + 1..nbrs.len() {
+      if !adj[nbrs[i]].contains(&nbrs[j]) {
+        missing += 1;
+      }
+    }
+  }
+  missing
+}
+
+// greedy elimination ordering, ties are broken by the smaller vertex
+pub fn elimination_ordering(n: usize, edges: &[(usize, usize)], heuristic: EliminationHeuristic) -> Vec<usize> {
+  let mut adj = adjacency_sets(n, edges);
+  let mut alive: BTreeSet<usize> = (0..n).collect();
+  let mut order = vec![];
+  while !alive.is_empty() {
+    let v = *alive.iter().min_by_key(|&&v| match heuristic {
+      EliminationHeuristic::MinDegree => adj[v].len(),
+      EliminationHeuristic::MinFillIn => fill_in(&adj, v),
+    }).unwrap();
+    eliminate(&mut adj, v);
+    alive.remove(&v);
+    order.push(v);
+  }
+  order
+}
+
+// removes v and turns its neighborhood into a clique
+fn eliminate(adj: &mut [BTreeSet<usize>], v: usize) -> Vec<usize> {
+  let nbrs: Vec<usize> = std::mem::take(&mut adj[v]).into_iter().collect();
+  for &a in &nbrs {
+    adj[a].remove(&v);
+    for &b in &nbrs {
+      if a != b {
+        adj[a].insert(b);
+      }
+    }
+  }
+  nbrs
+}
+
+pub fn from_elimination_ordering(n: usize, edges: &[(usize, usize)], order: &[usize]) -> TreeDecomposition {
+  assert_eq!(n, order.len());
+  let mut adj = adjacency_sets(n, edges);
+  let mut position = vec![0; n];
+  for (i, &v) in order.iter().enumerate() {
+    position[v] = i;
+  }
+  let mut bags = vec![];
+  let mut tree_edges = vec![];
+  for (i, &v) in order.iter().enumerate() {
+    let nbrs = eliminate(&mut adj, v);
+    // the bag of the neighbor eliminated next contains all of nbrs; an isolated bag is
+    // hooked to the next bag so the result stays a single tree
+    let parent = nbrs.iter().map(|&u| position[u]).min();
+    match parent {
+      Some(p) => tree_edges.push((i, p)),
+      None if i + 1 < n => tree_edges.push((i, i + 1)),
+      None => {},
+    }
+    let mut bag = nbrs;
+    bag.push(v);
+    bag.sort_unstable();
+    bags.push(bag);
+  }
+  TreeDecomposition { bags, edges: tree_edges }
+}
+
+pub fn tree_decomposition(n: usize, edges: &[(usize, usize)], heuristic: EliminationHeuristic) -> TreeDecomposition {
+  let order = elimination_ordering(n, edges, heuristic);
+  from_elimination_ordering(n, edges, &order)
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum NiceNodeKind {
+  Leaf, // empty bag, no children
+  Introduce(usize), // one child whose bag lacks the vertex
+  Forget(usize), // one child whose bag has the vertex in addition
+  Join, // two children with the same bag
+}
+
+// a rooted tree decomposition where every node is one of the four kinds above, the shape
+// dynamic programs over bags are usually written for; the root bag is empty
+#[derive(Clone, Debug)]
+pub struct NiceTreeDecomposition {
+  pub bags: Vec<Vec<usize>>,
+  pub kinds: Vec<NiceNodeKind>,
+  pub children: Vec<Vec<usize>>,
+  pub root: usize,
+}
+
+impl NiceTreeDecomposition {
+  fn push(&mut self, bag: Vec<usize>, kind: NiceNodeKind, children: Vec<usize>) -> usize {
+    self.bags.push(bag);
+    self.kinds.push(kind);
+    self.children.push(children);
+    self.bags.len() - 1
+  }
+
+  // turns the subtree rooted at node (with bag from) into one ending in bag to
+  fn morph(&mut self, mut node: usize, to: &[usize]) -> usize {
+    let from = self.bags[node].clone();
+    let mut bag = from.clone();
+    for &v in from.iter().filter(|v| to.binary_search(v).is_err()) {
+      bag.retain(|&u| u != v);
+      node = self.push(bag.clone(), NiceNodeKind::Forget(v), vec![node]);
+    }
+    for &v in to.iter().filter(|v| from.binary_search(v).is_err()) {
+      let pos = bag.binary_search(&v).unwrap_err();
+      bag.insert(pos, v);
+      node = self.push(bag.clone(), NiceNodeKind::Introduce(v), vec![node]);
+    }
+    node
+  }
+
+  pub fn from_decomposition(td: &TreeDecomposition) -> NiceTreeDecomposition {
+    let mut nice = NiceTreeDecomposition { bags: vec![], kinds: vec![], children: vec![], root: 0 };
+    if td.bags.is_empty() {
+      nice.root = nice.push(vec![], NiceNodeKind::Leaf, vec![]);
+      return nice;
+    }
+    let k = td.bags.len();
+    let mut tree = vec![vec![]; k];
+    for &(a, b) in &td.edges {
+      tree[a].push(b);
+      tree[b].push(a);
+    }
+    // bfs order from bag 0, then build bottom-up so children are ready before parents
+    let mut order = vec![];
+    let mut parent = vec![usize::MAX; k];
+    let mut queue = VecDeque::new();
+    parent[0] = 0;
+    queue.push_back(0);
+    while let Some(t) = queue.pop_front() {
+      order.push(t);
+      for &c in &tree[t] {
+        if parent[c] == usize::MAX {
+          parent[c] = t;
+          queue.push_back(c);
+        }
+      }
+    }
+    let mut made = vec![0; k];
+    for &t in order.iter().rev() {
+      let bag = &td.bags[t];
+      let mut node = None;
+      for &c in tree[t].iter().filter(|&&c| parent[c] == t) {
+        let sub = nice.morph(made[c], bag);
+        node = Some(match node {
+          None => sub,
+          Some(acc) => nice.push(bag.clone(), NiceNodeKind::Join, vec![acc, sub]),
+        });
+      }
+      made[t] = match node {
+        Some(node) => node,
+        None => {
+          let leaf = nice.push(vec![], NiceNodeKind::Leaf, vec![]);
+          nice.morph(leaf, bag)
+        },
+      };
+    }
+    nice.root = nice.morph(made[0], &[]);
+    nice
+  }
+
+  pub fn width(&self) -> usize {
+    self.bags.iter().map(|b| b.len()).max().unwrap_or(1).max(1) - 1
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  fn check_nice(nice: &NiceTreeDecomposition, n: usize, edges: &[(usize, usize)]) {
+    assert!(nice.bags[nice.root].is_empty());
+    for i in 0..nice.bags.len() {
+      let bag = &nice.bags[i];
+      let ch = &nice.children[i];
+      match nice.kinds[i] {
+        NiceNodeKind::Leaf => assert!(bag.is_empty() && ch.is_empty()),
+        NiceNodeKind::Introduce(v) => {
+          assert_eq!(1, ch.len());
+          let mut expected = nice.bags[ch[0]].clone();
+          assert!(!expected.contains(&v));
+          expected.push(v);
+          expected.sort();
+          assert_eq!(&expected, bag);
+        },
+        NiceNodeKind::Forget(v) => {
+          assert_eq!(1, ch.len());
+          let mut expected = bag.clone();
+          assert!(!expected.contains(&v));
+          expected.push(v);
+          expected.sort();
+          assert_eq!(expected, nice.bags[ch[0]]);
+        },
+        NiceNodeKind::Join => {
+          assert_eq!(2, ch.len());
+          assert_eq!(bag, &nice.bags[ch[0]]);
+          assert_eq!(bag, &nice.bags[ch[1]]);
+        },
+      }
+    }
+    // it is still a tree decomposition of the graph
+    let tree_edges = (0..nice.bags.len())
+      .flat_map(|i| nice.children[i].iter().map(move |&c| (i, c)))
+      .collect();
+    let td = TreeDecomposition { bags: nice.bags.clone(), edges: tree_edges };
+    assert!(td.is_valid(n, edges));
+  }
+
+  #[test]
+  fn test_known_widths() {
+    let path: Vec<_> = (0..9).map(|i| (i, i + 1)).collect();
+    let cycle: Vec<_> = (0..10).map(|i| (i, (i + 1) % 10)).collect();
+    let k6: Vec<_> = (0..6).flat_map(|i| (i + 1..6).map(move |j| (i, j))).collect();
+    for heuristic in [EliminationHeuristic::MinDegree, EliminationHeuristic::MinFillIn] {
+      let td = tree_decomposition(10, &path, heuristic);
+      assert!(td.is_valid(10, &path));
+      assert_eq!(1, td.width());
+      let td = tree_decomposition(10, &cycle, heuristic);
+      assert!(td.is_valid(10, &cycle));
+      assert_eq!(2, td.width());
+      let td = tree_decomposition(6, &k6, heuristic);
+      assert!(td.is_valid(6, &k6));
+      assert_eq!(5, td.width());
+      assert_eq!(0, tree_decomposition(4, &[], heuristic).width());
+    }
+  }
+
+  #[test]
+  fn test_random_graphs() {
+    let mut rand = rand_gen(17);
+    for _ in 0..100 {
+      let n = 1 + rand(25) as usize;
+      let edges: Vec<_> = (0..rand(3 * n as u64))
+        .map(|_| (rand(n as u64) as usize, rand(n as u64) as usize))
+        .collect();
+      for heuristic in [EliminationHeuristic::MinDegree, EliminationHeuristic::MinFillIn] {
+        let td = tree_decomposition(n, &edges, heuristic);
+        assert!(td.is_valid(n, &edges));
+        let nice = NiceTreeDecomposition::from_decomposition(&td);
+        assert_eq!(td.width(), nice.width());
+        check_nice(&nice, n, &edges);
+      }
+    }
+  }
+}
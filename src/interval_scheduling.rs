@@ -0,0 +1,170 @@
+use std::cmp::Reverse;
+
+use crate::binary_heap::BinaryHeap;
+use crate::graph::min_cost_flow::MinCostFlow;
+
+// Scheduling of half-open intervals [start, end) on identical machines. Intervals sharing only
+// an endpoint do not overlap.
+
+// machine index per interval using as few machines as possible, and that number. Intervals are
+// taken by start time; a min-heap of (end, machine) tells whether the machine freed earliest is
+// free already, O(n log n).
+pub fn assign_machines(intervals: &[(i64, i64)]) -> (usize, Vec<usize>) {
+  let mut order: Vec<usize> = (0..intervals.len()).collect();
+  order.sort_by_key(|&i| intervals[i]);
+  let mut machine = vec![0; intervals.len()];
+  let mut count = 0;
+  let mut busy: BinaryHeap<Reverse<(i64, usize)>, _> = BinaryHeap::new(); // earliest end on top
+  for i in order {
+    let (start, end) = intervals[i];
+    if busy.peek().is_some_and(|top| (top.0).0 <= start) {
+      let mut top = busy.peek_mut().unwrap();
+      machine[i] = (top.0).1;
+      *top = Reverse((end, machine[i])); // sifts down when the guard drops
+    } else {
+      machine[i] = count;
+      count += 1;
+      busy.push(Reverse((end, machine[i])));
+    }
+  }
+  (count, machine)
+}
+
+// the largest number of intervals overlapping at one point
+pub fn min_machines(intervals: &[(i64, i64)]) -> usize {
+  assign_machines(intervals).0
+}
+
+// a maximum weight set of pairwise disjoint intervals (start, end, weight) for one machine, as
+// the total weight and the chosen indices in order of time. Weighted interval scheduling: by
+// end time, each interval is either skipped or taken after the last interval ending by its start.
+pub fn max_weight_schedule(intervals: &[(i64, i64, i64)]) -> (i64, Vec<usize>) {
+  let mut order: Vec<usize> = (0..intervals.len()).collect();
+  order.sort_by_key(|&i| intervals[i].1);
+  let ends: Vec<i64> = order.iter().map(|&i| intervals[i].1).collect();
+  // best[j] is the optimum over the first j intervals by end time
+  let mut best = vec![0i64; order.len() + 1];
+  for (j, &i) in order.iter().enumerate() {
+    let (start, _, weight) = intervals[i];
+    let before = ends[..j].partition_point(|&e| e <= start);
+    best[j + 1] = best[j].max(best[before] + weight);
+  }
+  let mut chosen = vec![];
+  let mut j = order.len();
+  while j > 0 {
+    if best[j] == best[j - 1] {
+      j -= 1;
+    } else {
+      let i = order[j - 1];
+      chosen.push(i);
+      j = ends[..j - 1].partition_point(|&e| e <= intervals[i].0);
+    }
+  }
+  chosen.reverse();
+  (best[order.len()], chosen)
+}
+
+// the same on k machines: a maximum weight set of intervals with at most k overlapping at any
+// point, which assign_machines then fits on k machines. Solved as a min cost flow of k units
+// along the time line, where an interval is a shortcut from its start to its end of cost -weight.
+pub fn max_weight_schedule_k(intervals: &[(i64, i64, i64)], k: usize) -> (i64, Vec<usize>) {
+  let mut times: Vec<i64> = intervals.iter().flat_map(|&(s, e, _)| [s, e]).collect();
+  times.sort_unstable();
+  times.dedup();
+  if times.len() < 2 {
+    return (0, vec![]);
+  }
+  let index = |t: i64| times.binary_search(&t).unwrap();
+  let mut flow = MinCostFlow::new(times.len());
+  for p in 1..times.len() {
+    flow.add_edge(p - 1, p, k as i64, 0);
+  }
+  let mut edge = vec![None; intervals.len()];
+  for (i, &(s, e, w)) in intervals.iter().enumerate() {
+    // a negative weight never helps, an empty interval overlaps nothing
+    if w > 0 && s < e {
+      edge[i] = Some(flow.add_edge(index(s), index(e), 1, -w));
+    }
+  }
+  let (_, cost) = flow.min_cost_flow(0, times.len() - 1, k as i64);
+  let mut chosen: Vec<usize> = (0..intervals.len()).filter(|&i| edge[i].is_some_and(|e| flow.flow(e) == 1)).collect();
+  chosen.sort_by_key(|&i| (intervals[i].0, intervals[i].1, i));
+  (-cost, chosen)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  fn overlap(a: (i64, i64), b: (i64, i64)) -> bool {
+    a.0 < b.1 && b.0 < a.1
+  }
+
+  fn depth(intervals: &[(i64, i64)]) -> usize {
+    intervals.iter().map(|&(s, _)| intervals.iter().filter(|&&(a, b)| a <= s && s < b).count()).max().unwrap_or(0)
+  }
+
+  #[test]
+  fn test_assign_machines() {
+    let mut rand = rand_gen(37);
+    for _ in 0..200 {
+      let intervals: Vec<(i64, i64)> = (0..rand(20))
+        .map(|_| {
+          let s = rand(30) as i64;
+          (s, s + 1 + rand(10) as i64)
+        })
+        .collect();
+      let (count, machine) = assign_machines(&intervals);
+      assert_eq!(depth(&intervals), count);
+      assert_eq!(count, min_machines(&intervals));
+      for i in 0..intervals.len() {
+        assert!(machine[i] < count);
+        for j in i + 1..intervals.len() {
+          assert!(machine[i] != machine[j] || !overlap(intervals[i], intervals[j]));
+        }
+      }
+    }
+    assert_eq!((1, vec![0, 0, 0]), assign_machines(&[(0, 1), (1, 2), (2, 3)]));
+  }
+
+  // best total weight over subsets with at most k intervals overlapping anywhere
+  fn brute_force(intervals: &[(i64, i64, i64)], k: usize) -> i64 {
+    (0..1u32 << intervals.len())
+      .filter_map(|mask| {
+        let chosen: Vec<(i64, i64)> = (0..intervals.len())
+          .filter(|&i| mask >> i & 1 == 1)
+          .map(|i| (intervals[i].0, intervals[i].1))
+          .collect();
+        if depth(&chosen) > k { return None; }
+        Some((0..intervals.len()).filter(|&i| mask >> i & 1 == 1).map(|i| intervals[i].2).sum())
+      })
+      .max()
+      .unwrap()
+  }
+
+  #[test]
+  fn test_weighted() {
+    let mut rand = rand_gen(41);
+    for _ in 0..150 {
+      let intervals: Vec<(i64, i64, i64)> = (0..rand(10))
+        .map(|_| {
+          let s = rand(15) as i64;
+          (s, s + 1 + rand(6) as i64, 1 + rand(20) as i64)
+        })
+        .collect();
+      let (weight, chosen) = max_weight_schedule(&intervals);
+      assert_eq!(brute_force(&intervals, 1), weight);
+      assert_eq!(weight, chosen.iter().map(|&i| intervals[i].2).sum::<i64>());
+      assert!(chosen.windows(2).all(|w| intervals[w[0]].1 <= intervals[w[1]].0));
+      for k in 1..4 {
+        let (weight, chosen) = max_weight_schedule_k(&intervals, k);
+        assert_eq!(brute_force(&intervals, k), weight);
+        assert_eq!(weight, chosen.iter().map(|&i| intervals[i].2).sum::<i64>());
+        let spans: Vec<(i64, i64)> = chosen.iter().map(|&i| (intervals[i].0, intervals[i].1)).collect();
+        assert!(min_machines(&spans) <= k);
+      }
+    }
+    assert_eq!((0, vec![]), max_weight_schedule_k(&[], 2));
+  }
+}
@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::mem;
+
+#[inline] fn parent(i: usize) -> usize {  (i - 1) / 2 }
+#[inline] fn left_child(i: usize) -> usize { i * 2 + 1 }
+
+// a max-heap over (key, priority) where pos[key] tracks the heap index of each key,
+// so a key can be found and re-sifted in O(log n)
+pub struct KeyedPriorityQueue<K, P> {
+  data: Vec<(K, P)>,
+  pos: HashMap<K, usize>,
+}
+
+impl<K: Eq + Hash + Clone, P: Ord> KeyedPriorityQueue<K, P> {
+  pub fn new() -> KeyedPriorityQueue<K, P> {
+    KeyedPriorityQueue {
+      data: vec![],
+      pos: HashMap::new(),
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.data.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.data.is_empty()
+  }
+
+  pub fn contains_key(&self, key: &K) -> bool {
+    self.pos.contains_key(key)
+  }
+
+  pub fn get_priority(&self, key: &K) -> Option<&P> {
+    self.pos.get(key).map(|&i| &self.data[i].1)
+  }
+
+  pub fn peek(&self) -> Option<(&K, &P)> {
+    self.data.first().map(|(k, p)| (k, p))
+  }
+
+  // inserts a new key, or updates the priority of an existing one and returns the old priority
+  pub fn push(&mut self, key: K, prio: P) -> Option<P> {
+    if self.pos.contains_key(&key) {
+      return self.change_priority(&key, prio);
+    }
+    let i = self.data.len();
+    self.pos.insert(key.clone(), i);
+    self.data.push((key, prio));
+    self.sift_up(i);
+    None
+  }
+
+  pub fn change_priority(&mut self, key: &K, prio: P) -> Option<P> {
+    let i = *self.pos.get(key)?;
+    let old = mem::replace(&mut self.data[i].1, prio);
+    // only one of them will move the entry
+    self.sift_up(i);
+    self.sift_down(i);
+    Some(old)
+  }
+
+  pub fn remove(&mut self, key: &K) -> Option<P> {
+    let i = *self.pos.get(key)?;
+    Some(self.remove_at(i).1)
+  }
+
+  pub fn pop(&mut self) -> Option<(K, P)> {
+    if self.data.is_empty() {
+      return None;
+    }
+    Some(self.remove_at(0))
+  }
+
+  fn remove_at(&mut self, i: usize) -> (K, P) {
+    let last = self.data.len() - 1;
+    self.swap(i, last);
+    let (key, prio) = self.data.pop().unwrap();
+    self.pos.remove(&key);
+    if i < self.data.len() {
+      // the entry moved into i came from the bottom, it may need to go either way
+      self.sift_up(i);
+      self.sift_down(i);
+    }
+    (key, prio)
+  }
+
+  #[inline]
+  fn swap(&mut self, i: usize, j: usize) {
+    self.data.swap(i, j);
+    *self.pos.get_mut(&self.data[i].0).unwrap() = i;
+    *self.pos.get_mut(&self.data[j].0).unwrap() = j;
+  }
+
+  #[inline]
+  fn is_less(&self, i: usize, j: usize) -> bool {
+    self.data[i].1 < self.data[j].1
+  }
+
+  fn sift_up(&mut self, mut i: usize) {
+    while i != 0 {
+      let parent_i = parent(i);
+      if self.is_less(parent_i, i) {
+        self.swap(parent_i, i);
+        i = parent_i;
+      } else {
+        break;
+      }
+    }
+  }
+
+  fn sift_down(&mut self, mut i: usize) {
+    loop {
+      let lc_i = left_child(i);
+      if lc_i >= self.data.len() { break; }
+      let mut max_idx = if self.is_less(i, lc_i) { lc_i } else { i };
+      let rc_i = lc_i + 1;
+      if rc_i < self.data.len() && self.is_less(max_idx, rc_i) {
+        max_idx = rc_i;
+      }
+      if max_idx == i { break; }
+      self.swap(i, max_idx);
+      i = max_idx;
+    }
+  }
+}
+
+impl<K: Eq + Hash + Clone, P: Ord> Default for KeyedPriorityQueue<K, P> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_push_pop() {
+    let mut pq = KeyedPriorityQueue::new();
+    for (k, p) in [("b", 2), ("a", 1), ("f", 6), ("c", 3), ("i", 9), ("g", 7)] {
+      assert_eq!(None, pq.push(k, p));
+    }
+    assert_eq!(Some((&"i", &9)), pq.peek());
+    assert_eq!(Some(("i", 9)), pq.pop());
+    assert_eq!(Some(("g", 7)), pq.pop());
+    assert_eq!(Some(("f", 6)), pq.pop());
+    assert_eq!(3, pq.len());
+  }
+
+  #[test]
+  fn test_change_priority_and_remove() {
+    let mut pq = KeyedPriorityQueue::new();
+    for i in 0..10 {
+      pq.push(i, i * 10);
+    }
+    assert_eq!(Some(0), pq.change_priority(&0, 1000));
+    assert_eq!(Some(90), pq.change_priority(&9, -1));
+    assert_eq!(None, pq.change_priority(&42, 0));
+    assert_eq!(Some(50), pq.remove(&5));
+    assert_eq!(None, pq.remove(&5));
+    assert_eq!(Some(40), pq.push(4, 45));
+    assert_eq!(Some(&45), pq.get_priority(&4));
+
+    let order: Vec<_> = std::iter::from_fn(|| pq.pop()).collect();
+    assert_eq!(
+      vec![(0, 1000), (8, 80), (7, 70), (6, 60), (4, 45), (3, 30), (2, 20), (1, 10), (9, -1)],
+      order
+    );
+    assert!(pq.is_empty());
+  }
+}
@@ -0,0 +1,63 @@
+use std::cmp::Ordering;
+use std::mem;
+
+use crate::binary_heap::{BinaryHeap, PeekMut};
+
+// merges streams that are each sorted by comparator into one sorted stream,
+// O(log k) per element; equal elements come out in the order of their streams
+pub fn kway_merge<T, It, C>(iters: Vec<It>, mut comparator: C) -> impl Iterator<Item = T>
+where It: Iterator<Item = T>, C: FnMut(&T, &T) -> Ordering {
+  let mut iters = iters;
+  // BinaryHeap is a max-heap, so reverse the comparator to keep the smallest head on top
+  let mut heap = BinaryHeap::with_comparator(move |a: &(T, usize), b: &(T, usize)| {
+    comparator(&b.0, &a.0).then_with(|| b.1.cmp(&a.1))
+  });
+  for (i, it) in iters.iter_mut().enumerate() {
+    if let Some(v) = it.next() {
+      heap.push((v, i));
+    }
+  }
+  std::iter::from_fn(move || {
+    let mut top = heap.peek_mut()?;
+    match iters[top.1].next() {
+      // replace the head in place, a single sift down when the guard drops
+      Some(next) => Some(mem::replace(&mut top.0, next)),
+      None => Some(PeekMut::pop(top).0),
+    }
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_merge_sorted() {
+    let streams = vec![
+      vec![1, 4, 7, 10].into_iter(),
+      vec![].into_iter(),
+      vec![2, 5, 8].into_iter(),
+      vec![0, 3, 6, 9, 11, 12].into_iter(),
+    ];
+    let merged: Vec<i32> = kway_merge(streams, |a, b| a.cmp(b)).collect();
+    assert_eq!((0..=12).collect::<Vec<_>>(), merged);
+  }
+
+  #[test]
+  fn test_descending_and_stable() {
+    let streams = vec![
+      vec![(3, 'a'), (1, 'b')].into_iter(),
+      vec![(3, 'c'), (2, 'd'), (1, 'e')].into_iter(),
+    ];
+    let merged: String = kway_merge(streams, |a: &(i32, char), b| b.0.cmp(&a.0))
+      .map(|(_, c)| c)
+      .collect();
+    assert_eq!("acdbe", merged);
+  }
+
+  #[test]
+  fn test_empty() {
+    let streams: Vec<std::vec::IntoIter<i32>> = vec![];
+    assert_eq!(None, kway_merge(streams, |a, b| a.cmp(b)).next());
+  }
+}
@@ -0,0 +1,311 @@
+use std::ops::Range;
+
+use crate::segment_tree::{Max, Min, Monoid, Sum};
+
+// Actions on the values of a monoid, applied to a whole range at once. apply must distribute
+// over the monoid's combine, so a node's value can be updated without its children, and
+// compose gives the single action for applying inner first and then self.
+pub trait Action<M: Monoid>: Clone {
+  fn identity() -> Self;
+  fn compose(&self, inner: &Self) -> Self;
+  // the action on value, the combination of len positions
+  fn apply(&self, value: &M::Value, len: usize) -> M::Value;
+}
+
+// built-in actions over the primitive integers: adding to every position, and setting every
+// position, Assign(None) being the identity
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Add<T>(pub T);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Assign<T>(pub Option<T>);
+
+macro_rules! integer_actions {
+  ($($t:ty),*) => {$(
+    impl Action<Sum<$t>> for Add<$t> {
+      fn identity() -> Self {
+        Add(0)
+      }
+      fn compose(&self, inner: &Self) -> Self {
+        Add(self.0 + inner.0)
+      }
+      fn apply(&self, value: &$t, len: usize) -> $t {
+        value + self.0 * len as $t
+      }
+    }
+
+    impl Action<Min<$t>> for Add<$t> {
+      fn identity() -> Self {
+        Add(0)
+      }
+      fn compose(&self, inner: &Self) -> Self {
+        Add(self.0 + inner.0)
+      }
+      fn apply(&self, value: &$t, _len: usize) -> $t {
+        value + self.0
+      }
+    }
+
+    impl Action<Max<$t>> for Add<$t> {
+      fn identity() -> Self {
+        Add(0)
+      }
+      fn compose(&self, inner: &Self) -> Self {
+        Add(self.0 + inner.0)
+      }
+      fn apply(&self, value: &$t, _len: usize) -> $t {
+        value + self.0
+      }
+    }
+
+    impl Action<Sum<$t>> for Assign<$t> {
+      fn identity() -> Self {
+        Assign(None)
+      }
+      fn compose(&self, inner: &Self) -> Self {
+        Assign(self.0.or(inner.0))
+      }
+      fn apply(&self, value: &$t, len: usize) -> $t {
+        self.0.map_or(*value, |x| x * len as $t)
+      }
+    }
+
+    impl Action<Min<$t>> for Assign<$t> {
+      fn identity() -> Self {
+        Assign(None)
+      }
+      fn compose(&self, inner: &Self) -> Self {
+        Assign(self.0.or(inner.0))
+      }
+      fn apply(&self, value: &$t, _len: usize) -> $t {
+        self.0.unwrap_or(*value)
+      }
+    }
+
+    impl Action<Max<$t>> for Assign<$t> {
+      fn identity() -> Self {
+        Assign(None)
+      }
+      fn compose(&self, inner: &Self) -> Self {
+        Assign(self.0.or(inner.0))
+      }
+      fn apply(&self, value: &$t, _len: usize) -> $t {
+        self.0.unwrap_or(*value)
+      }
+    }
+  )*};
+}
+
+integer_actions!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+// Segment tree with lazy propagation: an action applied to a whole range and the combination of
+// a range, both O(log n). An update stops at the O(log n) nodes covering its range, applying
+// the action to their values and leaving it pending for their children, which get it pushed
+// down the next time a path goes through. Recursive over exactly 0..n, so no padding position
+// ever has an action applied: the node of l..r is followed by its left half, and its right half
+// starts 2 (mid - l) after it, 2n - 1 nodes in all.
+pub struct LazySegmentTree<M: Monoid, A: Action<M>> {
+  n: usize,
+  values: Vec<M::Value>,
+  pending: Vec<A>, // not yet applied to the children
+}
+
+pub type RangeAddMin<T> = LazySegmentTree<Min<T>, Add<T>>;
+pub type RangeAddMax<T> = LazySegmentTree<Max<T>, Add<T>>;
+pub type RangeAddSum<T> = LazySegmentTree<Sum<T>, Add<T>>;
+pub type RangeAssignSum<T> = LazySegmentTree<Sum<T>, Assign<T>>;
+pub type RangeAssignMin<T> = LazySegmentTree<Min<T>, Assign<T>>;
+
+impl<M: Monoid, A: Action<M>> LazySegmentTree<M, A> {
+  // n copies of the identity
+  pub fn new(n: usize) -> LazySegmentTree<M, A> {
+    LazySegmentTree::from_slice(&vec![M::identity(); n])
+  }
+
+  // O(n)
+  pub fn from_slice(values: &[M::Value]) -> LazySegmentTree<M, A> {
+    let n = values.len();
+    let nodes = (2 * n).saturating_sub(1);
+    let mut tree = LazySegmentTree { n, values: vec![M::identity(); nodes], pending: vec![A::identity(); nodes] };
+    if n > 0 {
+      tree.build(0, 0, n, values);
+    }
+    tree
+  }
+
+  fn build(&mut self, v: usize, l: usize, r: usize, values: &[M::Value]) {
+    if r - l == 1 {
+      self.values[v] = values[l].clone();
+      return;
+    }
+    let mid = (l + r) / 2;
+    let (left, right) = (v + 1, v + 2 * (mid - l));
+    self.build(left, l, mid, values);
+    self.build(right, mid, r, values);
+    self.values[v] = M::combine(&self.values[left], &self.values[right]);
+  }
+
+  pub fn len(&self) -> usize {
+    self.n
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.n == 0
+  }
+
+  fn apply(&mut self, v: usize, len: usize, action: &A) {
+    self.values[v] = action.apply(&self.values[v], len);
+    self.pending[v] = action.compose(&self.pending[v]);
+  }
+
+  fn push(&mut self, v: usize, l: usize, r: usize) {
+    let mid = (l + r) / 2;
+    let action = std::mem::replace(&mut self.pending[v], A::identity());
+    self.apply(v + 1, mid - l, &action);
+    self.apply(v + 2 * (mid - l), r - mid, &action);
+  }
+
+  // applies action to every position in range
+  pub fn update_range(&mut self, range: Range<usize>, action: A) {
+    assert!(range.start <= range.end && range.end <= self.n);
+    if range.start < range.end {
+      self.update_in(0, 0, self.n, &range, &action);
+    }
+  }
+
+  fn update_in(&mut self, v: usize, l: usize, r: usize, range: &Range<usize>, action: &A) {
+    if range.start <= l && r <= range.end {
+      self.apply(v, r - l, action);
+      return;
+    }
+    self.push(v, l, r);
+    let mid = (l + r) / 2;
+    let (left, right) = (v + 1, v + 2 * (mid - l));
+    if range.start < mid {
+      self.update_in(left, l, mid, range, action);
+    }
+    if mid < range.end {
+      self.update_in(right, mid, r, range, action);
+    }
+    self.values[v] = M::combine(&self.values[left], &self.values[right]);
+  }
+
+  // the combination of the values in range, the identity for an empty one
+  pub fn query(&mut self, range: Range<usize>) -> M::Value {
+    assert!(range.start <= range.end && range.end <= self.n);
+    if range.start == range.end {
+      return M::identity();
+    }
+    self.query_in(0, 0, self.n, &range)
+  }
+
+  fn query_in(&mut self, v: usize, l: usize, r: usize, range: &Range<usize>) -> M::Value {
+    if range.start <= l && r <= range.end {
+      return self.values[v].clone();
+    }
+    self.push(v, l, r);
+    let mid = (l + r) / 2;
+    let (left, right) = (v + 1, v + 2 * (mid - l));
+    if range.end <= mid {
+      self.query_in(left, l, mid, range)
+    } else if mid <= range.start {
+      self.query_in(right, mid, r, range)
+    } else {
+      M::combine(&self.query_in(left, l, mid, range), &self.query_in(right, mid, r, range))
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  // affine maps x -> a x + b mod p on every position, acting on sums mod p: b counts once per
+  // position summed
+  const P: u64 = 1_000_000_007;
+
+  struct SumMod;
+
+  impl Monoid for SumMod {
+    type Value = u64;
+    fn identity() -> u64 {
+      0
+    }
+    fn combine(a: &u64, b: &u64) -> u64 {
+      (a + b) % P
+    }
+  }
+
+  #[derive(Clone)]
+  struct Affine(u64, u64);
+
+  impl Action<SumMod> for Affine {
+    fn identity() -> Self {
+      Affine(1, 0)
+    }
+    fn compose(&self, inner: &Self) -> Self {
+      Affine(self.0 * inner.0 % P, (self.0 * inner.1 + self.1) % P)
+    }
+    fn apply(&self, value: &u64, len: usize) -> u64 {
+      (self.0 * value + self.1 * len as u64) % P
+    }
+  }
+
+  #[test]
+  fn test_presets() {
+    let mut tree: RangeAddMin<i64> = LazySegmentTree::from_slice(&[5, 3, 8, 6, 2, 7]);
+    tree.update_range(1..4, Add(-4));
+    assert_eq!((-1, 4, -1), (tree.query(0..4), tree.query(2..3), tree.query(0..6)));
+    tree.update_range(4..6, Add(10));
+    assert_eq!((-1, 12, i64::MAX), (tree.query(0..6), tree.query(4..6), tree.query(3..3)));
+    let mut sums: RangeAssignSum<u32> = LazySegmentTree::new(10);
+    sums.update_range(2..8, Assign(Some(3)));
+    sums.update_range(5..10, Assign(Some(1)));
+    assert_eq!((9 + 5, 3 + 1), (sums.query(0..10), sums.query(4..6)));
+    sums.update_range(0..10, Assign(None));
+    assert_eq!(14, sums.query(0..10));
+    assert!(RangeAddSum::<u8>::new(0).is_empty());
+  }
+
+  #[test]
+  fn test_random_against_naive() {
+    let mut rand = rand_gen(337);
+    for round in 0..100 {
+      let n = if round % 10 == 0 { 300 } else { 1 + rand(30) as usize };
+      let mut values: Vec<u64> = (0..n).map(|_| rand(P)).collect();
+      let mut affine: LazySegmentTree<SumMod, Affine> = LazySegmentTree::from_slice(&values);
+      let mut ints: Vec<i64> = (0..n).map(|_| rand(100) as i64).collect();
+      let mut maxes: RangeAddMax<i64> = LazySegmentTree::from_slice(&ints);
+      let mut mins: RangeAssignMin<i64> = LazySegmentTree::from_slice(&ints);
+      let mut assigned = ints.clone();
+      for _ in 0..100 {
+        let l = rand(n as u64 + 1) as usize;
+        let r = l + rand((n - l) as u64 + 1) as usize;
+        match rand(3) {
+          0 => {
+            let (a, b) = (rand(P), rand(P));
+            affine.update_range(l..r, Affine(a, b));
+            for x in &mut values[l..r] {
+              *x = (a * *x + b) % P;
+            }
+          },
+          1 => {
+            let d = rand(21) as i64 - 10;
+            maxes.update_range(l..r, Add(d));
+            ints[l..r].iter_mut().for_each(|x| *x += d);
+            let x = rand(100) as i64 - 50;
+            mins.update_range(l..r, Assign(Some(x)));
+            assigned[l..r].iter_mut().for_each(|y| *y = x);
+          },
+          _ => {},
+        }
+        let l = rand(n as u64 + 1) as usize;
+        let r = l + rand((n - l) as u64 + 1) as usize;
+        assert_eq!(values[l..r].iter().sum::<u64>() % P, affine.query(l..r));
+        assert_eq!(ints[l..r].iter().copied().max().unwrap_or(i64::MIN), maxes.query(l..r));
+        assert_eq!(assigned[l..r].iter().copied().min().unwrap_or(i64::MAX), mins.query(l..r));
+      }
+    }
+  }
+}
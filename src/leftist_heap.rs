@@ -0,0 +1,140 @@
+use std::mem;
+
+struct LeftistNode<T> {
+  value: T,
+  rank: usize, // length of the right spine, i.e. distance to the nearest empty subtree
+  left: LeftistTree<T>,
+  right: LeftistTree<T>,
+}
+
+type LeftistTree<T> = Option<Box<LeftistNode<T>>>;
+
+#[inline] fn rank<T>(t: &LeftistTree<T>) -> usize { t.as_ref().map_or(0, |n| n.rank) }
+
+fn merge_tree<T: Ord>(a: LeftistTree<T>, b: LeftistTree<T>) -> LeftistTree<T> {
+  match (a, b) {
+    (None, t) | (t, None) => t,
+    (Some(mut a), Some(mut b)) => {
+      if a.value < b.value { mem::swap(&mut a, &mut b); } // a holds the larger root
+      // only walk down the right spine, which has O(log n) nodes
+      a.right = merge_tree(a.right.take(), Some(b));
+      if rank(&a.left) < rank(&a.right) {
+        mem::swap(&mut a.left, &mut a.right); // keep the leftist property
+      }
+      a.rank = rank(&a.right) + 1;
+      Some(a)
+    }
+  }
+}
+
+pub struct LeftistHeap<T> {
+  root: LeftistTree<T>,
+  len: usize,
+}
+
+impl<T: Ord> LeftistHeap<T> {
+  pub fn new() -> LeftistHeap<T> {
+    LeftistHeap {
+      root: None,
+      len: 0,
+    }
+  }
+
+  pub fn merge(mut self, mut other: LeftistHeap<T>) -> LeftistHeap<T> {
+    LeftistHeap {
+      root: merge_tree(self.root.take(), other.root.take()),
+      len: self.len + other.len,
+    }
+  }
+
+  pub fn push(&mut self, v: T) {
+    let single = Some(Box::new(LeftistNode {
+      value: v,
+      rank: 1,
+      left: None,
+      right: None,
+    }));
+    self.root = merge_tree(self.root.take(), single);
+    self.len += 1;
+  }
+
+  pub fn pop(&mut self) -> Option<T> {
+    let mut node = self.root.take()?;
+    self.root = merge_tree(node.left.take(), node.right.take());
+    self.len -= 1;
+    Some(node.value)
+  }
+
+  pub fn peek(&self) -> Option<&T> {
+    self.root.as_ref().map(|n| &n.value)
+  }
+
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+}
+
+impl<T: Ord> Default for LeftistHeap<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T> Drop for LeftistHeap<T> {
+  fn drop(&mut self) {
+    // the left spine can be O(n) long, so drop iteratively instead of recursively
+    let mut stack: Vec<Box<LeftistNode<T>>> = self.root.take().into_iter().collect();
+    while let Some(mut node) = stack.pop() {
+      stack.extend(node.left.take());
+      stack.extend(node.right.take());
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_push_pop() {
+    let mut heap = LeftistHeap::new();
+    for v in vec![2,1,6,3,9,7,4,8,5] {
+      heap.push(v);
+    }
+    assert_eq!(9, heap.len());
+    assert_eq!(Some(&9), heap.peek());
+    for i in (1..=9).rev() {
+      assert_eq!(Some(i), heap.pop());
+    }
+    assert_eq!(None, heap.pop());
+    assert!(heap.is_empty());
+  }
+
+  #[test]
+  fn test_merge() {
+    let mut a = LeftistHeap::new();
+    let mut b = LeftistHeap::new();
+    for i in 0..50 {
+      if i % 3 == 0 { a.push(i) } else { b.push(i) }
+    }
+    let mut merged = a.merge(b);
+    assert_eq!(50, merged.len());
+    for i in (0..50).rev() {
+      assert_eq!(Some(i), merged.pop());
+    }
+    assert_eq!(None, merged.pop());
+  }
+
+  #[test]
+  fn test_long_left_spine() {
+    let mut heap = LeftistHeap::new();
+    for i in 0..200_000 {
+      heap.push(i);
+    }
+    assert_eq!(Some(199_999), heap.pop());
+  }
+}
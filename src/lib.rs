@@ -1,2 +1,34 @@
 pub mod string;
-pub mod binary_heap;
\ No newline at end of file
+pub mod binary_heap;
+pub mod leftist_heap;
+pub mod keyed_priority_queue;
+pub mod graph;
+pub mod kway_merge;
+pub mod sat;
+pub mod interval_scheduling;
+pub mod versioned_array;
+pub mod search;
+pub mod mitm;
+pub mod chunking;
+pub mod delta;
+pub mod compression;
+pub mod succinct_rmq;
+pub mod rate_limit;
+pub mod concurrent;
+pub mod parallel;
+pub mod sort;
+pub mod wavelet_tree;
+pub mod geometry;
+pub mod vp_tree;
+pub mod segment_tree;
+pub mod lazy_segment_tree;
+pub mod space_filling_curve;
+pub mod fenwick;
+pub mod perfect_hash;
+pub mod eytzinger;
+pub mod fractional_cascading;
+pub mod sparse_table;
+pub mod two_pointer;
+pub mod union_find;
+#[cfg(test)]
+pub(crate) mod test_util;
\ No newline at end of file
@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+// Meet in the middle over subsets: n items give 2^n subsets, but each half gives only 2^(n/2),
+// and most questions about a whole subset are answered by pairing one subset of each half, after
+// sorting or hashing one side. Masks are u64 bit sets over the items, so n is at most 64, and
+// in practice 2^(n/2) subsets per half must fit in memory.
+
+// every subset of items folded from init with add, as (mask, value) in mask order. Each subset
+// extends the one without its highest item, so this is one add per subset.
+pub fn enumerate_subsets<T, S: Clone>(items: &[T], init: S, add: impl Fn(&S, &T) -> S) -> Vec<(u64, S)> {
+  assert!(items.len() < 64);
+  let mut subsets = Vec::with_capacity(1 << items.len());
+  subsets.push((0, init));
+  for (i, item) in items.iter().enumerate() {
+    for j in 0..subsets.len() {
+      let value = add(&subsets[j].1, item);
+      subsets.push((subsets[j].0 | 1 << i, value));
+    }
+  }
+  subsets
+}
+
+// the generic driver: enumerates the subsets of both halves, the second half's masks shifted to
+// item positions, and hands both lists to combine
+pub fn meet_in_the_middle<T, S: Clone, R>(
+  items: &[T],
+  init: S,
+  add: impl Fn(&S, &T) -> S,
+  combine: impl FnOnce(Vec<(u64, S)>, Vec<(u64, S)>) -> R,
+) -> R {
+  let mid = items.len() / 2;
+  let left = enumerate_subsets(&items[..mid], init.clone(), &add);
+  let mut right = enumerate_subsets(&items[mid..], init, &add);
+  for (mask, _) in right.iter_mut() {
+    *mask <<= mid;
+  }
+  combine(left, right)
+}
+
+// every subset sum in increasing order, duplicates kept. Adding an item merges the sorted sums
+// with themselves shifted by it, O(2^n) in all.
+pub fn sorted_subset_sums(values: &[i64]) -> Vec<i64> {
+  let mut sums = vec![0];
+  for &x in values {
+    let mut merged = Vec::with_capacity(2 * sums.len());
+    let (mut i, mut j) = (0, 0);
+    while i < sums.len() || j < sums.len() {
+      if j == sums.len() || (i < sums.len() && sums[i] <= sums[j] + x) {
+        merged.push(sums[i]);
+        i += 1;
+      } else {
+        merged.push(sums[j] + x);
+        j += 1;
+      }
+    }
+    sums = merged;
+  }
+  sums
+}
+
+// number of pairs (x, y), x from a and y from b, both sorted ascending, with x + y <= target.
+// Two pointers: y's bound only shrinks as x grows.
+pub fn count_pairs_at_most(a: &[i64], b: &[i64], target: i64) -> u64 {
+  let mut count = 0;
+  let mut j = b.len();
+  for &x in a {
+    while j > 0 && x + b[j - 1] > target {
+      j -= 1;
+    }
+    count += j as u64;
+  }
+  count
+}
+
+// number of subsets, the empty one included, with sum at most target
+pub fn count_subsets_at_most(values: &[i64], target: i64) -> u64 {
+  let mid = values.len() / 2;
+  count_pairs_at_most(&sorted_subset_sums(&values[..mid]), &sorted_subset_sums(&values[mid..]), target)
+}
+
+// the largest subset sum not above target, None if even the smallest one is above it
+pub fn max_subset_sum_at_most(values: &[i64], target: i64) -> Option<i64> {
+  let mid = values.len() / 2;
+  let (a, b) = (sorted_subset_sums(&values[..mid]), sorted_subset_sums(&values[mid..]));
+  let mut best = None;
+  let mut j = b.len();
+  for &x in &a {
+    while j > 0 && x + b[j - 1] > target {
+      j -= 1;
+    }
+    if j > 0 {
+      best = best.max(Some(x + b[j - 1]));
+    }
+  }
+  best
+}
+
+// a subset with exactly this sum, as a mask over values, hashing the second half's sums
+pub fn subset_with_sum(values: &[i64], target: i64) -> Option<u64> {
+  meet_in_the_middle(values, 0i64, |s, &x| s + x, |left, right| {
+    let by_sum: HashMap<i64, u64> = right.into_iter().map(|(mask, s)| (s, mask)).collect();
+    left.into_iter().find_map(|(mask, s)| by_sum.get(&(target - s)).map(|&other| mask | other))
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  fn sum_of(values: &[i64], mask: u64) -> i64 {
+    (0..values.len()).filter(|&i| mask >> i & 1 == 1).map(|i| values[i]).sum()
+  }
+
+  #[test]
+  fn test_against_brute_force() {
+    let mut rand = rand_gen(89);
+    for _ in 0..200 {
+      let n = rand(11) as usize;
+      let values: Vec<i64> = (0..n).map(|_| rand(41) as i64 - 20).collect();
+      let mut all: Vec<i64> = (0..1u64 << n).map(|mask| sum_of(&values, mask)).collect();
+      let enumerated = enumerate_subsets(&values, 0, |s, &x| s + x);
+      assert!(enumerated.iter().all(|&(mask, s)| s == all[mask as usize]));
+      all.sort_unstable();
+      assert_eq!(all, sorted_subset_sums(&values));
+      let target = rand(81) as i64 - 40;
+      assert_eq!(all.iter().filter(|&&s| s <= target).count() as u64, count_subsets_at_most(&values, target));
+      assert_eq!(all.iter().copied().filter(|&s| s <= target).max(), max_subset_sum_at_most(&values, target));
+      match subset_with_sum(&values, target) {
+        Some(mask) => assert_eq!(target, sum_of(&values, mask)),
+        None => assert!(!all.contains(&target)),
+      }
+    }
+  }
+
+  #[test]
+  fn test_driver_masks() {
+    // the number of subsets of size 3, pairing sizes of the halves
+    let items = ['a', 'b', 'c', 'd', 'e'];
+    let count = meet_in_the_middle(&items, 0, |k, _| k + 1, |left, right| {
+      assert!(left.iter().chain(&right).all(|&(mask, k)| mask.count_ones() == k));
+      assert!(right.iter().all(|&(mask, _)| mask & 0b11 == 0));
+      left.iter().map(|&(_, a)| right.iter().filter(|&&(_, b)| a + b == 3).count()).sum::<usize>()
+    });
+    assert_eq!(10, count);
+  }
+}
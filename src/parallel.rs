@@ -0,0 +1,243 @@
+use std::cmp::Ordering;
+use std::thread;
+
+// below this many elements a piece of work stays on one thread
+const SEQUENTIAL: usize = 1 << 12;
+
+pub(crate) fn threads() -> usize {
+  thread::available_parallelism().map_or(4, |n| n.get())
+}
+
+// pieces of about equal size, one per thread, none smaller than SEQUENTIAL
+fn chunk_len(n: usize, threads: usize) -> usize {
+  n.div_ceil(threads).max(SEQUENTIAL)
+}
+
+fn scan_sequential<T: Clone>(a: &mut [T], op: &impl Fn(&T, &T) -> T) {
+  for i in 1..a.len() {
+    a[i] = op(&a[i - 1], &a[i]);
+  }
+}
+
+// Inclusive prefix scan in place: a[i] becomes a[0] op a[1] op ... op a[i], for any associative
+// op, commutative or not. Three passes: every chunk is scanned on its own thread, the chunk
+// totals are scanned on this one, and every chunk but the first is offset by the total before it.
+// About twice the work of the sequential loop, spread over the threads.
+pub fn scan<T: Clone + Send + Sync>(a: &mut [T], op: impl Fn(&T, &T) -> T + Sync) {
+  scan_on(a, op, threads());
+}
+
+fn scan_on<T: Clone + Send + Sync>(a: &mut [T], op: impl Fn(&T, &T) -> T + Sync, threads: usize) {
+  let chunk = chunk_len(a.len(), threads);
+  let op = &op;
+  thread::scope(|s| {
+    for c in a.chunks_mut(chunk) {
+      s.spawn(move || scan_sequential(c, op));
+    }
+  });
+  let mut before: Vec<T> = vec![];
+  for c in a.chunks(chunk) {
+    let last = c.last().unwrap();
+    let total = match before.last() {
+      Some(prev) => op(prev, last),
+      None => last.clone(),
+    };
+    before.push(total);
+  }
+  thread::scope(|s| {
+    for (c, offset) in a.chunks_mut(chunk).skip(1).zip(&before) {
+      s.spawn(move || c.iter_mut().for_each(|x| *x = op(offset, x)));
+    }
+  });
+}
+
+fn merge_sequential<T: Clone, F: Fn(&T, &T) -> Ordering>(a: &[T], b: &[T], out: &mut [T], compare: &F) {
+  let (mut i, mut j) = (0, 0);
+  for slot in out {
+    if j < b.len() && (i == a.len() || compare(&b[j], &a[i]) == Ordering::Less) {
+      *slot = b[j].clone();
+      j += 1;
+    } else {
+      *slot = a[i].clone();
+      i += 1;
+    }
+  }
+}
+
+fn merge_split<T: Clone + Send + Sync, F: Fn(&T, &T) -> Ordering + Sync>(a: &[T], b: &[T], out: &mut [T], compare: &F, depth: usize) {
+  if depth == 0 || out.len() <= SEQUENTIAL {
+    merge_sequential(a, b, out, compare);
+    return;
+  }
+  // the middle of the longer input, and where it falls in the other; ties keep a first
+  let (i, j) = if a.len() >= b.len() {
+    let m = a.len() / 2;
+    (m, b.partition_point(|x| compare(x, &a[m]) == Ordering::Less))
+  } else {
+    let m = b.len() / 2;
+    (a.partition_point(|x| compare(x, &b[m]) != Ordering::Greater), m)
+  };
+  let (left, right) = out.split_at_mut(i + j);
+  thread::scope(|s| {
+    s.spawn(|| merge_split(&a[..i], &b[..j], left, compare, depth - 1));
+    merge_split(&a[i..], &b[j..], right, compare, depth - 1);
+  });
+}
+
+// Stable merge of the sorted a and b into out, equal elements of a before those of b. Splits at
+// the median of the longer input and its position in the other by binary search, the halves
+// merged on two threads, down to one piece per thread.
+pub fn merge_by<T: Clone + Send + Sync>(a: &[T], b: &[T], out: &mut [T], compare: impl Fn(&T, &T) -> Ordering + Sync) {
+  merge_on(a, b, out, compare, threads());
+}
+
+pub(crate) fn merge_on<T: Clone + Send + Sync>(a: &[T], b: &[T], out: &mut [T], compare: impl Fn(&T, &T) -> Ordering + Sync, threads: usize) {
+  assert_eq!(a.len() + b.len(), out.len());
+  let depth = threads.next_power_of_two().trailing_zeros() as usize;
+  merge_split(a, b, out, &compare, depth);
+}
+
+pub fn merge<T: Ord + Clone + Send + Sync>(a: &[T], b: &[T], out: &mut [T]) {
+  merge_by(a, b, out, T::cmp);
+}
+
+// Stable partition in place: the elements satisfying pred move to the front, in their order, the
+// others after them, and the number of the former is returned. Every chunk counts its matches on
+// its own thread; the counts give each chunk disjoint places at the front and the back to copy
+// its elements to. pred is called twice per element.
+pub fn partition<T: Clone + Send + Sync>(a: &mut [T], pred: impl Fn(&T) -> bool + Sync) -> usize {
+  partition_on(a, pred, threads())
+}
+
+fn partition_on<T: Clone + Send + Sync>(a: &mut [T], pred: impl Fn(&T) -> bool + Sync, threads: usize) -> usize {
+  let source = a.to_vec();
+  let chunk = chunk_len(a.len(), threads);
+  let pred = &pred;
+  let counts: Vec<usize> = thread::scope(|s| {
+    let handles: Vec<_> = source.chunks(chunk).map(|c| s.spawn(move || c.iter().filter(|x| pred(x)).count())).collect();
+    handles.into_iter().map(|h| h.join().unwrap()).collect()
+  });
+  let matched = counts.iter().sum();
+  let (mut front, mut back) = a.split_at_mut(matched);
+  thread::scope(|s| {
+    for (c, &count) in source.chunks(chunk).zip(&counts) {
+      let (yes, rest) = std::mem::take(&mut front).split_at_mut(count);
+      front = rest;
+      let (no, rest) = std::mem::take(&mut back).split_at_mut(c.len() - count);
+      back = rest;
+      s.spawn(move || {
+        let (mut i, mut j) = (0, 0);
+        for x in c {
+          if pred(x) {
+            yes[i] = x.clone();
+            i += 1;
+          } else {
+            no[j] = x.clone();
+            j += 1;
+          }
+        }
+      });
+    }
+  });
+  matched
+}
+
+// f over every item, the results in the items' order. Chunks of items go to the threads whole,
+// so many small items cost one spawn per thread rather than one per item.
+pub fn map<T: Sync, U: Send>(items: &[T], f: impl Fn(&T) -> U + Sync) -> Vec<U> {
+  map_on(items, f, threads())
+}
+
+fn map_on<T: Sync, U: Send>(items: &[T], f: impl Fn(&T) -> U + Sync, threads: usize) -> Vec<U> {
+  let chunk = chunk_len(items.len(), threads);
+  let f = &f;
+  thread::scope(|s| {
+    let handles: Vec<_> = items.chunks(chunk).map(|c| s.spawn(move || c.iter().map(f).collect::<Vec<U>>())).collect();
+    handles.into_iter().flat_map(|h| h.join().unwrap()).collect()
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  fn random(seed: u64, n: usize, m: u64) -> Vec<u64> {
+    let mut rand = rand_gen(seed);
+    (0..n).map(|_| rand(m)).collect()
+  }
+
+  #[test]
+  fn test_scan() {
+    for n in [0, 1, 5, 100_000] {
+      let a = random(197, n, 1000);
+      let mut sums = a.clone();
+      // as on a machine with 8 threads, whatever this one has
+      scan_on(&mut sums, |x, y| x + y, 8);
+      let mut expected = 0;
+      for (x, s) in a.iter().zip(&sums) {
+        expected += x;
+        assert_eq!(expected, *s);
+      }
+    }
+    // not commutative: 2x2 matrix products modulo a prime
+    let m: Vec<[u64; 4]> = random(199, 50_000, 1 << 20).chunks(4).map(|c| [c[0], c[1], c[2], c[3]]).collect();
+    let mul = |x: &[u64; 4], y: &[u64; 4]| {
+      let p = 1_000_000_007;
+      [(x[0] * y[0] + x[1] * y[2]) % p, (x[0] * y[1] + x[1] * y[3]) % p, (x[2] * y[0] + x[3] * y[2]) % p, (x[2] * y[1] + x[3] * y[3]) % p]
+    };
+    let mut products = m.clone();
+    scan_on(&mut products, mul, 8);
+    let mut small = vec![1, 2, 3];
+    scan(&mut small, |x, y| x * y);
+    assert_eq!(vec![1, 2, 6], small);
+    let mut expected = m[0];
+    for i in 1..m.len() {
+      expected = mul(&expected, &m[i]);
+      assert_eq!(expected, products[i]);
+    }
+  }
+
+  #[test]
+  fn test_merge() {
+    for (n, k) in [(0, 10), (10, 0), (30_000, 50_000), (100_000, 3)] {
+      // keys with many ties, tagged with where they came from
+      let mut a: Vec<(u64, usize)> = random(211, n, 100).into_iter().zip(0..).collect();
+      let mut b: Vec<(u64, usize)> = random(223, k, 100).into_iter().zip(n..).collect();
+      a.sort_by_key(|x| x.0);
+      b.sort_by_key(|x| x.0);
+      let mut out = vec![(0, 0); n + k];
+      merge_on(&a, &b, &mut out, |x, y| x.0.cmp(&y.0), 8);
+      let mut expected = [a.clone(), b.clone()].concat();
+      expected.sort_by_key(|x| x.0);
+      assert_eq!(expected, out);
+    }
+    let mut out = vec![0; 6];
+    merge(&[1, 4, 6], &[2, 3, 5], &mut out);
+    assert_eq!(vec![1, 2, 3, 4, 5, 6], out);
+  }
+
+  #[test]
+  fn test_map() {
+    let a = random(239, 50_000, 1000);
+    let expected: Vec<String> = a.iter().map(|x| x.to_string()).collect();
+    assert_eq!(expected, map_on(&a, |x| x.to_string(), 8));
+    assert_eq!(vec![2, 4], map(&[1, 2], |x| 2 * x));
+    assert!(map(&[] as &[u8], |&x| x).is_empty());
+  }
+
+  #[test]
+  fn test_partition() {
+    for n in [0, 7, 100_000] {
+      let a = random(227, n, 1000);
+      let mut b = a.clone();
+      let matched = partition_on(&mut b, |x| x % 3 == 0, 8);
+      let (yes, no): (Vec<u64>, Vec<u64>) = a.iter().partition(|&x| x % 3 == 0);
+      assert_eq!(yes.len(), matched);
+      assert_eq!([yes, no].concat(), b);
+    }
+    let mut a = vec![1, 2, 3, 4, 5];
+    assert_eq!(2, partition(&mut a, |x| x % 2 == 0));
+    assert_eq!(vec![2, 4, 1, 3, 5], a);
+  }
+}
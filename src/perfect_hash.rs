@@ -0,0 +1,178 @@
+use std::hash::{Hash, Hasher};
+
+// splitmix64's finalizer
+fn mix(x: u64) -> u64 {
+  let mut z = x;
+  z = (z ^ z >> 30).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+  z = (z ^ z >> 27).wrapping_mul(0x94d0_49bb_1331_11eb);
+  z ^ z >> 31
+}
+
+// a hasher fixed by this file rather than by the standard library, so a structure built once
+// gives the same answers wherever it is queried
+struct KeyHasher {
+  state: u64,
+}
+
+impl Hasher for KeyHasher {
+  fn write(&mut self, bytes: &[u8]) {
+    for chunk in bytes.chunks(8) {
+      let mut word = [0; 8];
+      word[..chunk.len()].copy_from_slice(chunk);
+      self.state = (self.state.rotate_left(5) ^ u64::from_le_bytes(word)).wrapping_mul(0x517c_c1b7_2722_0a95);
+    }
+    self.state ^= bytes.len() as u64;
+  }
+
+  fn finish(&self) -> u64 {
+    mix(self.state)
+  }
+}
+
+fn hash_key<K: Hash + ?Sized>(key: &K) -> u64 {
+  let mut hasher = KeyHasher { state: 0 };
+  key.hash(&mut hasher);
+  hasher.finish()
+}
+
+// the position of a key's hash within a level of len bits
+fn slot(hash: u64, level: usize, len: usize) -> usize {
+  let h = mix(hash ^ (level as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+  ((h as u128 * len as u128) >> 64) as usize
+}
+
+// past this many levels the keys left can only be equal
+const MAX_LEVELS: usize = 64;
+
+// Minimal perfect hash function over a static set of n keys (BBHash, Limasset et al.): each key
+// gets its own index in 0..n, and nothing of the keys is stored. Level 0 is a bit array of
+// gamma n bits, in which every key hashes to one bit; the bits hit by exactly one key are kept,
+// and the keys that collided go on to the next, smaller level with another hash. A key's index
+// is the rank of its bit among the kept bits of all levels. With gamma = 2 that is about 3.7
+// bits per key; lower gammas save space for more levels to look through.
+//
+// A key outside the set gets None or the index of some key in it, so a dictionary keeping
+// values by index should also keep something to check the key against, like a fingerprint.
+pub struct PerfectHash {
+  n: usize,
+  starts: Vec<usize>, // the first bit of each level, and the end of the last one
+  words: Vec<u64>,
+  ranks: Vec<u64>, // ones before every block of 8 words
+}
+
+impl PerfectHash {
+  pub fn new<K: Hash>(keys: &[K]) -> PerfectHash {
+    PerfectHash::with_gamma(keys, 2.0)
+  }
+
+  // the keys must be distinct; gamma >= 1 the bits per key remaining at each level
+  pub fn with_gamma<K: Hash>(keys: &[K], gamma: f64) -> PerfectHash {
+    assert!(gamma >= 1.0);
+    let mut left: Vec<u64> = keys.iter().map(hash_key).collect();
+    let mut starts = vec![0];
+    let mut words: Vec<u64> = vec![];
+    while !left.is_empty() {
+      let level = starts.len() - 1;
+      assert!(level < MAX_LEVELS, "the keys of a perfect hash must be distinct");
+      // whole words, so every level starts at a word
+      let len = ((gamma * left.len() as f64).ceil() as usize).div_ceil(64) * 64;
+      let (mut hit, mut collided) = (vec![0u64; len / 64], vec![0u64; len / 64]);
+      for &h in &left {
+        let i = slot(h, level, len);
+        if hit[i / 64] >> (i % 64) & 1 == 1 {
+          collided[i / 64] |= 1 << (i % 64);
+        }
+        hit[i / 64] |= 1 << (i % 64);
+      }
+      left.retain(|&h| {
+        let i = slot(h, level, len);
+        collided[i / 64] >> (i % 64) & 1 == 1
+      });
+      words.extend(hit.iter().zip(&collided).map(|(h, c)| h & !c));
+      starts.push(starts[level] + len);
+    }
+    let mut ranks = vec![0];
+    for block in words.chunks(8) {
+      ranks.push(ranks.last().unwrap() + block.iter().map(|w| w.count_ones() as u64).sum::<u64>());
+    }
+    PerfectHash { n: keys.len(), starts, words, ranks }
+  }
+
+  pub fn len(&self) -> usize {
+    self.n
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.n == 0
+  }
+
+  // the index in 0..len() of a key of the set; O(levels looked through), usually one or two
+  pub fn index<K: Hash + ?Sized>(&self, key: &K) -> Option<usize> {
+    let h = hash_key(key);
+    for level in 0..self.starts.len() - 1 {
+      let start = self.starts[level];
+      let i = start + slot(h, level, self.starts[level + 1] - start);
+      let (w, b) = (i / 64, i % 64);
+      if self.words[w] >> b & 1 == 1 {
+        let before = self.words[w & !7..w].iter().map(|x| x.count_ones() as u64).sum::<u64>();
+        return Some((self.ranks[w / 8] + before + (self.words[w] & ((1 << b) - 1)).count_ones() as u64) as usize);
+      }
+    }
+    None
+  }
+
+  // the space taken by the bits and their ranks
+  pub fn size_in_bits(&self) -> usize {
+    64 * (self.words.len() + self.ranks.len() + self.starts.len())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_words() {
+    let words = ["apple", "banana", "cherry", "date", "elderberry", "fig", "grape"];
+    let table = PerfectHash::new(&words);
+    assert_eq!(7, table.len());
+    let mut indices: Vec<usize> = words.iter().map(|w| table.index(w).unwrap()).collect();
+    indices.sort_unstable();
+    assert_eq!((0..7).collect::<Vec<_>>(), indices);
+    // the same hash whether the key is given as &str or String
+    assert_eq!(table.index("fig"), table.index(&"fig".to_string()));
+    let empty = PerfectHash::new::<u32>(&[]);
+    assert!(empty.is_empty() && empty.index(&5u32).is_none());
+  }
+
+  #[test]
+  fn test_random_keys() {
+    let mut rand = rand_gen(353);
+    for round in 0..20 {
+      let n = if round % 5 == 0 { 20000 } else { rand(500) as usize };
+      let mut keys: Vec<u64> = (0..n).map(|_| rand(1 << 40)).collect();
+      keys.sort_unstable();
+      keys.dedup();
+      let gamma = [1.0, 1.5, 2.0, 3.0][round % 4];
+      let table = PerfectHash::with_gamma(&keys, gamma);
+      let mut seen = vec![false; keys.len()];
+      for key in &keys {
+        let i = table.index(key).unwrap();
+        assert!(!seen[i]);
+        seen[i] = true;
+      }
+      if keys.len() >= 1000 && gamma == 2.0 {
+        assert!(table.size_in_bits() < 5 * keys.len());
+      }
+      // consecutive integers too
+      let table = PerfectHash::with_gamma(&(0..n as u32).collect::<Vec<_>>(), gamma);
+      let mut seen = vec![false; n];
+      for key in 0..n as u32 {
+        let i = table.index(&key).unwrap();
+        assert!(!seen[i]);
+        seen[i] = true;
+      }
+    }
+  }
+}
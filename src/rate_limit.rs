@@ -0,0 +1,281 @@
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// Rate limiters deciding whether a request may go ahead now. They read time through Clock, so
+// tests can drive them with a ManualClock instead of sleeping.
+
+pub trait Clock {
+  // time since a fixed origin, never decreasing
+  fn now(&self) -> Duration;
+}
+
+// the monotonic system clock, counted from its creation
+pub struct SystemClock {
+  origin: Instant,
+}
+
+impl SystemClock {
+  pub fn new() -> SystemClock {
+    SystemClock { origin: Instant::now() }
+  }
+}
+
+impl Default for SystemClock {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Clock for SystemClock {
+  fn now(&self) -> Duration {
+    self.origin.elapsed()
+  }
+}
+
+// a clock that only moves when told to; limiters borrow it, so the test keeps a handle
+#[derive(Default)]
+pub struct ManualClock {
+  now: Cell<Duration>,
+}
+
+impl ManualClock {
+  pub fn new() -> ManualClock {
+    ManualClock::default()
+  }
+
+  pub fn advance(&self, by: Duration) {
+    self.now.set(self.now.get() + by);
+  }
+}
+
+impl Clock for ManualClock {
+  fn now(&self) -> Duration {
+    self.now.get()
+  }
+}
+
+impl<C: Clock> Clock for &C {
+  fn now(&self) -> Duration {
+    (*self).now()
+  }
+}
+
+// Token bucket: tokens drip in at rate per second up to capacity and each request takes some.
+// Allows bursts of up to capacity after a quiet spell, rate on average.
+pub struct TokenBucket<C> {
+  clock: C,
+  capacity: f64,
+  rate: f64,
+  tokens: f64,
+  last: Duration,
+}
+
+impl<C: Clock> TokenBucket<C> {
+  // starts full
+  pub fn new(clock: C, capacity: f64, rate: f64) -> TokenBucket<C> {
+    assert!(capacity > 0.0 && rate > 0.0);
+    let last = clock.now();
+    TokenBucket { clock, capacity, rate, tokens: capacity, last }
+  }
+
+  fn refill(&mut self) {
+    let now = self.clock.now();
+    self.tokens = (self.tokens + (now - self.last).as_secs_f64() * self.rate).min(self.capacity);
+    self.last = now;
+  }
+
+  pub fn available(&mut self) -> f64 {
+    self.refill();
+    self.tokens
+  }
+
+  // takes cost tokens if there are that many
+  pub fn try_acquire(&mut self, cost: f64) -> bool {
+    self.refill();
+    if self.tokens >= cost {
+      self.tokens -= cost;
+      true
+    } else {
+      false
+    }
+  }
+
+  // how long until cost tokens are there, None if cost is over capacity
+  pub fn wait_time(&mut self, cost: f64) -> Option<Duration> {
+    if cost > self.capacity {
+      return None;
+    }
+    self.refill();
+    Some(Duration::from_secs_f64(((cost - self.tokens) / self.rate).max(0.0)))
+  }
+}
+
+// Leaky bucket as a meter: each request pours one unit into a bucket that drains at rate per
+// second, and a request that would overflow capacity is refused. Unlike the token bucket, the
+// level it tracks is the recent load, so the two admit the same traffic but read differently.
+pub struct LeakyBucket<C> {
+  clock: C,
+  capacity: f64,
+  rate: f64,
+  level: f64,
+  last: Duration,
+}
+
+impl<C: Clock> LeakyBucket<C> {
+  // starts empty
+  pub fn new(clock: C, capacity: f64, rate: f64) -> LeakyBucket<C> {
+    assert!(capacity >= 1.0 && rate > 0.0);
+    let last = clock.now();
+    LeakyBucket { clock, capacity, rate, level: 0.0, last }
+  }
+
+  fn drain(&mut self) {
+    let now = self.clock.now();
+    self.level = (self.level - (now - self.last).as_secs_f64() * self.rate).max(0.0);
+    self.last = now;
+  }
+
+  pub fn level(&mut self) -> f64 {
+    self.drain();
+    self.level
+  }
+
+  pub fn try_acquire(&mut self) -> bool {
+    self.drain();
+    if self.level + 1.0 <= self.capacity {
+      self.level += 1.0;
+      true
+    } else {
+      false
+    }
+  }
+}
+
+// Sliding window log: the times of the requests admitted within the last window, at most limit
+// of them. Exact where the buckets smooth, at the price of memory per admitted request.
+pub struct SlidingWindowLog<C> {
+  clock: C,
+  limit: usize,
+  window: Duration,
+  log: VecDeque<Duration>,
+}
+
+impl<C: Clock> SlidingWindowLog<C> {
+  pub fn new(clock: C, limit: usize, window: Duration) -> SlidingWindowLog<C> {
+    SlidingWindowLog { clock, limit, window, log: VecDeque::new() }
+  }
+
+  // forgets requests older than the window, returning the time now
+  fn expire(&mut self) -> Duration {
+    let now = self.clock.now();
+    while self.log.front().is_some_and(|&t| t + self.window <= now) {
+      self.log.pop_front();
+    }
+    now
+  }
+
+  // requests counted against the limit right now
+  pub fn in_window(&mut self) -> usize {
+    self.expire();
+    self.log.len()
+  }
+
+  pub fn try_acquire(&mut self) -> bool {
+    let now = self.expire();
+    if self.log.len() < self.limit {
+      self.log.push_back(now);
+      true
+    } else {
+      false
+    }
+  }
+
+  // how long until a request would be admitted
+  pub fn wait_time(&mut self) -> Duration {
+    let now = self.expire();
+    if self.log.len() < self.limit {
+      Duration::ZERO
+    } else {
+      self.log[self.log.len() - self.limit] + self.window - now
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+  }
+
+  #[test]
+  fn test_token_bucket() {
+    let clock = ManualClock::new();
+    let mut bucket = TokenBucket::new(&clock, 5.0, 10.0);
+    // a burst of the capacity, then the rate
+    assert_eq!(5, (0..10).filter(|_| bucket.try_acquire(1.0)).count());
+    assert_eq!(Some(ms(100)), bucket.wait_time(1.0));
+    clock.advance(ms(250));
+    assert_eq!(2, (0..10).filter(|_| bucket.try_acquire(1.0)).count());
+    assert!((bucket.available() - 0.5).abs() < 1e-9);
+    clock.advance(Duration::from_secs(60));
+    assert_eq!(5.0, bucket.available());
+    assert!(bucket.try_acquire(4.0) && !bucket.try_acquire(4.0));
+    assert_eq!(None, bucket.wait_time(6.0));
+    // 10 a second over a long run
+    let mut admitted = 0;
+    for _ in 0..10_000 {
+      clock.advance(ms(1));
+      admitted += bucket.try_acquire(1.0) as usize;
+    }
+    assert!((100..=106).contains(&admitted), "{}", admitted);
+  }
+
+  #[test]
+  fn test_leaky_bucket() {
+    let clock = ManualClock::new();
+    let mut bucket = LeakyBucket::new(&clock, 3.0, 2.0);
+    assert_eq!(3, (0..5).filter(|_| bucket.try_acquire()).count());
+    assert_eq!(3.0, bucket.level());
+    clock.advance(ms(500));
+    assert_eq!(2.0, bucket.level());
+    assert!(bucket.try_acquire() && !bucket.try_acquire());
+    clock.advance(Duration::from_secs(10));
+    assert_eq!(0.0, bucket.level());
+  }
+
+  #[test]
+  fn test_sliding_window_log() {
+    let clock = ManualClock::new();
+    let mut log = SlidingWindowLog::new(&clock, 3, ms(1000));
+    // at 0, 100 and 200
+    for _ in 0..3 {
+      assert!(log.try_acquire());
+      clock.advance(ms(100));
+    }
+    assert!(!log.try_acquire());
+    assert_eq!(ms(700), log.wait_time());
+    clock.advance(ms(699));
+    assert!(!log.try_acquire());
+    clock.advance(ms(1));
+    assert_eq!(2, log.in_window());
+    assert!(log.try_acquire() && !log.try_acquire());
+    // unlike a fixed window, no burst of twice the limit across a boundary
+    clock.advance(ms(5000));
+    let mut admitted = 0;
+    for _ in 0..2000 {
+      clock.advance(ms(1));
+      admitted += log.try_acquire() as usize;
+      assert!(log.in_window() <= 3);
+    }
+    assert_eq!(6, admitted);
+  }
+
+  #[test]
+  fn test_system_clock() {
+    let mut bucket = TokenBucket::new(SystemClock::new(), 2.0, 1e-3);
+    assert!(bucket.try_acquire(2.0) && !bucket.try_acquire(1.0));
+  }
+}
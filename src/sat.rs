@@ -0,0 +1,671 @@
+// A small CDCL SAT solver: two watched literals, first-UIP clause learning, VSIDS-style
+// activities (picked by a linear scan), phase saving and Luby restarts. Learnt clauses are
+// never deleted, which is fine for the small instances this is meant for.
+//
+// Variables are 1..=num_vars and literals are DIMACS integers: v or -v.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cnf {
+  pub num_vars: usize,
+  pub clauses: Vec<Vec<i32>>,
+}
+
+// Encoding helpers: constraints are appended as clauses, with auxiliary variables allocated
+// after the ones already in use. They are satisfiable exactly when the constraint holds on the
+// given literals.
+impl Cnf {
+  pub fn new() -> Cnf {
+    Cnf { num_vars: 0, clauses: vec![] }
+  }
+
+  pub fn new_var(&mut self) -> i32 {
+    self.num_vars += 1;
+    self.num_vars as i32
+  }
+
+  pub fn add_clause(&mut self, clause: &[i32]) {
+    self.clauses.push(clause.to_vec());
+  }
+
+  // every premise true forces the conclusion
+  pub fn implies(&mut self, premises: &[i32], conclusion: i32) {
+    let mut clause: Vec<i32> = premises.iter().map(|&p| -p).collect();
+    clause.push(conclusion);
+    self.clauses.push(clause);
+  }
+
+  pub fn equivalent(&mut self, a: i32, b: i32) {
+    self.implies(&[a], b);
+    self.implies(&[b], a);
+  }
+
+  pub fn at_least_one(&mut self, lits: &[i32]) {
+    self.add_clause(lits);
+  }
+
+  // one clause per pair, no new variables; best for a handful of literals
+  pub fn at_most_one_pairwise(&mut self, lits: &[i32]) {
+    for (i, &a) in lits.iter().enumerate() {
+      for &b in &lits[i + 1..] {
+        self.clauses.push(vec![-a, -b]);
+      }
+    }
+  }
+
+  // Sinz's sequential counter: s_i says some of the first i + 1 literals is true. 3n clauses
+  // and n - 1 new variables.
+  pub fn at_most_one_sequential(&mut self, lits: &[i32]) {
+    if lits.len() < 2 {
+      return;
+    }
+    let mut prev = self.new_var();
+    self.implies(&[lits[0]], prev);
+    for &x in &lits[1..lits.len() - 1] {
+      let s = self.new_var();
+      self.implies(&[x], s);
+      self.implies(&[prev], s);
+      self.clauses.push(vec![-x, -prev]);
+      prev = s;
+    }
+    self.clauses.push(vec![-lits[lits.len() - 1], -prev]);
+  }
+
+  pub fn at_most_one(&mut self, lits: &[i32]) {
+    if lits.len() <= 6 {
+      self.at_most_one_pairwise(lits);
+    } else {
+      self.at_most_one_sequential(lits);
+    }
+  }
+
+  pub fn exactly_one(&mut self, lits: &[i32]) {
+    self.at_least_one(lits);
+    self.at_most_one(lits);
+  }
+
+  // Bailleux & Boufkhad's totalizer: returns outputs where outputs[j] is true exactly when at
+  // least j + 1 of lits are, given as a unary count built bottom up over a balanced tree.
+  pub fn totalizer(&mut self, lits: &[i32]) -> Vec<i32> {
+    if lits.len() <= 1 {
+      return lits.to_vec();
+    }
+    let (left, right) = lits.split_at(lits.len() / 2);
+    let a = self.totalizer(left);
+    let b = self.totalizer(right);
+    let r: Vec<i32> = (0..lits.len()).map(|_| self.new_var()).collect();
+    // index 0 stands for "at least 0", always true, so it drops out of the clauses
+    for i in 0..=a.len() {
+      for j in 0..=b.len() {
+        let mut premises = vec![];
+        if i > 0 { premises.push(a[i - 1]); }
+        if j > 0 { premises.push(b[j - 1]); }
+        if i + j > 0 {
+          self.implies(&premises, r[i + j - 1]);
+        }
+        // fewer than i + 1 on the left and j + 1 on the right means fewer than i + j + 1
+        let mut clause = vec![];
+        if i < a.len() { clause.push(a[i]); }
+        if j < b.len() { clause.push(b[j]); }
+        if i + j < r.len() {
+          clause.push(-r[i + j]);
+          self.clauses.push(clause);
+        }
+      }
+    }
+    r
+  }
+
+  pub fn at_most_k(&mut self, lits: &[i32], k: usize) {
+    if k < lits.len() {
+      let count = self.totalizer(lits);
+      self.clauses.push(vec![-count[k]]);
+    }
+  }
+
+  pub fn at_least_k(&mut self, lits: &[i32], k: usize) {
+    if k > lits.len() {
+      self.clauses.push(vec![]);
+    } else if k > 0 {
+      let count = self.totalizer(lits);
+      self.clauses.push(vec![count[k - 1]]);
+    }
+  }
+
+  pub fn exactly_k(&mut self, lits: &[i32], k: usize) {
+    if k > lits.len() {
+      self.clauses.push(vec![]);
+      return;
+    }
+    let count = self.totalizer(lits);
+    if k > 0 {
+      self.clauses.push(vec![count[k - 1]]);
+    }
+    if k < lits.len() {
+      self.clauses.push(vec![-count[k]]);
+    }
+  }
+}
+
+impl Default for Cnf {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+// parses "p cnf <vars> <clauses>" followed by clauses terminated by 0; comment lines start with c
+pub fn parse_dimacs(text: &str) -> Result<Cnf, String> {
+  let mut num_vars = None;
+  let mut clauses = vec![];
+  let mut clause = vec![];
+  for (i, line) in text.lines().enumerate() {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('c') || line.starts_with('%') {
+      continue;
+    }
+    if line.starts_with('p') {
+      let fields: Vec<&str> = line.split_whitespace().collect();
+      if fields.len() != 4 || fields[1] != "cnf" {
+        return Err(format!("line {}: bad problem line", i + 1));
+      }
+      num_vars = Some(fields[2].parse::<usize>().map_err(|_| format!("line {}: bad variable count", i + 1))?);
+      continue;
+    }
+    let n = match num_vars {
+      Some(n) => n,
+      None => return Err(format!("line {}: clause before the problem line", i + 1)),
+    };
+    for token in line.split_whitespace() {
+      let lit: i32 = token.parse().map_err(|_| format!("line {}: bad literal {}", i + 1, token))?;
+      if lit == 0 {
+        clauses.push(std::mem::take(&mut clause));
+      } else if lit.unsigned_abs() as usize > n {
+        return Err(format!("line {}: variable {} out of range", i + 1, lit.abs()));
+      } else {
+        clause.push(lit);
+      }
+    }
+  }
+  if !clause.is_empty() {
+    clauses.push(clause); // a missing final 0 is tolerated
+  }
+  match num_vars {
+    Some(num_vars) => Ok(Cnf { num_vars, clauses }),
+    None => Err("missing problem line".to_string()),
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SatResult {
+  Sat(Vec<bool>), // model[v - 1] is the value of variable v
+  Unsat,
+}
+
+const UNDEF: u8 = 2;
+
+// internal literal: 2 * var + (1 if negated), var from 0
+fn lit(dimacs: i32) -> usize {
+  2 * (dimacs.unsigned_abs() as usize - 1) + (dimacs < 0) as usize
+}
+
+fn var(l: usize) -> usize {
+  l >> 1
+}
+
+pub struct Solver {
+  clauses: Vec<Vec<usize>>,
+  watches: Vec<Vec<usize>>, // clauses whose first two literals include this one
+  value: Vec<u8>, // per variable: 0 false, 1 true, UNDEF
+  level: Vec<usize>,
+  reason: Vec<Option<usize>>, // the clause that implied the variable, its literal comes first
+  trail: Vec<usize>,
+  trail_lim: Vec<usize>, // trail length at the start of each decision level
+  queue_head: usize,
+  activity: Vec<f64>,
+  increment: f64,
+  phase: Vec<bool>, // last value of each variable, reused on decisions
+  seen: Vec<bool>, // scratch for conflict analysis
+  unsat: bool,
+}
+
+impl Solver {
+  pub fn new(num_vars: usize) -> Solver {
+    Solver {
+      clauses: vec![],
+      watches: vec![vec![]; 2 * num_vars],
+      value: vec![UNDEF; num_vars],
+      level: vec![0; num_vars],
+      reason: vec![None; num_vars],
+      trail: vec![],
+      trail_lim: vec![],
+      queue_head: 0,
+      activity: vec![0.0; num_vars],
+      increment: 1.0,
+      phase: vec![false; num_vars],
+      seen: vec![false; num_vars],
+      unsat: false,
+    }
+  }
+
+  pub fn from_cnf(cnf: &Cnf) -> Solver {
+    let mut solver = Solver::new(cnf.num_vars);
+    for clause in &cnf.clauses {
+      solver.add_clause(clause);
+    }
+    solver
+  }
+
+  pub fn num_vars(&self) -> usize {
+    self.value.len()
+  }
+
+  // the value of an internal literal: 0 false, 1 true, UNDEF
+  fn lit_value(&self, l: usize) -> u8 {
+    match self.value[var(l)] {
+      UNDEF => UNDEF,
+      v => v ^ (l & 1) as u8,
+    }
+  }
+
+  fn decision_level(&self) -> usize {
+    self.trail_lim.len()
+  }
+
+  fn assign(&mut self, l: usize, reason: Option<usize>) {
+    let v = var(l);
+    self.value[v] = 1 ^ (l & 1) as u8;
+    self.level[v] = self.decision_level();
+    self.reason[v] = reason;
+    self.trail.push(l);
+  }
+
+  fn attach(&mut self, clause: Vec<usize>) -> usize {
+    let id = self.clauses.len();
+    self.watches[clause[0]].push(id);
+    self.watches[clause[1]].push(id);
+    self.clauses.push(clause);
+    id
+  }
+
+  // clauses may be added before solving or between calls to solve
+  pub fn add_clause(&mut self, clause: &[i32]) {
+    if self.unsat {
+      return;
+    }
+    self.backtrack(0);
+    let mut lits: Vec<usize> = clause.iter().map(|&l| lit(l)).collect();
+    lits.sort_unstable();
+    lits.dedup();
+    if lits.windows(2).any(|w| w[0] ^ 1 == w[1]) {
+      return; // a tautology
+    }
+    // literals already false at level 0 can go, a true one satisfies the clause for good
+    if lits.iter().any(|&l| self.lit_value(l) == 1) {
+      return;
+    }
+    lits.retain(|&l| self.lit_value(l) == UNDEF);
+    match lits.len() {
+      0 => self.unsat = true,
+      1 => {
+        self.assign(lits[0], None);
+        if self.propagate().is_some() {
+          self.unsat = true;
+        }
+      },
+      _ => {
+        self.attach(lits);
+      },
+    }
+  }
+
+  // unit propagation; returns a conflicting clause if one shows up
+  fn propagate(&mut self) -> Option<usize> {
+    while self.queue_head < self.trail.len() {
+      let falsified = self.trail[self.queue_head] ^ 1;
+      self.queue_head += 1;
+      let watching = std::mem::take(&mut self.watches[falsified]);
+      let mut kept = Vec::with_capacity(watching.len());
+      let mut conflict = None;
+      for (i, &c) in watching.iter().enumerate() {
+        if conflict.is_some() {
+          kept.extend_from_slice(&watching[i..]);
+          break;
+        }
+        let clause = &mut self.clauses[c];
+        if clause[0] == falsified {
+          clause.swap(0, 1);
+        }
+        let first = clause[0];
+        if self.lit_value(first) == 1 {
+          kept.push(c);
+          continue;
+        }
+        let clause = &self.clauses[c];
+        match (2..clause.len()).find(|&k| self.lit_value(clause[k]) != 0) {
+          Some(k) => {
+            self.clauses[c].swap(1, k);
+            let l = self.clauses[c][1];
+            self.watches[l].push(c);
+          },
+          None => {
+            kept.push(c);
+            if self.lit_value(first) == 0 {
+              conflict = Some(c);
+            } else {
+              self.assign(first, Some(c));
+            }
+          },
+        }
+      }
+      self.watches[falsified] = kept;
+      if conflict.is_some() {
+        return conflict;
+      }
+    }
+    None
+  }
+
+  fn bump(&mut self, v: usize) {
+    self.activity[v] += self.increment;
+    if self.activity[v] > 1e100 {
+      for a in self.activity.iter_mut() {
+        *a *= 1e-100;
+      }
+      self.increment *= 1e-100;
+    }
+  }
+
+  // first-UIP learning: the learnt clause's first literal is the asserting one, its second
+  // has the highest level among the rest. Returns the clause and the level to go back to.
+  fn analyze(&mut self, conflict: usize) -> (Vec<usize>, usize) {
+    let mut learnt = vec![0];
+    let mut pending = 0; // literals of the current level still to resolve away
+    let mut clause = conflict;
+    let mut index = self.trail.len();
+    let mut implied = None;
+    loop {
+      let skip = implied.is_some() as usize; // a reason's first literal is the one it implied
+      for k in skip..self.clauses[clause].len() {
+        let q = self.clauses[clause][k];
+        let v = var(q);
+        if !self.seen[v] && self.level[v] > 0 {
+          self.seen[v] = true;
+          self.bump(v);
+          if self.level[v] == self.decision_level() {
+            pending += 1;
+          } else {
+            learnt.push(q);
+          }
+        }
+      }
+      loop {
+        index -= 1;
+        if self.seen[var(self.trail[index])] { break; }
+      }
+      let p = self.trail[index];
+      self.seen[var(p)] = false;
+      pending -= 1;
+      if pending == 0 {
+        learnt[0] = p ^ 1;
+        break;
+      }
+      implied = Some(p);
+      clause = self.reason[var(p)].unwrap();
+    }
+    for &q in &learnt[1..] {
+      self.seen[var(q)] = false;
+    }
+    let mut back = 0;
+    if learnt.len() > 1 {
+      let max = (1..learnt.len()).max_by_key(|&k| self.level[var(learnt[k])]).unwrap();
+      learnt.swap(1, max);
+      back = self.level[var(learnt[1])];
+    }
+    (learnt, back)
+  }
+
+  fn backtrack(&mut self, level: usize) {
+    if self.decision_level() <= level {
+      return;
+    }
+    let start = self.trail_lim[level];
+    for &l in &self.trail[start..] {
+      let v = var(l);
+      self.phase[v] = l & 1 == 0;
+      self.value[v] = UNDEF;
+      self.reason[v] = None;
+    }
+    self.trail.truncate(start);
+    self.trail_lim.truncate(level);
+    self.queue_head = start;
+  }
+
+  fn pick_branch(&self) -> Option<usize> {
+    let mut best: Option<usize> = None;
+    for v in 0..self.num_vars() {
+      if self.value[v] == UNDEF && best.is_none_or(|b| self.activity[v] > self.activity[b]) {
+        best = Some(v);
+      }
+    }
+    best.map(|v| 2 * v + !self.phase[v] as usize)
+  }
+
+  pub fn solve(&mut self) -> SatResult {
+    if self.unsat {
+      return SatResult::Unsat;
+    }
+    let mut restart = 1;
+    loop {
+      let budget = 100 * luby(restart);
+      restart += 1;
+      let mut conflicts = 0;
+      loop {
+        if let Some(conflict) = self.propagate() {
+          if self.decision_level() == 0 {
+            self.unsat = true;
+            return SatResult::Unsat;
+          }
+          conflicts += 1;
+          let (learnt, back) = self.analyze(conflict);
+          self.backtrack(back);
+          if learnt.len() == 1 {
+            self.assign(learnt[0], None);
+          } else {
+            let asserting = learnt[0];
+            let id = self.attach(learnt);
+            self.assign(asserting, Some(id));
+          }
+          self.increment /= 0.95;
+        } else if conflicts >= budget {
+          self.backtrack(0);
+          break;
+        } else {
+          match self.pick_branch() {
+            Some(l) => {
+              self.trail_lim.push(self.trail.len());
+              self.assign(l, None);
+            },
+            None => {
+              let model = self.value.iter().map(|&v| v == 1).collect();
+              self.backtrack(0);
+              return SatResult::Sat(model);
+            },
+          }
+        }
+      }
+    }
+  }
+}
+
+// 1, 1, 2, 1, 1, 2, 4, 1, 1, 2, ... for i >= 1
+fn luby(i: usize) -> usize {
+  let mut i = i;
+  loop {
+    let mut k = 1;
+    while (1 << k) - 1 < i {
+      k += 1;
+    }
+    if (1 << k) - 1 == i {
+      return 1 << (k - 1);
+    }
+    i -= (1 << (k - 1)) - 1;
+  }
+}
+
+pub fn solve(cnf: &Cnf) -> SatResult {
+  Solver::from_cnf(cnf).solve()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  fn satisfies(cnf: &Cnf, model: &[bool]) -> bool {
+    cnf.clauses.iter().all(|c| c.iter().any(|&l| model[l.unsigned_abs() as usize - 1] == (l > 0)))
+  }
+
+  fn brute_force(cnf: &Cnf) -> bool {
+    (0..1u32 << cnf.num_vars).any(|mask| {
+      let model: Vec<bool> = (0..cnf.num_vars).map(|v| mask >> v & 1 == 1).collect();
+      satisfies(cnf, &model)
+    })
+  }
+
+  #[test]
+  fn test_luby() {
+    let seq: Vec<usize> = (1..16).map(luby).collect();
+    assert_eq!(vec![1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8], seq);
+  }
+
+  #[test]
+  fn test_random_3sat() {
+    let mut rand = rand_gen(5);
+    let mut outcomes = [0, 0];
+    for _ in 0..300 {
+      let num_vars = 1 + rand(12) as usize;
+      // around the 4.26 clauses per variable threshold, so both outcomes show up
+      let clauses = (0..rand(6 * num_vars as u64))
+        .map(|_| (0..1 + rand(3)).map(|_| {
+          let v = 1 + rand(num_vars as u64) as i32;
+          if rand(2) == 0 { v } else { -v }
+        }).collect())
+        .collect();
+      let cnf = Cnf { num_vars, clauses };
+      match solve(&cnf) {
+        SatResult::Sat(model) => {
+          assert!(satisfies(&cnf, &model));
+          outcomes[0] += 1;
+        },
+        SatResult::Unsat => {
+          assert!(!brute_force(&cnf));
+          outcomes[1] += 1;
+        },
+      }
+    }
+    assert!(outcomes[0] > 0 && outcomes[1] > 0);
+  }
+
+  // n + 1 pigeons in n holes; variable p * n + h + 1 says pigeon p sits in hole h
+  fn pigeonhole(n: usize) -> Cnf {
+    let x = |p: usize, h: usize| (p * n + h + 1) as i32;
+    let mut clauses: Vec<Vec<i32>> = (0..=n).map(|p| (0..n).map(|h| x(p, h)).collect()).collect();
+    for h in 0..n {
+      for p in 0..=n {
+        for q in p + 1..=n {
+          clauses.push(vec![-x(p, h), -x(q, h)]);
+        }
+      }
+    }
+    Cnf { num_vars: (n + 1) * n, clauses }
+  }
+
+  #[test]
+  fn test_pigeonhole() {
+    assert_eq!(SatResult::Unsat, solve(&pigeonhole(5)));
+    let mut cnf = pigeonhole(4);
+    cnf.clauses.remove(0); // pigeon 0 may stay out
+    match solve(&cnf) {
+      SatResult::Sat(model) => assert!(satisfies(&cnf, &model)),
+      SatResult::Unsat => panic!("satisfiable"),
+    }
+  }
+
+  #[test]
+  fn test_dimacs_and_incremental() {
+    let text = "c example\np cnf 3 4\n1 -2 0\n2 3 0\n-1 0\n-3\n";
+    let cnf = parse_dimacs(text).unwrap();
+    assert_eq!(3, cnf.num_vars);
+    assert_eq!(vec![vec![1, -2], vec![2, 3], vec![-1], vec![-3]], cnf.clauses);
+    assert_eq!(SatResult::Unsat, solve(&cnf));
+
+    let mut solver = Solver::from_cnf(&Cnf { num_vars: 3, clauses: cnf.clauses[..3].to_vec() });
+    assert_eq!(SatResult::Sat(vec![false, false, true]), solver.solve());
+    solver.add_clause(&[-3, 1]);
+    assert_eq!(SatResult::Unsat, solver.solve());
+
+    assert!(parse_dimacs("1 2 0").is_err());
+    assert!(parse_dimacs("p cnf 2 1\n1 3 0").is_err());
+    assert!(parse_dimacs("p cnf x 1").is_err());
+  }
+
+  // the encoding of constraint over x_1..x_n is satisfiable with the inputs fixed exactly when
+  // accepts holds on the number of true inputs
+  fn check_encoding(n: usize, encode: impl Fn(&mut Cnf, &[i32]), accepts: impl Fn(usize) -> bool) {
+    let mut cnf = Cnf::new();
+    let inputs: Vec<i32> = (0..n).map(|_| cnf.new_var()).collect();
+    encode(&mut cnf, &inputs);
+    for mask in 0..1u32 << n {
+      let mut fixed = cnf.clone();
+      for (i, &x) in inputs.iter().enumerate() {
+        fixed.add_clause(&[if mask >> i & 1 == 1 { x } else { -x }]);
+      }
+      let sat = solve(&fixed) != SatResult::Unsat;
+      assert_eq!(accepts(mask.count_ones() as usize), sat, "n {} mask {:b}", n, mask);
+    }
+  }
+
+  #[test]
+  fn test_encodings() {
+    for n in 0..8 {
+      check_encoding(n, |cnf, x| cnf.at_most_one_pairwise(x), |c| c <= 1);
+      check_encoding(n, |cnf, x| cnf.at_most_one_sequential(x), |c| c <= 1);
+      check_encoding(n, |cnf, x| cnf.exactly_one(x), |c| c == 1);
+      for k in 0..=n + 1 {
+        check_encoding(n, |cnf, x| cnf.at_most_k(x, k), |c| c <= k);
+        check_encoding(n, |cnf, x| cnf.at_least_k(x, k), |c| c >= k);
+        check_encoding(n, |cnf, x| cnf.exactly_k(x, k), |c| c == k);
+      }
+    }
+  }
+
+  #[test]
+  fn test_model_queens() {
+    // 6 queens: one per row and column, at most one per diagonal
+    let n = 6;
+    let mut cnf = Cnf::new();
+    let q: Vec<Vec<i32>> = (0..n).map(|_| (0..n).map(|_| cnf.new_var()).collect()).collect();
+    for (i, row) in q.iter().enumerate() {
+      cnf.exactly_one(row);
+      let column: Vec<i32> = (0..n).map(|r| q[r][i]).collect();
+      cnf.exactly_one(&column);
+    }
+    for d in 0..2 * n - 1 {
+      let diagonal: Vec<i32> = (0..n).filter(|&r| d >= r && d - r < n).map(|r| q[r][d - r]).collect();
+      let anti: Vec<i32> = (0..n).filter(|&r| d + r >= n - 1 && d + r - (n - 1) < n).map(|r| q[r][d + r - (n - 1)]).collect();
+      cnf.at_most_one(&diagonal);
+      cnf.at_most_one(&anti);
+    }
+    cnf.implies(&[q[0][1]], -q[5][4]); // rules out one of the four solutions
+    let model = match solve(&cnf) {
+      SatResult::Sat(model) => model,
+      SatResult::Unsat => panic!("6 queens has solutions"),
+    };
+    let cols: Vec<usize> = (0..n).map(|r| (0..n).find(|&c| model[q[r][c] as usize - 1]).unwrap()).collect();
+    for a in 0..n {
+      for b in a + 1..n {
+        assert!(cols[a] != cols[b] && cols[a] + b != cols[b] + a && cols[a] + a != cols[b] + b);
+      }
+    }
+    assert!(!(cols[0] == 1 && cols[5] == 4));
+  }
+}
@@ -0,0 +1,518 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::hash::Hash;
+
+// Searches over implicit graphs: a start state, a successor closure yielding (state, step cost)
+// pairs and a goal test. States are only generated as the search reaches them, so the graph never
+// has to fit in memory. Results are optimal when the heuristic never overestimates the
+// remaining cost to a goal.
+
+const INF: u64 = u64::MAX;
+const NIL: usize = usize::MAX;
+
+struct Ida<S, F, H, G> {
+  successors: F,
+  heuristic: H,
+  is_goal: G,
+  path: Vec<S>,
+  on_path: HashSet<S>,
+}
+
+impl<S, I, F, H, G> Ida<S, F, H, G>
+where
+  S: Eq + Hash + Clone,
+  I: IntoIterator<Item = (S, u64)>,
+  F: FnMut(&S) -> I,
+  H: Fn(&S) -> u64,
+  G: Fn(&S) -> bool,
+{
+  // depth-first below the end of path, Ok(cost) at a goal, or Err(the smallest f over bound)
+  fn probe(&mut self, g: u64, bound: u64) -> Result<u64, u64> {
+    let state = self.path.last().unwrap();
+    let f = g.saturating_add((self.heuristic)(state));
+    if f > bound {
+      return Err(f);
+    }
+    if (self.is_goal)(state) {
+      return Ok(g);
+    }
+    let next: Vec<(S, u64)> = (self.successors)(state).into_iter().collect();
+    let mut exceeded = INF;
+    for (s, cost) in next {
+      if !self.on_path.insert(s.clone()) {
+        continue;
+      }
+      self.path.push(s);
+      match self.probe(g + cost, bound) {
+        Ok(total) => return Ok(total),
+        Err(f) => exceeded = exceeded.min(f),
+      }
+      let s = self.path.pop().unwrap();
+      self.on_path.remove(&s);
+    }
+    Err(exceeded)
+  }
+}
+
+// iterative deepening A*: depth-first searches cut off at f = g + h > bound, each raising bound
+// to the smallest f that went over it. Memory is the current path, with a hash set of the states
+// on it to skip cycles. Returns the cost and the states from start to a goal.
+pub fn ida_star<S, I>(start: S, successors: impl FnMut(&S) -> I, heuristic: impl Fn(&S) -> u64, is_goal: impl Fn(&S) -> bool) -> Option<(u64, Vec<S>)>
+where
+  S: Eq + Hash + Clone,
+  I: IntoIterator<Item = (S, u64)>,
+{
+  let mut bound = heuristic(&start);
+  let mut ida = Ida {
+    successors,
+    heuristic,
+    is_goal,
+    path: vec![start.clone()],
+    on_path: HashSet::new(),
+  };
+  ida.on_path.insert(start);
+  loop {
+    match ida.probe(0, bound) {
+      Ok(cost) => return Some((cost, ida.path)),
+      Err(INF) => return None,
+      Err(next) => bound = next,
+    }
+  }
+}
+
+struct SmaNode<S> {
+  state: S,
+  parent: usize,
+  g: u64,
+  f: u64, // the key on the open list, raised to the best forgotten child when reopened
+  depth: usize,
+  children: Vec<usize>,
+  forgotten: u64, // smallest f among children dropped since the last expansion
+  open: bool,
+}
+
+struct Sma<S> {
+  nodes: Vec<Option<SmaNode<S>>>,
+  free: Vec<usize>,
+  open: BTreeSet<(u64, Reverse<usize>, usize)>, // lowest f first, deeper first among equals
+  alive: usize,
+}
+
+impl<S: Eq + Clone> Sma<S> {
+  fn node(&self, id: usize) -> &SmaNode<S> {
+    self.nodes[id].as_ref().unwrap()
+  }
+
+  fn node_mut(&mut self, id: usize) -> &mut SmaNode<S> {
+    self.nodes[id].as_mut().unwrap()
+  }
+
+  fn push_open(&mut self, id: usize) {
+    let n = self.node_mut(id);
+    n.open = true;
+    let entry = (n.f, Reverse(n.depth), id);
+    self.open.insert(entry);
+  }
+
+  fn pop_open(&mut self, id: usize) {
+    let n = self.node_mut(id);
+    if n.open {
+      n.open = false;
+      let entry = (n.f, Reverse(n.depth), id);
+      self.open.remove(&entry);
+    }
+  }
+
+  fn alloc(&mut self, state: S, parent: usize, g: u64, f: u64) -> usize {
+    let depth = if parent == NIL { 0 } else { self.node(parent).depth + 1 };
+    let node = SmaNode { state, parent, g, f, depth, children: vec![], forgotten: INF, open: false };
+    self.alive += 1;
+    let id = match self.free.pop() {
+      Some(id) => {
+        self.nodes[id] = Some(node);
+        id
+      },
+      None => {
+        self.nodes.push(Some(node));
+        self.nodes.len() - 1
+      },
+    };
+    if parent != NIL {
+      self.node_mut(parent).children.push(id);
+    }
+    self.push_open(id);
+    id
+  }
+
+  // whether s is id or one of its ancestors
+  fn on_path(&self, mut id: usize, s: &S) -> bool {
+    while id != NIL {
+      if self.node(id).state == *s {
+        return true;
+      }
+      id = self.node(id).parent;
+    }
+    false
+  }
+
+  // drops the leaf id and backs its f up into the parent, which is reopened to regenerate it
+  // later, or dropped in turn when it has nothing left to offer
+  fn forget(&mut self, id: usize) {
+    self.pop_open(id);
+    let node = self.nodes[id].take().unwrap();
+    self.alive -= 1;
+    self.free.push(id);
+    let p = node.parent;
+    if p == NIL {
+      return;
+    }
+    self.pop_open(p);
+    let parent = self.node_mut(p);
+    parent.children.retain(|&c| c != id);
+    parent.forgotten = parent.forgotten.min(node.f);
+    if parent.forgotten < INF {
+      parent.f = parent.forgotten;
+      self.push_open(p);
+    } else if parent.children.is_empty() && parent.parent != NIL {
+      parent.f = INF; // a dead end
+      self.forget(p);
+    }
+  }
+
+  fn path(&self, mut id: usize) -> Vec<S> {
+    let mut path = vec![];
+    while id != NIL {
+      path.push(self.node(id).state.clone());
+      id = self.node(id).parent;
+    }
+    path.reverse();
+    path
+  }
+}
+
+// memory-bounded A* in the manner of SMA*: best-first like A*, but with at most max_nodes search
+// nodes kept. When memory runs out the worst leaf, highest f and then shallowest, is dropped and
+// its f backed up into its parent, which goes back on the open list to regenerate the dropped
+// branch once everything cheaper is used up. Expanding a node generates all its successors at
+// once, and leaves tied with the cheapest open f are never dropped. The result is optimal when
+// the optimal path and the frontier beside it fit in max_nodes; None means no goal was reachable
+// within them.
+pub fn memory_bounded_a_star<S, I>(
+  start: S,
+  mut successors: impl FnMut(&S) -> I,
+  heuristic: impl Fn(&S) -> u64,
+  is_goal: impl Fn(&S) -> bool,
+  max_nodes: usize,
+) -> Option<(u64, Vec<S>)>
+where
+  S: Eq + Hash + Clone,
+  I: IntoIterator<Item = (S, u64)>,
+{
+  assert!(max_nodes >= 2);
+  let mut sma = Sma { nodes: vec![], free: vec![], open: BTreeSet::new(), alive: 0 };
+  let f = heuristic(&start);
+  sma.alloc(start, NIL, 0, f);
+  while let Some(&(key, _, id)) = sma.open.iter().next() {
+    if key == INF {
+      return None;
+    }
+    sma.pop_open(id);
+    if is_goal(&sma.node(id).state) {
+      return Some((sma.node(id).g, sma.path(id)));
+    }
+    // regenerate whatever is not in memory below id
+    sma.node_mut(id).forgotten = INF;
+    let g = sma.node(id).g;
+    // a child per state, through the cheapest of parallel steps
+    let mut next: Vec<(S, u64)> = vec![];
+    let mut at: HashMap<S, usize> = HashMap::new();
+    for (s, cost) in successors(&sma.node(id).state) {
+      match at.get(&s) {
+        Some(&i) => next[i].1 = next[i].1.min(cost),
+        None => {
+          at.insert(s.clone(), next.len());
+          next.push((s, cost));
+        },
+      }
+    }
+    for (s, cost) in next {
+      if sma.on_path(id, &s) || sma.node(id).children.iter().any(|&c| sma.node(c).state == s) {
+        continue;
+      }
+      // pathmax keeps f from dropping below what is already known about the parent, and a
+      // node as deep as memory allows is worthless unless it is a goal
+      let mut f = (g + cost).saturating_add(heuristic(&s)).max(key);
+      if sma.node(id).depth + 2 >= max_nodes && !is_goal(&s) {
+        f = INF;
+      }
+      sma.alloc(s, id, g + cost, f);
+    }
+    if sma.node(id).children.is_empty() {
+      if sma.node(id).parent == NIL {
+        return None;
+      }
+      sma.node_mut(id).f = INF;
+      sma.forget(id);
+      continue;
+    }
+    while sma.alive > max_nodes {
+      // only leaves above the cheapest open f go, so keys rise and the search cannot cycle
+      let floor = sma.open.iter().next().map_or(INF, |e| e.0);
+      let worst = sma.open.iter().rev().take_while(|e| e.0 > floor).map(|&(_, _, v)| v).find(|&v| {
+        sma.node(v).children.is_empty() && sma.node(v).parent != NIL
+      });
+      match worst {
+        Some(v) => sma.forget(v),
+        None => return None,
+      }
+    }
+  }
+  None
+}
+
+// states reached from one end: distance and the neighbour it was reached from
+type Visited<S> = HashMap<S, (usize, Option<S>)>;
+
+// expands one whole BFS layer of a side, returning the shortest start to goal length through a
+// state the other side has already seen, and that state
+fn expand_layer<S, I>(frontier: &mut Vec<S>, seen: &mut Visited<S>, other: &Visited<S>, next: &mut impl FnMut(&S) -> I) -> Option<(usize, S)>
+where
+  S: Eq + Hash + Clone,
+  I: IntoIterator<Item = S>,
+{
+  let mut meet: Option<(usize, S)> = None;
+  for u in std::mem::take(frontier) {
+    let d = seen[&u].0 + 1;
+    for v in next(&u) {
+      if seen.contains_key(&v) {
+        continue;
+      }
+      seen.insert(v.clone(), (d, Some(u.clone())));
+      if let Some(&(e, _)) = other.get(&v) {
+        if meet.as_ref().is_none_or(|m| d + e < m.0) {
+          meet = Some((d + e, v.clone()));
+        }
+      }
+      frontier.push(v);
+    }
+  }
+  meet
+}
+
+// shortest path by number of steps from start to goal, searching forward from start and
+// backward from goal (backward gives the states with a step into its argument) until the two
+// meet. Each round expands the smaller frontier by a whole layer, so for branching factor b
+// about 2 b^(d/2) states are visited instead of b^d. Once a layer meets the other side the best
+// meeting state in that layer is on a shortest path.
+pub fn bidirectional_bfs<S, I, J>(start: S, goal: S, mut forward: impl FnMut(&S) -> I, mut backward: impl FnMut(&S) -> J) -> Option<Vec<S>>
+where
+  S: Eq + Hash + Clone,
+  I: IntoIterator<Item = S>,
+  J: IntoIterator<Item = S>,
+{
+  if start == goal {
+    return Some(vec![start]);
+  }
+  let mut from_start: Visited<S> = HashMap::new();
+  let mut from_goal: Visited<S> = HashMap::new();
+  from_start.insert(start.clone(), (0, None));
+  from_goal.insert(goal.clone(), (0, None));
+  let (mut front_start, mut front_goal) = (vec![start], vec![goal]);
+  let meet = loop {
+    if front_start.is_empty() || front_goal.is_empty() {
+      return None;
+    }
+    let met = if front_start.len() <= front_goal.len() {
+      expand_layer(&mut front_start, &mut from_start, &from_goal, &mut forward)
+    } else {
+      expand_layer(&mut front_goal, &mut from_goal, &from_start, &mut backward)
+    };
+    if let Some((_, v)) = met {
+      break v;
+    }
+  };
+  let mut path = vec![meet.clone()];
+  let mut at = &meet;
+  while let Some(p) = &from_start[at].1 {
+    path.push(p.clone());
+    at = p;
+  }
+  path.reverse();
+  let mut at = &meet;
+  while let Some(n) = &from_goal[at].1 {
+    path.push(n.clone());
+    at = n;
+  }
+  Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+  use std::collections::VecDeque;
+
+  fn dijkstra(adj: &[Vec<(usize, u64)>], s: usize, t: usize) -> Option<u64> {
+    let mut dist = vec![INF; adj.len()];
+    let mut done = vec![false; adj.len()];
+    dist[s] = 0;
+    loop {
+      let u = (0..adj.len()).filter(|&u| !done[u] && dist[u] < INF).min_by_key(|&u| dist[u])?;
+      if u == t {
+        return Some(dist[t]);
+      }
+      done[u] = true;
+      for &(v, w) in &adj[u] {
+        dist[v] = dist[v].min(dist[u] + w);
+      }
+    }
+  }
+
+  fn check_path(adj: &[Vec<(usize, u64)>], path: &[usize], cost: u64) {
+    let total: u64 = path.windows(2).map(|w| adj[w[0]].iter().filter(|e| e.0 == w[1]).map(|e| e.1).min().unwrap()).sum();
+    assert_eq!(cost, total);
+  }
+
+  #[test]
+  fn test_random_graphs() {
+    let mut rand = rand_gen(71);
+    for _ in 0..200 {
+      let n = 1 + rand(9) as usize;
+      let mut adj = vec![vec![]; n];
+      for _ in 0..rand(3 * n as u64) {
+        let (u, v) = (rand(n as u64) as usize, rand(n as u64) as usize);
+        adj[u].push((v, 1 + rand(9)));
+      }
+      let t = rand(n as u64) as usize;
+      let expected = dijkstra(&adj, 0, t);
+      let successors = |&u: &usize| adj[u].clone();
+      let found = ida_star(0, successors, |_| 0, |&u| u == t);
+      assert_eq!(expected, found.as_ref().map(|r| r.0));
+      if let Some((cost, path)) = found {
+        assert_eq!((0, t), (path[0], *path.last().unwrap()));
+        check_path(&adj, &path, cost);
+      }
+      let found = memory_bounded_a_star(0, successors, |_| 0, |&u| u == t, 1000);
+      assert_eq!(expected, found.as_ref().map(|r| r.0));
+      // with little memory an answer may be lost but never wrong
+      let tight = 2 + rand(4) as usize;
+      if let Some((cost, path)) = memory_bounded_a_star(0, successors, |_| 0, |&u| u == t, tight) {
+        assert!(expected.unwrap() <= cost);
+        assert_eq!((0, t), (path[0], *path.last().unwrap()));
+        check_path(&adj, &path, cost);
+      }
+    }
+  }
+
+  type Board = [u8; 9];
+
+  fn moves(b: &Board) -> Vec<(Board, u64)> {
+    let z = b.iter().position(|&x| x == 0).unwrap();
+    let mut next = vec![];
+    for (ok, d) in [(z >= 3, -3), (z < 6, 3), (z % 3 > 0, -1), (z % 3 < 2, 1)] {
+      if ok {
+        let mut c = *b;
+        c.swap(z, (z as i32 + d) as usize);
+        next.push((c, 1));
+      }
+    }
+    next
+  }
+
+  fn manhattan(b: &Board) -> u64 {
+    b.iter().enumerate().filter(|&(_, &x)| x != 0).map(|(i, &x)| {
+      let j = x as usize - 1;
+      ((i / 3) as i64 - (j / 3) as i64).unsigned_abs() + ((i % 3) as i64 - (j % 3) as i64).unsigned_abs()
+    }).sum()
+  }
+
+  fn bfs(start: Board, goal: Board) -> u64 {
+    let mut dist = HashMap::new();
+    dist.insert(start, 0);
+    let mut queue = VecDeque::from(vec![start]);
+    while let Some(b) = queue.pop_front() {
+      if b == goal {
+        return dist[&b];
+      }
+      let d = dist[&b];
+      for (c, _) in moves(&b) {
+        dist.entry(c).or_insert_with(|| {
+          queue.push_back(c);
+          d + 1
+        });
+      }
+    }
+    unreachable!()
+  }
+
+  #[test]
+  fn test_eight_puzzle() {
+    let mut rand = rand_gen(73);
+    let goal: Board = [1, 2, 3, 4, 5, 6, 7, 8, 0];
+    for _ in 0..10 {
+      let mut start = goal;
+      for _ in 0..30 {
+        let next = moves(&start);
+        start = next[rand(next.len() as u64) as usize].0;
+      }
+      let expected = bfs(start, goal);
+      let (cost, path) = ida_star(start, moves, manhattan, |b| *b == goal).unwrap();
+      assert_eq!(expected, cost);
+      assert_eq!(cost as usize + 1, path.len());
+      assert!(path.windows(2).all(|w| moves(&w[0]).iter().any(|m| m.0 == w[1])));
+      let (cost, path) = memory_bounded_a_star(start, moves, manhattan, |b| *b == goal, 500).unwrap();
+      assert_eq!(expected, cost);
+      assert_eq!(goal, *path.last().unwrap());
+    }
+  }
+
+  fn bfs_distance(adj: &[Vec<usize>], s: usize, t: usize) -> Option<usize> {
+    let mut dist = vec![usize::MAX; adj.len()];
+    dist[s] = 0;
+    let mut queue = VecDeque::from(vec![s]);
+    while let Some(u) = queue.pop_front() {
+      for &v in &adj[u] {
+        if dist[v] == usize::MAX {
+          dist[v] = dist[u] + 1;
+          queue.push_back(v);
+        }
+      }
+    }
+    if dist[t] == usize::MAX { None } else { Some(dist[t]) }
+  }
+
+  #[test]
+  fn test_bidirectional_random() {
+    let mut rand = rand_gen(79);
+    for _ in 0..300 {
+      let n = 1 + rand(30) as usize;
+      let mut adj = vec![vec![]; n];
+      let mut radj = vec![vec![]; n];
+      for _ in 0..rand(3 * n as u64) {
+        let (u, v) = (rand(n as u64) as usize, rand(n as u64) as usize);
+        adj[u].push(v);
+        radj[v].push(u);
+      }
+      let (s, t) = (rand(n as u64) as usize, rand(n as u64) as usize);
+      let path = bidirectional_bfs(s, t, |&u| adj[u].clone(), |&v| radj[v].clone());
+      assert_eq!(bfs_distance(&adj, s, t), path.as_ref().map(|p| p.len() - 1));
+      if let Some(path) = path {
+        assert_eq!((s, t), (path[0], *path.last().unwrap()));
+        assert!(path.windows(2).all(|w| adj[w[0]].contains(&w[1])));
+      }
+    }
+  }
+
+  #[test]
+  fn test_word_ladder() {
+    let words = ["hit", "hot", "dot", "dog", "cog", "lot", "log", "cot"];
+    let neighbours = |w: &&str| -> Vec<&str> {
+      words.iter().copied().filter(|v| v.chars().zip(w.chars()).filter(|(a, b)| a != b).count() == 1).collect()
+    };
+    let path = bidirectional_bfs("hit", "cog", neighbours, neighbours).unwrap();
+    assert_eq!(4, path.len()); // through cot
+    assert_eq!(("hit", "hot", "cog"), (path[0], path[1], path[3]));
+    assert_eq!(None, bidirectional_bfs("hit", "axe", neighbours, neighbours));
+    assert_eq!(Some(vec!["dog"]), bidirectional_bfs("dog", "dog", neighbours, neighbours));
+  }
+}
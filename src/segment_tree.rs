@@ -0,0 +1,207 @@
+use std::marker::PhantomData;
+use std::ops::Range;
+
+// An associative combine with an identity. Need not be commutative: values are always combined
+// in the order of their positions.
+pub trait Monoid {
+  type Value: Clone;
+  fn identity() -> Self::Value;
+  fn combine(a: &Self::Value, b: &Self::Value) -> Self::Value;
+}
+
+// built-in monoids over the primitive integers
+pub struct Sum<T>(PhantomData<T>);
+pub struct Min<T>(PhantomData<T>);
+pub struct Max<T>(PhantomData<T>);
+pub struct Gcd<T>(PhantomData<T>);
+
+macro_rules! integer_monoids {
+  ($($t:ty),*) => {$(
+    impl Monoid for Sum<$t> {
+      type Value = $t;
+      fn identity() -> $t {
+        0
+      }
+      fn combine(a: &$t, b: &$t) -> $t {
+        a + b
+      }
+    }
+
+    impl Monoid for Min<$t> {
+      type Value = $t;
+      fn identity() -> $t {
+        <$t>::MAX
+      }
+      fn combine(a: &$t, b: &$t) -> $t {
+        *a.min(b)
+      }
+    }
+
+    impl Monoid for Max<$t> {
+      type Value = $t;
+      fn identity() -> $t {
+        <$t>::MIN
+      }
+      fn combine(a: &$t, b: &$t) -> $t {
+        *a.max(b)
+      }
+    }
+  )*};
+}
+
+macro_rules! gcd_monoid {
+  ($($t:ty),*) => {$(
+    impl Monoid for Gcd<$t> {
+      type Value = $t;
+      fn identity() -> $t {
+        0
+      }
+      fn combine(a: &$t, b: &$t) -> $t {
+        let (mut a, mut b) = (*a, *b);
+        while b != 0 {
+          (a, b) = (b, a % b);
+        }
+        a
+      }
+    }
+  )*};
+}
+
+integer_monoids!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+gcd_monoid!(u8, u16, u32, u64, u128, usize);
+
+// Segment tree over a monoid: point updates and the combination of any range, both O(log n).
+// Stored bottom-up in 2n slots, leaves at n..2n and node i combining 2i and 2i + 1, with no
+// padding to a power of two. A query climbs from both ends of the range at once, keeping what
+// it gathers on the left and on the right apart so the order is kept.
+pub struct SegmentTree<M: Monoid> {
+  n: usize,
+  tree: Vec<M::Value>,
+}
+
+impl<M: Monoid> SegmentTree<M> {
+  // n copies of the identity
+  pub fn new(n: usize) -> SegmentTree<M> {
+    SegmentTree { n, tree: vec![M::identity(); 2 * n] }
+  }
+
+  // O(n)
+  pub fn from_slice(values: &[M::Value]) -> SegmentTree<M> {
+    let n = values.len();
+    let mut tree = vec![M::identity(); 2 * n];
+    tree[n..].clone_from_slice(values);
+    for i in (1..n).rev() {
+      tree[i] = M::combine(&tree[2 * i], &tree[2 * i + 1]);
+    }
+    SegmentTree { n, tree }
+  }
+
+  pub fn len(&self) -> usize {
+    self.n
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.n == 0
+  }
+
+  pub fn get(&self, i: usize) -> &M::Value {
+    assert!(i < self.n);
+    &self.tree[self.n + i]
+  }
+
+  // sets position i to value
+  pub fn update(&mut self, i: usize, value: M::Value) {
+    assert!(i < self.n);
+    let mut i = i + self.n;
+    self.tree[i] = value;
+    while i > 1 {
+      i /= 2;
+      self.tree[i] = M::combine(&self.tree[2 * i], &self.tree[2 * i + 1]);
+    }
+  }
+
+  // the combination of the values in range, the identity for an empty one
+  pub fn query(&self, range: Range<usize>) -> M::Value {
+    assert!(range.start <= range.end && range.end <= self.n);
+    let (mut l, mut r) = (range.start + self.n, range.end + self.n);
+    let (mut left, mut right) = (M::identity(), M::identity());
+    while l < r {
+      if l & 1 == 1 {
+        left = M::combine(&left, &self.tree[l]);
+        l += 1;
+      }
+      if r & 1 == 1 {
+        r -= 1;
+        right = M::combine(&self.tree[r], &right);
+      }
+      l /= 2;
+      r /= 2;
+    }
+    M::combine(&left, &right)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  // affine maps x -> a x + b mod p, composed first to last: not commutative
+  struct Affine;
+
+  const P: u64 = 1_000_000_007;
+
+  impl Monoid for Affine {
+    type Value = (u64, u64);
+    fn identity() -> (u64, u64) {
+      (1, 0)
+    }
+    fn combine(f: &(u64, u64), g: &(u64, u64)) -> (u64, u64) {
+      (f.0 * g.0 % P, (f.1 * g.0 + g.1) % P)
+    }
+  }
+
+  #[test]
+  fn test_builtin() {
+    let values = [5u64, 12, 18, 7, 30, 3];
+    let sums: SegmentTree<Sum<u64>> = SegmentTree::from_slice(&values);
+    assert_eq!(37, sums.query(1..4));
+    assert_eq!(0, sums.query(2..2));
+    let gcds: SegmentTree<Gcd<u64>> = SegmentTree::from_slice(&values);
+    assert_eq!(6, gcds.query(1..3));
+    assert_eq!(1, gcds.query(0..6));
+    let mut mins: SegmentTree<Min<i32>> = SegmentTree::new(4);
+    assert_eq!(i32::MAX, mins.query(0..4));
+    mins.update(2, -7);
+    mins.update(0, 3);
+    assert_eq!((-7, 3), (mins.query(0..4), mins.query(0..2)));
+    let maxes: SegmentTree<Max<i64>> = SegmentTree::from_slice(&[-4, -9, -1]);
+    assert_eq!((-1, -4, 3), (maxes.query(0..3), *maxes.get(0), maxes.len()));
+    assert!(SegmentTree::<Sum<u8>>::new(0).is_empty());
+  }
+
+  #[test]
+  fn test_random_against_naive() {
+    let mut rand = rand_gen(331);
+    for round in 0..100 {
+      let n = if round % 10 == 0 { 500 } else { 1 + rand(40) as usize };
+      let mut values: Vec<(u64, u64)> = (0..n).map(|_| (rand(P), rand(P))).collect();
+      let mut affine: SegmentTree<Affine> = SegmentTree::from_slice(&values);
+      let mut mins: Vec<i64> = (0..n).map(|_| rand(1000) as i64 - 500).collect();
+      let mut min_tree: SegmentTree<Min<i64>> = SegmentTree::from_slice(&mins);
+      for _ in 0..100 {
+        let i = rand(n as u64) as usize;
+        if rand(2) == 0 {
+          values[i] = (rand(P), rand(P));
+          affine.update(i, values[i]);
+          mins[i] = rand(1000) as i64 - 500;
+          min_tree.update(i, mins[i]);
+        }
+        let l = rand(n as u64 + 1) as usize;
+        let r = l + rand((n - l) as u64 + 1) as usize;
+        assert_eq!(values[l..r].iter().fold(Affine::identity(), |f, g| Affine::combine(&f, g)), affine.query(l..r));
+        assert_eq!(mins[l..r].iter().copied().min().unwrap_or(i64::MAX), min_tree.query(l..r));
+      }
+    }
+  }
+}
@@ -0,0 +1,92 @@
+use std::cmp::Ordering;
+use std::thread;
+
+use crate::parallel::{merge_on, threads};
+
+// below this many elements per thread the slice is sorted on one
+const SEQUENTIAL: usize = 1 << 13;
+
+// merges pairs of adjacent sorted runs of width keys from source into target, one thread per
+// pair, each merge split further over its share of the threads
+fn merge_pass<T: Clone + Send + Sync, F: Fn(&T, &T) -> Ordering + Sync>(source: &[T], target: &mut [T], width: usize, compare: &F, threads: usize) {
+  let pairs = source.len().div_ceil(2 * width);
+  thread::scope(|s| {
+    for (pair, out) in source.chunks(2 * width).zip(target.chunks_mut(2 * width)) {
+      if pair.len() <= width {
+        // the last run, without a partner
+        out.clone_from_slice(pair);
+        continue;
+      }
+      let (left, right) = pair.split_at(width);
+      s.spawn(move || merge_on(left, right, out, compare, (threads / pairs).max(1)));
+    }
+  });
+}
+
+fn sort_on<T: Clone + Send + Sync>(a: &mut [T], compare: impl Fn(&T, &T) -> Ordering + Sync, threads: usize) {
+  let n = a.len();
+  let runs = threads.min(n / SEQUENTIAL);
+  if runs <= 1 {
+    a.sort_by(compare);
+    return;
+  }
+  let width = n.div_ceil(runs);
+  let compare = &compare;
+  thread::scope(|s| {
+    for run in a.chunks_mut(width) {
+      s.spawn(move || run.sort_by(compare));
+    }
+  });
+  // back and forth between a and a buffer, the runs doubling in width each pass
+  let mut buffer = a.to_vec();
+  let mut width = width;
+  let mut in_buffer = false;
+  while width < n {
+    if in_buffer {
+      merge_pass(&buffer, a, width, compare, threads);
+    } else {
+      merge_pass(a, &mut buffer, width, compare, threads);
+    }
+    in_buffer = !in_buffer;
+    width *= 2;
+  }
+  if in_buffer {
+    a.clone_from_slice(&buffer);
+  }
+}
+
+// Stable parallel merge sort: one run per thread sorted by the standard library's stable sort,
+// then rounds of pairwise merges with parallel::merge_by's splitting, so the last merges use all
+// the threads too. O(n log n) work, O(n) extra space; small slices are sorted on the calling
+// thread alone.
+pub fn par_sort_by<T: Clone + Send + Sync>(a: &mut [T], compare: impl Fn(&T, &T) -> Ordering + Sync) {
+  sort_on(a, compare, threads());
+}
+
+pub fn par_sort<T: Ord + Clone + Send + Sync>(a: &mut [T]) {
+  par_sort_by(a, T::cmp);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_against_std() {
+    let mut rand = rand_gen(233);
+    for (n, threads) in [(0, 8), (1, 8), (1000, 8), (100_000, 8), (100_000, 3), (70_001, 16)] {
+      // many ties, tagged with their position to check stability
+      let keys = if n % 2 == 0 { 50 } else { u64::MAX };
+      let a: Vec<(u64, usize)> = (0..n).map(|i| (rand(keys), i)).collect();
+      let mut expected = a.clone();
+      expected.sort_by_key(|x| x.0);
+      let mut sorted = a.clone();
+      sort_on(&mut sorted, |x, y| x.0.cmp(&y.0), threads);
+      assert_eq!(expected, sorted);
+    }
+    let mut words = vec!["pear", "fig", "apple", "kiwi"];
+    par_sort(&mut words);
+    assert_eq!(vec!["apple", "fig", "kiwi", "pear"], words);
+  }
+}
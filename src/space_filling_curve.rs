@@ -0,0 +1,237 @@
+// Space-filling curves over grids of unsigned coordinates: the position of a cell along the
+// curve and back. Cells close along a curve are close in space, so sorting by it keeps neighbours
+// together, for spatial sorting and for ordering queries as in Mo's algorithm. The Z-order
+// (Morton) curve just interleaves the coordinates' bits; the Hilbert curve also moves to an
+// adjacent cell at every step, which keeps locality better, at a few more operations per bit.
+
+// the bits of x spread to every second position
+fn spread_2(x: u32) -> u64 {
+  let mut x = x as u64;
+  x = (x | x << 16) & 0x0000_ffff_0000_ffff;
+  x = (x | x << 8) & 0x00ff_00ff_00ff_00ff;
+  x = (x | x << 4) & 0x0f0f_0f0f_0f0f_0f0f;
+  x = (x | x << 2) & 0x3333_3333_3333_3333;
+  (x | x << 1) & 0x5555_5555_5555_5555
+}
+
+fn compact_2(x: u64) -> u32 {
+  let mut x = x & 0x5555_5555_5555_5555;
+  x = (x | x >> 1) & 0x3333_3333_3333_3333;
+  x = (x | x >> 2) & 0x0f0f_0f0f_0f0f_0f0f;
+  x = (x | x >> 4) & 0x00ff_00ff_00ff_00ff;
+  x = (x | x >> 8) & 0x0000_ffff_0000_ffff;
+  (x | x >> 16) as u32
+}
+
+// the low 21 bits of x spread to every third position
+fn spread_3(x: u32) -> u64 {
+  let mut x = x as u64 & 0x1f_ffff;
+  x = (x | x << 32) & 0x001f_0000_0000_ffff;
+  x = (x | x << 16) & 0x001f_0000_ff00_00ff;
+  x = (x | x << 8) & 0x100f_00f0_0f00_f00f;
+  x = (x | x << 4) & 0x10c3_0c30_c30c_30c3;
+  (x | x << 2) & 0x1249_2492_4924_9249
+}
+
+fn compact_3(x: u64) -> u32 {
+  let mut x = x & 0x1249_2492_4924_9249;
+  x = (x | x >> 2) & 0x10c3_0c30_c30c_30c3;
+  x = (x | x >> 4) & 0x100f_00f0_0f00_f00f;
+  x = (x | x >> 8) & 0x001f_0000_ff00_00ff;
+  x = (x | x >> 16) & 0x001f_0000_0000_ffff;
+  ((x | x >> 32) & 0x1f_ffff) as u32
+}
+
+// the Z-order index of (x, y): x's bits at even positions, y's at odd ones
+pub fn morton_encode_2d(x: u32, y: u32) -> u64 {
+  spread_2(x) | spread_2(y) << 1
+}
+
+pub fn morton_decode_2d(index: u64) -> (u32, u32) {
+  (compact_2(index), compact_2(index >> 1))
+}
+
+// the Z-order index of (x, y, z), each below 2^21, x's bits at positions 0 mod 3
+pub fn morton_encode_3d(x: u32, y: u32, z: u32) -> u64 {
+  assert!(x < 1 << 21 && y < 1 << 21 && z < 1 << 21);
+  spread_3(x) | spread_3(y) << 1 | spread_3(z) << 2
+}
+
+pub fn morton_decode_3d(index: u64) -> (u32, u32, u32) {
+  (compact_3(index), compact_3(index >> 1), compact_3(index >> 2))
+}
+
+// Skilling's transform from coordinates of the given bits to the "transposed" Hilbert index,
+// whose bits interleaved, first coordinate first, give the index; in place, O(bits n)
+fn axes_to_transpose(x: &mut [u32], bits: u32) {
+  let n = x.len();
+  let top = 1u32 << (bits - 1);
+  // inverse undo, from the top bit down
+  let mut q = top;
+  while q > 1 {
+    let p = q - 1;
+    for i in 0..n {
+      if x[i] & q != 0 {
+        x[0] ^= p;
+      } else {
+        let t = (x[0] ^ x[i]) & p;
+        x[0] ^= t;
+        x[i] ^= t;
+      }
+    }
+    q >>= 1;
+  }
+  // Gray encode
+  for i in 1..n {
+    x[i] ^= x[i - 1];
+  }
+  let mut t = 0;
+  let mut q = top;
+  while q > 1 {
+    if x[n - 1] & q != 0 {
+      t ^= q - 1;
+    }
+    q >>= 1;
+  }
+  for c in x.iter_mut() {
+    *c ^= t;
+  }
+}
+
+fn transpose_to_axes(x: &mut [u32], bits: u32) {
+  let n = x.len();
+  // Gray decode
+  let t = x[n - 1] >> 1;
+  for i in (1..n).rev() {
+    x[i] ^= x[i - 1];
+  }
+  x[0] ^= t;
+  // undo excess work, from the bottom bit up
+  let mut q = 2u64;
+  while q != 1 << bits {
+    let (qq, p) = (q as u32, q as u32 - 1);
+    for i in (0..n).rev() {
+      if x[i] & qq != 0 {
+        x[0] ^= p;
+      } else {
+        let t = (x[0] ^ x[i]) & p;
+        x[0] ^= t;
+        x[i] ^= t;
+      }
+    }
+    q <<= 1;
+  }
+}
+
+fn interleave(x: &[u32], bits: u32) -> u64 {
+  let mut index = 0;
+  for b in (0..bits).rev() {
+    for &c in x {
+      index = index << 1 | (c >> b & 1) as u64;
+    }
+  }
+  index
+}
+
+fn deinterleave(index: u64, x: &mut [u32], bits: u32) {
+  let n = x.len() as u32;
+  for (i, c) in x.iter_mut().enumerate() {
+    *c = 0;
+    for b in (0..bits).rev() {
+      *c = *c << 1 | (index >> (b * n + n - 1 - i as u32) & 1) as u32;
+    }
+  }
+}
+
+// the position of (x, y) along the Hilbert curve filling the 2^bits by 2^bits grid, for bits in
+// 1..=32; the curve starts at (0, 0) and ends at (2^bits - 1, 0)
+pub fn hilbert_encode_2d(bits: u32, x: u32, y: u32) -> u64 {
+  assert!((1..=32).contains(&bits) && (bits == 32 || (x | y) >> bits == 0));
+  let mut axes = [x, y];
+  axes_to_transpose(&mut axes, bits);
+  interleave(&axes, bits)
+}
+
+pub fn hilbert_decode_2d(bits: u32, index: u64) -> (u32, u32) {
+  assert!((1..=32).contains(&bits));
+  let mut axes = [0; 2];
+  deinterleave(index, &mut axes, bits);
+  transpose_to_axes(&mut axes, bits);
+  (axes[0], axes[1])
+}
+
+// the same over the 2^bits cube, for bits in 1..=21
+pub fn hilbert_encode_3d(bits: u32, x: u32, y: u32, z: u32) -> u64 {
+  assert!((1..=21).contains(&bits) && (x | y | z) >> bits == 0);
+  let mut axes = [x, y, z];
+  axes_to_transpose(&mut axes, bits);
+  interleave(&axes, bits)
+}
+
+pub fn hilbert_decode_3d(bits: u32, index: u64) -> (u32, u32, u32) {
+  assert!((1..=21).contains(&bits));
+  let mut axes = [0; 3];
+  deinterleave(index, &mut axes, bits);
+  transpose_to_axes(&mut axes, bits);
+  (axes[0], axes[1], axes[2])
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_morton() {
+    assert_eq!(39, morton_encode_2d(3, 5));
+    assert_eq!((3, 5), morton_decode_2d(39));
+    assert_eq!(u64::MAX, morton_encode_2d(u32::MAX, u32::MAX));
+    assert_eq!(0b100_010_001, morton_encode_3d(1, 2, 4));
+    assert_eq!((1, 2, 4), morton_decode_3d(0b100_010_001));
+    let mut rand = rand_gen(347);
+    for _ in 0..1000 {
+      let (x, y, z) = (rand(1 << 32) as u32, rand(1 << 32) as u32, rand(1 << 21) as u32);
+      let index = morton_encode_2d(x, y);
+      assert_eq!((x, y), morton_decode_2d(index));
+      // the z-order sorts by the highest bit where two points differ
+      let u = x ^ 1 << rand(32);
+      assert_eq!(index < morton_encode_2d(u, y), x < u);
+      assert_eq!((z, x >> 11, y >> 11), morton_decode_3d(morton_encode_3d(z, x >> 11, y >> 11)));
+    }
+  }
+
+  #[test]
+  fn test_hilbert_steps_to_neighbours() {
+    for bits in 1..=5 {
+      let side = 1u32 << bits;
+      let mut seen = vec![false; (side * side) as usize];
+      let mut last = None;
+      for d in 0..(side * side) as u64 {
+        let (x, y) = hilbert_decode_2d(bits, d);
+        assert_eq!(d, hilbert_encode_2d(bits, x, y));
+        assert!(!seen[(y * side + x) as usize]);
+        seen[(y * side + x) as usize] = true;
+        if let Some((a, b)) = last {
+          assert_eq!(1, x.abs_diff(a) + y.abs_diff(b));
+        }
+        last = Some((x, y));
+      }
+      assert_eq!((0, 0), hilbert_decode_2d(bits, 0));
+    }
+    for bits in 1..=3 {
+      let side = 1u32 << bits;
+      let mut last = None;
+      for d in 0..(side * side * side) as u64 {
+        let (x, y, z) = hilbert_decode_3d(bits, d);
+        assert_eq!(d, hilbert_encode_3d(bits, x, y, z));
+        if let Some((a, b, c)) = last {
+          assert_eq!(1, x.abs_diff(a) + y.abs_diff(b) + z.abs_diff(c));
+        }
+        last = Some((x, y, z));
+      }
+    }
+    let (x, y) = (0xdead_beef, 0x0bad_f00d);
+    assert_eq!((x, y), hilbert_decode_2d(32, hilbert_encode_2d(32, x, y)));
+    assert_eq!((x >> 11, y >> 11, 12345), hilbert_decode_3d(21, hilbert_encode_3d(21, x >> 11, y >> 11, 12345)));
+  }
+}
@@ -0,0 +1,89 @@
+use std::ops::Range;
+
+// Sparse table: the combination of any range in O(1) after O(n log n) preprocessing, for an
+// associative and idempotent op (op(x, x) == x) such as min, max, gcd, bitwise and or or. Level
+// j holds the combination of every run of 2^j values, and a range is covered by the two runs of
+// the largest such length starting at its start and ending at its end; they overlap, which
+// idempotence makes harmless. They are combined left one first, so op need not commute. Static:
+// for updates, or ops like sums that count the overlap twice, there is the segment tree.
+pub struct SparseTable<T, F> {
+  levels: Vec<Vec<T>>, // levels[j][i] combines i..i + 2^j
+  op: F,
+}
+
+impl<T: Clone, F: Fn(&T, &T) -> T> SparseTable<T, F> {
+  pub fn new(values: Vec<T>, op: F) -> SparseTable<T, F> {
+    let mut levels = vec![values];
+    let mut width = 1;
+    while 2 * width <= levels[0].len() {
+      let last = levels.last().unwrap();
+      let next = (0..last.len() - width).map(|i| op(&last[i], &last[i + width])).collect();
+      levels.push(next);
+      width *= 2;
+    }
+    SparseTable { levels, op }
+  }
+
+  pub fn len(&self) -> usize {
+    self.levels[0].len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.levels[0].is_empty()
+  }
+
+  // the combination of the values in a non-empty range
+  pub fn query(&self, range: Range<usize>) -> T {
+    assert!(range.start < range.end && range.end <= self.len());
+    let j = (usize::BITS - 1 - (range.end - range.start).leading_zeros()) as usize;
+    let level = &self.levels[j];
+    (self.op)(&level[range.start], &level[range.end - (1 << j)])
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_ops() {
+    let values = vec![12u32, 18, 7, 30, 6, 42, 9];
+    let mins = SparseTable::new(values.clone(), |a: &u32, b: &u32| *a.min(b));
+    assert_eq!((7, 12, 6), (mins.query(0..3), mins.query(0..1), mins.query(0..7)));
+    let gcds = SparseTable::new(values.clone(), |a: &u32, b: &u32| {
+      let (mut a, mut b) = (*a, *b);
+      while b != 0 {
+        (a, b) = (b, a % b);
+      }
+      a
+    });
+    assert_eq!((6, 1, 6), (gcds.query(0..2), gcds.query(0..3), gcds.query(3..6)));
+    let ors = SparseTable::new(values.clone(), |a: &u32, b: &u32| a | b);
+    let ands = SparseTable::new(values, |a: &u32, b: &u32| a & b);
+    assert_eq!((12 | 18 | 7, 30 & 6 & 42), (ors.query(0..3), ands.query(3..6)));
+    // the leftmost position of the minimum, by comparing (value, position)
+    let positions = SparseTable::new(vec![(3, 0), (1, 1), (4, 2), (1, 3)], |a: &(i32, usize), b: &(i32, usize)| *a.min(b));
+    assert_eq!((1, 1), positions.query(0..4));
+    assert!(SparseTable::new(Vec::<u8>::new(), |a: &u8, b: &u8| *a.max(b)).is_empty());
+  }
+
+  #[test]
+  fn test_random_against_naive() {
+    let mut rand = rand_gen(379);
+    for round in 0..100 {
+      let n = if round % 10 == 0 { 1000 } else { 1 + rand(70) as usize };
+      let values: Vec<i64> = (0..n).map(|_| rand(1000) as i64 - 500).collect();
+      let maxes = SparseTable::new(values.clone(), |a: &i64, b: &i64| *a.max(b));
+      // the span covered by a union of ranges, idempotent on tuples
+      let spans = SparseTable::new((0..n).map(|i| (i, i + 1)).collect(), |a: &(usize, usize), b: &(usize, usize)| (a.0.min(b.0), a.1.max(b.1)));
+      assert_eq!(n, maxes.len());
+      for _ in 0..100 {
+        let l = rand(n as u64) as usize;
+        let r = l + 1 + rand((n - l) as u64) as usize;
+        assert_eq!(*values[l..r].iter().max().unwrap(), maxes.query(l..r));
+        assert_eq!((l, r), spans.query(l..r));
+      }
+    }
+  }
+}
@@ -0,0 +1,292 @@
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+use super::trie::Trie;
+
+// Aho-Corasick automaton over a set of patterns. The pattern trie is stored flat, node 0 is the
+// root; fail[v] is the node of the longest proper suffix of v's path that is also in the trie, and
+// dict[v] is the nearest node on the failure chain that ends a pattern, so reporting every match
+// ending at a position costs one step per match. Empty patterns never match.
+pub struct AhoCorasick<K> {
+  goto: Vec<HashMap<K, usize>>,
+  fail: Vec<usize>,
+  dict: Vec<Option<usize>>,
+  out: Vec<Vec<usize>>, // ids of the patterns ending exactly at this node
+  patterns: Vec<Vec<K>>,
+}
+
+impl<K: Eq + Hash + Copy> AhoCorasick<K> {
+  // pattern ids are positions in the input
+  pub fn new<P: IntoIterator<Item = K>>(patterns: impl IntoIterator<Item = P>) -> AhoCorasick<K> {
+    let mut ac = AhoCorasick {
+      goto: vec![HashMap::new()],
+      fail: vec![0],
+      dict: vec![None],
+      out: vec![vec![]],
+      patterns: vec![],
+    };
+    for pattern in patterns {
+      let pattern: Vec<K> = pattern.into_iter().collect();
+      let mut v = 0;
+      for &k in &pattern {
+        v = match ac.goto[v].get(&k) {
+          Some(&next) => next,
+          None => {
+            ac.goto.push(HashMap::new());
+            ac.fail.push(0);
+            ac.dict.push(None);
+            ac.out.push(vec![]);
+            let next = ac.goto.len() - 1;
+            ac.goto[v].insert(k, next);
+            next
+          },
+        };
+      }
+      if v != 0 {
+        ac.out[v].push(ac.patterns.len());
+      }
+      ac.patterns.push(pattern);
+    }
+    ac.build_links();
+    ac
+  }
+
+  // breadth-first, so the failure target of a node is always finished before the node
+  fn build_links(&mut self) {
+    let mut queue: VecDeque<usize> = self.goto[0].values().cloned().collect();
+    while let Some(v) = queue.pop_front() {
+      let edges: Vec<(K, usize)> = self.goto[v].iter().map(|(&k, &u)| (k, u)).collect();
+      for (k, u) in edges {
+        let mut f = self.fail[v];
+        while f != 0 && !self.goto[f].contains_key(&k) {
+          f = self.fail[f];
+        }
+        let f = match self.goto[f].get(&k) {
+          Some(&g) if g != u => g,
+          _ => 0,
+        };
+        self.fail[u] = f;
+        self.dict[u] = if self.out[f].is_empty() { self.dict[f] } else { Some(f) };
+        queue.push_back(u);
+      }
+    }
+  }
+
+  fn step(&self, mut v: usize, k: &K) -> usize {
+    loop {
+      if let Some(&next) = self.goto[v].get(k) {
+        return next;
+      }
+      if v == 0 {
+        return 0;
+      }
+      v = self.fail[v];
+    }
+  }
+
+  pub fn pattern_count(&self) -> usize {
+    self.patterns.len()
+  }
+
+  pub fn pattern(&self, id: usize) -> &[K] {
+    &self.patterns[id]
+  }
+
+  // every (pattern_id, start) occurrence, overlaps included, ordered by end position and then
+  // from the longest pattern to the shortest
+  pub fn find_iter<I: IntoIterator<Item = K>>(&self, haystack: I) -> FindIter<'_, K, I::IntoIter> {
+    FindIter {
+      ac: self,
+      haystack: haystack.into_iter(),
+      state: 0,
+      pos: 0,
+      pending: None,
+    }
+  }
+
+  // a matcher for a text arriving in chunks, which need not be kept: the automaton state carries
+  // over from one chunk to the next, so matches spanning chunks are found too
+  pub fn stream(&self) -> AhoCorasickStream<'_, K> {
+    AhoCorasickStream { ac: self, state: 0, fed: 0 }
+  }
+}
+
+impl<K: Eq + Hash + Copy + Ord> AhoCorasick<K> {
+  // the keys stored in the trie become the patterns, numbered in lexicographic order
+  pub fn from_trie<U: Default + Clone>(trie: &Trie<K, U>) -> AhoCorasick<K> {
+    AhoCorasick::new(trie.iter().map(|(key, _)| key))
+  }
+}
+
+pub struct FindIter<'a, K, I> {
+  ac: &'a AhoCorasick<K>,
+  haystack: I,
+  state: usize,
+  pos: usize, // number of keys consumed
+  pending: Option<(usize, usize)>, // (node, index into its out list) still to report
+}
+
+impl<'a, K: Eq + Hash + Copy, I: Iterator<Item = K>> Iterator for FindIter<'a, K, I> {
+  type Item = (usize, usize);
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      if let Some((v, i)) = self.pending {
+        let ac = self.ac;
+        if i < ac.out[v].len() {
+          self.pending = Some((v, i + 1));
+          let id = ac.out[v][i];
+          return Some((id, self.pos - ac.patterns[id].len()));
+        }
+        self.pending = ac.dict[v].map(|u| (u, 0));
+        continue;
+      }
+      let k = self.haystack.next()?;
+      self.state = self.ac.step(self.state, &k);
+      self.pos += 1;
+      let v = self.state;
+      self.pending = if self.ac.out[v].is_empty() { self.ac.dict[v] } else { Some(v) }.map(|u| (u, 0));
+    }
+  }
+}
+
+pub struct AhoCorasickStream<'a, K> {
+  ac: &'a AhoCorasick<K>,
+  state: usize,
+  fed: usize,
+}
+
+impl<'a, K: Eq + Hash + Copy> AhoCorasickStream<'a, K> {
+  // the (pattern_id, start) occurrences ending in the chunk, in the order of find_iter, with
+  // starts counted from the first key fed since the last reset. The whole chunk is read even if
+  // the iterator is dropped early, keeping the stream in step with the text.
+  pub fn feed<'s>(&'s mut self, chunk: &'s [K]) -> StreamFeed<'a, 's, K> {
+    let find = FindIter { ac: self.ac, haystack: chunk.iter().copied(), state: self.state, pos: self.fed, pending: None };
+    StreamFeed { find, stream: self }
+  }
+
+  // keys fed since the last reset
+  pub fn position(&self) -> usize {
+    self.fed
+  }
+
+  pub fn reset(&mut self) {
+    self.state = 0;
+    self.fed = 0;
+  }
+}
+
+pub struct StreamFeed<'a, 's, K: Eq + Hash + Copy> {
+  find: FindIter<'a, K, std::iter::Copied<std::slice::Iter<'s, K>>>,
+  stream: &'s mut AhoCorasickStream<'a, K>,
+}
+
+impl<'a, 's, K: Eq + Hash + Copy> Iterator for StreamFeed<'a, 's, K> {
+  type Item = (usize, usize);
+  fn next(&mut self) -> Option<Self::Item> {
+    self.find.next()
+  }
+}
+
+impl<'a, 's, K: Eq + Hash + Copy> Drop for StreamFeed<'a, 's, K> {
+  fn drop(&mut self) {
+    let find = &mut self.find;
+    for k in &mut find.haystack {
+      find.state = find.ac.step(find.state, &k);
+      find.pos += 1;
+    }
+    self.stream.state = find.state;
+    self.stream.fed = find.pos;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  fn brute_force(patterns: &[&str], text: &str) -> Vec<(usize, usize)> {
+    let text = text.as_bytes();
+    let mut found = vec![];
+    for end in 1..=text.len() {
+      found.extend(patterns.iter().enumerate()
+        .filter(|(_, p)| !p.is_empty() && text[..end].ends_with(p.as_bytes()))
+        .map(|(id, p)| (id, end - p.len())));
+    }
+    found
+  }
+
+  #[test]
+  fn test_classic() {
+    let patterns = ["he", "she", "his", "hers"];
+    let ac = AhoCorasick::new(patterns.iter().map(|p| p.chars()));
+    let found: Vec<_> = ac.find_iter("ushers".chars()).collect();
+    assert_eq!(vec![(1, 1), (0, 2), (3, 2)], found);
+    assert_eq!(&['h', 'i', 's'], ac.pattern(2));
+    assert_eq!(4, ac.pattern_count());
+  }
+
+  #[test]
+  fn test_random_against_brute_force() {
+    let mut rand = rand_gen(97);
+    for _ in 0..300 {
+      let gen = |len: u64, rand: &mut dyn FnMut(u64) -> u64| -> String {
+        (0..len).map(|_| (b'a' + rand(3) as u8) as char).collect()
+      };
+      let count = rand(6);
+      let patterns: Vec<String> = (0..count).map(|_| { let len = rand(5); gen(len, &mut rand) }).collect();
+      let len = rand(40);
+      let text = gen(len, &mut rand);
+      let refs: Vec<&str> = patterns.iter().map(|p| p.as_str()).collect();
+      let ac = AhoCorasick::new(patterns.iter().map(|p| p.bytes()));
+      // duplicate patterns share a node, so compare as sets
+      let mut found: Vec<_> = ac.find_iter(text.bytes()).collect();
+      let mut expected = brute_force(&refs, &text);
+      let key = |&(id, start): &(usize, usize)| (start + patterns[id].len(), start, id);
+      found.sort_by_key(key);
+      expected.sort_by_key(key);
+      assert_eq!(expected, found);
+    }
+  }
+
+  #[test]
+  fn test_from_trie() {
+    let mut trie: Trie<char, bool> = Trie::new();
+    for w in ["ab", "b", "bca", "c"].iter() {
+      trie.insert(w.chars(), true);
+    }
+    let ac = AhoCorasick::from_trie(&trie);
+    let found: Vec<String> = ac.find_iter("abca".chars())
+      .map(|(id, start)| format!("{}@{}", ac.pattern(id).iter().collect::<String>(), start))
+      .collect();
+    assert_eq!(vec!["ab@0", "b@1", "c@2", "bca@1"], found);
+  }
+
+  #[test]
+  fn test_stream() {
+    let ac = AhoCorasick::new(["he", "she", "his", "hers"].iter().map(|p| p.bytes()));
+    let mut stream = ac.stream();
+    assert!(stream.feed(b"ush").next().is_none());
+    assert_eq!(vec![(1, 1), (0, 2), (3, 2)], stream.feed(b"ers").collect::<Vec<_>>());
+    assert_eq!(6, stream.position());
+    // dropped before the end, and still in step
+    assert_eq!(Some((1, 6)), stream.feed(b"shehi").next());
+    assert_eq!(vec![(2, 9)], stream.feed(b"s").collect::<Vec<_>>());
+    stream.reset();
+    assert_eq!(vec![(0, 0)], stream.feed(b"he").collect::<Vec<_>>());
+    let mut rand = rand_gen(313);
+    for _ in 0..200 {
+      let patterns: Vec<Vec<u8>> = (0..1 + rand(5)).map(|_| (0..1 + rand(4)).map(|_| rand(3) as u8).collect()).collect();
+      let text: Vec<u8> = (0..rand(200)).map(|_| rand(3) as u8).collect();
+      let ac = AhoCorasick::new(patterns.iter().map(|p| p.iter().copied()));
+      let expected: Vec<_> = ac.find_iter(text.iter().copied()).collect();
+      let mut stream = ac.stream();
+      let (mut found, mut at) = (vec![], 0);
+      while at < text.len() {
+        let end = (at + rand(20) as usize).min(text.len());
+        found.extend(stream.feed(&text[at..end]));
+        at = end;
+      }
+      assert_eq!(expected, found);
+    }
+  }
+}
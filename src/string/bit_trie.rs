@@ -0,0 +1,185 @@
+// Binary trie over the low `bits` bits of u64 values, most significant bit first. Holds a
+// multiset; every node counts the values below it, so a node whose count dropped to 0 after
+// removals reads as absent and is reused by the next insert through it. Queries walk one node
+// per bit, O(bits).
+pub struct BitTrie {
+  bits: u32,
+  child: Vec<[u32; 2]>, // 0 means no child, node 0 is the root
+  count: Vec<usize>,
+}
+
+impl BitTrie {
+  pub fn new(bits: u32) -> BitTrie {
+    assert!((1..=64).contains(&bits));
+    BitTrie { bits, child: vec![[0, 0]], count: vec![0] }
+  }
+
+  pub fn len(&self) -> usize {
+    self.count[0]
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.count[0] == 0
+  }
+
+  fn bit(&self, x: u64, level: u32) -> usize {
+    (x >> (self.bits - 1 - level) & 1) as usize
+  }
+
+  // the child on bit b, if it holds any value
+  fn live(&self, node: u32, b: usize) -> Option<u32> {
+    let c = self.child[node as usize][b];
+    if c != 0 && self.count[c as usize] > 0 { Some(c) } else { None }
+  }
+
+  pub fn insert(&mut self, x: u64) {
+    assert!(self.bits == 64 || x >> self.bits == 0);
+    let mut node = 0;
+    self.count[0] += 1;
+    for level in 0..self.bits {
+      let b = self.bit(x, level);
+      if self.child[node][b] == 0 {
+        self.child.push([0, 0]);
+        self.count.push(0);
+        self.child[node][b] = (self.child.len() - 1) as u32;
+      }
+      node = self.child[node][b] as usize;
+      self.count[node] += 1;
+    }
+  }
+
+  // how many times x is stored
+  pub fn count(&self, x: u64) -> usize {
+    let mut node = 0;
+    for level in 0..self.bits {
+      match self.live(node, self.bit(x, level)) {
+        Some(c) => node = c,
+        None => return 0,
+      }
+    }
+    self.count[node as usize]
+  }
+
+  pub fn contains(&self, x: u64) -> bool {
+    self.count(x) > 0
+  }
+
+  // removes one copy of x, false if there was none
+  pub fn remove(&mut self, x: u64) -> bool {
+    if !self.contains(x) {
+      return false;
+    }
+    let mut node = 0;
+    self.count[0] -= 1;
+    for level in 0..self.bits {
+      node = self.child[node][self.bit(x, level)] as usize;
+      self.count[node] -= 1;
+    }
+    true
+  }
+
+  // the largest x ^ y over stored x, None when empty: at each bit, go against y's bit if possible
+  pub fn max_xor_with(&self, y: u64) -> Option<u64> {
+    self.extreme_xor(y, true)
+  }
+
+  pub fn min_xor_with(&self, y: u64) -> Option<u64> {
+    self.extreme_xor(y, false)
+  }
+
+  fn extreme_xor(&self, y: u64, max: bool) -> Option<u64> {
+    if self.is_empty() {
+      return None;
+    }
+    let mut node = 0;
+    let mut result = 0;
+    for level in 0..self.bits {
+      let preferred = self.bit(y, level) ^ max as usize;
+      result <<= 1;
+      node = match self.live(node, preferred) {
+        Some(c) => {
+          result |= max as u64;
+          c
+        },
+        None => {
+          result |= !max as u64;
+          self.live(node, preferred ^ 1).unwrap()
+        },
+      };
+    }
+    Some(result)
+  }
+
+  // how many stored x (with multiplicity) have x ^ y < limit. Where limit has a 1 bit, the
+  // whole subtree matching y on that bit is below limit; the walk follows the other side.
+  pub fn count_less_than_xor(&self, y: u64, limit: u64) -> usize {
+    if self.bits < 64 && limit >> self.bits != 0 {
+      return self.len(); // every xor fits in bits, so it is below limit
+    }
+    let mut total = 0;
+    let mut node = 0;
+    for level in 0..self.bits {
+      let yb = self.bit(y, level);
+      let next = if self.bit(limit, level) == 1 {
+        if let Some(c) = self.live(node, yb) {
+          total += self.count[c as usize];
+        }
+        self.live(node, yb ^ 1)
+      } else {
+        self.live(node, yb)
+      };
+      match next {
+        Some(c) => node = c,
+        None => return total,
+      }
+    }
+    total
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_random_against_brute_force() {
+    let mut rand = rand_gen(67);
+    for bits in [1, 3, 6, 10] {
+      let mut trie = BitTrie::new(bits);
+      let mut model: Vec<u64> = vec![];
+      for _ in 0..400 {
+        let x = rand(1 << bits);
+        if rand(3) == 0 {
+          let at = model.iter().position(|&v| v == x);
+          if let Some(i) = at {
+            model.swap_remove(i);
+          }
+          assert_eq!(at.is_some(), trie.remove(x));
+        } else {
+          model.push(x);
+          trie.insert(x);
+        }
+        assert_eq!(model.len(), trie.len());
+        let y = rand(1 << bits);
+        assert_eq!(model.iter().map(|&v| v ^ y).max(), trie.max_xor_with(y));
+        assert_eq!(model.iter().map(|&v| v ^ y).min(), trie.min_xor_with(y));
+        let limit = rand(2 << bits);
+        assert_eq!(model.iter().filter(|&&v| v ^ y < limit).count(), trie.count_less_than_xor(y, limit));
+        assert_eq!(model.iter().filter(|&&v| v == y).count(), trie.count(y));
+      }
+    }
+  }
+
+  #[test]
+  fn test_full_width() {
+    let mut trie = BitTrie::new(64);
+    trie.insert(u64::MAX);
+    trie.insert(0);
+    assert_eq!(Some(u64::MAX), trie.max_xor_with(0));
+    assert_eq!(Some(u64::MAX ^ 5), trie.max_xor_with(5));
+    assert_eq!(1, trie.count_less_than_xor(0, u64::MAX));
+    assert!(trie.remove(0) && !trie.remove(0));
+    assert_eq!(Some(0), trie.min_xor_with(u64::MAX));
+  }
+}
@@ -0,0 +1,109 @@
+// Bitap (Baeza-Yates & Gonnet's Shift-And, with Wu & Manber's extension to mismatches): the
+// state is a bit vector whose bit j says pattern[..=j] matches the text read so far ending here,
+// advanced per key by one shift, one or and one and with the key's mask of positions in the
+// pattern. Allowing up to k mismatches keeps k + 1 such vectors, vector d for at most d
+// mismatches, which also takes a shifted vector d - 1 without the mask. Patterns of up to 64
+// keys fit one word per vector; longer ones span several, the shift carrying between them.
+// O(n (k + 1) m / 64), reading the text as it comes. As in Kmp, the empty pattern matches
+// nowhere.
+pub struct Bitap {
+  pattern: Vec<u8>,
+  words: usize, // per vector
+  masks: Vec<u64>, // masks[b * words..][..words]: the positions of byte b in the pattern
+}
+
+impl Bitap {
+  pub fn new(pattern: &[u8]) -> Bitap {
+    let words = pattern.len().div_ceil(64).max(1);
+    let mut masks = vec![0; 256 * words];
+    for (j, &b) in pattern.iter().enumerate() {
+      masks[b as usize * words + j / 64] |= 1 << (j % 64);
+    }
+    Bitap { pattern: pattern.to_vec(), words, masks }
+  }
+
+  pub fn pattern(&self) -> &[u8] {
+    &self.pattern
+  }
+
+  // start of every exact occurrence in haystack, overlaps included, in increasing order
+  pub fn find_iter<'a, I: IntoIterator<Item = u8> + 'a>(&'a self, haystack: I) -> impl Iterator<Item = usize> + 'a {
+    self.find_with_mismatches(haystack, 0).map(|(start, _)| start)
+  }
+
+  // start of every window of haystack differing from the pattern in at most k places, with the
+  // number of places, in increasing order
+  pub fn find_with_mismatches<'a, I: IntoIterator<Item = u8> + 'a>(&'a self, haystack: I, k: usize) -> impl Iterator<Item = (usize, usize)> + 'a {
+    let (m, w) = (self.pattern.len(), self.words);
+    let k = k.min(m);
+    let top = 1 << ((m + 63) % 64); // the bit of a whole match, in the last word
+    let mut state = vec![0u64; (k + 1) * w];
+    let mut below = vec![0u64; w];
+    let mut haystack = haystack.into_iter().enumerate();
+    std::iter::from_fn(move || {
+      if m == 0 {
+        return None;
+      }
+      for (i, b) in haystack.by_ref() {
+        let mask = &self.masks[b as usize * w..][..w];
+        for d in 0..=k {
+          let vector = &mut state[d * w..][..w];
+          let mut carry = 1;
+          for (word, x) in vector.iter_mut().enumerate() {
+            let shifted = *x << 1 | carry;
+            carry = *x >> 63;
+            // below: vector d - 1 as it was, shifted, matching with a mismatch at this key
+            let mismatched = if d > 0 { below[word] } else { 0 };
+            below[word] = shifted;
+            *x = shifted & mask[word] | mismatched;
+          }
+        }
+        if let Some(d) = (0..=k).find(|&d| state[d * w + w - 1] & top != 0) {
+          return Some((i + 1 - m, d));
+        }
+      }
+      None
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_examples() {
+    let bitap = Bitap::new(b"abab");
+    assert_eq!(vec![0, 2, 7], bitap.find_iter(b"ababab_abab".iter().copied()).collect::<Vec<_>>());
+    let cut = Bitap::new(b"cut");
+    assert_eq!(vec![(0, 1), (4, 0), (8, 1)], cut.find_with_mismatches(b"cat cut cot".iter().copied(), 1).collect::<Vec<_>>());
+    assert_eq!(vec![4], cut.find_iter(b"cat cut cot".iter().copied()).collect::<Vec<_>>());
+    assert_eq!(0, Bitap::new(b"").find_iter(b"abc".iter().copied()).count());
+    assert_eq!(0, Bitap::new(b"abcd").find_with_mismatches(b"abc".iter().copied(), 4).count());
+  }
+
+  #[test]
+  fn test_random_against_naive() {
+    let mut rand = rand_gen(229);
+    for round in 0..300 {
+      let sigma = 1 + rand(3);
+      // past one word now and then
+      let m = if round % 5 == 0 { 60 + rand(100) } else { 1 + rand(8) };
+      let pattern: Vec<u8> = (0..m).map(|_| b'a' + rand(sigma) as u8).collect();
+      let text: Vec<u8> = (0..rand(400)).map(|_| b'a' + rand(sigma) as u8).collect();
+      let k = rand(m / 4 + 2) as usize;
+      let bitap = Bitap::new(&pattern);
+      let mut expected = vec![];
+      for start in 0..(text.len() + 1).saturating_sub(pattern.len()) {
+        let d = pattern.iter().zip(&text[start..]).filter(|(x, y)| x != y).count();
+        if d <= k {
+          expected.push((start, d));
+        }
+      }
+      assert_eq!(expected, bitap.find_with_mismatches(text.iter().copied(), k).collect::<Vec<_>>());
+      let exact: Vec<usize> = expected.iter().filter(|&&(_, d)| d == 0).map(|&(start, _)| start).collect();
+      assert_eq!(exact, bitap.find_iter(text.iter().copied()).collect::<Vec<_>>());
+    }
+  }
+}
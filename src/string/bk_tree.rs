@@ -0,0 +1,126 @@
+use super::edit_distance::levenshtein;
+
+struct BkNode<K> {
+  word: Vec<K>,
+  children: Vec<(usize, usize)>, // (distance from this word, node)
+}
+
+// Burkhard-Keller tree: a dictionary for fuzzy lookup under the Levenshtein distance. A word
+// hangs below the first node it meets at a distance no other child of that node has, so a
+// subtree under edge d holds only words at distance d from its parent. A query within k of the node's word at distance e can only find words under edges in
+// e - k..=e + k, by the triangle inequality, and the rest of the tree is skipped. Simpler than
+// searching a trie with a Levenshtein automaton; good for moderate dictionaries and small k.
+pub struct BkTree<K> {
+  nodes: Vec<BkNode<K>>, // node 0 is the root, the first word inserted
+}
+
+impl<K: Eq + Clone> BkTree<K> {
+  pub fn new() -> BkTree<K> {
+    BkTree { nodes: vec![] }
+  }
+
+  pub fn len(&self) -> usize {
+    self.nodes.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.nodes.is_empty()
+  }
+
+  // false if the word was already there; a path of O(depth) distance computations
+  pub fn insert(&mut self, word: &[K]) -> bool {
+    if self.nodes.is_empty() {
+      self.nodes.push(BkNode { word: word.to_vec(), children: vec![] });
+      return true;
+    }
+    let mut v = 0;
+    loop {
+      let d = levenshtein(&self.nodes[v].word, word);
+      if d == 0 {
+        return false;
+      }
+      match self.nodes[v].children.iter().find(|&&(e, _)| e == d) {
+        Some(&(_, child)) => v = child,
+        None => {
+          let child = self.nodes.len();
+          self.nodes.push(BkNode { word: word.to_vec(), children: vec![] });
+          self.nodes[v].children.push((d, child));
+          return true;
+        },
+      }
+    }
+  }
+
+  pub fn contains(&self, word: &[K]) -> bool {
+    !self.find_within(word, 0).is_empty()
+  }
+
+  // every word within distance k of query, with its distance, nearest first and then in order
+  // of insertion
+  pub fn find_within(&self, query: &[K], k: usize) -> Vec<(&[K], usize)> {
+    let mut found = vec![];
+    let mut stack = if self.nodes.is_empty() { vec![] } else { vec![0] };
+    while let Some(v) = stack.pop() {
+      let node = &self.nodes[v];
+      let d = levenshtein(&node.word, query);
+      if d <= k {
+        found.push((d, v));
+      }
+      stack.extend(node.children.iter().filter(|&&(e, _)| d.abs_diff(e) <= k).map(|&(_, child)| child));
+    }
+    found.sort_unstable();
+    found.into_iter().map(|(d, v)| (&self.nodes[v].word[..], d)).collect()
+  }
+}
+
+impl<K: Eq + Clone> Default for BkTree<K> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_dictionary() {
+    let mut tree = BkTree::new();
+    for w in ["book", "books", "cake", "boo", "boon", "cook", "cape", "cart"] {
+      assert!(tree.insert(w.as_bytes()));
+    }
+    assert!(!tree.insert(b"cake"));
+    assert_eq!(8, tree.len());
+    let found: Vec<(&[u8], usize)> = tree.find_within(b"bool", 1);
+    assert_eq!(vec![(&b"book"[..], 1), (b"boo", 1), (b"boon", 1)], found);
+    assert_eq!(vec![(&b"cake"[..], 0), (b"cape", 1)], tree.find_within(b"cake", 1));
+    assert!(tree.contains(b"cart") && !tree.contains(b"car"));
+    assert!(BkTree::<u8>::new().find_within(b"a", 3).is_empty());
+  }
+
+  #[test]
+  fn test_random_against_brute_force() {
+    let mut rand = rand_gen(317);
+    for _ in 0..50 {
+      let mut tree = BkTree::new();
+      let mut words: Vec<Vec<u8>> = vec![];
+      for _ in 0..rand(300) {
+        let word: Vec<u8> = (0..rand(8)).map(|_| b'a' + rand(3) as u8).collect();
+        assert_eq!(!words.contains(&word), tree.insert(&word));
+        if !words.contains(&word) {
+          words.push(word);
+        }
+      }
+      assert_eq!(words.len(), tree.len());
+      for _ in 0..20 {
+        let query: Vec<u8> = (0..rand(8)).map(|_| b'a' + rand(3) as u8).collect();
+        let k = rand(4) as usize;
+        let mut expected: Vec<(usize, usize)> = words.iter().enumerate().map(|(i, w)| (levenshtein(w, &query), i)).filter(|&(d, _)| d <= k).collect();
+        expected.sort_unstable();
+        let expected: Vec<(&[u8], usize)> = expected.into_iter().map(|(d, i)| (&words[i][..], d)).collect();
+        assert_eq!(expected, tree.find_within(&query, k));
+      }
+    }
+  }
+}
@@ -0,0 +1,172 @@
+// Byte searchers comparing the pattern right to left against a window of the haystack, which
+// lets a mismatch move the window by up to the pattern's length: sublinear on average for long
+// patterns, where Kmp reads every byte. As in Kmp, the empty pattern matches nowhere.
+
+// bad-character table: how far the window may move when its last byte is b, the distance from
+// the last occurrence of b in pattern[..m - 1] to the end, or m
+fn bad_char_shifts(pattern: &[u8]) -> [usize; 256] {
+  let m = pattern.len();
+  let mut shift = [m; 256];
+  for (i, &b) in pattern[..m.saturating_sub(1)].iter().enumerate() {
+    shift[b as usize] = m - 1 - i;
+  }
+  shift
+}
+
+// Horspool's simplification: on any outcome, shift by the bad-character rule for the byte under
+// the window's last position. O(n / m) on random text, O(nm) in the worst case.
+pub struct Horspool {
+  pattern: Vec<u8>,
+  shift: [usize; 256],
+}
+
+impl Horspool {
+  pub fn new(pattern: &[u8]) -> Horspool {
+    Horspool { pattern: pattern.to_vec(), shift: bad_char_shifts(pattern) }
+  }
+
+  pub fn pattern(&self) -> &[u8] {
+    &self.pattern
+  }
+
+  // start of every occurrence in haystack, overlaps included, in increasing order
+  pub fn find_iter<'a>(&'a self, haystack: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+    let m = self.pattern.len();
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+      if m == 0 {
+        return None;
+      }
+      while pos + m <= haystack.len() {
+        let window = &haystack[pos..pos + m];
+        let at = pos;
+        pos += self.shift[window[m - 1] as usize];
+        if window[m - 1] == self.pattern[m - 1] && window[..m - 1] == self.pattern[..m - 1] {
+          return Some(at);
+        }
+      }
+      None
+    })
+  }
+}
+
+// Boyer-Moore with both the bad-character and the good-suffix rule: after matching a suffix of
+// the pattern, the window moves to the next place that suffix (or a prefix of the pattern that
+// is a suffix of it) could line up again. With the good-suffix rule alone, finding the first
+// occurrence is O(n + m); reporting all of them can degrade to O(nm) on periodic patterns.
+pub struct BoyerMoore {
+  pattern: Vec<u8>,
+  bad_char: [usize; 256],
+  good_suffix: Vec<usize>, // shift on a mismatch at position i after matching pattern[i + 1..]
+}
+
+impl BoyerMoore {
+  pub fn new(pattern: &[u8]) -> BoyerMoore {
+    let m = pattern.len();
+    // suffix[i]: the longest common suffix of pattern[..=i] and pattern, the Z-function of the
+    // reversed pattern
+    let rev: Vec<u8> = pattern.iter().rev().copied().collect();
+    let mut z = vec![m; m];
+    let (mut l, mut r) = (0, 0);
+    for k in 1..m {
+      z[k] = if k < r { z[k - l].min(r - k) } else { 0 };
+      while k + z[k] < m && rev[z[k]] == rev[k + z[k]] {
+        z[k] += 1;
+      }
+      if k + z[k] > r {
+        l = k;
+        r = k + z[k];
+      }
+    }
+    let suffix: Vec<usize> = (0..m).map(|i| z[m - 1 - i]).collect();
+    let mut good_suffix = vec![m; m];
+    // a prefix that is also a suffix, for mismatches left of where it would line up
+    let mut j = 0;
+    for i in (0..m).rev() {
+      if suffix[i] == i + 1 {
+        while j < m - 1 - i {
+          if good_suffix[j] == m {
+            good_suffix[j] = m - 1 - i;
+          }
+          j += 1;
+        }
+      }
+    }
+    // the rightmost other occurrence of the matched suffix, preceded by a different byte
+    for i in 0..m.saturating_sub(1) {
+      good_suffix[m - 1 - suffix[i]] = m - 1 - i;
+    }
+    BoyerMoore { pattern: pattern.to_vec(), bad_char: bad_char_shifts(pattern), good_suffix }
+  }
+
+  pub fn pattern(&self) -> &[u8] {
+    &self.pattern
+  }
+
+  // start of every occurrence in haystack, overlaps included, in increasing order
+  pub fn find_iter<'a>(&'a self, haystack: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+    let m = self.pattern.len();
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+      if m == 0 {
+        return None;
+      }
+      while pos + m <= haystack.len() {
+        let window = &haystack[pos..pos + m];
+        match (0..m).rev().find(|&i| window[i] != self.pattern[i]) {
+          None => {
+            let at = pos;
+            pos += self.good_suffix[0];
+            return Some(at);
+          },
+          Some(i) => {
+            // the bad-character table counts from the window's end
+            let bad = self.bad_char[window[i] as usize] as isize - (m - 1 - i) as isize;
+            pos += self.good_suffix[i].max(bad.max(1) as usize);
+          },
+        }
+      }
+      None
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  fn naive(pattern: &[u8], haystack: &[u8]) -> Vec<usize> {
+    if pattern.is_empty() || pattern.len() > haystack.len() {
+      return vec![];
+    }
+    (0..=haystack.len() - pattern.len()).filter(|&i| haystack[i..].starts_with(pattern)).collect()
+  }
+
+  #[test]
+  fn test_examples() {
+    let haystack = b"HERE IS A SIMPLE EXAMPLE, AN EXAMPLE OF EXAMPLES";
+    assert_eq!(vec![17, 29, 40], Horspool::new(b"EXAMPLE").find_iter(haystack).collect::<Vec<_>>());
+    assert_eq!(vec![17, 29, 40], BoyerMoore::new(b"EXAMPLE").find_iter(haystack).collect::<Vec<_>>());
+    assert_eq!(vec![0, 1, 2], BoyerMoore::new(b"aa").find_iter(b"aaaa").collect::<Vec<_>>());
+    assert_eq!(vec![0, 2], Horspool::new(b"aba").find_iter(b"ababa").collect::<Vec<_>>());
+    assert_eq!(0, BoyerMoore::new(b"").find_iter(b"abc").count());
+    assert_eq!(0, Horspool::new(b"abcd").find_iter(b"abc").count());
+    // the good-suffix rule alone moves past "ab" in "cabdab": ab matched, c mismatched
+    let bm = BoyerMoore::new(b"cabdab");
+    assert_eq!(&bm.good_suffix, &[6, 6, 6, 3, 6, 1]);
+  }
+
+  #[test]
+  fn test_random_against_naive() {
+    let mut rand = rand_gen(157);
+    for _ in 0..500 {
+      let sigma = 1 + rand(4);
+      let haystack: Vec<u8> = (0..rand(200)).map(|_| b'a' + rand(sigma) as u8).collect();
+      let pattern: Vec<u8> = (0..rand(8)).map(|_| b'a' + rand(sigma) as u8).collect();
+      let expected = naive(&pattern, &haystack);
+      assert_eq!(expected, Horspool::new(&pattern).find_iter(&haystack).collect::<Vec<_>>());
+      assert_eq!(expected, BoyerMoore::new(&pattern).find_iter(&haystack).collect::<Vec<_>>());
+    }
+  }
+}
@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::trie::Trie;
+
+const NONE: usize = usize::MAX;
+
+// Static trie in the classic double-array layout (Aoe). Keys are numbered 1..=alphabet, and
+// state s moves on key code c to t = base[s] + c if check[t] == s. A lookup is a code lookup
+// plus two array reads per key, and the whole structure is three flat arrays. State 0 is the
+// root. Built once from a finished Trie, it does not support updates.
+pub struct DoubleArrayTrie<K, U> {
+  code: HashMap<K, usize>,
+  base: Vec<usize>,
+  check: Vec<usize>, // parent of each slot, NONE for free slots
+  value: Vec<usize>, // index into values, NONE if no key ends at the state
+  values: Vec<U>,
+}
+
+impl<K: Eq + Hash + Copy + Ord, U: Default + Clone> DoubleArrayTrie<K, U> {
+  pub fn build(trie: &Trie<K, U>) -> DoubleArrayTrie<K, U> {
+    let entries: Vec<(Vec<K>, U)> = trie.iter().collect(); // sorted, so subtrees are ranges
+    let mut alphabet: Vec<K> = entries.iter().flat_map(|(k, _)| k.iter().copied()).collect();
+    alphabet.sort_unstable();
+    alphabet.dedup();
+    let mut dat = DoubleArrayTrie {
+      code: alphabet.into_iter().enumerate().map(|(i, k)| (k, i + 1)).collect(),
+      base: vec![0],
+      check: vec![0], // the root is taken
+      value: vec![NONE],
+      values: vec![],
+    };
+    let mut first_free = 1;
+    // (state, range of entries below it, depth)
+    let mut stack = vec![(0, 0, entries.len(), 0)];
+    while let Some((state, mut lo, hi, depth)) = stack.pop() {
+      if lo < hi && entries[lo].0.len() == depth {
+        dat.value[state] = dat.values.len();
+        dat.values.push(entries[lo].1.clone());
+        lo += 1;
+      }
+      let mut children = vec![]; // (code, lo, hi)
+      while lo < hi {
+        let k = entries[lo].0[depth];
+        let mut end = lo + 1;
+        while end < hi && entries[end].0[depth] == k {
+          end += 1;
+        }
+        children.push((dat.code[&k], lo, end));
+        lo = end;
+      }
+      if children.is_empty() {
+        continue;
+      }
+      // the smallest base placing every child on a free slot
+      let min_code = children[0].0;
+      let mut base = first_free.max(min_code + 1) - min_code;
+      while children.iter().any(|&(c, _, _)| dat.check.get(base + c).is_some_and(|&p| p != NONE)) {
+        base += 1;
+      }
+      dat.base[state] = base;
+      for &(c, lo, hi) in &children {
+        let t = base + c;
+        if t >= dat.check.len() {
+          dat.check.resize(t + 1, NONE);
+          dat.base.resize(t + 1, 0);
+          dat.value.resize(t + 1, NONE);
+        }
+        dat.check[t] = state;
+        stack.push((t, lo, hi, depth + 1));
+      }
+      while first_free < dat.check.len() && dat.check[first_free] != NONE {
+        first_free += 1;
+      }
+    }
+    dat
+  }
+
+  fn step(&self, state: usize, k: &K) -> Option<usize> {
+    let t = self.base[state] + self.code.get(k)?;
+    if t < self.check.len() && self.check[t] == state { Some(t) } else { None }
+  }
+
+  fn value_at(&self, state: usize) -> Option<U> {
+    match self.value[state] {
+      NONE => None,
+      i => Some(self.values[i].clone()),
+    }
+  }
+
+  // the value stored under exactly this key
+  pub fn get(&self, path: impl Iterator<Item = K>) -> Option<U> {
+    let mut state = 0;
+    for k in path {
+      state = self.step(state, &k)?;
+    }
+    self.value_at(state)
+  }
+
+  // every stored key that is a prefix of the input, as (length, value) by increasing length
+  pub fn common_prefix_search(&self, path: impl Iterator<Item = K>) -> Vec<(usize, U)> {
+    let mut found: Vec<(usize, U)> = self.value_at(0).map(|v| (0, v)).into_iter().collect();
+    let mut state = 0;
+    for (i, k) in path.enumerate() {
+      state = match self.step(state, &k) {
+        Some(t) => t,
+        None => break,
+      };
+      if let Some(v) = self.value_at(state) {
+        found.push((i + 1, v));
+      }
+    }
+    found
+  }
+
+  pub fn len(&self) -> usize {
+    self.values.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.values.is_empty()
+  }
+
+  // number of slots in the arrays, used or not
+  pub fn capacity(&self) -> usize {
+    self.check.len()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_dictionary() {
+    let mut trie: Trie<char, usize> = Trie::new();
+    let words = ["a", "ab", "abc", "abd", "b", "bcd", "", "東京", "東京都"];
+    for (i, w) in words.iter().enumerate() {
+      trie.insert(w.chars(), i);
+    }
+    let dat = DoubleArrayTrie::build(&trie);
+    assert_eq!(words.len(), dat.len());
+    for (i, w) in words.iter().enumerate() {
+      assert_eq!(Some(i), dat.get(w.chars()));
+    }
+    for w in ["abcd", "bc", "c", "東"] {
+      assert_eq!(None, dat.get(w.chars()));
+    }
+    assert_eq!(vec![(0, 6), (1, 0), (2, 1), (3, 2)], dat.common_prefix_search("abcz".chars()));
+    assert_eq!(vec![(0, 6), (2, 7), (3, 8)], dat.common_prefix_search("東京都庁".chars()));
+    assert_eq!(vec![(0, 6)], dat.common_prefix_search("x".chars()));
+
+    let empty: DoubleArrayTrie<char, usize> = DoubleArrayTrie::build(&Trie::new());
+    assert!(empty.is_empty());
+    assert_eq!(None, empty.get("".chars()));
+  }
+
+  #[test]
+  fn test_random_against_trie() {
+    let mut rand = rand_gen(23);
+    for _ in 0..50 {
+      let mut trie: Trie<u8, u32> = Trie::new();
+      let count = rand(200);
+      for i in 0..count {
+        let key: Vec<u8> = (0..rand(8)).map(|_| rand(6) as u8 * 7).collect();
+        trie.insert(key.into_iter(), i as u32);
+      }
+      let dat = DoubleArrayTrie::build(&trie);
+      let entries: Vec<(Vec<u8>, u32)> = trie.iter().collect();
+      assert_eq!(entries.len(), dat.len());
+      // one node per distinct prefix fits in a small multiple of that many slots
+      let nodes: usize = 1 + entries.iter().map(|(k, _)| k.len()).sum::<usize>();
+      assert!(dat.capacity() <= 2 * nodes + 50);
+      for _ in 0..100 {
+        let query: Vec<u8> = (0..rand(10)).map(|_| rand(7) as u8 * 7).collect();
+        let stored = entries.iter().find(|(k, _)| *k == query).map(|(_, v)| *v);
+        assert_eq!(stored, dat.get(query.iter().copied()));
+        let expected: Vec<(usize, u32)> = entries.iter()
+          .filter(|(k, _)| query.starts_with(k))
+          .map(|(k, v)| (k.len(), *v))
+          .collect();
+        assert_eq!(expected, dat.common_prefix_search(query.into_iter()));
+      }
+    }
+  }
+}
@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+// Levenshtein distance: the fewest insertions, deletions and substitutions of single keys that
+// turn a into b, by the row-by-row DP in O(nm) time and O(m) space
+pub fn levenshtein<K: Eq>(a: &[K], b: &[K]) -> usize {
+  let mut row: Vec<usize> = (0..=b.len()).collect();
+  for (i, x) in a.iter().enumerate() {
+    let mut diagonal = row[0];
+    row[0] = i + 1;
+    for (j, y) in b.iter().enumerate() {
+      let substitute = diagonal + (x != y) as usize;
+      diagonal = row[j + 1];
+      row[j + 1] = substitute.min(row[j] + 1).min(diagonal + 1);
+    }
+  }
+  row[b.len()]
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EditOp {
+  Keep, // a's next key equals b's next
+  Substitute, // a's next key becomes b's next
+  Delete, // a's next key goes
+  Insert, // b's next key comes in
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EditAlignment {
+  pub distance: usize,
+  pub ops: Vec<EditOp>, // from the front of both sequences
+}
+
+// an optimal script along with the distance, traced back through the full DP table, O(nm)
+// time and space
+pub fn edit_alignment<K: Eq>(a: &[K], b: &[K]) -> EditAlignment {
+  let (n, m) = (a.len(), b.len());
+  let mut d = vec![vec![0; m + 1]; n + 1];
+  for (i, row) in d.iter_mut().enumerate() {
+    row[0] = i;
+  }
+  d[0] = (0..=m).collect();
+  for i in 1..=n {
+    for j in 1..=m {
+      d[i][j] = (d[i - 1][j - 1] + (a[i - 1] != b[j - 1]) as usize).min(d[i - 1][j] + 1).min(d[i][j - 1] + 1);
+    }
+  }
+  let mut ops = vec![];
+  let (mut i, mut j) = (n, m);
+  while i > 0 || j > 0 {
+    if i > 0 && j > 0 && d[i][j] == d[i - 1][j - 1] + (a[i - 1] != b[j - 1]) as usize {
+      ops.push(if a[i - 1] == b[j - 1] { EditOp::Keep } else { EditOp::Substitute });
+      i -= 1;
+      j -= 1;
+    } else if i > 0 && d[i][j] == d[i - 1][j] + 1 {
+      ops.push(EditOp::Delete);
+      i -= 1;
+    } else {
+      ops.push(EditOp::Insert);
+      j -= 1;
+    }
+  }
+  ops.reverse();
+  EditAlignment { distance: d[n][m], ops }
+}
+
+// Myers' bit-parallel algorithm, in Hyyro's formulation with blocks of 64 rows: a column of the
+// DP table is kept as bit vectors of its +1 and -1 vertical steps and advanced by a handful of
+// word operations per key of b, O(n m / 64). Only the distance comes out.
+pub fn levenshtein_myers<K: Eq + Hash + Copy>(a: &[K], b: &[K]) -> usize {
+  if a.is_empty() {
+    return b.len();
+  }
+  let blocks = a.len().div_ceil(64);
+  let mut peq: HashMap<K, Vec<u64>> = HashMap::new();
+  for (i, &k) in a.iter().enumerate() {
+    peq.entry(k).or_insert_with(|| vec![0; blocks])[i / 64] |= 1 << (i % 64);
+  }
+  let none = vec![0; blocks];
+  let last = (a.len() - 1) % 64; // the row of a's end in the last block
+  let mut pv = vec![!0u64; blocks];
+  let mut mv = vec![0u64; blocks];
+  let mut score = a.len();
+  for k in b {
+    let eq = peq.get(k).unwrap_or(&none);
+    // the top row grows by one per column
+    let mut carry = 1i32;
+    for block in 0..blocks {
+      let (p, m) = (pv[block], mv[block]);
+      let mut e = eq[block];
+      let xv = e | m;
+      if carry < 0 {
+        e |= 1;
+      }
+      let xh = ((e & p).wrapping_add(p) ^ p) | e;
+      let mut ph = m | !(xh | p);
+      let mut mh = p & xh;
+      let high = if block + 1 == blocks { last } else { 63 };
+      let out = (ph >> high & 1) as i32 - (mh >> high & 1) as i32;
+      ph <<= 1;
+      mh <<= 1;
+      if carry < 0 {
+        mh |= 1;
+      } else if carry > 0 {
+        ph |= 1;
+      }
+      pv[block] = mh | !(xv | ph);
+      mv[block] = ph & xv;
+      carry = out;
+    }
+    score = (score as i64 + carry as i64) as usize;
+  }
+  score
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  fn apply(a: &[u8], b: &[u8], ops: &[EditOp]) -> (Vec<u8>, usize) {
+    let (mut i, mut j) = (0, 0);
+    let mut out = vec![];
+    let mut cost = 0;
+    for op in ops {
+      match op {
+        EditOp::Keep => {
+          assert_eq!(a[i], b[j]);
+          out.push(a[i]);
+          i += 1;
+          j += 1;
+        },
+        EditOp::Substitute => {
+          assert_ne!(a[i], b[j]);
+          out.push(b[j]);
+          i += 1;
+          j += 1;
+          cost += 1;
+        },
+        EditOp::Delete => {
+          i += 1;
+          cost += 1;
+        },
+        EditOp::Insert => {
+          out.push(b[j]);
+          j += 1;
+          cost += 1;
+        },
+      }
+    }
+    assert_eq!((a.len(), b.len()), (i, j));
+    (out, cost)
+  }
+
+  #[test]
+  fn test_examples() {
+    assert_eq!(3, levenshtein(b"kitten", b"sitting"));
+    assert_eq!(3, levenshtein_myers(b"kitten", b"sitting"));
+    let kitten: Vec<char> = "kitten".chars().collect();
+    let sitting: Vec<char> = "sitting".chars().collect();
+    assert_eq!(3, levenshtein_myers(&kitten, &sitting));
+    let alignment = edit_alignment(b"kitten", b"sitting");
+    use EditOp::*;
+    assert_eq!(vec![Substitute, Keep, Keep, Keep, Substitute, Keep, Insert], alignment.ops);
+    assert_eq!(0, levenshtein_myers::<u8>(b"", b""));
+    assert_eq!(4, levenshtein_myers(b"", b"abcd"));
+    assert_eq!(4, levenshtein_myers(b"abcd", b""));
+  }
+
+  #[test]
+  fn test_random_against_dp() {
+    let mut rand = rand_gen(173);
+    for round in 0..300 {
+      let sigma = 1 + rand(4);
+      let max = if round % 10 == 0 { 300 } else { 30 };
+      let a: Vec<u8> = (0..rand(max)).map(|_| rand(sigma) as u8).collect();
+      let mut b = a.clone();
+      for _ in 0..rand(max / 3 + 1) {
+        let at = rand(b.len() as u64 + 1) as usize;
+        match rand(3) {
+          0 => b.insert(at, rand(sigma) as u8),
+          1 if at < b.len() => b[at] = rand(sigma) as u8,
+          _ if at < b.len() => drop(b.remove(at)),
+          _ => {},
+        }
+      }
+      let d = levenshtein(&a, &b);
+      assert_eq!(d, levenshtein_myers(&a, &b));
+      assert_eq!(d, levenshtein(&b, &a));
+      let alignment = edit_alignment(&a, &b);
+      assert_eq!(d, alignment.distance);
+      assert_eq!((b.clone(), d), apply(&a, &b, &alignment.ops));
+    }
+  }
+}
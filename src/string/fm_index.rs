@@ -0,0 +1,194 @@
+use super::suffix_array::SuffixArray;
+
+const CHECKPOINT: usize = 64; // rows between rank checkpoints
+
+// FM-index (Ferragina & Manzini): a full-text index over the BWT of the text instead of the text
+// and its suffix array. Backward search narrows the range of sorted suffixes prefixed by the
+// pattern one key at a time from its end, each step two rank queries on the BWT, so counting
+// takes O(m) rank queries whatever the text length. Locating follows LF from each row to the
+// nearest row whose suffix start was sampled, at most rate steps.
+//
+// The BWT here is the one compression::bwt produces, with the end marker's row kept: row 0 is
+// the empty suffix, and the row of the whole text has the marker as its last key. Ranks come
+// from per-symbol counts every CHECKPOINT rows over the symbols that occur, and a scan from the
+// nearest checkpoint.
+pub struct FmIndex {
+  n: usize,
+  symbols: Vec<u8>, // the distinct bytes of the text, sorted
+  code: [u8; 256], // index into symbols
+  last: Vec<u8>, // codes, the marker's row holding 0 but skipped by rank
+  primary: usize, // the marker's row
+  first: Vec<usize>, // first[c]: rows before those starting with symbols[c]
+  checkpoints: Vec<u32>, // checkpoints[k * sigma + c]: c in last[..k * CHECKPOINT]
+  sampled: Vec<u64>, // rows whose suffix start is kept
+  sampled_rank: Vec<u32>, // sampled rows before each word
+  samples: Vec<u32>, // suffix starts of the sampled rows, in row order
+}
+
+impl FmIndex {
+  // keeps the starts of suffixes at multiples of rate, trading locate time for space
+  pub fn new(text: &[u8], rate: usize) -> FmIndex {
+    assert!(rate > 0 && text.len() < u32::MAX as usize);
+    let n = text.len();
+    let mut present = [false; 256];
+    for &b in text {
+      present[b as usize] = true;
+    }
+    let symbols: Vec<u8> = (0..=255).filter(|&b| present[b as usize]).collect();
+    let mut code = [0; 256];
+    for (c, &b) in symbols.iter().enumerate() {
+      code[b as usize] = c as u8;
+    }
+    let sigma = symbols.len();
+    let sa = SuffixArray::new(text);
+    // rows: the empty suffix, then the suffix array
+    let starts = || std::iter::once(n).chain(sa.sa().iter().copied());
+    let mut last = Vec::with_capacity(n + 1);
+    let mut primary = 0;
+    let mut sampled = vec![0u64; (n + 1).div_ceil(64)];
+    let mut samples = vec![];
+    for (row, s) in starts().enumerate() {
+      if s == 0 {
+        primary = row;
+        last.push(0);
+      } else {
+        last.push(code[text[s - 1] as usize]);
+      }
+      if s % rate == 0 || s == n {
+        sampled[row / 64] |= 1 << (row % 64);
+        samples.push(s as u32);
+      }
+    }
+    let mut first = vec![1; sigma];
+    let mut counts = vec![0u32; sigma];
+    let mut checkpoints = vec![];
+    for (row, &c) in last.iter().enumerate() {
+      if row % CHECKPOINT == 0 {
+        checkpoints.extend_from_slice(&counts);
+      }
+      if row != primary {
+        counts[c as usize] += 1;
+      }
+    }
+    checkpoints.extend_from_slice(&counts);
+    for c in 1..sigma {
+      first[c] = first[c - 1] + counts[c - 1] as usize;
+    }
+    let mut sampled_rank = vec![0];
+    for w in &sampled {
+      sampled_rank.push(sampled_rank.last().unwrap() + w.count_ones());
+    }
+    FmIndex { n, symbols, code, last, primary, first, checkpoints, sampled, sampled_rank, samples }
+  }
+
+  pub fn len(&self) -> usize {
+    self.n
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.n == 0
+  }
+
+  // the BWT without the marker and the marker's row, as compression::bwt returns them
+  pub fn bwt(&self) -> (Vec<u8>, usize) {
+    let bytes = self.last.iter().enumerate().filter(|&(row, _)| row != self.primary).map(|(_, &c)| self.symbols[c as usize]);
+    (bytes.collect(), self.primary)
+  }
+
+  // occurrences of code c in last[..row]
+  fn rank(&self, c: usize, row: usize) -> usize {
+    let k = row / CHECKPOINT;
+    let mut count = self.checkpoints[k * self.symbols.len() + c] as usize;
+    for r in k * CHECKPOINT..row {
+      count += (self.last[r] as usize == c && r != self.primary) as usize;
+    }
+    count
+  }
+
+  // the rows of the suffixes prefixed by pattern
+  fn rows(&self, pattern: &[u8]) -> std::ops::Range<usize> {
+    let (mut lo, mut hi) = (0, self.n + 1);
+    for &b in pattern.iter().rev() {
+      let c = self.code[b as usize] as usize;
+      if lo == hi || self.symbols.get(c) != Some(&b) {
+        return 0..0;
+      }
+      lo = self.first[c] + self.rank(c, lo);
+      hi = self.first[c] + self.rank(c, hi);
+    }
+    lo..hi
+  }
+
+  // occurrences of pattern, overlaps included; the empty pattern occurs at all n + 1 positions
+  pub fn count(&self, pattern: &[u8]) -> usize {
+    self.rows(pattern).len()
+  }
+
+  pub fn contains(&self, pattern: &[u8]) -> bool {
+    self.count(pattern) > 0
+  }
+
+  fn is_sampled(&self, row: usize) -> bool {
+    self.sampled[row / 64] >> (row % 64) & 1 == 1
+  }
+
+  // suffix start of a row: LF steps back through the text until a sampled row
+  fn suffix_start(&self, mut row: usize) -> usize {
+    let mut steps = 0;
+    while !self.is_sampled(row) {
+      // the marker's row holds suffix 0, which is always sampled
+      let c = self.last[row] as usize;
+      row = self.first[c] + self.rank(c, row);
+      steps += 1;
+    }
+    let below = self.sampled_rank[row / 64] as usize + (self.sampled[row / 64] & ((1 << (row % 64)) - 1)).count_ones() as usize;
+    self.samples[below] as usize + steps
+  }
+
+  // start of every occurrence of pattern, in increasing order
+  pub fn locate(&self, pattern: &[u8]) -> Vec<usize> {
+    let mut found: Vec<usize> = self.rows(pattern).map(|row| self.suffix_start(row)).collect();
+    found.sort_unstable();
+    found
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+  use crate::compression::bwt::{bwt, inverse_bwt};
+
+  #[test]
+  fn test_banana() {
+    let index = FmIndex::new(b"banana", 2);
+    assert_eq!(bwt(b"banana"), index.bwt());
+    assert_eq!(b"banana".to_vec(), inverse_bwt(&index.bwt().0, index.bwt().1));
+    assert_eq!(2, index.count(b"ana"));
+    assert_eq!(vec![1, 3], index.locate(b"ana"));
+    assert_eq!(vec![0], index.locate(b"banana"));
+    assert_eq!(vec![1, 3, 5], index.locate(b"a"));
+    assert_eq!(0, index.count(b"nab"));
+    assert_eq!(0, index.count(b"x"));
+    assert_eq!(7, index.count(b""));
+    assert!(FmIndex::new(b"", 4).locate(b"a").is_empty());
+  }
+
+  #[test]
+  fn test_random_against_naive() {
+    let mut rand = rand_gen(181);
+    for round in 0..100 {
+      let sigma = 1 + rand(5);
+      let n = if round % 10 == 0 { 2000 } else { rand(100) };
+      let text: Vec<u8> = (0..n).map(|_| b'a' + rand(sigma) as u8).collect();
+      let index = FmIndex::new(&text, 1 + rand(8) as usize);
+      assert_eq!(bwt(&text), index.bwt());
+      for _ in 0..30 {
+        let pattern: Vec<u8> = (0..1 + rand(4)).map(|_| b'a' + rand(sigma + 1) as u8).collect();
+        let expected: Vec<usize> = (0..text.len()).filter(|&i| text[i..].starts_with(&pattern)).collect();
+        assert_eq!(expected.len(), index.count(&pattern));
+        assert_eq!(expected, index.locate(&pattern));
+      }
+    }
+  }
+}
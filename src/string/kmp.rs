@@ -0,0 +1,266 @@
+// prefix function: pi[i] is the length of the longest proper prefix of s[..=i] that is also a
+// suffix of it, O(n) amortized
+pub fn prefix_function<K: Eq>(s: &[K]) -> Vec<usize> {
+  let mut pi = vec![0; s.len()];
+  for i in 1..s.len() {
+    let mut j = pi[i - 1];
+    while j > 0 && s[i] != s[j] {
+      j = pi[j - 1];
+    }
+    if s[i] == s[j] {
+      j += 1;
+    }
+    pi[i] = j;
+  }
+  pi
+}
+
+// lengths of all the proper borders of s, the strings both a proper prefix and a suffix of it,
+// longest first: the prefix function's chain from the end
+pub fn borders<K: Eq>(s: &[K]) -> Vec<usize> {
+  let pi = prefix_function(s);
+  let mut found = vec![];
+  let mut b = pi.last().copied().unwrap_or(0);
+  while b > 0 {
+    found.push(b);
+    b = pi[b - 1];
+  }
+  found
+}
+
+// the least p > 0 with s[i] == s[i + p] wherever both exist, n minus the longest border; n for
+// a string with no border, 0 for the empty one
+pub fn smallest_period<K: Eq>(s: &[K]) -> usize {
+  s.len() - prefix_function(s).last().copied().unwrap_or(0)
+}
+
+// whether p is a period of s, that is s[..n - p] is a border; every p >= n is one
+pub fn is_periodic_with<K: Eq>(s: &[K], p: usize) -> bool {
+  p > 0 && (p >= s.len() || s[p..] == s[..s.len() - p])
+}
+
+// Z-function: z[i] is the length of the longest common prefix of s and s[i..], z[0] = n. The
+// rightmost match [l, r) found so far lets z[i] start from z[i - l], O(n).
+pub fn z_function<K: Eq>(s: &[K]) -> Vec<usize> {
+  let n = s.len();
+  let mut z = vec![n; n];
+  let (mut l, mut r) = (0, 0);
+  for i in 1..n {
+    z[i] = if i < r { z[i - l].min(r - i) } else { 0 };
+    while i + z[i] < n && s[z[i]] == s[i + z[i]] {
+      z[i] += 1;
+    }
+    if i + z[i] > r {
+      l = i;
+      r = i + z[i];
+    }
+  }
+  z
+}
+
+// the Z-functions of many independent strings, spread over the threads, aligned with strings
+pub fn z_batch<K: Eq + Sync>(strings: &[&[K]]) -> Vec<Vec<usize>> {
+  crate::parallel::map(strings, |s| z_function(s))
+}
+
+// Knuth-Morris-Pratt matcher for a single pattern. The state is the length of the longest
+// pattern prefix ending at the current position; on a mismatch it falls back along the prefix
+// function, so every key is looked at a constant number of times amortized. The empty pattern
+// matches nowhere, as in AhoCorasick.
+pub struct Kmp<K> {
+  pattern: Vec<K>,
+  pi: Vec<usize>,
+  state: usize, // for feed
+  fed: usize,
+}
+
+impl<K: Eq + Copy> Kmp<K> {
+  pub fn new(pattern: impl IntoIterator<Item = K>) -> Kmp<K> {
+    let pattern: Vec<K> = pattern.into_iter().collect();
+    let pi = prefix_function(&pattern);
+    Kmp { pattern, pi, state: 0, fed: 0 }
+  }
+
+  pub fn pattern(&self) -> &[K] {
+    &self.pattern
+  }
+
+  pub fn prefix_function(&self) -> &[usize] {
+    &self.pi
+  }
+
+  // the state after reading k in state, where a state of pattern.len() is a match
+  fn step(&self, mut state: usize, k: &K) -> usize {
+    if state == self.pattern.len() {
+      state = self.pi[state - 1];
+    }
+    while state > 0 && self.pattern[state] != *k {
+      state = self.pi[state - 1];
+    }
+    if self.pattern[state] == *k {
+      state += 1;
+    }
+    state
+  }
+
+  // start of every occurrence in haystack, overlaps included, in increasing order
+  pub fn find_iter<I: IntoIterator<Item = K>>(&self, haystack: I) -> KmpIter<'_, K, I::IntoIter> {
+    KmpIter { kmp: self, haystack: haystack.into_iter(), state: 0, pos: 0 }
+  }
+
+  // streaming mode: reads the next key of a text arriving piecewise and returns the start of the
+  // occurrence ending with it, counted from the first key fed since the last reset
+  pub fn feed(&mut self, k: K) -> Option<usize> {
+    if self.pattern.is_empty() {
+      return None;
+    }
+    self.state = self.step(self.state, &k);
+    self.fed += 1;
+    if self.state == self.pattern.len() { Some(self.fed - self.pattern.len()) } else { None }
+  }
+
+  // streaming a chunk at a time: the start of every occurrence ending in the chunk, as feed
+  // gives them. The whole chunk is read even if the iterator is dropped early.
+  pub fn feed_chunk<'s>(&'s mut self, chunk: &'s [K]) -> KmpFeed<'s, K> {
+    KmpFeed { kmp: self, chunk: chunk.iter() }
+  }
+
+  pub fn reset(&mut self) {
+    self.state = 0;
+    self.fed = 0;
+  }
+}
+
+pub struct KmpFeed<'s, K: Eq + Copy> {
+  kmp: &'s mut Kmp<K>,
+  chunk: std::slice::Iter<'s, K>,
+}
+
+impl<'s, K: Eq + Copy> Iterator for KmpFeed<'s, K> {
+  type Item = usize;
+  fn next(&mut self) -> Option<Self::Item> {
+    for &k in &mut self.chunk {
+      if let Some(start) = self.kmp.feed(k) {
+        return Some(start);
+      }
+    }
+    None
+  }
+}
+
+impl<'s, K: Eq + Copy> Drop for KmpFeed<'s, K> {
+  fn drop(&mut self) {
+    for &k in &mut self.chunk {
+      self.kmp.feed(k);
+    }
+  }
+}
+
+pub struct KmpIter<'a, K, I> {
+  kmp: &'a Kmp<K>,
+  haystack: I,
+  state: usize,
+  pos: usize, // number of keys consumed
+}
+
+impl<'a, K: Eq + Copy, I: Iterator<Item = K>> Iterator for KmpIter<'a, K, I> {
+  type Item = usize;
+  fn next(&mut self) -> Option<Self::Item> {
+    let m = self.kmp.pattern.len();
+    if m == 0 {
+      return None;
+    }
+    loop {
+      let k = self.haystack.next()?;
+      self.state = self.kmp.step(self.state, &k);
+      self.pos += 1;
+      if self.state == m {
+        return Some(self.pos - m);
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_prefix_function() {
+    assert_eq!(vec![0, 0, 1, 2, 3, 4, 0], prefix_function(b"abababc"));
+    assert_eq!(vec![0, 1, 0, 1, 2, 2, 3], prefix_function(b"aabaaab"));
+    assert!(prefix_function::<u8>(&[]).is_empty());
+    let kmp = Kmp::new("abcab".chars());
+    assert_eq!(&[0, 0, 0, 1, 2], kmp.prefix_function());
+  }
+
+  #[test]
+  fn test_periods() {
+    assert_eq!(vec![5, 3, 1], borders(b"abababa"));
+    assert_eq!(2, smallest_period(b"abababa"));
+    assert!(borders(b"abc").is_empty());
+    assert_eq!(3, smallest_period(b"abc"));
+    assert_eq!(0, smallest_period::<u8>(b""));
+    assert!(is_periodic_with(b"abcabca", 3) && is_periodic_with(b"abcabca", 6) && is_periodic_with(b"abcabca", 9));
+    assert!(!is_periodic_with(b"abcabca", 4) && !is_periodic_with(b"abcabca", 0));
+    let mut rand = rand_gen(269);
+    for _ in 0..300 {
+      let s: Vec<u8> = (0..rand(30)).map(|_| rand(2) as u8).collect();
+      let n = s.len();
+      let naive: Vec<usize> = (1..n).rev().filter(|&b| s[..b] == s[n - b..]).collect();
+      assert_eq!(naive, borders(&s));
+      let periods: Vec<usize> = (1..=n).filter(|&p| (0..n - p).all(|i| s[i] == s[i + p])).collect();
+      assert_eq!(periods.first().copied().unwrap_or(0), smallest_period(&s));
+      assert_eq!(periods, (1..=n).filter(|&p| is_periodic_with(&s, p)).collect::<Vec<_>>());
+    }
+  }
+
+  #[test]
+  fn test_z_function() {
+    assert_eq!(vec![7, 0, 1, 0, 3, 0, 1], z_function(b"abacaba"));
+    assert_eq!(vec![4, 3, 2, 1], z_function(b"aaaa"));
+    assert!(z_function::<u8>(&[]).is_empty());
+    let strings: Vec<Vec<u8>> = (0..200).map(|i| (0..i % 17).map(|j| (i * j % 3) as u8).collect()).collect();
+    let slices: Vec<&[u8]> = strings.iter().map(|s| &s[..]).collect();
+    let batch = z_batch(&slices);
+    assert_eq!(slices.len(), batch.len());
+    for (s, z) in slices.iter().zip(batch) {
+      let naive: Vec<usize> = (0..s.len()).map(|i| s.iter().zip(&s[i..]).take_while(|(x, y)| x == y).count()).collect();
+      assert_eq!(naive, z);
+    }
+  }
+
+  #[test]
+  fn test_find() {
+    let kmp = Kmp::new("aba".chars());
+    assert_eq!(vec![0, 2, 6], kmp.find_iter("ababa?aba".chars()).collect::<Vec<_>>());
+    assert_eq!(0, Kmp::new("".chars()).find_iter("abc".chars()).count());
+
+    let mut rand = rand_gen(83);
+    for _ in 0..300 {
+      let pattern: Vec<u8> = (0..1 + rand(5)).map(|_| rand(3) as u8).collect();
+      let text: Vec<u8> = (0..rand(60)).map(|_| rand(3) as u8).collect();
+      let expected: Vec<usize> = (0..text.len()).filter(|&i| text[i..].starts_with(&pattern)).collect();
+      let mut kmp = Kmp::new(pattern.iter().copied());
+      assert_eq!(expected, kmp.find_iter(text.iter().copied()).collect::<Vec<_>>());
+      // the whole text streamed, then only its second half after a reset
+      let half = text.len() / 2;
+      let mut streamed: Vec<usize> = text.iter().filter_map(|&k| kmp.feed(k)).collect();
+      assert_eq!(expected, streamed);
+      kmp.reset();
+      streamed = text[half..].iter().filter_map(|&k| kmp.feed(k)).map(|i| i + half).collect();
+      assert_eq!(expected.iter().copied().filter(|&i| i >= half).collect::<Vec<_>>(), streamed);
+      // in chunks, only the first occurrence ending in each taken before the rest is skipped
+      kmp.reset();
+      let (mut chunked, mut first, mut at) = (vec![], vec![], 0);
+      while at < text.len() {
+        let end = (at + 1 + rand(10) as usize).min(text.len());
+        chunked.extend(kmp.feed_chunk(&text[at..end]).next());
+        first.extend(expected.iter().copied().find(|&i| (at + 1..=end).contains(&(i + pattern.len()))));
+        at = end;
+      }
+      assert_eq!(first, chunked);
+    }
+  }
+}
@@ -0,0 +1,112 @@
+// Longest common subsequence in linear space. The DP row for a against every prefix of b takes
+// O(|b|) memory; Hirschberg's divide and conquer recovers an actual subsequence with the same
+// memory by splitting a in half, finding where an optimal alignment crosses the split from a
+// forward row for the top half and a backward row for the bottom half, and recursing on the two
+// quadrants. Time stays O(nm), twice the plain DP.
+
+// row[j] = LCS length of a and b[..j], or with reverse of a and b read backwards
+fn lcs_row<K: Eq>(a: &[K], b: &[K], reverse: bool) -> Vec<usize> {
+  let m = b.len();
+  let mut row = vec![0; m + 1];
+  for i in 0..a.len() {
+    let x = if reverse { &a[a.len() - 1 - i] } else { &a[i] };
+    let mut diagonal = 0;
+    for j in 0..m {
+      let y = if reverse { &b[m - 1 - j] } else { &b[j] };
+      let up = row[j + 1];
+      row[j + 1] = if x == y { diagonal + 1 } else { up.max(row[j]) };
+      diagonal = up;
+    }
+  }
+  row
+}
+
+// length only, in O(min(n, m)) memory
+pub fn lcs_length<K: Eq>(a: &[K], b: &[K]) -> usize {
+  if a.len() < b.len() {
+    lcs_row(b, a, false)[a.len()]
+  } else {
+    lcs_row(a, b, false)[b.len()]
+  }
+}
+
+fn hirschberg<K: Eq>(a: &[K], b: &[K], (i0, j0): (usize, usize), pairs: &mut Vec<(usize, usize)>) {
+  if a.is_empty() || b.is_empty() {
+    return;
+  }
+  if a.len() == 1 {
+    if let Some(j) = b.iter().position(|y| *y == a[0]) {
+      pairs.push((i0, j0 + j));
+    }
+    return;
+  }
+  let mid = a.len() / 2;
+  let top = lcs_row(&a[..mid], b, false);
+  let bottom = lcs_row(&a[mid..], b, true);
+  let m = b.len();
+  let split = (0..=m).max_by_key(|&k| (top[k] + bottom[m - k], std::cmp::Reverse(k))).unwrap();
+  hirschberg(&a[..mid], &b[..split], (i0, j0), pairs);
+  hirschberg(&a[mid..], &b[split..], (i0 + mid, j0 + split), pairs);
+}
+
+// the index pairs (i, j) with a[i] == b[j] of a longest common subsequence, increasing in both;
+// its length is the number of pairs. O(nm) time, O(n + m) memory.
+pub fn lcs<K: Eq>(a: &[K], b: &[K]) -> Vec<(usize, usize)> {
+  let mut pairs = vec![];
+  hirschberg(a, b, (0, 0), &mut pairs);
+  pairs
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  fn quadratic(a: &[u8], b: &[u8]) -> usize {
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for i in 1..=a.len() {
+      for j in 1..=b.len() {
+        d[i][j] = if a[i - 1] == b[j - 1] { d[i - 1][j - 1] + 1 } else { d[i - 1][j].max(d[i][j - 1]) };
+      }
+    }
+    d[a.len()][b.len()]
+  }
+
+  fn check(a: &[u8], b: &[u8], pairs: &[(usize, usize)]) {
+    assert!(pairs.iter().all(|&(i, j)| a[i] == b[j]));
+    assert!(pairs.windows(2).all(|w| w[0].0 < w[1].0 && w[0].1 < w[1].1));
+  }
+
+  #[test]
+  fn test_examples() {
+    let (a, b) = (b"ABCBDAB", b"BDCABA");
+    let pairs = lcs(a, b);
+    check(a, b, &pairs);
+    assert_eq!(4, pairs.len());
+    assert_eq!(4, lcs_length(a, b));
+    assert_eq!(4, lcs_length(b, a));
+    assert!(lcs(b"abc", b"").is_empty());
+    assert_eq!(vec![(0, 1), (1, 2)], lcs(b"xy", b"axyb"));
+  }
+
+  #[test]
+  fn test_random_against_dp() {
+    let mut rand = rand_gen(179);
+    for _ in 0..300 {
+      let sigma = 1 + rand(5);
+      let a: Vec<u8> = (0..rand(40)).map(|_| rand(sigma) as u8).collect();
+      let b: Vec<u8> = (0..rand(40)).map(|_| rand(sigma) as u8).collect();
+      let expected = quadratic(&a, &b);
+      assert_eq!(expected, lcs_length(&a, &b));
+      let pairs = lcs(&a, &b);
+      check(&a, &b, &pairs);
+      assert_eq!(expected, pairs.len());
+    }
+    // long inputs a quadratic table would not hold comfortably
+    let a: Vec<u8> = (0..5000).map(|_| rand(4) as u8).collect();
+    let b: Vec<u8> = (0..5000).map(|_| rand(4) as u8).collect();
+    let pairs = lcs(&a, &b);
+    check(&a, &b, &pairs);
+    assert_eq!(lcs_length(&a, &b), pairs.len());
+  }
+}
@@ -0,0 +1,88 @@
+use std::ops::Range;
+
+// Duval's algorithm: the Lyndon factorization of s, its unique split into a non-increasing
+// sequence of Lyndon words (strings strictly smaller than all their proper rotations), O(n)
+// time and O(1) extra space. The scan keeps a prefix of the form w^k w' with w Lyndon and w' a
+// proper prefix of w; the next key either extends it, makes the whole thing one Lyndon word, or
+// ends the k copies of w as factors.
+pub fn lyndon_factorization<K: Ord>(s: &[K]) -> Vec<Range<usize>> {
+  let n = s.len();
+  let mut factors = vec![];
+  let mut i = 0;
+  while i < n {
+    // s[i..j] is w^k w', k compared against the key one period back
+    let (mut j, mut k) = (i + 1, i);
+    while j < n && s[k] <= s[j] {
+      k = if s[k] < s[j] { i } else { k + 1 };
+      j += 1;
+    }
+    let period = j - k;
+    while i <= k {
+      factors.push(i..i + period);
+      i += period;
+    }
+  }
+  factors
+}
+
+// start of the lexicographically least rotation of s, the smallest such start if several are
+// equal: the last Lyndon factor of s s starting in the first copy
+pub fn least_rotation<K: Ord>(s: &[K]) -> usize {
+  let n = s.len();
+  let at = |i: usize| &s[i % n];
+  let mut best = 0;
+  let mut i = 0;
+  while i < n {
+    best = i;
+    let (mut j, mut k) = (i + 1, i);
+    while j < 2 * n && at(k) <= at(j) {
+      k = if at(k) < at(j) { i } else { k + 1 };
+      j += 1;
+    }
+    while i <= k {
+      i += j - k;
+    }
+  }
+  best
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  fn is_lyndon(w: &[u8]) -> bool {
+    !w.is_empty() && (1..w.len()).all(|r| w < &[&w[r..], &w[..r]].concat()[..])
+  }
+
+  #[test]
+  fn test_examples() {
+    let s = b"abracadabra";
+    let words: Vec<&[u8]> = lyndon_factorization(s).into_iter().map(|r| &s[r]).collect();
+    assert_eq!(vec![&b"abracad"[..], b"abr", b"a"], words);
+    assert_eq!(vec![0..1, 1..2, 2..3], lyndon_factorization(b"aaa"));
+    assert!(lyndon_factorization::<u8>(&[]).is_empty());
+    assert_eq!(2, least_rotation(b"bbaaccaadd"));
+    assert_eq!(0, least_rotation(b"aaaa"));
+    assert_eq!(1, least_rotation(b"baba"));
+    assert_eq!(0, least_rotation::<u8>(&[]));
+  }
+
+  #[test]
+  fn test_random_against_naive() {
+    let mut rand = rand_gen(167);
+    for _ in 0..500 {
+      let sigma = 1 + rand(3);
+      let s: Vec<u8> = (0..rand(30)).map(|_| rand(sigma) as u8).collect();
+      let factors = lyndon_factorization(&s);
+      assert_eq!(s.len(), factors.iter().map(|r| r.len()).sum::<usize>());
+      assert!(factors.windows(2).all(|w| w[0].end == w[1].start && s[w[0].clone()] >= s[w[1].clone()]));
+      assert!(factors.iter().all(|r| is_lyndon(&s[r.clone()])));
+      if !s.is_empty() {
+        let rotation = |r: usize| [&s[r..], &s[..r]].concat();
+        let expected = (0..s.len()).min_by_key(|&r| (rotation(r), r)).unwrap();
+        assert_eq!(expected, least_rotation(&s));
+      }
+    }
+  }
+}
@@ -16,7 +16,7 @@ impl<'a, T: Eq + Copy> ManacherString<'a, T> {
   }
   fn get(&self, ti: usize) -> ManacherValue<T> {
     assert!(ti < self.len());
-    if ti % 2 == 0 {
+    if ti.is_multiple_of(2) {
       ManacherValue::Sep
     } else {
       ManacherValue::Char(self.0[ti_to_si(ti)])
@@ -85,6 +85,11 @@ impl Manacher {
     Manacher(ans)
   }
 
+  // one Manacher per string, aligned with strings, the strings spread over the threads
+  pub fn new_batch<T: Eq + Copy + Sync>(strings: &[&[T]]) -> Vec<Manacher> {
+    crate::parallel::map(strings, |s| Manacher::new(s))
+  }
+
   pub fn max_palindrome_len(&self) -> usize {
     *self.0.iter().max().unwrap()
   }
@@ -131,7 +136,7 @@ impl Manacher {
     // [l, r)
     if sl >= sr { return true; }
     let slen = sr - sl;
-    let range = if slen % 2 == 0 { // even palindrome
+    let range = if slen.is_multiple_of(2) { // even palindrome
       let center = sl + slen / 2 - 1;
       self.even_longest_at(center, center + 1)
     } else { // odd palindrome
@@ -142,6 +147,7 @@ impl Manacher {
   }
 }
 
+#[cfg(test)]
 fn naive_palindrome<T: Eq>(s: &[T], l: usize, r: usize) -> bool {
   // [l, r)
   for d in 0.. {
@@ -250,4 +256,16 @@ mod tests {
     assert_eq!(Some(31..38), iter_len_7.next());
     assert_eq!(None, iter_len_7.next());
   }
-}
\ No newline at end of file
+
+  #[test]
+  fn test_batch() {
+    let strings = ["bananas", "", "abracadabra", "x", "aaaa"];
+    let slices: Vec<&[u8]> = strings.iter().map(|s| s.as_bytes()).collect();
+    let batch = Manacher::new_batch(&slices);
+    assert_eq!(strings.len(), batch.len());
+    for (s, m) in slices.iter().zip(&batch) {
+      assert_eq!(Manacher::new(s).0, m.0);
+    }
+    assert_eq!(4, batch[4].max_palindrome_len());
+  }
+}
@@ -1,2 +1,25 @@
 pub mod manacher;
-pub mod trie;
\ No newline at end of file
+pub mod trie;
+pub mod aho_corasick;
+pub mod radix_trie;
+pub mod persistent_trie;
+pub mod double_array_trie;
+pub mod ternary_search_tree;
+pub mod bit_trie;
+pub mod kmp;
+pub mod ngram;
+pub mod suffix_array;
+pub mod segment;
+pub mod suffix_automaton;
+pub mod suffix_tree;
+pub mod rolling_hash;
+pub mod boyer_moore;
+pub mod lyndon;
+pub mod edit_distance;
+pub mod lcs;
+pub mod fm_index;
+pub mod runs;
+pub mod bitap;
+pub mod subsequence_automaton;
+pub mod string_sort;
+pub mod bk_tree;
\ No newline at end of file
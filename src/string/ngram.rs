@@ -0,0 +1,167 @@
+use std::hash::Hash;
+
+use super::trie::Trie;
+
+// what the model knows about one gram g, kept at g's node
+#[derive(Clone, Copy, Default)]
+struct NgramStats {
+  count: usize, // occurrences of g
+  continuation: usize, // distinct tokens u with u g seen, N1+(. g)
+  // over the tokens w following g: total count of g w, how many distinct w, and the same two
+  // for continuation counts
+  follow_total: usize,
+  follow_types: usize,
+  continuation_total: usize,
+  continuation_types: usize,
+}
+
+// n-gram counts over token sequences with interpolated Kneser-Ney smoothing at a fixed discount.
+// Every gram up to the order is a key of one Trie, so a context's node also holds the totals
+// over what follows it. The highest order uses raw counts; lower orders use continuation counts,
+// how many distinct tokens precede a gram, so a token seen often but only after one word does
+// not get much weight in new contexts. The lowest level is uniform over the vocabulary, which is
+// also all an unseen token gets.
+pub struct NgramModel<K> {
+  order: usize,
+  discount: f64,
+  trie: Trie<K, NgramStats>,
+}
+
+impl<K: Eq + Hash + Copy> NgramModel<K> {
+  // discount in (0, 1); 0.75 is the usual choice
+  pub fn new(order: usize, discount: f64) -> NgramModel<K> {
+    assert!(order >= 1 && discount > 0.0 && discount < 1.0);
+    NgramModel { order, discount, trie: Trie::new() }
+  }
+
+  pub fn order(&self) -> usize {
+    self.order
+  }
+
+  fn stats(&self, gram: &[K]) -> NgramStats {
+    self.trie.get_ref(gram.iter().copied()).copied().unwrap_or_default()
+  }
+
+  fn stats_mut(&mut self, gram: &[K]) -> &mut NgramStats {
+    self.trie.entry(gram.iter().copied()).or_default()
+  }
+
+  // counts every gram of length 1..=order inside the sentence; grams do not cross sentences
+  pub fn add_sentence(&mut self, tokens: &[K]) {
+    for i in 0..tokens.len() {
+      for len in 1..=self.order.min(tokens.len() - i) {
+        let gram = &tokens[i..i + len];
+        let g = self.stats_mut(gram);
+        g.count += 1;
+        let new = g.count == 1;
+        let context = self.stats_mut(&gram[..len - 1]);
+        context.follow_total += 1;
+        context.follow_types += new as usize;
+        if new && len >= 2 {
+          // a new left extension of the suffix
+          let suffix = &gram[1..];
+          let s = self.stats_mut(suffix);
+          s.continuation += 1;
+          let first = s.continuation == 1;
+          let context = self.stats_mut(&suffix[..len - 2]);
+          context.continuation_total += 1;
+          context.continuation_types += first as usize;
+        }
+      }
+    }
+  }
+
+  // occurrences of gram, for grams no longer than the order
+  pub fn count(&self, gram: &[K]) -> usize {
+    self.stats(gram).count
+  }
+
+  // number of distinct tokens seen
+  pub fn vocabulary_size(&self) -> usize {
+    self.stats(&[]).follow_types
+  }
+
+  // P(next | context), using the last order - 1 tokens of context
+  pub fn probability(&self, context: &[K], next: K) -> f64 {
+    let context = &context[context.len().saturating_sub(self.order - 1)..];
+    self.interpolated(context, next, true)
+  }
+
+  fn interpolated(&self, context: &[K], next: K, highest: bool) -> f64 {
+    let lower = match context.split_first() {
+      Some((_, shorter)) => self.interpolated(shorter, next, false),
+      None => 1.0 / self.vocabulary_size().max(1) as f64,
+    };
+    let c = self.stats(context);
+    let (total, types) = if highest { (c.follow_total, c.follow_types) } else { (c.continuation_total, c.continuation_types) };
+    if total == 0 {
+      return lower; // an unseen context says nothing
+    }
+    let mut gram = context.to_vec();
+    gram.push(next);
+    let g = self.stats(&gram);
+    let seen = if highest { g.count } else { g.continuation } as f64;
+    let total = total as f64;
+    (seen - self.discount).max(0.0) / total + self.discount * types as f64 / total * lower
+  }
+
+  // natural log of the probability of the whole sequence, each token conditioned on the ones
+  // before it
+  pub fn log_probability(&self, tokens: &[K]) -> f64 {
+    (0..tokens.len()).map(|i| self.probability(&tokens[..i], tokens[i]).ln()).sum()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn words(s: &str) -> Vec<&str> {
+    s.split(' ').collect()
+  }
+
+  fn model(order: usize) -> NgramModel<&'static str> {
+    let mut model = NgramModel::new(order, 0.75);
+    for s in ["the cat sat on the mat", "the dog sat on the log", "a cat ate the fish", "san francisco is foggy", "i like san francisco"] {
+      model.add_sentence(&words(s));
+    }
+    model
+  }
+
+  #[test]
+  fn test_counts() {
+    let model = model(3);
+    assert_eq!(5, model.count(&["the"]));
+    assert_eq!(2, model.count(&["sat", "on"]));
+    assert_eq!(2, model.count(&["sat", "on", "the"]));
+    assert_eq!(0, model.count(&["mat", "the"])); // across sentences
+    assert_eq!(16, model.vocabulary_size());
+  }
+
+  #[test]
+  fn test_distributions_sum_to_one() {
+    for order in 1..=4 {
+      let model = model(order);
+      let vocabulary = words("the cat sat on mat dog log a ate fish san francisco is foggy i like");
+      for context in ["", "the", "sat on", "on the", "zebra", "the zebra", "a cat sat on the"] {
+        let context = if context.is_empty() { vec![] } else { words(context) };
+        let total: f64 = vocabulary.iter().map(|&w| model.probability(&context, w)).sum();
+        assert!((total - 1.0).abs() < 1e-9, "order {} context {:?}: {}", order, context, total);
+      }
+    }
+  }
+
+  #[test]
+  fn test_smoothing() {
+    let model = model(2);
+    // seen continuations win, and an unseen word still gets some mass
+    assert!(model.probability(&["sat"], "on") > 0.5);
+    assert!(model.probability(&["the"], "cat") > model.probability(&["the"], "foggy"));
+    assert!(model.probability(&["the"], "foggy") > 0.0);
+    // "francisco" is as frequent as "cat" but only ever follows "san", so as a continuation
+    // after a context it never saw it gets less
+    assert_eq!(model.count(&["francisco"]), model.count(&["cat"]));
+    assert!(model.probability(&["zebra"], "cat") > model.probability(&["zebra"], "francisco"));
+    assert!(model.log_probability(&words("the cat sat on the mat")) > model.log_probability(&words("mat the on sat cat the")));
+  }
+}
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::rc::Rc;
+
+// Immutable trie: insert and remove leave self untouched and return a new version. Only the
+// nodes on the changed path are copied (each copy clones a map of Rc pointers), every other
+// subtree is shared between versions, so keeping old versions around is cheap.
+struct PersistentNode<K, U> {
+  child: HashMap<K, Link<K, U>>,
+  data: Option<U>,
+}
+
+type Link<K, U> = Rc<PersistentNode<K, U>>;
+
+impl<K: Eq + Hash + Copy, U: Clone> PersistentNode<K, U> {
+  fn new() -> PersistentNode<K, U> {
+    PersistentNode { child: HashMap::new(), data: None }
+  }
+
+  fn copy(&self) -> PersistentNode<K, U> {
+    PersistentNode { child: self.child.clone(), data: self.data.clone() }
+  }
+
+  // the new node for this position, and whether a key was added
+  fn insert(node: Option<&Link<K, U>>, key: &[K], data: U) -> (Link<K, U>, bool) {
+    let mut copy = node.map_or_else(PersistentNode::new, |n| n.copy());
+    let added = match key.split_first() {
+      None => copy.data.replace(data).is_none(),
+      Some((k, rest)) => {
+        let (c, added) = PersistentNode::insert(copy.child.get(k), rest, data);
+        copy.child.insert(*k, c);
+        added
+      },
+    };
+    (Rc::new(copy), added)
+  }
+
+  // None if key is not stored; otherwise the node replacing this one (None once it is empty)
+  // and the removed value
+  fn remove(node: &Link<K, U>, key: &[K]) -> Option<(Option<Link<K, U>>, U)> {
+    let mut copy = node.copy();
+    let removed = match key.split_first() {
+      None => copy.data.take()?,
+      Some((k, rest)) => {
+        let (c, removed) = PersistentNode::remove(node.child.get(k)?, rest)?;
+        match c {
+          Some(c) => copy.child.insert(*k, c),
+          None => copy.child.remove(k),
+        };
+        removed
+      },
+    };
+    let empty = copy.data.is_none() && copy.child.is_empty();
+    Some((if empty { None } else { Some(Rc::new(copy)) }, removed))
+  }
+}
+
+pub struct PersistentTrie<K, U> {
+  root: Link<K, U>,
+  len: usize,
+}
+
+// cloning a version is O(1), it shares the whole tree
+impl<K, U> Clone for PersistentTrie<K, U> {
+  fn clone(&self) -> Self {
+    PersistentTrie { root: self.root.clone(), len: self.len }
+  }
+}
+
+impl<K: Eq + Hash + Copy, U: Clone> PersistentTrie<K, U> {
+  pub fn new() -> PersistentTrie<K, U> {
+    PersistentTrie { root: Rc::new(PersistentNode::new()), len: 0 }
+  }
+
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  pub fn get(&self, path: impl Iterator<Item = K>) -> Option<U> {
+    let mut node = &self.root;
+    for k in path {
+      node = node.child.get(&k)?;
+    }
+    node.data.clone()
+  }
+
+  pub fn insert(&self, path: impl Iterator<Item = K>, data: U) -> PersistentTrie<K, U> {
+    let key: Vec<K> = path.collect();
+    let (root, added) = PersistentNode::insert(Some(&self.root), &key, data);
+    PersistentTrie { root, len: self.len + added as usize }
+  }
+
+  // the version without path, and the value it held; a missing key returns a copy of self
+  pub fn remove(&self, path: impl Iterator<Item = K>) -> (PersistentTrie<K, U>, Option<U>) {
+    let key: Vec<K> = path.collect();
+    match PersistentNode::remove(&self.root, &key) {
+      None => (self.clone(), None),
+      Some((root, removed)) => {
+        let root = root.unwrap_or_else(|| Rc::new(PersistentNode::new()));
+        (PersistentTrie { root, len: self.len - 1 }, Some(removed))
+      },
+    }
+  }
+}
+
+impl<K: Eq + Hash + Copy, U: Clone> Default for PersistentTrie<K, U> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+  use std::collections::BTreeMap;
+
+  #[test]
+  fn test_versions() {
+    let empty: PersistentTrie<char, u32> = PersistentTrie::new();
+    let v1 = empty.insert("tea".chars(), 1);
+    let v2 = v1.insert("ten".chars(), 2);
+    let v3 = v2.insert("tea".chars(), 3);
+    let (v4, removed) = v3.remove("ten".chars());
+    assert_eq!(Some(2), removed);
+    assert!(empty.is_empty());
+    assert_eq!((1, 2, 2, 1), (v1.len(), v2.len(), v3.len(), v4.len()));
+    assert_eq!(None, empty.get("tea".chars()));
+    assert_eq!(Some(1), v1.get("tea".chars()));
+    assert_eq!(Some(1), v2.get("tea".chars()));
+    assert_eq!(Some(3), v3.get("tea".chars()));
+    assert_eq!(Some(2), v3.get("ten".chars()));
+    assert_eq!(None, v4.get("ten".chars()));
+    assert_eq!(None, v4.get("te".chars()));
+    assert_eq!(None, v4.remove("te".chars()).1);
+    let (v5, removed) = v4.remove("tea".chars());
+    assert_eq!(Some(3), removed);
+    assert!(v5.is_empty() && v5.root.child.is_empty());
+
+    // "i" is untouched by the insert under "t", so both versions point at the same subtree
+    let a = v2.insert("inn".chars(), 4);
+    let b = a.insert("to".chars(), 5);
+    assert!(Rc::ptr_eq(&a.root.child[&'i'], &b.root.child[&'i']));
+    assert!(!Rc::ptr_eq(&a.root.child[&'t'], &b.root.child[&'t']));
+    assert!(Rc::ptr_eq(&a.root.child[&'t'].child[&'e'], &b.root.child[&'t'].child[&'e']));
+  }
+
+  #[test]
+  fn test_random_history() {
+    let mut rand = rand_gen(17);
+    let mut versions = vec![(PersistentTrie::new(), BTreeMap::new())];
+    for step in 0..2000 {
+      let (trie, model) = versions[rand(versions.len() as u64) as usize].clone();
+      let key: Vec<u8> = (0..rand(4)).map(|_| rand(3) as u8).collect();
+      let next = if rand(3) == 0 {
+        let (next, removed) = trie.remove(key.iter().copied());
+        let mut model = model;
+        assert_eq!(model.remove(&key), removed);
+        (next, model)
+      } else {
+        let mut model = model;
+        model.insert(key.clone(), step);
+        (trie.insert(key.into_iter(), step), model)
+      };
+      versions.push(next);
+    }
+    for (trie, model) in &versions {
+      assert_eq!(model.len(), trie.len());
+      for (key, &value) in model {
+        assert_eq!(Some(value), trie.get(key.iter().copied()));
+      }
+    }
+  }
+}
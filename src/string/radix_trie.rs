@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::trie::Trie;
+
+// Compressed trie: every edge carries a run of keys, and apart from the root no node is
+// valueless with a single child, so the node count is at most twice the number of stored keys
+// no matter how long they are. Children are keyed by the first key of their label.
+struct RadixNode<K, U> {
+  label: Vec<K>,
+  child: HashMap<K, RadixNode<K, U>>,
+  data: Option<U>,
+}
+
+impl<K: Eq + Hash + Copy, U> RadixNode<K, U> {
+  fn new(label: Vec<K>, data: Option<U>) -> RadixNode<K, U> {
+    RadixNode { label, child: HashMap::new(), data }
+  }
+
+  // splits the label after at keys, the tail moves into a new child
+  fn split(&mut self, at: usize) {
+    let tail = self.label.split_off(at);
+    let mut lower = RadixNode::new(tail, self.data.take());
+    std::mem::swap(&mut lower.child, &mut self.child);
+    self.child.insert(lower.label[0], lower);
+  }
+
+  // absorbs the only child when this node holds no value
+  fn compress(&mut self) {
+    if self.data.is_none() && self.child.len() == 1 {
+      let (_, only) = self.child.drain().next().unwrap();
+      self.label.extend(only.label);
+      self.child = only.child;
+      self.data = only.data;
+    }
+  }
+
+  fn insert(&mut self, key: &[K], data: U) {
+    if key.is_empty() {
+      self.data = Some(data);
+      return;
+    }
+    match self.child.get_mut(&key[0]) {
+      None => {
+        self.child.insert(key[0], RadixNode::new(key.to_vec(), Some(data)));
+      },
+      Some(c) => {
+        let common = c.label.iter().zip(key).take_while(|(a, b)| a == b).count();
+        if common < c.label.len() {
+          c.split(common);
+        }
+        c.insert(&key[common..], data);
+      },
+    }
+  }
+
+  fn remove(&mut self, key: &[K]) -> Option<U> {
+    if key.is_empty() {
+      return self.data.take();
+    }
+    let c = self.child.get_mut(&key[0])?;
+    if !key.starts_with(&c.label) {
+      return None;
+    }
+    let len = c.label.len();
+    let removed = c.remove(&key[len..])?;
+    if c.data.is_none() && c.child.is_empty() {
+      self.child.remove(&key[0]);
+    } else {
+      c.compress();
+    }
+    Some(removed)
+  }
+}
+
+pub struct RadixTrie<K, U> {
+  root: RadixNode<K, U>,
+}
+
+impl<K: Eq + Hash + Copy, U: Default + Clone> RadixTrie<K, U> {
+  pub fn new() -> RadixTrie<K, U> {
+    RadixTrie { root: RadixNode::new(vec![], None) }
+  }
+  pub fn insert(&mut self, path: impl Iterator<Item = K>, data: U) {
+    let key: Vec<K> = path.collect();
+    self.root.insert(&key, data);
+  }
+  // the node at or just below the end of key, with the full path leading to it
+  fn walk(&self, key: &[K]) -> Option<(Vec<K>, &RadixNode<K, U>)> {
+    let mut node = &self.root;
+    let mut path = vec![];
+    let mut rest = key;
+    while !rest.is_empty() {
+      let c = node.child.get(&rest[0])?;
+      let common = c.label.iter().zip(rest).take_while(|(a, b)| a == b).count();
+      if common < rest.len() && common < c.label.len() {
+        return None;
+      }
+      path.extend_from_slice(&c.label);
+      rest = &rest[common..];
+      node = c;
+    }
+    Some((path, node))
+  }
+  // the value stored under exactly this key
+  pub fn get(&self, path: impl Iterator<Item = K>) -> Option<U> {
+    let key: Vec<K> = path.collect();
+    match self.walk(&key) {
+      Some((full, node)) if full.len() == key.len() => node.data.clone(),
+      _ => None,
+    }
+  }
+  pub fn remove(&mut self, path: impl Iterator<Item = K>) -> Option<U> {
+    // the root keeps its empty label, it is never merged into a child
+    let key: Vec<K> = path.collect();
+    self.root.remove(&key)
+  }
+  pub fn clear(&mut self) {
+    self.root = RadixNode::new(vec![], None);
+  }
+}
+
+impl<K: Eq + Hash + Copy + Ord, U: Default + Clone> RadixTrie<K, U> {
+  // every stored key starting with prefix together with its value, in lexicographic order
+  pub fn iter_prefix(&self, prefix: impl Iterator<Item = K>) -> RadixTrieIter<'_, K, U> {
+    let prefix: Vec<K> = prefix.collect();
+    RadixTrieIter {
+      stack: self.walk(&prefix).into_iter().collect(),
+    }
+  }
+}
+
+impl<K: Eq + Hash + Copy, U: Default + Clone> Default for RadixTrie<K, U> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<K: Eq + Hash + Copy + Ord, U: Default + Clone> From<&Trie<K, U>> for RadixTrie<K, U> {
+  fn from(trie: &Trie<K, U>) -> Self {
+    let mut radix = RadixTrie::new();
+    for (key, data) in trie.iter() {
+      radix.root.insert(&key, data);
+    }
+    radix
+  }
+}
+
+// depth-first walk, children are pushed in reverse order so the smallest key pops first
+pub struct RadixTrieIter<'a, K, U> {
+  stack: Vec<(Vec<K>, &'a RadixNode<K, U>)>,
+}
+
+impl<'a, K: Eq + Hash + Copy + Ord, U: Clone> Iterator for RadixTrieIter<'a, K, U> {
+  type Item = (Vec<K>, U);
+  fn next(&mut self) -> Option<Self::Item> {
+    while let Some((path, node)) = self.stack.pop() {
+      let mut keys: Vec<&K> = node.child.keys().collect();
+      keys.sort();
+      for k in keys.into_iter().rev() {
+        let c = &node.child[k];
+        let mut child_path = path.clone();
+        child_path.extend_from_slice(&c.label);
+        self.stack.push((child_path, c));
+      }
+      if let Some(data) = &node.data {
+        return Some((path, data.clone()));
+      }
+    }
+    None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+  use std::collections::BTreeMap;
+
+  fn count_and_check(node: &RadixNode<u8, u32>, is_root: bool) -> usize {
+    if !is_root {
+      assert!(!node.label.is_empty());
+      assert!(node.data.is_some() || node.child.len() >= 2);
+    }
+    for (k, c) in &node.child {
+      assert_eq!(*k, c.label[0]);
+    }
+    1 + node.child.values().map(|c| count_and_check(c, false)).sum::<usize>()
+  }
+
+  #[test]
+  fn test_basic() {
+    let mut trie: RadixTrie<char, u32> = RadixTrie::new();
+    trie.insert("romane".chars(), 1);
+    trie.insert("romanus".chars(), 2);
+    trie.insert("romulus".chars(), 3);
+    trie.insert("rubens".chars(), 4);
+    assert_eq!(Some(2), trie.get("romanus".chars()));
+    assert_eq!(None, trie.get("roman".chars()));
+    assert_eq!(None, trie.get("romanes".chars()));
+    let keys: Vec<String> = trie.iter_prefix("rom".chars()).map(|(k, _)| k.into_iter().collect()).collect();
+    assert_eq!(vec!["romane", "romanus", "romulus"], keys);
+    let keys: Vec<String> = trie.iter_prefix("roma".chars()).map(|(k, _)| k.into_iter().collect()).collect();
+    assert_eq!(vec!["romane", "romanus"], keys);
+    assert_eq!(Some(1), trie.remove("romane".chars()));
+    assert_eq!(None, trie.remove("romane".chars()));
+    assert_eq!(None, trie.remove("rom".chars()));
+    assert_eq!(Some(2), trie.get("romanus".chars()));
+    trie.clear();
+    assert_eq!(None, trie.get("romanus".chars()));
+  }
+
+  #[test]
+  fn test_random_against_btree_map() {
+    let mut rand = rand_gen(41);
+    let mut trie: RadixTrie<u8, u32> = RadixTrie::new();
+    let mut map = BTreeMap::new();
+    for step in 0..5000 {
+      let len = rand(6);
+      let key: Vec<u8> = (0..len).map(|_| rand(3) as u8).collect();
+      if rand(3) == 0 {
+        assert_eq!(map.remove(&key), trie.remove(key.iter().copied()));
+      } else {
+        map.insert(key.clone(), step);
+        trie.insert(key.iter().copied(), step);
+      }
+      assert_eq!(map.get(&key).copied(), trie.get(key.iter().copied()));
+      if step % 50 == 0 {
+        let nodes = count_and_check(&trie.root, true);
+        assert!(nodes <= 2 * map.len() + 1);
+        let prefix: Vec<u8> = (0..rand(3)).map(|_| rand(3) as u8).collect();
+        let expected: Vec<(Vec<u8>, u32)> = map.iter()
+          .filter(|(k, _)| k.starts_with(&prefix))
+          .map(|(k, v)| (k.clone(), *v))
+          .collect();
+        assert_eq!(expected, trie.iter_prefix(prefix.into_iter()).collect::<Vec<_>>());
+      }
+    }
+  }
+
+  #[test]
+  fn test_from_trie() {
+    let mut trie: Trie<char, usize> = Trie::new();
+    let words = ["to", "tea", "ted", "ten", "A", "i", "in", "inn"];
+    for (i, w) in words.iter().enumerate() {
+      trie.insert(w.chars(), i);
+    }
+    let radix = RadixTrie::from(&trie);
+    for (i, w) in words.iter().enumerate() {
+      assert_eq!(Some(i), radix.get(w.chars()));
+    }
+    assert_eq!(None, radix.get("te".chars()));
+    assert_eq!(trie.iter_prefix("".chars()).collect::<Vec<_>>(), radix.iter_prefix("".chars()).collect::<Vec<_>>());
+  }
+}
@@ -0,0 +1,126 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::ops::Range;
+
+// 2^61 - 1, a Mersenne prime, so reduction is a shift and an add
+const P: u64 = (1 << 61) - 1;
+
+fn mul(a: u64, b: u64) -> u64 {
+  let x = a as u128 * b as u128;
+  let r = (x >> 61) as u64 + (x as u64 & P);
+  if r >= P { r - P } else { r }
+}
+
+// Polynomial hashes of every substring in O(1) after O(n) prefix sums: h(s) = sum s[i] b^(len-1-i)
+// mod 2^61 - 1. Modulo a prime instead of 2^64, where Thue-Morse strings collide for every odd base, and
+// with the base drawn at random, two different strings of length n collide with probability
+// at most n / 2^61 however they were chosen.
+pub struct RollingHash {
+  prefix: Vec<u64>, // prefix[i] = h(text[..i])
+  power: Vec<u64>, // power[i] = b^i
+  base: u64,
+}
+
+impl RollingHash {
+  pub fn new<K: Copy + Into<u64>>(text: &[K]) -> RollingHash {
+    let random = RandomState::new().build_hasher().finish();
+    Self::with_base(text, 256 + random % (P - 512))
+  }
+
+  // a fixed base, for hashes that must agree between runs; 256 <= base < P
+  pub fn with_base<K: Copy + Into<u64>>(text: &[K], base: u64) -> RollingHash {
+    assert!((256..P).contains(&base));
+    let mut prefix = vec![0];
+    let mut power = vec![1];
+    for &k in text {
+      let h = mul(*prefix.last().unwrap(), base) + k.into() % P;
+      prefix.push(if h >= P { h - P } else { h });
+      power.push(mul(*power.last().unwrap(), base));
+    }
+    RollingHash { prefix, power, base }
+  }
+
+  pub fn len(&self) -> usize {
+    self.prefix.len() - 1
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  pub fn hash(&self, range: Range<usize>) -> u64 {
+    let shifted = mul(self.prefix[range.start], self.power[range.len()]);
+    let h = self.prefix[range.end] + P - shifted;
+    if h >= P { h - P } else { h }
+  }
+
+  // the hash of a string from outside the text under the same base, for pattern matching
+  pub fn hash_of<K: Copy + Into<u64>>(&self, s: &[K]) -> u64 {
+    s.iter().fold(0, |h, &k| {
+      let h = mul(h, self.base) + k.into() % P;
+      if h >= P { h - P } else { h }
+    })
+  }
+
+  // whether the two substrings are equal, up to the collision probability
+  pub fn eq(&self, a: Range<usize>, b: Range<usize>) -> bool {
+    a.len() == b.len() && self.hash(a) == self.hash(b)
+  }
+
+  // length of the longest common prefix of the suffixes at i and j, O(log n)
+  pub fn lcp_by_binary_search(&self, i: usize, j: usize) -> usize {
+    let (mut lo, mut hi) = (0, self.len() - i.max(j));
+    while lo < hi {
+      let mid = (lo + hi).div_ceil(2);
+      if self.eq(i..i + mid, j..j + mid) {
+        lo = mid;
+      } else {
+        hi = mid - 1;
+      }
+    }
+    lo
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_random_against_naive() {
+    let mut rand = rand_gen(139);
+    for _ in 0..100 {
+      let text: Vec<u8> = (0..1 + rand(60)).map(|_| b'a' + rand(2) as u8).collect();
+      let n = text.len();
+      let hash = RollingHash::new(&text);
+      assert_eq!(n, hash.len());
+      for _ in 0..100 {
+        let (i, j) = (rand(n as u64 + 1) as usize, rand(n as u64 + 1) as usize);
+        let len = rand((n - i.max(j)) as u64 + 1) as usize;
+        assert_eq!(text[i..i + len] == text[j..j + len], hash.eq(i..i + len, j..j + len));
+        assert_eq!(hash.hash(i..i + len), hash.hash_of(&text[i..i + len]));
+        let lcp = text[i..].iter().zip(&text[j..]).take_while(|(a, b)| a == b).count();
+        assert_eq!(lcp, hash.lcp_by_binary_search(i, j));
+      }
+    }
+  }
+
+  #[test]
+  fn test_thue_morse() {
+    // the string and its complement share every hash mod 2^64 for odd bases once long enough
+    let thue: Vec<u8> = (0..2048u32).map(|i| (i.count_ones() % 2) as u8).collect();
+    let complement: Vec<u8> = thue.iter().map(|&b| 1 - b).collect();
+    let mut both = thue.clone();
+    both.extend_from_slice(&complement);
+    for base in [257, 1 << 40 | 1, P - 2] {
+      let hash = RollingHash::with_base(&both, base);
+      assert!(!hash.eq(0..2048, 2048..4096));
+      assert_eq!(hash.hash(0..2048), RollingHash::with_base(&thue, base).hash(0..2048));
+    }
+    let hash = RollingHash::new(&[1u32, 2, 3, 1, 2, 3]);
+    assert!(hash.eq(0..3, 3..6) && !hash.eq(0..3, 1..4));
+    assert_eq!(3, hash.lcp_by_binary_search(0, 3));
+    assert_eq!(0, hash.lcp_by_binary_search(0, 6));
+  }
+}
@@ -0,0 +1,122 @@
+use super::suffix_array::SuffixArray;
+use crate::succinct_rmq::SuccinctRmq;
+
+// longest common extensions: the common prefix of two suffixes is the least LCP entry between
+// their ranks
+struct Lce {
+  rank: Vec<usize>,
+  lcp: Vec<usize>,
+  rmq: SuccinctRmq,
+}
+
+impl Lce {
+  fn new<K: Ord + Copy>(s: &[K]) -> Lce {
+    let sa = SuffixArray::new(s);
+    Lce { rank: sa.rank().to_vec(), lcp: sa.lcp().to_vec(), rmq: SuccinctRmq::new(sa.lcp()) }
+  }
+
+  // the empty suffix at n included
+  fn get(&self, i: usize, j: usize) -> usize {
+    let n = self.rank.len();
+    if i == n || j == n {
+      return 0;
+    }
+    if i == j {
+      return n - i;
+    }
+    let (a, b) = (self.rank[i].min(self.rank[j]), self.rank[i].max(self.rank[j]));
+    self.lcp[self.rmq.argmin(a + 1..b + 1)]
+  }
+}
+
+// All runs of s, its maximal repetitions: the (start, end, period) of every substring
+// s[start..end] at least twice as long as its smallest period that cannot be extended either
+// way with the same period. By the runs theorem (Bannai et al.) every run has a Lyndon word of
+// its period starting in it that is the longest Lyndon word there, under either the order of
+// the keys or its reverse. So each entry of the Lyndon arrays for both orders is one candidate,
+// extended by LCE queries on s and its reverse; there are fewer than n runs. O(n log n) for the
+// suffix arrays and O(n) otherwise.
+pub fn runs<K: Ord + Copy>(s: &[K]) -> Vec<(usize, usize, usize)> {
+  let n = s.len();
+  let forward = Lce::new(s);
+  let reversed: Vec<K> = s.iter().rev().copied().collect();
+  let backward = Lce::new(&reversed);
+  let mut found = vec![];
+  for inverted in [false, true] {
+    // suffix i against a later suffix j under the order
+    let less = |i: usize, j: usize| {
+      let l = forward.get(i, j);
+      j + l < n && (s[i + l] < s[j + l]) != inverted
+    };
+    // end[i]: the end of the longest Lyndon word at i, the next smaller suffix
+    let mut end = vec![n; n];
+    for i in (0..n).rev() {
+      let mut j = i + 1;
+      while j < n && less(i, j) {
+        j = end[j];
+      }
+      end[i] = j;
+      // s[i..j] repeats as far as s[j..] follows it and s[..i] ends like s[..j]
+      let (start, stop) = (i - backward.get(n - i, n - j), j + forward.get(i, j));
+      if stop - start >= 2 * (j - i) {
+        found.push((start, stop, j - i));
+      }
+    }
+  }
+  found.sort_unstable();
+  found.dedup();
+  found
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  fn naive(s: &[u8]) -> Vec<(usize, usize, usize)> {
+    let n = s.len();
+    let mut found: Vec<(usize, usize, usize)> = vec![];
+    for p in 1..=n / 2 {
+      let mut k = 0;
+      while k + p < n {
+        if s[k] != s[k + p] {
+          k += 1;
+          continue;
+        }
+        let start = k;
+        while k + p < n && s[k] == s[k + p] {
+          k += 1;
+        }
+        if k + p - start >= 2 * p {
+          found.push((start, k + p, p));
+        }
+      }
+    }
+    // the same interval for a multiple of its smallest period
+    found.sort_unstable();
+    found.dedup_by_key(|&mut (start, end, _)| (start, end));
+    found
+  }
+
+  #[test]
+  fn test_examples() {
+    assert_eq!(vec![(0, 5, 1)], runs(b"aaaaa"));
+    assert_eq!(vec![(0, 2, 1), (1, 7, 2), (6, 8, 1)], runs(b"aabababb"));
+    assert_eq!(naive(b"mississippi"), runs(b"mississippi"));
+    assert!(runs(b"abcd").is_empty());
+    assert!(runs::<u8>(b"").is_empty());
+  }
+
+  #[test]
+  fn test_random_against_naive() {
+    let mut rand = rand_gen(191);
+    for round in 0..500 {
+      let sigma = 1 + rand(3);
+      let n = if round % 50 == 0 { 1000 } else { rand(40) };
+      let s: Vec<u8> = (0..n).map(|_| b'a' + rand(sigma) as u8).collect();
+      let found = runs(&s);
+      assert_eq!(naive(&s), found);
+      assert!(found.len() < s.len().max(1));
+    }
+  }
+}
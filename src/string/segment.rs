@@ -0,0 +1,142 @@
+use std::hash::Hash;
+use std::ops::Range;
+
+use super::trie::Trie;
+
+// Dictionary segmentation: splitting a text into words stored in a Trie, e.g. CJK text without
+// spaces or log lines into known tokens. Positions no word covers become single key tokens.
+// Results are ranges into the text, in order and covering all of it.
+
+// greedy: at each position take the longest dictionary word, O(n L) for words of length L
+pub fn segment_max_munch<K: Eq + Hash + Copy, U>(text: &[K], dict: &Trie<K, U>) -> Vec<Range<usize>> {
+  let mut tokens = vec![];
+  let mut i = 0;
+  while i < text.len() {
+    let longest = dict.common_prefix_search(text[i..].iter().copied()).last().map_or(0, |m| m.0);
+    let end = i + longest.max(1);
+    tokens.push(i..end);
+    i = end;
+  }
+  tokens
+}
+
+// Viterbi over the words starting at each position: the segmentation of least total cost, where
+// a dictionary word costs word_cost(its value), say a negative log probability, and a key no word
+// starts with costs unknown_cost. best[i] is the cheapest split of text[..i].
+pub fn segment_by_cost<K: Eq + Hash + Copy, U>(text: &[K], dict: &Trie<K, U>, word_cost: impl Fn(&U) -> f64, unknown_cost: f64) -> Vec<Range<usize>> {
+  let n = text.len();
+  let mut best = vec![f64::INFINITY; n + 1];
+  let mut from = vec![0; n + 1];
+  best[0] = 0.0;
+  for i in 0..n {
+    let matches = dict.common_prefix_search(text[i..].iter().copied());
+    let steps = matches.iter().filter(|m| m.0 > 0).map(|&(len, data)| (len, word_cost(data)));
+    let unknown = Some((1, unknown_cost)).filter(|_| !matches.iter().any(|m| m.0 == 1));
+    for (len, cost) in steps.chain(unknown) {
+      if best[i] + cost < best[i + len] {
+        best[i + len] = best[i] + cost;
+        from[i + len] = i;
+      }
+    }
+  }
+  let mut tokens = vec![];
+  let mut end = n;
+  while end > 0 {
+    tokens.push(from[end]..end);
+    end = from[end];
+  }
+  tokens.reverse();
+  tokens
+}
+
+// the segmentation with the fewest unknown keys, and among those the fewest tokens
+pub fn segment<K: Eq + Hash + Copy, U>(text: &[K], dict: &Trie<K, U>) -> Vec<Range<usize>> {
+  // an unknown key costs more than any number of words
+  segment_by_cost(text, dict, |_| 1.0, text.len() as f64 + 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  fn words(text: &str, tokens: &[Range<usize>]) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    tokens.iter().map(|r| chars[r.clone()].iter().collect()).collect()
+  }
+
+  fn dict(words: &[&str]) -> Trie<char, ()> {
+    let mut trie = Trie::new();
+    for w in words {
+      trie.insert(w.chars(), ());
+    }
+    trie
+  }
+
+  #[test]
+  fn test_max_munch_and_dp() {
+    // greedy takes "研究生" and strands "命", the DP finds 研究 生命 起源
+    let dict = dict(&["研究", "研究生", "生命", "命", "起源"]);
+    let text = "研究生命起源";
+    let chars: Vec<char> = text.chars().collect();
+    assert_eq!(vec!["研究生", "命", "起源"], words(text, &segment_max_munch(&chars, &dict)));
+    assert_eq!(vec!["研究", "生命", "起源"], words(text, &segment(&chars, &dict)));
+
+    let dict = self::dict(&["the", "them", "theme", "me", "mesh", "she", "he", "shell"]);
+    let text = "themeshellx";
+    let chars: Vec<char> = text.chars().collect();
+    assert_eq!(vec!["theme", "shell", "x"], words(text, &segment_max_munch(&chars, &dict)));
+    assert_eq!(vec!["theme", "shell", "x"], words(text, &segment(&chars, &dict)));
+    assert!(segment(&[], &dict).is_empty());
+  }
+
+  #[test]
+  fn test_by_cost() {
+    let mut dict: Trie<char, f64> = Trie::new();
+    for (w, p) in [("a", 0.4), ("b", 0.4), ("ab", 0.05), ("abc", 0.01), ("c", 0.1)] {
+      dict.insert(w.chars(), -f64::ln(p));
+    }
+    let chars: Vec<char> = "abc".chars().collect();
+    // a b c: 0.4 * 0.4 * 0.1 = 0.016 beats abc = 0.01 and ab c = 0.005
+    assert_eq!(vec![0..1, 1..2, 2..3], segment_by_cost(&chars, &dict, |&c| c, 100.0));
+    assert_eq!(vec![0..3], segment(&chars, &dict));
+    let chars: Vec<char> = "xabz".chars().collect();
+    assert_eq!(vec![0..1, 1..3, 3..4], segment(&chars, &dict));
+  }
+
+  #[test]
+  fn test_random_optimal() {
+    let mut rand = rand_gen(101);
+    for _ in 0..200 {
+      let mut dict: Trie<u8, ()> = Trie::new();
+      let mut list = vec![];
+      for _ in 0..rand(8) {
+        let w: Vec<u8> = (0..1 + rand(3)).map(|_| rand(3) as u8).collect();
+        dict.insert(w.iter().copied(), ());
+        list.push(w);
+      }
+      let text: Vec<u8> = (0..rand(12)).map(|_| rand(3) as u8).collect();
+      // (unknown, tokens) of the best split of every prefix, by brute force over split points
+      let n = text.len();
+      let mut best = vec![(usize::MAX, usize::MAX); n + 1];
+      best[0] = (0, 0);
+      for end in 1..=n {
+        for start in 0..end {
+          let known = list.iter().any(|w| *w == text[start..end]);
+          if !known && end - start > 1 {
+            continue;
+          }
+          let (u, t) = best[start];
+          best[end] = best[end].min((u + !known as usize, t + 1));
+        }
+      }
+      let tokens = segment(&text, &dict);
+      assert_eq!(n, tokens.iter().map(|r| r.len()).sum::<usize>());
+      assert!(tokens.windows(2).all(|w| w[0].end == w[1].start));
+      let unknown = tokens.iter().filter(|r| !list.iter().any(|w| *w == text[(*r).clone()])).count();
+      assert_eq!(best[n], (unknown, tokens.len()));
+      let munched = segment_max_munch(&text, &dict);
+      assert_eq!(n, munched.iter().map(|r| r.len()).sum::<usize>());
+    }
+  }
+}
@@ -0,0 +1,143 @@
+use std::cmp::Ordering;
+
+// ranges shorter than this are finished by insertion sort on what is left of the strings
+const SMALL: usize = 16;
+
+// sorts order[..] by the strings from depth on, ties by index
+fn insertion_sort<K: Ord>(strings: &[&[K]], order: &mut [usize], depth: usize) {
+  let key = |i: usize| (&strings[i][depth.min(strings[i].len())..], i);
+  for i in 1..order.len() {
+    let mut j = i;
+    while j > 0 && key(order[j - 1]) > key(order[j]) {
+      order.swap(j - 1, j);
+      j -= 1;
+    }
+  }
+}
+
+// MSD radix sort of byte strings: a counting sort on the byte at the current depth, with the
+// strings that end there first, then each bucket on the next byte, the buckets kept on an
+// explicit stack so long common prefixes cannot overflow the call stack. Stable, and each byte
+// is looked at about once, O(total distinguishing prefix length + 256 per bucket split). Returns
+// the indices of the strings in sorted order.
+pub fn msd_radix_sort(strings: &[&[u8]]) -> Vec<usize> {
+  let mut order: Vec<usize> = (0..strings.len()).collect();
+  let mut buffer = vec![0; strings.len()];
+  let mut stack = vec![(0, strings.len(), 0)];
+  while let Some((lo, hi, depth)) = stack.pop() {
+    if hi - lo < SMALL {
+      insertion_sort(strings, &mut order[lo..hi], depth);
+      continue;
+    }
+    // bucket 0 for strings ending here, 1 + b for byte b
+    let bucket = |i: usize| strings[i].get(depth).map_or(0, |&b| 1 + b as usize);
+    let mut starts = [0; 258];
+    for &i in &order[lo..hi] {
+      starts[bucket(i) + 1] += 1;
+    }
+    for b in 0..257 {
+      starts[b + 1] += starts[b];
+    }
+    let mut fill = starts;
+    for &i in &order[lo..hi] {
+      buffer[lo + fill[bucket(i)]] = i;
+      fill[bucket(i)] += 1;
+    }
+    order[lo..hi].copy_from_slice(&buffer[lo..hi]);
+    // the ended strings are equal, and already in index order
+    for b in 1..257 {
+      if starts[b + 1] - starts[b] > 1 {
+        stack.push((lo + starts[b], lo + starts[b + 1], depth + 1));
+      }
+    }
+  }
+  order
+}
+
+// Three-way string quicksort (Bentley & Sedgewick) over any ordered keys: partitions around the
+// key of a pivot at the current depth into less, equal and greater, and only the equal part goes
+// one key deeper, so no prefix is compared twice. Strings that end at the pivot's depth are
+// equal, and are put in index order, which makes the result the stable order. Expected
+// O(total distinguishing prefix length + n log n) key comparisons. Returns the indices of the
+// strings in sorted order.
+pub fn multikey_quicksort<K: Ord + Copy>(strings: &[&[K]]) -> Vec<usize> {
+  let mut order: Vec<usize> = (0..strings.len()).collect();
+  let mut stack = vec![(0, strings.len(), 0)];
+  while let Some((lo, hi, depth)) = stack.pop() {
+    if hi - lo < SMALL {
+      insertion_sort(strings, &mut order[lo..hi], depth);
+      continue;
+    }
+    let key = |i: usize| strings[i].get(depth).copied();
+    // median of three
+    let mut sample = [order[lo], order[lo + (hi - lo) / 2], order[hi - 1]];
+    sample.sort_by_key(|&i| key(i));
+    let pivot = key(sample[1]);
+    // order[lo..lt] less, [lt..i] equal, [gt..hi] greater
+    let (mut lt, mut i, mut gt) = (lo, lo, hi);
+    while i < gt {
+      match key(order[i]).cmp(&pivot) {
+        Ordering::Less => {
+          order.swap(lt, i);
+          lt += 1;
+          i += 1;
+        },
+        Ordering::Greater => {
+          gt -= 1;
+          order.swap(i, gt);
+        },
+        Ordering::Equal => i += 1,
+      }
+    }
+    stack.push((lo, lt, depth));
+    stack.push((gt, hi, depth));
+    if pivot.is_some() {
+      stack.push((lt, gt, depth + 1));
+    } else {
+      order[lt..gt].sort_unstable();
+    }
+  }
+  order
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_words() {
+    let words: Vec<&[u8]> = ["she", "sells", "sea", "shells", "by", "the", "sea", "shore", "", "s"].iter().map(|w| w.as_bytes()).collect();
+    let expected = vec![8, 4, 9, 2, 6, 1, 0, 3, 7, 5];
+    assert_eq!(expected, msd_radix_sort(&words));
+    assert_eq!(expected, multikey_quicksort(&words));
+    assert!(msd_radix_sort(&[]).is_empty());
+    assert!(multikey_quicksort::<u32>(&[]).is_empty());
+  }
+
+  #[test]
+  fn test_random_against_sort() {
+    let mut rand = rand_gen(307);
+    for round in 0..100 {
+      let sigma = 1 + rand(if round % 2 == 0 { 3 } else { 256 });
+      let n = if round % 10 == 0 { 3000 } else { rand(200) };
+      // shared prefixes of varying length, so buckets go deep
+      let prefix: Vec<u8> = (0..rand(50)).map(|_| rand(sigma) as u8).collect();
+      let strings: Vec<Vec<u8>> = (0..n)
+        .map(|_| {
+          let cut = rand(prefix.len() as u64 + 1) as usize;
+          let tail = rand(8);
+          prefix[..cut].iter().copied().chain((0..tail).map(|_| rand(sigma) as u8)).collect()
+        })
+        .collect();
+      let slices: Vec<&[u8]> = strings.iter().map(|s| &s[..]).collect();
+      let mut expected: Vec<usize> = (0..slices.len()).collect();
+      expected.sort_by_key(|&i| slices[i]);
+      assert_eq!(expected, msd_radix_sort(&slices));
+      assert_eq!(expected, multikey_quicksort(&slices));
+      let wide: Vec<Vec<u32>> = strings.iter().map(|s| s.iter().map(|&b| b as u32 * 1000).collect()).collect();
+      let wide: Vec<&[u32]> = wide.iter().map(|s| &s[..]).collect();
+      assert_eq!(expected, multikey_quicksort(&wide));
+    }
+  }
+}
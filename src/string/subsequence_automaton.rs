@@ -0,0 +1,128 @@
+// Subsequence automaton: the DFA of all subsequences of a text, whose state i is "matched a
+// prefix of the query within text[..i]" and whose transition on k jumps past the next k at or
+// after i. The transitions are a dense table over the text's distinct keys, (n + 1) sigma
+// entries built right to left in O(n sigma); a query then takes a binary search among the keys
+// and a lookup per key, O(m log sigma) whatever the text length.
+pub struct SubsequenceAutomaton<K> {
+  keys: Vec<K>, // distinct, sorted
+  next: Vec<u32>, // next[i * sigma + c]: the first position at or after i holding keys[c], or n
+}
+
+impl<K: Ord + Copy> SubsequenceAutomaton<K> {
+  pub fn new(text: &[K]) -> SubsequenceAutomaton<K> {
+    assert!(text.len() < u32::MAX as usize);
+    let mut keys = text.to_vec();
+    keys.sort_unstable();
+    keys.dedup();
+    let (n, sigma) = (text.len(), keys.len());
+    let mut next = vec![n as u32; (n + 1) * sigma];
+    for i in (0..n).rev() {
+      next.copy_within((i + 1) * sigma..(i + 2) * sigma, i * sigma);
+      next[i * sigma + keys.binary_search(&text[i]).unwrap()] = i as u32;
+    }
+    SubsequenceAutomaton { keys, next }
+  }
+
+  pub fn len(&self) -> usize {
+    self.next.len().checked_div(self.keys.len()).map_or(0, |rows| rows - 1)
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.keys.is_empty()
+  }
+
+  // the first position at or after i holding k
+  pub fn next_position(&self, i: usize, k: &K) -> Option<usize> {
+    let c = self.keys.binary_search(k).ok()?;
+    let at = *self.next.get(i * self.keys.len() + c)? as usize;
+    Some(at).filter(|&at| at < self.len())
+  }
+
+  // where query embeds in the text taking every key as early as possible, None if it is not a
+  // subsequence
+  pub fn leftmost_embedding(&self, query: impl IntoIterator<Item = K>) -> Option<Vec<usize>> {
+    let mut i = 0;
+    let mut positions = vec![];
+    for k in query {
+      let at = self.next_position(i, &k)?;
+      positions.push(at);
+      i = at + 1;
+    }
+    Some(positions)
+  }
+
+  pub fn is_subsequence(&self, query: impl IntoIterator<Item = K>) -> bool {
+    let mut i = 0;
+    for k in query {
+      match self.next_position(i, &k) {
+        Some(at) => i = at + 1,
+        None => return false,
+      }
+    }
+    true
+  }
+
+  // distinct subsequences of the text, the empty one included, modulo modulus: each is read
+  // along one path of the automaton, so the count from state i is one plus the counts after
+  // every transition out of it
+  pub fn distinct_subsequences(&self, modulus: u64) -> u64 {
+    let (n, sigma) = (self.len(), self.keys.len());
+    let mut count = vec![0u64; n + 2];
+    for i in (0..=n).rev() {
+      let mut total = 1 % modulus;
+      for &at in &self.next[i * sigma..(i + 1) * sigma] {
+        if (at as usize) < n {
+          total = ((total as u128 + count[at as usize + 1] as u128) % modulus as u128) as u64;
+        }
+      }
+      count[i] = total;
+    }
+    count[0]
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+  use std::collections::HashSet;
+
+  #[test]
+  fn test_examples() {
+    let sa = SubsequenceAutomaton::new(b"abracadabra");
+    assert_eq!(11, sa.len());
+    assert!(sa.is_subsequence(b"acdr".iter().copied()));
+    assert!(sa.is_subsequence(b"".iter().copied()));
+    assert!(!sa.is_subsequence(b"dc".iter().copied()));
+    assert!(!sa.is_subsequence(b"z".iter().copied()));
+    assert_eq!(Some(vec![0, 4, 6, 9]), sa.leftmost_embedding(b"acdr".iter().copied()));
+    assert_eq!(Some(10), sa.next_position(8, &b'a'));
+    assert_eq!(None, sa.next_position(11, &b'a'));
+    // "", a, b, ab, ba, aa, aba
+    assert_eq!(7, SubsequenceAutomaton::new(b"aba").distinct_subsequences(1_000_000_007));
+    let empty = SubsequenceAutomaton::<u8>::new(b"");
+    assert!(empty.is_empty() && empty.is_subsequence(b"".iter().copied()) && !empty.is_subsequence(b"a".iter().copied()));
+    assert_eq!(1, empty.distinct_subsequences(1_000_000_007));
+  }
+
+  #[test]
+  fn test_random_against_naive() {
+    let mut rand = rand_gen(263);
+    for _ in 0..200 {
+      let sigma = 1 + rand(4);
+      let text: Vec<u8> = (0..rand(12)).map(|_| rand(sigma) as u8).collect();
+      let sa = SubsequenceAutomaton::new(&text);
+      let all: HashSet<Vec<u8>> = (0..1u32 << text.len()).map(|mask| (0..text.len()).filter(|&i| mask >> i & 1 == 1).map(|i| text[i]).collect()).collect();
+      assert_eq!(all.len() as u64, sa.distinct_subsequences(u64::MAX));
+      assert_eq!(all.len() as u64 % 97, sa.distinct_subsequences(97));
+      for _ in 0..30 {
+        let query: Vec<u8> = (0..rand(6)).map(|_| rand(sigma + 1) as u8).collect();
+        assert_eq!(all.contains(&query), sa.is_subsequence(query.iter().copied()));
+        if let Some(positions) = sa.leftmost_embedding(query.iter().copied()) {
+          assert_eq!(query, positions.iter().map(|&i| text[i]).collect::<Vec<_>>());
+          assert!(positions.windows(2).all(|w| w[0] < w[1]));
+        }
+      }
+    }
+  }
+}
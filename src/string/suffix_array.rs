@@ -0,0 +1,389 @@
+use std::cmp::Ordering;
+use std::ops::Range;
+
+const NONE: usize = usize::MAX;
+
+// SA-IS (Nong, Zhang & Chan) over s with values in 0..=upper, O(n + upper). Suffixes are typed S
+// (smaller than the next suffix) or L; the leftmost S of each run (LMS) are sorted by inducing
+// from the buckets' ends, their substrings renamed and, if not all distinct yet, sorted by
+// recursion, and a final induction places every other suffix from the sorted LMS ones.
+fn sa_is(s: &[usize], upper: usize) -> Vec<usize> {
+  let n = s.len();
+  match n {
+    0 => return vec![],
+    1 => return vec![0],
+    2 => return if s[0] < s[1] { vec![0, 1] } else { vec![1, 0] },
+    _ => {},
+  }
+  let mut ls = vec![false; n]; // true for S
+  for i in (0..n - 1).rev() {
+    ls[i] = if s[i] == s[i + 1] { ls[i + 1] } else { s[i] < s[i + 1] };
+  }
+  // bucket starts for L suffixes and for S suffixes of each value
+  let mut sum_l = vec![0; upper + 1];
+  let mut sum_s = vec![0; upper + 1];
+  for i in 0..n {
+    if !ls[i] {
+      sum_s[s[i]] += 1;
+    } else {
+      sum_l[s[i] + 1] += 1;
+    }
+  }
+  for i in 0..=upper {
+    sum_s[i] += sum_l[i];
+    if i < upper {
+      sum_l[i + 1] += sum_s[i];
+    }
+  }
+  let induce = |sa: &mut Vec<usize>, lms: &[usize]| {
+    sa.clear();
+    sa.resize(n, NONE);
+    let mut buf = sum_s.clone();
+    for &d in lms {
+      if d != n {
+        sa[buf[s[d]]] = d;
+        buf[s[d]] += 1;
+      }
+    }
+    buf.copy_from_slice(&sum_l);
+    sa[buf[s[n - 1]]] = n - 1;
+    buf[s[n - 1]] += 1;
+    for i in 0..n {
+      let v = sa[i];
+      if v != NONE && v >= 1 && !ls[v - 1] {
+        sa[buf[s[v - 1]]] = v - 1;
+        buf[s[v - 1]] += 1;
+      }
+    }
+    buf.copy_from_slice(&sum_l);
+    for i in (0..n).rev() {
+      let v = sa[i];
+      if v != NONE && v >= 1 && ls[v - 1] {
+        buf[s[v - 1] + 1] -= 1;
+        sa[buf[s[v - 1] + 1]] = v - 1;
+      }
+    }
+  };
+  let lms: Vec<usize> = (1..n).filter(|&i| !ls[i - 1] && ls[i]).collect();
+  let mut lms_index = vec![NONE; n + 1];
+  for (j, &i) in lms.iter().enumerate() {
+    lms_index[i] = j;
+  }
+  let m = lms.len();
+  let mut sa = vec![];
+  induce(&mut sa, &lms);
+  if m > 0 {
+    let mut sorted_lms: Vec<usize> = sa.iter().copied().filter(|&v| lms_index[v] != NONE).collect();
+    // name the LMS substrings, equal substrings getting equal names
+    let mut names = vec![0; m];
+    let mut upper = 0;
+    for i in 1..m {
+      let (mut l, mut r) = (sorted_lms[i - 1], sorted_lms[i]);
+      let end_l = lms.get(lms_index[l] + 1).copied().unwrap_or(n);
+      let end_r = lms.get(lms_index[r] + 1).copied().unwrap_or(n);
+      let mut same = end_l - l == end_r - r;
+      if same {
+        while l < end_l && s[l] == s[r] {
+          l += 1;
+          r += 1;
+        }
+        same = l != n && s[l] == s[r];
+      }
+      if !same {
+        upper += 1;
+      }
+      names[lms_index[sorted_lms[i]]] = upper;
+    }
+    let order = sa_is(&names, upper);
+    for (i, &j) in order.iter().enumerate() {
+      sorted_lms[i] = lms[j];
+    }
+    induce(&mut sa, &sorted_lms);
+  }
+  sa
+}
+
+// prefix doubling: suffixes sorted by their first 2k keys from the order by their first k, with
+// two counting sorts per round, O(n log n)
+fn doubling(s: &[usize], upper: usize) -> Vec<usize> {
+  let n = s.len();
+  let mut sa: Vec<usize> = (0..n).collect();
+  sa.sort_by_key(|&i| s[i]);
+  let mut rank = s.to_vec();
+  let mut classes = upper + 1;
+  let mut k = 1;
+  while k < n {
+    // by the second half: suffixes too short to have one first, then in the current order
+    let mut second: Vec<usize> = (n - k..n).collect();
+    second.extend(sa.iter().filter(|&&i| i >= k).map(|&i| i - k));
+    let mut start = vec![0; classes + 1];
+    for &i in &second {
+      start[rank[i] + 1] += 1;
+    }
+    for c in 0..classes {
+      start[c + 1] += start[c];
+    }
+    for &i in &second {
+      sa[start[rank[i]]] = i;
+      start[rank[i]] += 1;
+    }
+    let key = |i: usize| (rank[i], if i + k < n { rank[i + k] as isize } else { -1 });
+    let mut next = vec![0; n];
+    for j in 1..n {
+      next[sa[j]] = next[sa[j - 1]] + (key(sa[j - 1]) != key(sa[j])) as usize;
+    }
+    classes = next[sa[n - 1]] + 1;
+    rank = next;
+    if classes == n {
+      break;
+    }
+    k *= 2;
+  }
+  sa
+}
+
+// Suffix array of a sequence: the starts of all suffixes in lexicographic order, with its
+// inverse and the LCP array. Occurrences of a pattern are a contiguous range of it.
+pub struct SuffixArray<T> {
+  text: Vec<T>,
+  sa: Vec<usize>,
+  rank: Vec<usize>,
+  lcp: Vec<usize>,
+}
+
+impl<T: Ord + Copy> SuffixArray<T> {
+  // built by SA-IS, O(n) after ranking the distinct keys
+  pub fn new(text: &[T]) -> SuffixArray<T> {
+    SuffixArray::build(text, sa_is)
+  }
+
+  // built by prefix doubling, O(n log n); simpler, and the reference SA-IS is tested against
+  pub fn new_doubling(text: &[T]) -> SuffixArray<T> {
+    SuffixArray::build(text, doubling)
+  }
+
+  fn build(text: &[T], sort: fn(&[usize], usize) -> Vec<usize>) -> SuffixArray<T> {
+    let mut keys = text.to_vec();
+    keys.sort_unstable();
+    keys.dedup();
+    let s: Vec<usize> = text.iter().map(|k| keys.binary_search(k).unwrap()).collect();
+    let sa = if s.is_empty() { vec![] } else { sort(&s, keys.len() - 1) };
+    let mut rank = vec![0; sa.len()];
+    for (i, &p) in sa.iter().enumerate() {
+      rank[p] = i;
+    }
+    let lcp = kasai(text, &sa, &rank);
+    SuffixArray { text: text.to_vec(), sa, rank, lcp }
+  }
+
+  pub fn len(&self) -> usize {
+    self.sa.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.sa.is_empty()
+  }
+
+  pub fn text(&self) -> &[T] {
+    &self.text
+  }
+
+  // sa()[i] is the start of the i-th smallest suffix
+  pub fn sa(&self) -> &[usize] {
+    &self.sa
+  }
+
+  // the inverse: rank()[p] is the position of suffix p in sa()
+  pub fn rank(&self) -> &[usize] {
+    &self.rank
+  }
+
+  // lcp()[i] is the longest common prefix of the suffixes sa()[i - 1] and sa()[i], 0 for i = 0
+  pub fn lcp(&self) -> &[usize] {
+    &self.lcp
+  }
+
+  // the suffix at p against pattern, looking no further than the pattern's length
+  fn compare(&self, p: usize, pattern: &[T]) -> Ordering {
+    let suffix = &self.text[p..];
+    suffix[..suffix.len().min(pattern.len())].cmp(pattern)
+  }
+
+  // the range of sa() whose suffixes start with pattern, so its length is the number of
+  // occurrences. Two binary searches, O(m log n).
+  pub fn find(&self, pattern: &[T]) -> Range<usize> {
+    let lo = self.sa.partition_point(|&p| self.compare(p, pattern) == Ordering::Less);
+    let hi = lo + self.sa[lo..].partition_point(|&p| self.compare(p, pattern) == Ordering::Equal);
+    lo..hi
+  }
+
+  // the longest prefix of pattern occurring in the text, as (start, length). The range of
+  // suffixes agreeing with pattern narrows one key at a time, O(m log n).
+  pub fn longest_match(&self, pattern: &[T]) -> (usize, usize) {
+    let (mut lo, mut hi) = (0, self.sa.len());
+    let mut depth = 0;
+    while depth < pattern.len() {
+      // suffixes in lo..hi share depth keys; among them those too short sort first
+      let key = |p: usize| self.text.get(p + depth);
+      let k = Some(&pattern[depth]);
+      let l = lo + self.sa[lo..hi].partition_point(|&p| key(p) < k);
+      let h = l + self.sa[l..hi].partition_point(|&p| key(p) == k);
+      if l == h {
+        break;
+      }
+      lo = l;
+      hi = h;
+      depth += 1;
+    }
+    if lo == hi { (0, 0) } else { (self.sa[lo], depth) }
+  }
+
+  // every start of pattern in the text, in increasing order
+  pub fn occurrences(&self, pattern: &[T]) -> Vec<usize> {
+    let mut found = self.sa[self.find(pattern)].to_vec();
+    found.sort_unstable();
+    found
+  }
+
+  // number of distinct non-empty substrings: each suffix brings its prefixes longer than its
+  // common prefix with the suffix before it in sa
+  pub fn distinct_substring_count(&self) -> u64 {
+    self.sa.iter().zip(&self.lcp).map(|(&p, &h)| (self.text.len() - p - h) as u64).sum()
+  }
+
+  // the distinct non-empty substrings with a length in lengths, in lexicographic order: the new
+  // prefixes of each suffix in sa order, shortest first, each greater than everything before
+  // since it parts from the previous suffix at a larger key. O(1) per substring, plus one step
+  // per suffix skipped.
+  pub fn distinct_substrings(&self, lengths: Range<usize>) -> DistinctSubstrings<'_, T> {
+    DistinctSubstrings { sa: self, i: 0, len: 0, lengths }
+  }
+}
+
+pub struct DistinctSubstrings<'a, T> {
+  sa: &'a SuffixArray<T>,
+  i: usize, // the suffix in sa order
+  len: usize, // the last length given out from it, 0 before the first
+  lengths: Range<usize>,
+}
+
+impl<'a, T> Iterator for DistinctSubstrings<'a, T> {
+  type Item = &'a [T];
+  fn next(&mut self) -> Option<Self::Item> {
+    let sa = self.sa;
+    while self.i < sa.sa.len() {
+      let p = sa.sa[self.i];
+      let len = if self.len == 0 { (sa.lcp[self.i] + 1).max(self.lengths.start) } else { self.len + 1 };
+      if len <= sa.text.len() - p && len < self.lengths.end {
+        self.len = len;
+        return Some(&sa.text[p..p + len]);
+      }
+      self.i += 1;
+      self.len = 0;
+    }
+    None
+  }
+}
+
+// number of distinct non-empty substrings of s, over its suffix array
+pub fn distinct_substring_count<T: Ord + Copy>(s: &[T]) -> u64 {
+  SuffixArray::new(s).distinct_substring_count()
+}
+
+// Kasai et al.: the LCP array in O(n). Going through suffixes in text order, the common prefix
+// with the previous suffix in sa shrinks by at most one per step.
+fn kasai<T: Eq>(text: &[T], sa: &[usize], rank: &[usize]) -> Vec<usize> {
+  let n = text.len();
+  let mut lcp = vec![0; n];
+  let mut h: usize = 0;
+  for p in 0..n {
+    if rank[p] == 0 {
+      h = 0;
+      continue;
+    }
+    let q = sa[rank[p] - 1];
+    while p + h < n && q + h < n && text[p + h] == text[q + h] {
+      h += 1;
+    }
+    lcp[rank[p]] = h;
+    h = h.saturating_sub(1);
+  }
+  lcp
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_banana() {
+    let sa = SuffixArray::new(b"banana");
+    assert_eq!(&[5, 3, 1, 0, 4, 2], sa.sa());
+    assert_eq!(&[3, 2, 5, 1, 4, 0], sa.rank());
+    assert_eq!(&[0, 1, 3, 0, 0, 2], sa.lcp());
+    assert_eq!(1..3, sa.find(b"ana"));
+    assert_eq!(vec![1, 3], sa.occurrences(b"ana"));
+    assert_eq!(vec![0, 1, 2, 3, 4, 5], sa.occurrences(b""));
+    assert!(sa.find(b"nab").is_empty());
+    assert_eq!((3, 2), sa.longest_match(b"anx"));
+    assert_eq!((1, 4), sa.longest_match(b"anan"));
+    assert_eq!((0, 6), sa.longest_match(b"banana"));
+    assert_eq!(0, sa.longest_match(b"x").1);
+    assert!(SuffixArray::<u8>::new(&[]).is_empty());
+    assert_eq!((0, 0), SuffixArray::<u8>::new(&[]).longest_match(b"a"));
+  }
+
+  #[test]
+  fn test_random_against_naive() {
+    let mut rand = rand_gen(97);
+    for _ in 0..300 {
+      let sigma = 1 + rand(4);
+      let text: Vec<u32> = (0..rand(80)).map(|_| rand(sigma) as u32 * 1000).collect();
+      let mut naive: Vec<usize> = (0..text.len()).collect();
+      naive.sort_by(|&a, &b| text[a..].cmp(&text[b..]));
+      let sa = SuffixArray::new(&text);
+      assert_eq!(naive, sa.sa());
+      assert_eq!(naive, SuffixArray::new_doubling(&text).sa());
+      for i in 1..naive.len() {
+        let (a, b) = (&text[naive[i - 1]..], &text[naive[i]..]);
+        assert_eq!(a.iter().zip(b).take_while(|(x, y)| x == y).count(), sa.lcp()[i]);
+      }
+      for _ in 0..5 {
+        let pattern: Vec<u32> = (0..rand(4)).map(|_| rand(sigma) as u32 * 1000).collect();
+        let expected: Vec<usize> = (0..text.len()).filter(|&i| text[i..].starts_with(&pattern)).collect();
+        assert_eq!(expected, sa.occurrences(&pattern));
+        let (start, len) = sa.longest_match(&pattern);
+        assert!(text[start.min(text.len())..].starts_with(&pattern[..len]));
+        assert!(len == pattern.len() || !(0..text.len()).any(|i| text[i..].starts_with(&pattern[..=len])));
+      }
+    }
+  }
+
+  #[test]
+  fn test_distinct_substrings() {
+    assert_eq!(15, distinct_substring_count(b"banana"));
+    assert_eq!(0, distinct_substring_count::<u8>(&[]));
+    let sa = SuffixArray::new(b"abab");
+    let all: Vec<&[u8]> = sa.distinct_substrings(0..usize::MAX).collect();
+    assert_eq!(vec![&b"a"[..], b"ab", b"aba", b"abab", b"b", b"ba", b"bab"], all);
+    let middle: Vec<&[u8]> = sa.distinct_substrings(2..4).collect();
+    assert_eq!(vec![&b"ab"[..], b"aba", b"ba", b"bab"], middle);
+    assert_eq!(0, sa.distinct_substrings(5..9).count());
+    let mut rand = rand_gen(281);
+    for _ in 0..200 {
+      let sigma = 1 + rand(4);
+      let text: Vec<u8> = (0..rand(40)).map(|_| rand(sigma) as u8).collect();
+      let mut naive: Vec<&[u8]> = (0..text.len()).flat_map(|i| (i + 1..=text.len()).map(|j| &text[i..j]).collect::<Vec<_>>()).collect();
+      naive.sort_unstable();
+      naive.dedup();
+      let sa = SuffixArray::new(&text);
+      assert_eq!(naive.len() as u64, sa.distinct_substring_count());
+      let automaton = crate::string::suffix_automaton::SuffixAutomaton::from_keys(text.iter().copied());
+      assert_eq!(naive.len() as u64, automaton.distinct_substrings());
+      let (lo, hi) = (rand(6) as usize, rand(12) as usize);
+      let bounded: Vec<&[u8]> = naive.iter().copied().filter(|t| (lo..hi).contains(&t.len())).collect();
+      assert_eq!(bounded, sa.distinct_substrings(lo..hi).collect::<Vec<_>>());
+    }
+  }
+}
@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Range;
+
+use super::kmp::Kmp;
+
+const NONE: usize = usize::MAX;
+
+struct SamState<K> {
+  len: usize, // the longest string reaching this state
+  link: usize, // suffix link, NONE at the root
+  next: HashMap<K, usize>,
+  terminal_count: usize, // 1 for states created as the new end, 0 for clones
+  first_end: usize, // where the first occurrence of its substrings ends
+}
+
+// Suffix automaton (Blumer et al.): the smallest DFA accepting every substring of the text read
+// so far. A state is a class of substrings with the same set of end positions; its suffix link
+// goes to the class of the longest suffix outside it. Appending a key takes O(1) amortized
+// (times the HashMap transitions), and the automaton has at most 2n - 1 states. State 0 is the
+// root, the class of the empty string.
+pub struct SuffixAutomaton<K> {
+  states: Vec<SamState<K>>,
+  last: usize, // the state of the whole text
+}
+
+impl<K: Eq + Hash + Copy> SuffixAutomaton<K> {
+  pub fn new() -> SuffixAutomaton<K> {
+    SuffixAutomaton {
+      states: vec![SamState { len: 0, link: NONE, next: HashMap::new(), terminal_count: 0, first_end: 0 }],
+      last: 0,
+    }
+  }
+
+  pub fn from_keys(text: impl IntoIterator<Item = K>) -> SuffixAutomaton<K> {
+    let mut sam = SuffixAutomaton::new();
+    for k in text {
+      sam.push(k);
+    }
+    sam
+  }
+
+  // length of the text read so far
+  pub fn len(&self) -> usize {
+    self.states[self.last].len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.last == 0
+  }
+
+  pub fn state_count(&self) -> usize {
+    self.states.len()
+  }
+
+  // the longest substring in the state's class
+  pub fn state_len(&self, state: usize) -> usize {
+    self.states[state].len
+  }
+
+  // end of the first occurrence of the state's substrings in the text
+  pub fn first_end(&self, state: usize) -> usize {
+    self.states[state].first_end
+  }
+
+  pub fn link(&self, state: usize) -> Option<usize> {
+    Some(self.states[state].link).filter(|&l| l != NONE)
+  }
+
+  pub fn transition(&self, state: usize, k: &K) -> Option<usize> {
+    self.states[state].next.get(k).copied()
+  }
+
+  pub fn push(&mut self, k: K) {
+    let cur = self.states.len();
+    let len = self.len() + 1;
+    self.states.push(SamState { len, link: 0, next: HashMap::new(), terminal_count: 1, first_end: len });
+    // every suffix of the old text without a k transition gets one to cur
+    let mut p = self.last;
+    while p != NONE && !self.states[p].next.contains_key(&k) {
+      self.states[p].next.insert(k, cur);
+      p = self.states[p].link;
+    }
+    if p != NONE {
+      let q = self.states[p].next[&k];
+      if self.states[p].len + 1 == self.states[q].len {
+        self.states[cur].link = q;
+      } else {
+        // q holds strings longer than p's plus k: split them off into a clone
+        let clone = self.states.len();
+        let state = SamState {
+          len: self.states[p].len + 1,
+          link: self.states[q].link,
+          next: self.states[q].next.clone(),
+          terminal_count: 0,
+          first_end: self.states[q].first_end,
+        };
+        self.states.push(state);
+        while p != NONE && self.states[p].next.get(&k) == Some(&q) {
+          self.states[p].next.insert(k, clone);
+          p = self.states[p].link;
+        }
+        self.states[q].link = clone;
+        self.states[cur].link = clone;
+      }
+    }
+    self.last = cur;
+  }
+
+  // the state reached by reading pattern from the root
+  pub fn state_of(&self, pattern: impl IntoIterator<Item = K>) -> Option<usize> {
+    let mut state = 0;
+    for k in pattern {
+      state = self.transition(state, &k)?;
+    }
+    Some(state)
+  }
+
+  pub fn contains(&self, pattern: impl IntoIterator<Item = K>) -> bool {
+    self.state_of(pattern).is_some()
+  }
+
+  // number of distinct non-empty substrings: each state holds the lengths from its link's
+  // len + 1 up to its own len
+  pub fn distinct_substrings(&self) -> u64 {
+    self.states[1..].iter().map(|s| (s.len - self.states[s.link].len) as u64).sum()
+  }
+
+  // states by increasing len, so a state comes after its link; O(n) by a counting sort
+  fn order_by_len(&self) -> Vec<usize> {
+    let mut by_len = vec![0; self.len() + 2];
+    for s in &self.states {
+      by_len[s.len + 1] += 1;
+    }
+    for i in 1..by_len.len() {
+      by_len[i] += by_len[i - 1];
+    }
+    let mut order = vec![0; self.states.len()];
+    for (v, s) in self.states.iter().enumerate() {
+      order[by_len[s.len]] = v;
+      by_len[s.len] += 1;
+    }
+    order
+  }
+
+  // occurrences of the substrings of each state: its end positions, summed up the link tree from
+  // longer states to shorter ones
+  pub fn occurrence_counts(&self) -> Vec<usize> {
+    let mut counts: Vec<usize> = self.states.iter().map(|s| s.terminal_count).collect();
+    for v in self.order_by_len().into_iter().rev() {
+      let link = self.states[v].link;
+      if link != NONE {
+        counts[link] += counts[v];
+      }
+    }
+    counts
+  }
+
+  // how many times pattern occurs, overlaps included; O(n) as it recounts every state, so for
+  // many queries index occurrence_counts() by state_of instead
+  pub fn count_occurrences(&self, pattern: impl IntoIterator<Item = K>) -> usize {
+    match self.state_of(pattern) {
+      Some(0) => self.len() + 1, // the empty string
+      Some(state) => self.occurrence_counts()[state],
+      None => 0,
+    }
+  }
+}
+
+impl<K: Eq + Hash + Copy> Default for SuffixAutomaton<K> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+// A longest substring common to all the strings, as its first occurrence in each: ranges of the
+// same length, empty if the strings share no key. The automaton of the shortest string reads
+// every other one, keeping per state the longest match ending there, raised along suffix links
+// to the shorter states it implies; a state's common length is its minimum over the strings.
+// O(total length) times the HashMap transitions.
+pub fn longest_common_substring<K: Eq + Hash + Copy>(strings: &[&[K]]) -> Vec<Range<usize>> {
+  let base = match (0..strings.len()).min_by_key(|&i| strings[i].len()) {
+    Some(base) => base,
+    None => return vec![],
+  };
+  let sam = SuffixAutomaton::from_keys(strings[base].iter().copied());
+  let order = sam.order_by_len();
+  let mut common: Vec<usize> = sam.states.iter().map(|s| s.len).collect();
+  for (_, other) in strings.iter().enumerate().filter(|&(t, _)| t != base) {
+    let mut best = vec![0; sam.states.len()];
+    let (mut state, mut len) = (0, 0);
+    for k in other.iter() {
+      while state != 0 && sam.transition(state, k).is_none() {
+        state = sam.states[state].link;
+        len = sam.states[state].len;
+      }
+      if let Some(next) = sam.transition(state, k) {
+        state = next;
+        len += 1;
+      }
+      best[state] = best[state].max(len);
+    }
+    for &v in order.iter().rev() {
+      let link = sam.states[v].link;
+      // the match is longer than every string of the link's class
+      if link != NONE && best[v] > 0 {
+        best[link] = sam.states[link].len;
+      }
+    }
+    for (c, b) in common.iter_mut().zip(best) {
+      *c = (*c).min(b);
+    }
+  }
+  let v = (0..common.len()).max_by_key(|&v| common[v]).unwrap();
+  let len = common[v];
+  if len == 0 {
+    return vec![0..0; strings.len()];
+  }
+  let end = sam.first_end(v);
+  let kmp = Kmp::new(strings[base][end - len..end].iter().copied());
+  strings.iter().map(|s| {
+    let start = kmp.find_iter(s.iter().copied()).next().unwrap();
+    start..start + len
+  }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+  use std::collections::HashSet;
+
+  #[test]
+  fn test_abcbc() {
+    let sam = SuffixAutomaton::from_keys("abcbc".chars());
+    assert_eq!(5, sam.len());
+    for s in ["", "a", "bcb", "cbc", "abcbc", "bc"] {
+      assert!(sam.contains(s.chars()));
+    }
+    for s in ["ac", "cc", "abcbcb", "d"] {
+      assert!(!sam.contains(s.chars()));
+    }
+    // a ab abc abcb abcbc b bc bcb bcbc c cb cbc
+    assert_eq!(12, sam.distinct_substrings());
+    assert_eq!(2, sam.count_occurrences("bc".chars()));
+    assert_eq!(2, sam.count_occurrences("c".chars()));
+    assert_eq!(1, sam.count_occurrences("cbc".chars()));
+    assert_eq!(0, sam.count_occurrences("ca".chars()));
+    let s = sam.state_of("bc".chars()).unwrap();
+    // "c" and "bc" end at the same places, "abc" only once
+    assert_eq!(Some(s), sam.state_of("c".chars()));
+    assert_eq!(2, sam.state_len(s));
+    assert_eq!(3, sam.first_end(s));
+    assert_eq!(Some(0), sam.link(s));
+    assert_eq!(None, sam.link(0));
+  }
+
+  #[test]
+  fn test_random_against_naive() {
+    let mut rand = rand_gen(109);
+    for _ in 0..100 {
+      let text: Vec<u8> = (0..rand(40)).map(|_| rand(3) as u8).collect();
+      let sam = SuffixAutomaton::from_keys(text.iter().copied());
+      assert!(sam.state_count() <= (2 * text.len()).max(1));
+      let n = text.len();
+      let substrings: HashSet<&[u8]> = (0..n).flat_map(|i| (i + 1..=n).map(move |j| (i, j))).map(|(i, j)| &text[i..j]).collect();
+      assert_eq!(substrings.len() as u64, sam.distinct_substrings());
+      let counts = sam.occurrence_counts();
+      for _ in 0..30 {
+        let pattern: Vec<u8> = (0..1 + rand(4)).map(|_| rand(3) as u8).collect();
+        let expected = (0..n).filter(|&i| text[i..].starts_with(&pattern)).count();
+        assert_eq!(expected > 0, sam.contains(pattern.iter().copied()));
+        assert_eq!(expected, sam.state_of(pattern.iter().copied()).map_or(0, |s| counts[s]));
+      }
+    }
+  }
+
+  #[test]
+  fn test_longest_common_substring() {
+    let strings: [&[u8]; 3] = [b"xabcdey", b"abcdz", b"zzbcdab"];
+    assert_eq!(vec![2..5, 1..4, 2..5], longest_common_substring(&strings));
+    assert_eq!(vec![0..0, 0..0], longest_common_substring(&[&b"ab"[..], b"cd"]));
+    assert_eq!(vec![0..4], longest_common_substring(&[&b"abcd"[..]]));
+    assert!(longest_common_substring::<u8>(&[]).is_empty());
+  }
+
+  #[test]
+  fn test_longest_common_substring_against_naive() {
+    let mut rand = rand_gen(193);
+    for _ in 0..300 {
+      let k = 1 + rand(4) as usize;
+      let sigma = 1 + rand(3);
+      let strings: Vec<Vec<u8>> = (0..k).map(|_| (0..rand(30)).map(|_| rand(sigma) as u8).collect()).collect();
+      let slices: Vec<&[u8]> = strings.iter().map(|s| &s[..]).collect();
+      let substrings = |s: &[u8]| -> HashSet<Vec<u8>> { (0..s.len()).flat_map(|i| (i + 1..=s.len()).map(move |j| s[i..j].to_vec())).collect() };
+      let mut shared = substrings(slices[0]);
+      for s in &slices[1..] {
+        shared = shared.intersection(&substrings(s)).cloned().collect();
+      }
+      let longest = shared.iter().map(|w| w.len()).max().unwrap_or(0);
+      let found = longest_common_substring(&slices);
+      assert_eq!(k, found.len());
+      let w = &slices[0][found[0].clone()];
+      assert_eq!(longest, w.len());
+      for (s, r) in slices.iter().zip(found) {
+        assert_eq!(w, &s[r.clone()]);
+        // the first occurrence
+        assert!(longest == 0 || !s[..r.start + longest - 1].windows(longest).any(|x| x == w));
+      }
+    }
+  }
+}
@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Range;
+
+const LEAF_END: usize = usize::MAX; // a leaf's edge grows with the text during the build
+
+struct SuffixTreeNode<K> {
+  start: usize, // the edge into the node is text[start..end]
+  end: usize,
+  link: usize, // suffix link of internal nodes, the root otherwise
+  // keyed by the first key of the edge, None for the end marker
+  children: HashMap<Option<K>, usize>,
+  depth: usize, // length of the path from the root, end marker excluded
+  suffix: usize, // the start of some suffix in the subtree, its own one for a leaf
+}
+
+// Suffix tree built online by Ukkonen's algorithm with suffix links and the active point, O(n)
+// node operations. The text gets an end marker so that every suffix, the empty one at n
+// included, ends at its own leaf. Node 0 is the root.
+pub struct SuffixTree<K> {
+  text: Vec<K>,
+  nodes: Vec<SuffixTreeNode<K>>,
+}
+
+impl<K: Eq + Hash + Copy> SuffixTree<K> {
+  pub fn new(text: &[K]) -> SuffixTree<K> {
+    let mut tree = SuffixTree { text: text.to_vec(), nodes: vec![] };
+    tree.alloc(0, 0);
+    tree.build();
+    tree.annotate();
+    tree
+  }
+
+  // the key at i, None for the end marker at n
+  fn key(&self, i: usize) -> Option<K> {
+    self.text.get(i).copied()
+  }
+
+  fn alloc(&mut self, start: usize, end: usize) -> usize {
+    self.nodes.push(SuffixTreeNode { start, end, link: 0, children: HashMap::new(), depth: 0, suffix: 0 });
+    self.nodes.len() - 1
+  }
+
+  fn edge_len(&self, v: usize, pos: usize) -> usize {
+    self.nodes[v].end.min(pos + 1) - self.nodes[v].start
+  }
+
+  fn build(&mut self) {
+    let (mut active_node, mut active_edge, mut active_len) = (0, 0, 0);
+    let mut remainder = 0;
+    for i in 0..=self.text.len() {
+      let c = self.key(i);
+      let mut last_new = 0; // the internal node awaiting a suffix link, 0 for none
+      remainder += 1;
+      while remainder > 0 {
+        if active_len == 0 {
+          active_edge = i;
+        }
+        let first = self.key(active_edge);
+        match self.nodes[active_node].children.get(&first).copied() {
+          None => {
+            let leaf = self.alloc(i, LEAF_END);
+            self.nodes[active_node].children.insert(first, leaf);
+            if last_new != 0 {
+              self.nodes[last_new].link = active_node;
+              last_new = 0;
+            }
+          },
+          Some(next) => {
+            let len = self.edge_len(next, i);
+            if active_len >= len {
+              // walk down
+              active_edge += len;
+              active_len -= len;
+              active_node = next;
+              continue;
+            }
+            if self.key(self.nodes[next].start + active_len) == c {
+              // already there: the rest of this phase is implicit
+              if last_new != 0 && active_node != 0 {
+                self.nodes[last_new].link = active_node;
+              }
+              active_len += 1;
+              break;
+            }
+            let start = self.nodes[next].start;
+            let split = self.alloc(start, start + active_len);
+            self.nodes[active_node].children.insert(first, split);
+            let leaf = self.alloc(i, LEAF_END);
+            self.nodes[split].children.insert(c, leaf);
+            self.nodes[next].start += active_len;
+            let rest = self.key(self.nodes[next].start);
+            self.nodes[split].children.insert(rest, next);
+            if last_new != 0 {
+              self.nodes[last_new].link = split;
+            }
+            last_new = split;
+          },
+        }
+        remainder -= 1;
+        if active_node == 0 && active_len > 0 {
+          active_len -= 1;
+          active_edge = i + 1 - remainder;
+        } else if active_node != 0 {
+          active_node = self.nodes[active_node].link;
+        }
+      }
+    }
+  }
+
+  // fixes leaf ends and fills in depths and suffixes, parents before children and back
+  fn annotate(&mut self) {
+    let n = self.text.len();
+    let mut order = vec![0];
+    let mut i = 0;
+    while i < order.len() {
+      let v = order[i];
+      i += 1;
+      if self.nodes[v].end == LEAF_END {
+        self.nodes[v].end = n + 1;
+      }
+      let depth = self.nodes[v].depth;
+      let children: Vec<usize> = self.nodes[v].children.values().copied().collect();
+      for c in children {
+        let len = self.nodes[c].end.min(n + 1) - self.nodes[c].start;
+        let node = &mut self.nodes[c];
+        node.depth = depth + len;
+        if node.end == LEAF_END || node.end == n + 1 {
+          node.depth -= 1; // the end marker
+          node.suffix = n - node.depth;
+        }
+        order.push(c);
+      }
+    }
+    for &v in order.iter().rev() {
+      if let Some(&c) = self.nodes[v].children.values().next() {
+        self.nodes[v].suffix = self.nodes[c].suffix;
+      }
+    }
+  }
+
+  pub fn text(&self) -> &[K] {
+    &self.text
+  }
+
+  pub fn node_count(&self) -> usize {
+    self.nodes.len()
+  }
+
+  pub fn root(&self) -> usize {
+    0
+  }
+
+  pub fn children(&self, v: usize) -> impl Iterator<Item = usize> + '_ {
+    self.nodes[v].children.values().copied()
+  }
+
+  pub fn is_leaf(&self, v: usize) -> bool {
+    self.nodes[v].children.is_empty()
+  }
+
+  // the label of the edge into v as a range of the text, without the end marker
+  pub fn edge(&self, v: usize) -> Range<usize> {
+    let n = self.text.len();
+    self.nodes[v].start.min(n)..self.nodes[v].end.min(n)
+  }
+
+  // length of the string spelled from the root to v
+  pub fn depth(&self, v: usize) -> usize {
+    self.nodes[v].depth
+  }
+
+  // the suffix a leaf stands for
+  pub fn suffix(&self, v: usize) -> Option<usize> {
+    if self.is_leaf(v) { Some(self.nodes[v].suffix) } else { None }
+  }
+
+  // every node with its depth, parents before children
+  pub fn iter(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let mut stack = vec![0];
+    std::iter::from_fn(move || {
+      let v = stack.pop()?;
+      stack.extend(self.children(v));
+      Some((v, self.nodes[v].depth))
+    })
+  }
+
+  // the node at or below the end of pattern's path, None if pattern does not occur
+  fn locate(&self, pattern: &[K]) -> Option<usize> {
+    let mut v = 0;
+    let mut matched = 0;
+    while matched < pattern.len() {
+      v = *self.nodes[v].children.get(&Some(pattern[matched]))?;
+      let edge = self.edge(v);
+      for i in edge {
+        if matched == pattern.len() {
+          break;
+        }
+        if self.text[i] != pattern[matched] {
+          return None;
+        }
+        matched += 1;
+      }
+      if matched < pattern.len() && self.is_leaf(v) {
+        return None;
+      }
+    }
+    Some(v)
+  }
+
+  pub fn contains(&self, pattern: &[K]) -> bool {
+    self.locate(pattern).is_some()
+  }
+
+  // every start of pattern, in increasing order: the leaves below its locus
+  pub fn find(&self, pattern: &[K]) -> Vec<usize> {
+    let mut found = vec![];
+    if let Some(v) = self.locate(pattern) {
+      let mut stack = vec![v];
+      while let Some(u) = stack.pop() {
+        match self.suffix(u) {
+          Some(s) => found.push(s),
+          None => stack.extend(self.children(u)),
+        }
+      }
+    }
+    found.sort_unstable();
+    found
+  }
+
+  // a longest substring occurring at least twice, read off the deepest internal node
+  pub fn longest_repeated_substring(&self) -> Range<usize> {
+    let v = (0..self.nodes.len()).filter(|&v| !self.is_leaf(v)).max_by_key(|&v| (self.nodes[v].depth, std::cmp::Reverse(v))).unwrap();
+    let start = self.nodes[v].suffix;
+    start..start + self.nodes[v].depth
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_banana() {
+    let tree = SuffixTree::new(b"banana");
+    assert_eq!(vec![1, 3], tree.find(b"ana"));
+    assert_eq!(vec![0, 1, 2, 3, 4, 5, 6], tree.find(b""));
+    assert_eq!(vec![2, 4], tree.find(b"na"));
+    assert!(tree.find(b"nab").is_empty());
+    assert!(tree.contains(b"banana") && !tree.contains(b"bananas"));
+    assert_eq!(b"ana", &tree.text()[tree.longest_repeated_substring()]);
+    // 7 leaves, the root and internal nodes for a, ana, na
+    assert_eq!(11, tree.node_count());
+    let mut leaves: Vec<usize> = tree.iter().filter_map(|(v, _)| tree.suffix(v)).collect();
+    leaves.sort_unstable();
+    assert_eq!(vec![0, 1, 2, 3, 4, 5, 6], leaves);
+    let mut internal: Vec<&[u8]> = tree.iter()
+      .filter(|&(v, d)| v != tree.root() && !tree.is_leaf(v) && d > 0)
+      .map(|(v, d)| &tree.text()[tree.nodes[v].suffix..tree.nodes[v].suffix + d])
+      .collect();
+    internal.sort();
+    assert_eq!(vec![&b"a"[..], b"ana", b"na"], internal);
+  }
+
+  #[test]
+  fn test_random_against_naive() {
+    let mut rand = rand_gen(127);
+    for _ in 0..200 {
+      let sigma = 1 + rand(4);
+      let text: Vec<u8> = (0..rand(50)).map(|_| rand(sigma) as u8).collect();
+      let n = text.len();
+      let tree = SuffixTree::new(&text);
+      assert!(tree.node_count() <= 2 * (n + 1));
+      // every leaf spells its suffix
+      for (v, d) in tree.iter() {
+        if let Some(s) = tree.suffix(v) {
+          assert_eq!(n - s, d);
+        }
+      }
+      for _ in 0..20 {
+        let pattern: Vec<u8> = (0..rand(5)).map(|_| rand(3) as u8).collect();
+        let expected: Vec<usize> = (0..=n).filter(|&i| text[i..].starts_with(&pattern)).collect();
+        assert_eq!(expected, tree.find(&pattern));
+      }
+      let longest = (1..=n).rev().find(|&len| {
+        (0..=n - len).any(|i| (i + 1..=n - len).any(|j| text[i..i + len] == text[j..j + len]))
+      });
+      let range = tree.longest_repeated_substring();
+      assert_eq!(longest.unwrap_or(0), range.len());
+      assert!(range.is_empty() || tree.find(&text[range.clone()]).len() >= 2);
+    }
+  }
+}
@@ -0,0 +1,397 @@
+const NIL: u32 = u32::MAX;
+
+struct TstNode<K, V> {
+  key: K,
+  lo: u32, // subtree of keys smaller than key at this position
+  eq: u32, // continues the key past this position
+  hi: u32,
+  value: Option<V>, // the key ending here
+}
+
+// Ternary search tree (Bentley & Sedgewick): a binary search tree per key position, where the
+// equal branch moves on to the next position. Nodes hold one key element and three links
+// instead of a whole child map, which keeps sparse alphabets compact. Nodes live in one arena;
+// removal prunes the nodes that no longer lead to a key, and their slots are reused.
+//
+// The binary search trees are not rebalanced, so their shape follows insertion order: keys
+// inserted in sorted order turn each of them into a path, and n such inserts cost O(n^2) when
+// the keys differ early. Insert in random order, or middle first from a sorted list, to keep
+// lookups within O(key length + log n) comparisons.
+pub struct TernarySearchTree<K, V> {
+  nodes: Vec<TstNode<K, V>>,
+  free: Vec<u32>,
+  root: u32,
+  empty: Option<V>, // the value of the empty key, which has no node
+  len: usize,
+}
+
+impl<K: Ord + Copy, V> TernarySearchTree<K, V> {
+  pub fn new() -> TernarySearchTree<K, V> {
+    TernarySearchTree { nodes: vec![], free: vec![], root: NIL, empty: None, len: 0 }
+  }
+
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  fn alloc(&mut self, key: K) -> u32 {
+    let node = TstNode { key, lo: NIL, eq: NIL, hi: NIL, value: None };
+    match self.free.pop() {
+      Some(i) => {
+        self.nodes[i as usize] = node;
+        i
+      },
+      None => {
+        self.nodes.push(node);
+        (self.nodes.len() - 1) as u32
+      },
+    }
+  }
+
+  // the node of the last element of key
+  fn find(&self, key: &[K]) -> Option<u32> {
+    let mut node = self.root;
+    let mut i = 0;
+    while node != NIL {
+      let n = &self.nodes[node as usize];
+      match key[i].cmp(&n.key) {
+        std::cmp::Ordering::Less => node = n.lo,
+        std::cmp::Ordering::Greater => node = n.hi,
+        std::cmp::Ordering::Equal => {
+          i += 1;
+          if i == key.len() {
+            return Some(node);
+          }
+          node = n.eq;
+        },
+      }
+    }
+    None
+  }
+
+  fn slot(&mut self, key: &[K]) -> &mut Option<V> {
+    if key.is_empty() {
+      return &mut self.empty;
+    }
+    if self.root == NIL {
+      self.root = self.alloc(key[0]);
+    }
+    let mut node = self.root;
+    let mut i = 0;
+    loop {
+      let order = key[i].cmp(&self.nodes[node as usize].key);
+      let next = match order {
+        std::cmp::Ordering::Less => self.nodes[node as usize].lo,
+        std::cmp::Ordering::Greater => self.nodes[node as usize].hi,
+        std::cmp::Ordering::Equal => {
+          i += 1;
+          if i == key.len() {
+            return &mut self.nodes[node as usize].value;
+          }
+          self.nodes[node as usize].eq
+        },
+      };
+      node = if next != NIL {
+        next
+      } else {
+        let created = self.alloc(key[i]);
+        let n = &mut self.nodes[node as usize];
+        match order {
+          std::cmp::Ordering::Less => n.lo = created,
+          std::cmp::Ordering::Greater => n.hi = created,
+          std::cmp::Ordering::Equal => n.eq = created,
+        }
+        created
+      };
+    }
+  }
+
+  // returns the value previously stored under key
+  pub fn insert(&mut self, key: &[K], value: V) -> Option<V> {
+    let old = self.slot(key).replace(value);
+    if old.is_none() {
+      self.len += 1;
+    }
+    old
+  }
+
+  pub fn get(&self, key: &[K]) -> Option<&V> {
+    if key.is_empty() {
+      return self.empty.as_ref();
+    }
+    self.find(key).and_then(|node| self.nodes[node as usize].value.as_ref())
+  }
+
+  pub fn remove(&mut self, key: &[K]) -> Option<V> {
+    if key.is_empty() {
+      let removed = self.empty.take();
+      if removed.is_some() {
+        self.len -= 1;
+      }
+      return removed;
+    }
+    // the nodes on the way down with the branch taken at each
+    let mut trail = vec![];
+    let mut node = self.root;
+    let mut i = 0;
+    while node != NIL {
+      let n = &self.nodes[node as usize];
+      let order = key[i].cmp(&n.key);
+      if order == std::cmp::Ordering::Equal {
+        i += 1;
+        if i == key.len() {
+          break;
+        }
+      }
+      trail.push((node, order));
+      node = match order {
+        std::cmp::Ordering::Less => n.lo,
+        std::cmp::Ordering::Greater => n.hi,
+        std::cmp::Ordering::Equal => n.eq,
+      };
+    }
+    if node == NIL {
+      return None;
+    }
+    let removed = self.nodes[node as usize].value.take()?;
+    self.len -= 1;
+    // every node holds a value or continues to one; unlink those that stopped doing so, up
+    // through the equal branches that led to them
+    while self.nodes[node as usize].value.is_none() && self.nodes[node as usize].eq == NIL {
+      let replacement = self.unlink(node);
+      match trail.pop() {
+        None => {
+          self.root = replacement;
+          break;
+        },
+        Some((parent, order)) => {
+          let p = &mut self.nodes[parent as usize];
+          match order {
+            std::cmp::Ordering::Less => p.lo = replacement,
+            std::cmp::Ordering::Greater => p.hi = replacement,
+            std::cmp::Ordering::Equal => p.eq = replacement,
+          }
+          if order != std::cmp::Ordering::Equal || replacement != NIL {
+            break;
+          }
+          node = parent;
+        },
+      }
+    }
+    Some(removed)
+  }
+
+  // frees node and returns what takes its place in its binary search tree: a lone child, or
+  // the least node of the right subtree
+  fn unlink(&mut self, node: u32) -> u32 {
+    self.free.push(node);
+    let TstNode { lo, hi, .. } = self.nodes[node as usize];
+    if lo == NIL || hi == NIL {
+      return if lo == NIL { hi } else { lo };
+    }
+    let (mut parent, mut least) = (NIL, hi);
+    while self.nodes[least as usize].lo != NIL {
+      parent = least;
+      least = self.nodes[least as usize].lo;
+    }
+    if parent != NIL {
+      self.nodes[parent as usize].lo = self.nodes[least as usize].hi;
+      self.nodes[least as usize].hi = hi;
+    }
+    self.nodes[least as usize].lo = lo;
+    least
+  }
+
+  // every stored key starting with prefix together with its value, in lexicographic order
+  pub fn iter_prefix(&self, prefix: &[K]) -> TstIter<'_, K, V> {
+    let mut iter = TstIter { tree: self, pending: None, stack: vec![], path: prefix.to_vec() };
+    if prefix.is_empty() {
+      iter.pending = self.empty.as_ref();
+      iter.stack.push((self.root, 0, false));
+    } else if let Some(node) = self.find(prefix) {
+      let n = &self.nodes[node as usize];
+      iter.pending = n.value.as_ref();
+      iter.stack.push((n.eq, prefix.len(), false));
+    }
+    iter
+  }
+
+  // every stored key of the same length as key differing in at most max_distance positions,
+  // in lexicographic order. Branches off the query are only followed while mismatches remain.
+  pub fn near(&self, key: &[K], max_distance: usize) -> Vec<(Vec<K>, &V)> {
+    let mut found = vec![];
+    if key.is_empty() {
+      found.extend(self.empty.as_ref().map(|v| (vec![], v)));
+      return found;
+    }
+    // in-order walk as in TstIter, each node with its position in key and the mismatches left
+    let mut path = vec![];
+    let mut stack = vec![(self.root, 0, max_distance, false)];
+    while let Some((node, i, budget, entered)) = stack.pop() {
+      if node == NIL {
+        continue;
+      }
+      let n = &self.nodes[node as usize];
+      if !entered {
+        if budget > 0 || key[i] > n.key {
+          stack.push((n.hi, i, budget, false));
+        }
+        stack.push((node, i, budget, true));
+        if budget > 0 || key[i] < n.key {
+          stack.push((n.lo, i, budget, false));
+        }
+        continue;
+      }
+      let cost = (key[i] != n.key) as usize;
+      if cost <= budget {
+        path.truncate(i);
+        path.push(n.key);
+        if i + 1 == key.len() {
+          if let Some(value) = &n.value {
+            found.push((path.clone(), value));
+          }
+        } else {
+          stack.push((n.eq, i + 1, budget - cost, false));
+        }
+      }
+    }
+    found
+  }
+}
+
+impl<K: Ord + Copy, V> Default for TernarySearchTree<K, V> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+// in-order walk on an explicit stack of (node, depth, entered): a node is first expanded into
+// its low subtree, itself and its high subtree, pushed so they pop in that order, and entering
+// it yields its key and queues its equal subtree one position deeper. path holds the key of the
+// node entered last, and depth the position of a node's element in its key.
+pub struct TstIter<'a, K, V> {
+  tree: &'a TernarySearchTree<K, V>,
+  pending: Option<&'a V>, // the value of path itself, before the walk
+  stack: Vec<(u32, usize, bool)>,
+  path: Vec<K>,
+}
+
+impl<'a, K: Ord + Copy, V> Iterator for TstIter<'a, K, V> {
+  type Item = (Vec<K>, &'a V);
+  fn next(&mut self) -> Option<Self::Item> {
+    if let Some(value) = self.pending.take() {
+      return Some((self.path.clone(), value));
+    }
+    while let Some((node, depth, entered)) = self.stack.pop() {
+      if node == NIL {
+        continue;
+      }
+      let n = &self.tree.nodes[node as usize];
+      if !entered {
+        self.stack.push((n.hi, depth, false));
+        self.stack.push((node, depth, true));
+        self.stack.push((n.lo, depth, false));
+        continue;
+      }
+      self.path.truncate(depth);
+      self.path.push(n.key);
+      self.stack.push((n.eq, depth + 1, false));
+      if let Some(value) = &n.value {
+        return Some((self.path.clone(), value));
+      }
+    }
+    None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+  use std::collections::{BTreeMap, BTreeSet};
+
+  #[test]
+  fn test_basic() {
+    let mut tst = TernarySearchTree::new();
+    for (i, w) in ["cute", "cup", "at", "as", "he", "us", "i", ""].iter().enumerate() {
+      assert_eq!(None, tst.insert(w.as_bytes(), i));
+    }
+    assert_eq!(Some(1), tst.insert(b"cup", 10));
+    assert_eq!(8, tst.len());
+    assert_eq!(Some(&10), tst.get(b"cup"));
+    assert_eq!(Some(&7), tst.get(b""));
+    assert_eq!(None, tst.get(b"cu"));
+    assert_eq!(None, tst.get(b"cuter"));
+    let keys = |found: Vec<(Vec<u8>, &usize)>| -> Vec<String> {
+      found.into_iter().map(|(k, _)| String::from_utf8(k).unwrap()).collect()
+    };
+    assert_eq!(vec!["cup", "cute"], keys(tst.iter_prefix(b"cu").collect()));
+    assert_eq!(vec!["", "as", "at", "cup", "cute", "he", "i", "us"], keys(tst.iter_prefix(b"").collect()));
+    assert_eq!(vec!["as", "at"], keys(tst.near(b"at", 1)));
+    assert_eq!(vec!["as", "at", "he", "us"], keys(tst.near(b"us", 2)));
+    assert_eq!(vec!["cute"], keys(tst.near(b"mute", 1)));
+    assert_eq!(Some(10), tst.remove(b"cup"));
+    assert_eq!(None, tst.remove(b"cup"));
+    assert_eq!(vec!["cute"], keys(tst.iter_prefix(b"c").collect()));
+    assert_eq!(7, tst.len());
+    // the node of "cup" went with it, one is left per prefix: c, cu, cut, cute, a, as, at, ...
+    assert_eq!(12, tst.nodes.len() - tst.free.len());
+    for w in ["cute", "at", "as", "he", "us", "i", ""] {
+      assert!(tst.remove(w.as_bytes()).is_some());
+    }
+    assert!(tst.is_empty() && tst.root == NIL && tst.free.len() == tst.nodes.len());
+  }
+
+  #[test]
+  fn test_long_keys() {
+    // deep enough to overflow the stack with recursive walks
+    let mut tst = TernarySearchTree::new();
+    let long: Vec<u32> = (0..100_000).map(|i| i % 3).collect();
+    tst.insert(&long, 1);
+    tst.insert(&long[..50_000], 2);
+    let found: Vec<_> = tst.iter_prefix(&long[..10]).map(|(k, v)| (k.len(), *v)).collect();
+    assert_eq!(vec![(50_000, 2), (100_000, 1)], found);
+    let mut typo = long.clone();
+    typo[77_777] = 5;
+    assert_eq!(vec![(long.clone(), &1)], tst.near(&typo, 1));
+    assert_eq!(Some(1), tst.remove(&long));
+    assert_eq!(50_000, tst.nodes.len() - tst.free.len());
+  }
+
+  #[test]
+  fn test_random_against_btree() {
+    let mut rand = rand_gen(61);
+    for _ in 0..30 {
+      let mut tst = TernarySearchTree::new();
+      let mut model = BTreeMap::new();
+      for step in 0..300 {
+        let key: Vec<u8> = (0..rand(5)).map(|_| rand(4) as u8).collect();
+        if rand(4) == 0 {
+          assert_eq!(model.remove(&key), tst.remove(&key));
+        } else {
+          assert_eq!(model.insert(key.clone(), step), tst.insert(&key, step));
+        }
+        assert_eq!(model.len(), tst.len());
+        // one node per distinct non-empty prefix of a stored key, and nothing left over
+        let prefixes: BTreeSet<&[u8]> = model.keys().flat_map(|k| (1..=k.len()).map(move |l| &k[..l])).collect();
+        assert_eq!(prefixes.len(), tst.nodes.len() - tst.free.len());
+      }
+      for _ in 0..50 {
+        let query: Vec<u8> = (0..rand(5)).map(|_| rand(4) as u8).collect();
+        assert_eq!(model.get(&query), tst.get(&query));
+        let prefixed: Vec<(Vec<u8>, &usize)> = model.iter().filter(|(k, _)| k.starts_with(&query)).map(|(k, v)| (k.clone(), v)).collect();
+        assert_eq!(prefixed, tst.iter_prefix(&query).collect::<Vec<_>>());
+        let d = rand(3) as usize;
+        let close: Vec<(Vec<u8>, &usize)> = model.iter()
+          .filter(|(k, _)| k.len() == query.len() && k.iter().zip(&query).filter(|(a, b)| a != b).count() <= d)
+          .map(|(k, v)| (k.clone(), v))
+          .collect();
+        assert_eq!(close, tst.near(&query, d));
+      }
+    }
+  }
+}
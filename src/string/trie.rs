@@ -1,81 +1,725 @@
-use std::rc::Rc;
-use std::cell::RefCell;
-
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
 struct TrieNode<K, U> {
-  child: HashMap<K, Rc<RefCell<TrieNode<K, U>>>>,
-  data: U,
+  child: HashMap<K, u32>, // indices into Trie::nodes
+  data: Option<U>, // None until a value is set, reads as U::default()
+  parent: u32, // the root is its own parent
+  key: Option<K>, // the key leading here from the parent, None at the root
+  count: usize, // number of values set in this subtree, this node included
 }
 use std::hash::Hash;
 
-impl<K: Eq + Hash, U: Default> TrieNode<K, U> {
-  fn new() -> TrieNode<K, U> {
+impl<K: Eq + Hash, U> TrieNode<K, U> {
+  fn new(parent: u32, key: Option<K>) -> TrieNode<K, U> {
     TrieNode {
       child: HashMap::new(),
-      data: Default::default(),
+      data: None,
+      parent,
+      key,
+      count: 0,
     }
   }
 }
 
+// nodes live in one arena, node 0 is the root; slots of pruned nodes are kept on a free list
+// and reused by later inserts
 pub struct Trie<K, U> {
-  root: Rc<RefCell<TrieNode<K, U>>>,
+  nodes: Vec<TrieNode<K, U>>,
+  free: Vec<u32>,
 }
 
-impl<K: Eq + Hash + Copy, U: Default + Clone> Trie<K, U> {
+impl<K: Eq + Hash + Copy, U> Trie<K, U> {
   pub fn new() -> Trie<K, U> {
     Trie {
-      root: Rc::new(RefCell::new(TrieNode::new())),
+      nodes: vec![TrieNode::new(0, None)],
+      free: vec![],
+    }
+  }
+  fn alloc(&mut self, parent: u32, key: K) -> u32 {
+    match self.free.pop() {
+      Some(i) => {
+        let node = &mut self.nodes[i as usize];
+        node.parent = parent;
+        node.key = Some(key);
+        i
+      },
+      None => {
+        self.nodes.push(TrieNode::new(parent, Some(key)));
+        (self.nodes.len() - 1) as u32
+      },
+    }
+  }
+  // adds delta to the counters from node up to the root
+  fn add_count(&mut self, mut node: u32, delta: isize) {
+    loop {
+      let n = &mut self.nodes[node as usize];
+      n.count = (n.count as isize + delta) as usize;
+      if node == 0 { break; }
+      node = n.parent;
+    }
+  }
+  fn walk(&self, path: impl Iterator<Item = K>) -> Option<u32> {
+    let mut node = 0;
+    for k in path {
+      node = *self.nodes[node as usize].child.get(&k)?;
+    }
+    Some(node)
+  }
+  // how many stored keys start with prefix, read off the node's counter
+  pub fn count_with_prefix(&self, prefix: impl Iterator<Item = K>) -> usize {
+    self.walk(prefix).map_or(0, |node| self.nodes[node as usize].count)
+  }
+  // the value stored under exactly this key, borrowed
+  pub fn get_ref(&self, path: impl Iterator<Item = K>) -> Option<&U> {
+    self.walk(path).and_then(|node| self.nodes[node as usize].data.as_ref())
+  }
+  // every stored key that is a prefix of path, as (length, value) by increasing length
+  pub fn common_prefix_search(&self, path: impl Iterator<Item = K>) -> Vec<(usize, &U)> {
+    let mut found: Vec<(usize, &U)> = self.nodes[0].data.iter().map(|data| (0, data)).collect();
+    let mut node = 0;
+    for (i, k) in path.enumerate() {
+      node = match self.nodes[node as usize].child.get(&k) {
+        Some(&c) => c,
+        None => break,
+      };
+      found.extend(self.nodes[node as usize].data.iter().map(|data| (i + 1, data)));
+    }
+    found
+  }
+  pub fn get_mut(&mut self, path: impl Iterator<Item = K>) -> Option<&mut U> {
+    let node = self.walk(path)?;
+    self.nodes[node as usize].data.as_mut()
+  }
+  // runs f on the value in place, None if the key is not stored
+  pub fn with_data_mut<R>(&mut self, path: impl Iterator<Item = K>, f: impl FnOnce(&mut U) -> R) -> Option<R> {
+    self.get_mut(path).map(f)
+  }
+  // walks as far as the key exists; nodes for the rest are only created on insertion
+  pub fn entry(&mut self, path: impl Iterator<Item = K>) -> Entry<'_, K, U> {
+    let mut node = 0;
+    let mut path = path.peekable();
+    while let Some(&c) = path.peek().and_then(|k| self.nodes[node as usize].child.get(k)) {
+      node = c;
+      path.next();
     }
+    Entry { trie: self, node, rest: path.collect() }
+  }
+}
+
+impl<K: Eq + Hash + Copy, U: Default + Clone> Trie<K, U> {
+  pub fn cursor(&self) -> TrieCursor<'_, K, U> {
+    TrieCursor { trie: self, node: 0 }
   }
-  pub fn cursor(&self) -> TrieCursor<K, U> {
-    TrieCursor(self.root.clone())
+  pub fn cursor_mut(&mut self) -> TrieCursorMut<'_, K, U> {
+    TrieCursorMut { trie: self, node: 0 }
   }
-  pub fn insert(&self, mut path: impl Iterator<Item = K>, data: U) {
-    let mut cursor = self.cursor();
-    while let Some(k) = path.next() {
+  pub fn insert(&mut self, path: impl Iterator<Item = K>, data: U) {
+    let mut cursor = self.cursor_mut();
+    for k in path {
       cursor = cursor.child_or_insert_default(k);
     }
     cursor.set_data(data);
   }
-  pub fn get(&self, mut path: impl Iterator<Item = K>) -> Option<U> {
-    let mut cursor = self.cursor();
-    while let Some(k) = path.next() {
-      match cursor.child(&k) {
-        Some(next_cursor) => cursor = next_cursor,
-        None => return None,
+  pub fn get(&self, path: impl Iterator<Item = K>) -> Option<U> {
+    self.walk(path).map(|node| TrieCursor { trie: self, node }.get_data())
+  }
+  pub fn remove(&mut self, path: impl Iterator<Item = K>) -> Option<U> {
+    let mut nodes = vec![0];
+    let mut keys = vec![];
+    for k in path {
+      let next = *self.nodes[*nodes.last().unwrap() as usize].child.get(&k)?;
+      nodes.push(next);
+      keys.push(k);
+    }
+    let last = *nodes.last().unwrap();
+    let removed = self.nodes[last as usize].data.take();
+    if removed.is_some() {
+      self.add_count(last, -1);
+    }
+    // prune the chain of nodes that now hold no value and lead nowhere
+    while let Some(k) = keys.pop() {
+      let node = nodes.pop().unwrap();
+      if !self.is_dead(node) { break; }
+      self.nodes[*nodes.last().unwrap() as usize].child.remove(&k);
+      self.free.push(node);
+    }
+    removed
+  }
+  fn is_dead(&self, node: u32) -> bool {
+    let node = &self.nodes[node as usize];
+    node.data.is_none() && node.child.is_empty()
+  }
+  pub fn clear(&mut self) {
+    self.nodes.truncate(1);
+    self.free.clear();
+    let root = &mut self.nodes[0];
+    root.child.clear();
+    root.data = None;
+    root.count = 0;
+  }
+  // folds other into this trie in one simultaneous walk of both; keys present in both get
+  // resolve(ours, theirs)
+  pub fn merge(&mut self, mut other: Trie<K, U>, mut resolve: impl FnMut(U, U) -> U) {
+    let added = self.merge_node(0, &mut other, 0, &mut resolve);
+    self.nodes[0].count += added;
+  }
+  // returns how many keys the subtree at ours gained
+  fn merge_node(&mut self, ours: u32, other: &mut Trie<K, U>, theirs: u32, resolve: &mut impl FnMut(U, U) -> U) -> usize {
+    let mut added = 0;
+    if let Some(data) = other.nodes[theirs as usize].data.take() {
+      let slot = &mut self.nodes[ours as usize].data;
+      *slot = Some(match slot.take() {
+        Some(current) => resolve(current, data),
+        None => {
+          added += 1;
+          data
+        },
+      });
+    }
+    let children = std::mem::take(&mut other.nodes[theirs as usize].child);
+    for (k, their_child) in children {
+      let our_child = match self.nodes[ours as usize].child.get(&k) {
+        Some(&c) => c,
+        None => {
+          let c = self.alloc(ours, k);
+          self.nodes[ours as usize].child.insert(k, c);
+          c
+        },
+      };
+      let gained = self.merge_node(our_child, other, their_child, resolve);
+      self.nodes[our_child as usize].count += gained;
+      added += gained;
+    }
+    added
+  }
+  // every stored key within max_edits insertions, deletions or substitutions of query, with its
+  // value and distance, in no particular order. Each trie node extends the Levenshtein DP table
+  // of its parent by one row, and a subtree is skipped once its row has no entry <= max_edits.
+  pub fn search_fuzzy(&self, query: impl Iterator<Item = K>, max_edits: usize) -> Vec<(Vec<K>, U, usize)> {
+    let query: Vec<K> = query.collect();
+    let first_row: Vec<usize> = (0..=query.len()).collect();
+    let mut found = vec![];
+    let mut stack = vec![(vec![], 0u32, first_row)];
+    while let Some((path, node, row)) = stack.pop() {
+      let node = &self.nodes[node as usize];
+      if let (Some(data), Some(&dist)) = (&node.data, row.last()) {
+        if dist <= max_edits {
+          found.push((path.clone(), data.clone(), dist));
+        }
+      }
+      for (&k, &c) in &node.child {
+        let mut next = vec![row[0] + 1];
+        for j in 1..=query.len() {
+          let replace = row[j - 1] + if query[j - 1] == k { 0 } else { 1 };
+          next.push(replace.min(row[j] + 1).min(next[j - 1] + 1));
+        }
+        if next.iter().any(|&d| d <= max_edits) {
+          let mut child_path = path.clone();
+          child_path.push(k);
+          stack.push((child_path, c, next));
+        }
+      }
+    }
+    found
+  }
+  // every stored entry matching the pattern, in no particular order. Each state of the walk is
+  // a node and a position in the pattern; visiting a state once keeps "**" runs from reporting
+  // the same key twice.
+  pub fn get_wildcard(&self, pattern: impl Iterator<Item = Wildcard<K>>) -> Vec<(Vec<K>, U)> {
+    let pattern: Vec<Wildcard<K>> = pattern.collect();
+    let mut found = vec![];
+    let mut visited = HashSet::new();
+    let mut stack = vec![(vec![], 0u32, 0)];
+    while let Some((path, node, i)) = stack.pop() {
+      if !visited.insert((node, i)) {
+        continue;
+      }
+      let n = &self.nodes[node as usize];
+      let mut step = |k: &K, c: u32, next: usize| {
+        let mut child_path = path.clone();
+        child_path.push(*k);
+        stack.push((child_path, c, next));
+      };
+      match pattern.get(i) {
+        None => {
+          if let Some(data) = &n.data {
+            found.push((path, data.clone()));
+          }
+        },
+        Some(Wildcard::Key(k)) => {
+          if let Some(&c) = n.child.get(k) {
+            step(k, c, i + 1);
+          }
+        },
+        Some(Wildcard::Any) => {
+          for (k, &c) in &n.child {
+            step(k, c, i + 1);
+          }
+        },
+        Some(Wildcard::AnyRun) => {
+          for (k, &c) in &n.child {
+            step(k, c, i);
+          }
+          stack.push((path, node, i + 1));
+        },
+      }
+    }
+    found
+  }
+  // every stored entry level by level, shorter keys first; siblings come in no particular order
+  pub fn iter_bfs(&self) -> TrieBfsIter<'_, K, U> {
+    TrieBfsIter {
+      trie: self,
+      queue: vec![(vec![], 0)].into_iter().collect(),
+    }
+  }
+}
+
+impl<K: Eq + Hash + Copy + Ord, U: Default + Clone> Trie<K, U> {
+  // every stored key starting with prefix together with its value, in lexicographic order
+  pub fn iter_prefix(&self, prefix: impl Iterator<Item = K>) -> TrieIter<'_, K, U> {
+    let prefix: Vec<K> = prefix.collect();
+    TrieIter {
+      trie: self,
+      stack: self.walk(prefix.iter().copied()).map(|node| (prefix, node)).into_iter().collect(),
+    }
+  }
+  // every stored entry in lexicographic order
+  pub fn iter(&self) -> TrieIter<'_, K, U> {
+    self.iter_prefix(std::iter::empty())
+  }
+}
+
+impl<K: Eq + Hash + Copy, I: Ord + Clone> Trie<K, Vec<I>> {
+  // an index over many sequences: every node, the root included, holds the sorted ids of the
+  // sequences passing through it, so get(prefix) lists the documents containing that prefix.
+  // Inserting every suffix of each document makes it a generalized suffix trie.
+  pub fn from_sequences<S: IntoIterator<Item = K>>(sequences: impl IntoIterator<Item = (I, S)>) -> Trie<K, Vec<I>> {
+    let mut trie = Trie::new();
+    for (id, sequence) in sequences {
+      let mut cursor = trie.cursor_mut();
+      let mut rest = sequence.into_iter();
+      loop {
+        let ids = cursor.trie.nodes[cursor.node as usize].data.get_or_insert_with(Vec::new);
+        if ids.last() != Some(&id) {
+          ids.push(id.clone());
+        }
+        match rest.next() {
+          Some(k) => cursor = cursor.child_or_insert_default(k),
+          None => break,
+        }
+      }
+    }
+    // every node holds a value, and a fresh arena allocates children after their parents, so
+    // the counters are subtree sizes summed in reverse allocation order
+    for node in trie.nodes.iter_mut() {
+      if let Some(ids) = &mut node.data {
+        ids.sort();
+        ids.dedup();
       }
+      node.count = 1;
+    }
+    for i in (1..trie.nodes.len()).rev() {
+      let (count, parent) = (trie.nodes[i].count, trie.nodes[i].parent);
+      trie.nodes[parent as usize].count += count;
+    }
+    trie
+  }
+}
+
+impl<K: Eq + Hash + Copy + Ord + Codec, U: Codec> Trie<K, U> {
+  // compact preorder dump: a magic header, then per node varint(children << 1 | has value), the
+  // value if any, and each child as its key followed by its subtree, smallest key first. Equal
+  // tries give equal bytes.
+  pub fn serialize_to(&self, writer: &mut impl Write) -> io::Result<()> {
+    writer.write_all(MAGIC)?;
+    // a key of None is the root, which has none
+    let mut stack: Vec<(Option<K>, u32)> = vec![(None, 0)];
+    while let Some((key, node)) = stack.pop() {
+      if let Some(k) = key {
+        k.encode(writer)?;
+      }
+      let node = &self.nodes[node as usize];
+      write_varint(writer, (node.child.len() as u64) << 1 | node.data.is_some() as u64)?;
+      if let Some(data) = &node.data {
+        data.encode(writer)?;
+      }
+      let mut children: Vec<(&K, &u32)> = node.child.iter().collect();
+      children.sort();
+      stack.extend(children.into_iter().rev().map(|(&k, &c)| (Some(k), c)));
+    }
+    Ok(())
+  }
+
+  pub fn deserialize_from(reader: &mut impl Read) -> io::Result<Trie<K, U>> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if magic != *MAGIC {
+      return Err(invalid_data("not a serialized trie"));
+    }
+    let mut trie = Trie::new();
+    // (node, children still to read)
+    let mut stack = vec![(0, trie.read_node(0, reader)?)];
+    while let Some(top) = stack.last_mut() {
+      if top.1 == 0 {
+        stack.pop();
+        continue;
+      }
+      top.1 -= 1;
+      let parent = top.0;
+      let k = K::decode(reader)?;
+      let node = trie.alloc(parent, k);
+      if trie.nodes[parent as usize].child.insert(k, node).is_some() {
+        return Err(invalid_data("repeated key"));
+      }
+      let children = trie.read_node(node, reader)?;
+      stack.push((node, children));
+    }
+    // nodes were allocated after their parents, so counters add up in reverse
+    for i in (1..trie.nodes.len()).rev() {
+      let (count, parent) = (trie.nodes[i].count, trie.nodes[i].parent);
+      trie.nodes[parent as usize].count += count;
+    }
+    Ok(trie)
+  }
+
+  // reads a node header and value, returning the number of children that follow
+  fn read_node(&mut self, node: u32, reader: &mut impl Read) -> io::Result<u64> {
+    let header = read_varint(reader)?;
+    if header & 1 == 1 {
+      let n = &mut self.nodes[node as usize];
+      n.data = Some(U::decode(reader)?);
+      n.count = 1;
+    }
+    Ok(header >> 1)
+  }
+}
+
+impl<K: Eq + Hash + Copy, U> Default for Trie<K, U> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+const MAGIC: &[u8; 4] = b"TRI1";
+
+fn invalid_data(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// LEB128, 7 bits per byte with the high bit set on all but the last
+fn write_varint(writer: &mut impl Write, mut x: u64) -> io::Result<()> {
+  while x >= 0x80 {
+    writer.write_all(&[x as u8 | 0x80])?;
+    x >>= 7;
+  }
+  writer.write_all(&[x as u8])
+}
+
+fn read_varint(reader: &mut impl Read) -> io::Result<u64> {
+  let mut x = 0;
+  for shift in (0..64).step_by(7) {
+    let mut byte = [0];
+    reader.read_exact(&mut byte)?;
+    // the tenth byte has room for just the top bit
+    if shift == 63 && byte[0] & 0x7f > 1 {
+      return Err(invalid_data("varint too long"));
+    }
+    x |= ((byte[0] & 0x7f) as u64) << shift;
+    if byte[0] < 0x80 {
+      return Ok(x);
+    }
+  }
+  Err(invalid_data("varint too long"))
+}
+
+// binary encoding of keys and values for Trie::serialize_to. Integers are varints, signed ones
+// zigzag encoded so small negatives stay short.
+pub trait Codec: Sized {
+  fn encode(&self, writer: &mut impl Write) -> io::Result<()>;
+  fn decode(reader: &mut impl Read) -> io::Result<Self>;
+}
+
+macro_rules! unsigned_codec {
+  ($($t:ty),*) => {$(
+    impl Codec for $t {
+      fn encode(&self, writer: &mut impl Write) -> io::Result<()> {
+        write_varint(writer, *self as u64)
+      }
+      fn decode(reader: &mut impl Read) -> io::Result<Self> {
+        <$t>::try_from(read_varint(reader)?).map_err(|_| invalid_data("integer out of range"))
+      }
+    }
+  )*};
+}
+
+macro_rules! signed_codec {
+  ($($t:ty),*) => {$(
+    impl Codec for $t {
+      fn encode(&self, writer: &mut impl Write) -> io::Result<()> {
+        let x = *self as i64;
+        write_varint(writer, ((x << 1) ^ (x >> 63)) as u64)
+      }
+      fn decode(reader: &mut impl Read) -> io::Result<Self> {
+        let x = read_varint(reader)?;
+        <$t>::try_from((x >> 1) as i64 ^ -((x & 1) as i64)).map_err(|_| invalid_data("integer out of range"))
+      }
+    }
+  )*};
+}
+
+unsigned_codec!(u8, u16, u32, u64, usize);
+signed_codec!(i8, i16, i32, i64, isize);
+
+impl Codec for bool {
+  fn encode(&self, writer: &mut impl Write) -> io::Result<()> {
+    writer.write_all(&[*self as u8])
+  }
+  fn decode(reader: &mut impl Read) -> io::Result<Self> {
+    match u8::decode(reader)? {
+      0 => Ok(false),
+      1 => Ok(true),
+      _ => Err(invalid_data("bad bool")),
+    }
+  }
+}
+
+impl Codec for char {
+  fn encode(&self, writer: &mut impl Write) -> io::Result<()> {
+    write_varint(writer, *self as u64)
+  }
+  fn decode(reader: &mut impl Read) -> io::Result<Self> {
+    char::from_u32(u32::decode(reader)?).ok_or_else(|| invalid_data("bad char"))
+  }
+}
+
+impl Codec for String {
+  fn encode(&self, writer: &mut impl Write) -> io::Result<()> {
+    write_varint(writer, self.len() as u64)?;
+    writer.write_all(self.as_bytes())
+  }
+  fn decode(reader: &mut impl Read) -> io::Result<Self> {
+    let len = read_varint(reader)?;
+    let mut bytes = vec![];
+    if reader.by_ref().take(len).read_to_end(&mut bytes)? as u64 != len {
+      return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    String::from_utf8(bytes).map_err(|_| invalid_data("bad utf-8"))
+  }
+}
+
+impl<T: Codec> Codec for Vec<T> {
+  fn encode(&self, writer: &mut impl Write) -> io::Result<()> {
+    write_varint(writer, self.len() as u64)?;
+    self.iter().try_for_each(|x| x.encode(writer))
+  }
+  fn decode(reader: &mut impl Read) -> io::Result<Self> {
+    let len = read_varint(reader)?;
+    (0..len).map(|_| T::decode(reader)).collect()
+  }
+}
+
+// a key's slot, occupied or not, as returned by Trie::entry
+pub struct Entry<'a, K, U> {
+  trie: &'a mut Trie<K, U>,
+  node: u32, // the deepest existing node on the path
+  rest: Vec<K>, // keys below node still to be created
+}
+
+impl<'a, K: Eq + Hash + Copy, U> Entry<'a, K, U> {
+  fn data(&mut self) -> Option<&mut U> {
+    if !self.rest.is_empty() {
+      return None;
+    }
+    self.trie.nodes[self.node as usize].data.as_mut()
+  }
+  pub fn and_modify(mut self, f: impl FnOnce(&mut U)) -> Entry<'a, K, U> {
+    if let Some(data) = self.data() {
+      f(data);
+    }
+    self
+  }
+  pub fn or_insert_with(self, f: impl FnOnce() -> U) -> &'a mut U {
+    let Entry { trie, mut node, rest } = self;
+    for k in rest {
+      let c = trie.alloc(node, k);
+      trie.nodes[node as usize].child.insert(k, c);
+      node = c;
+    }
+    if trie.nodes[node as usize].data.is_none() {
+      trie.nodes[node as usize].data = Some(f());
+      trie.add_count(node, 1);
+    }
+    trie.nodes[node as usize].data.as_mut().unwrap()
+  }
+  pub fn or_insert(self, data: U) -> &'a mut U {
+    self.or_insert_with(|| data)
+  }
+}
+
+impl<'a, K: Eq + Hash + Copy, U: Default> Entry<'a, K, U> {
+  pub fn or_default(self) -> &'a mut U {
+    self.or_insert_with(U::default)
+  }
+}
+
+// one position of a get_wildcard pattern
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Wildcard<K> {
+  Key(K),
+  Any, // exactly one key, "?"
+  AnyRun, // zero or more keys, "**"
+}
+
+impl Wildcard<char> {
+  // reads '?' as Any and "**" as AnyRun, anything else literally
+  pub fn parse(pattern: &str) -> Vec<Wildcard<char>> {
+    let mut parsed = vec![];
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+      parsed.push(match c {
+        '?' => Wildcard::Any,
+        '*' if chars.peek() == Some(&'*') => {
+          chars.next();
+          Wildcard::AnyRun
+        },
+        c => Wildcard::Key(c),
+      });
+    }
+    parsed
+  }
+}
+
+pub struct TrieCursor<'a, K, U> {
+  trie: &'a Trie<K, U>,
+  node: u32,
+}
+impl<'a, K: Eq + Hash + Copy, U: Default + Clone> TrieCursor<'a, K, U> {
+  pub fn child(&self, k: &K) -> Option<TrieCursor<'a, K, U>> {
+    let trie = self.trie;
+    trie.nodes[self.node as usize].child.get(k).map(|&node| TrieCursor { trie, node })
+  }
+  pub fn get_data(&self) -> U {
+    self.trie.nodes[self.node as usize].data.clone().unwrap_or_default()
+  }
+  // None at the root
+  pub fn to_parent(&self) -> Option<TrieCursor<'a, K, U>> {
+    if self.node == 0 {
+      return None;
     }
-    Some(cursor.get_data())
+    Some(TrieCursor { trie: self.trie, node: self.trie.nodes[self.node as usize].parent })
+  }
+  // number of keys between the root and the cursor
+  pub fn depth(&self) -> usize {
+    let mut depth = 0;
+    let mut node = self.node;
+    while node != 0 {
+      node = self.trie.nodes[node as usize].parent;
+      depth += 1;
+    }
+    depth
+  }
+  // the keys leading from the root to the cursor
+  pub fn path_so_far(&self) -> Vec<K> {
+    let mut path = vec![];
+    let mut node = &self.trie.nodes[self.node as usize];
+    while let Some(k) = node.key {
+      path.push(k);
+      node = &self.trie.nodes[node.parent as usize];
+    }
+    path.reverse();
+    path
   }
 }
 
-pub struct TrieCursor<K, U>(Rc<RefCell<TrieNode<K, U>>>);
-impl<K: Eq + Hash + Copy, U: Default + Clone> TrieCursor<K, U> {
-  fn child_or_insert_default(&self, k: K) -> TrieCursor<K, U> {
-    let mut node = self.0.borrow_mut();
-    let entry = node.child
-      .entry(k)
-      .or_insert(Rc::new(RefCell::new(TrieNode::new())));  // init child
-    TrieCursor(entry.clone())
+pub struct TrieCursorMut<'a, K, U> {
+  trie: &'a mut Trie<K, U>,
+  node: u32,
+}
+impl<'a, K: Eq + Hash + Copy, U: Default + Clone> TrieCursorMut<'a, K, U> {
+  fn child_or_insert_default(self, k: K) -> TrieCursorMut<'a, K, U> {
+    let node = match self.trie.nodes[self.node as usize].child.get(&k) {
+      Some(&node) => node,
+      None => {
+        let node = self.trie.alloc(self.node, k);  // init child
+        self.trie.nodes[self.node as usize].child.insert(k, node);
+        node
+      },
+    };
+    TrieCursorMut { trie: self.trie, node }
   }
-  pub fn child(&self, k: &K) -> Option<TrieCursor<K, U>> {
-    self.0.borrow().child.get(k).map(|c| TrieCursor(Rc::clone(c)))
+  pub fn child(self, k: &K) -> Option<TrieCursorMut<'a, K, U>> {
+    let node = *self.trie.nodes[self.node as usize].child.get(k)?;
+    Some(TrieCursorMut { trie: self.trie, node })
   }
-  pub fn set_data(&self, data: U) {
-    self.0.borrow_mut().data = data;
+  pub fn set_data(&mut self, data: U) {
+    if self.trie.nodes[self.node as usize].data.replace(data).is_none() {
+      self.trie.add_count(self.node, 1);
+    }
   }
   pub fn get_data(&self) -> U {
-    self.0.borrow().data.clone()
+    self.trie.nodes[self.node as usize].data.clone().unwrap_or_default()
+  }
+}
+
+// depth-first walk, children are pushed in reverse order so the smallest key pops first
+pub struct TrieIter<'a, K, U> {
+  trie: &'a Trie<K, U>,
+  stack: Vec<(Vec<K>, u32)>,
+}
+
+impl<'a, K: Eq + Hash + Copy + Ord, U: Default + Clone> Iterator for TrieIter<'a, K, U> {
+  type Item = (Vec<K>, U);
+  fn next(&mut self) -> Option<Self::Item> {
+    while let Some((path, node)) = self.stack.pop() {
+      let node = &self.trie.nodes[node as usize];
+      let mut keys: Vec<&K> = node.child.keys().collect();
+      keys.sort();
+      for k in keys.into_iter().rev() {
+        let mut child_path = path.clone();
+        child_path.push(*k);
+        self.stack.push((child_path, node.child[k]));
+      }
+      if let Some(data) = &node.data {
+        return Some((path, data.clone()));
+      }
+    }
+    None
+  }
+}
+
+pub struct TrieBfsIter<'a, K, U> {
+  trie: &'a Trie<K, U>,
+  queue: VecDeque<(Vec<K>, u32)>,
+}
+
+impl<'a, K: Eq + Hash + Copy, U: Default + Clone> Iterator for TrieBfsIter<'a, K, U> {
+  type Item = (Vec<K>, U);
+  fn next(&mut self) -> Option<Self::Item> {
+    while let Some((path, node)) = self.queue.pop_front() {
+      let node = &self.trie.nodes[node as usize];
+      for (k, &c) in &node.child {
+        let mut child_path = path.clone();
+        child_path.push(*k);
+        self.queue.push_back((child_path, c));
+      }
+      if let Some(data) = &node.data {
+        return Some((path, data.clone()));
+      }
+    }
+    None
   }
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::test_util::rand_gen;
 
   #[test]
   fn test() {
-    let trie: Trie<char, bool> = Trie::new();
+    let mut trie: Trie<char, bool> = Trie::new();
     trie.insert("to".chars(), true);
     trie.insert("A".chars(), true);
     trie.insert("tea".chars(), true);
@@ -92,4 +736,387 @@ mod tests {
       assert!(trie.get(word.chars()).unwrap_or(false));
     }
   }
+
+  #[test]
+  fn test_remove() {
+    let mut trie: Trie<char, u32> = Trie::new();
+    trie.insert("tea".chars(), 1);
+    trie.insert("team".chars(), 2);
+    trie.insert("ten".chars(), 3);
+    trie.insert("to".chars(), 4);
+
+    assert_eq!(None, trie.remove("te".chars())); // only a prefix
+    assert_eq!(None, trie.remove("tex".chars()));
+    assert_eq!(Some(1), trie.remove("tea".chars()));
+    assert_eq!(None, trie.remove("tea".chars()));
+    assert_eq!(Some(2), trie.get("team".chars())); // the node stays, it leads to "team"
+
+    assert_eq!(Some(2), trie.remove("team".chars()));
+    assert_eq!(None, trie.get("tea".chars())); // "a" and "m" got pruned
+    assert_eq!(Some(3), trie.get("ten".chars()));
+
+    assert_eq!(Some(3), trie.remove("ten".chars()));
+    assert_eq!(None, trie.get("te".chars()));
+    assert_eq!(Some(4), trie.get("to".chars()));
+    assert_eq!(1, trie.nodes[0].child.len());
+
+    trie.clear();
+    assert_eq!(None, trie.get("to".chars()));
+    assert!(trie.nodes[0].child.is_empty());
+  }
+
+  #[test]
+  fn test_iter_prefix() {
+    let mut trie: Trie<char, usize> = Trie::new();
+    let words = ["to", "tea", "ted", "ten", "A", "i", "in", "inn", "te"];
+    for (i, w) in words.iter().enumerate() {
+      trie.insert(w.chars(), i);
+    }
+    let collect = |prefix: &str| -> Vec<(String, usize)> {
+      trie.iter_prefix(prefix.chars())
+        .map(|(k, v)| (k.into_iter().collect(), v))
+        .collect()
+    };
+    assert_eq!(
+      vec![("te".to_string(), 8), ("tea".to_string(), 1), ("ted".to_string(), 2), ("ten".to_string(), 3)],
+      collect("te")
+    );
+    assert_eq!(vec!["A", "i", "in", "inn", "te", "tea", "ted", "ten", "to"],
+      collect("").into_iter().map(|(k, _)| k).collect::<Vec<_>>());
+    assert_eq!(vec![("inn".to_string(), 7)], collect("inn"));
+    assert!(collect("x").is_empty());
+    assert!(collect("tex").is_empty());
+  }
+
+  #[test]
+  fn test_iter() {
+    let mut trie: Trie<char, usize> = Trie::new();
+    assert_eq!(None, trie.iter().next());
+    assert_eq!(None, trie.iter_bfs().next());
+    let words = ["tea", "to", "", "inn", "t", "in", "ten"];
+    for (i, w) in words.iter().enumerate() {
+      trie.insert(w.chars(), i);
+    }
+    let dfs: Vec<(String, usize)> = trie.iter().map(|(k, v)| (k.into_iter().collect(), v)).collect();
+    let mut sorted: Vec<(String, usize)> = words.iter().enumerate().map(|(i, w)| (w.to_string(), i)).collect();
+    sorted.sort();
+    assert_eq!(sorted, dfs);
+    let bfs: Vec<(String, usize)> = trie.iter_bfs().map(|(k, v)| (k.into_iter().collect(), v)).collect();
+    assert!(bfs.windows(2).all(|w| w[0].0.len() <= w[1].0.len()));
+    let mut bfs = bfs;
+    bfs.sort();
+    assert_eq!(sorted, bfs);
+  }
+
+  fn levenshtein(a: &[u8], b: &[u8]) -> usize {
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+      let mut next = vec![i];
+      for j in 1..=b.len() {
+        next.push((row[j - 1] + (a[i - 1] != b[j - 1]) as usize).min(row[j] + 1).min(next[j - 1] + 1));
+      }
+      row = next;
+    }
+    row[b.len()]
+  }
+
+  #[test]
+  fn test_search_fuzzy() {
+    let mut trie: Trie<char, usize> = Trie::new();
+    let words = ["cat", "cart", "care", "act", "at", "dog", "", "cats"];
+    for (i, w) in words.iter().enumerate() {
+      trie.insert(w.chars(), i);
+    }
+    let search = |query: &str, k: usize| -> Vec<(String, usize)> {
+      let mut found: Vec<(String, usize)> = trie.search_fuzzy(query.chars(), k)
+        .into_iter()
+        .map(|(key, value, dist)| {
+          assert_eq!(words[value].chars().collect::<Vec<_>>(), key);
+          (key.into_iter().collect(), dist)
+        })
+        .collect();
+      found.sort();
+      found
+    };
+    assert_eq!(vec![("cat".to_string(), 0)], search("cat", 0));
+    assert_eq!(vec!["at", "cart", "cat", "cats"], search("cat", 1).into_iter().map(|(w, _)| w).collect::<Vec<_>>());
+    assert_eq!(vec![("".to_string(), 0), ("at".to_string(), 2)], search("", 2));
+    assert!(search("zzzzzz", 2).is_empty());
+
+    // random words over a tiny alphabet against a direct distance computation
+    let mut rand = rand_gen(19);
+    let mut trie: Trie<u8, usize> = Trie::new();
+    let mut words = vec![];
+    for i in 0..300 {
+      let len = rand(7);
+      let word: Vec<u8> = (0..len).map(|_| rand(3) as u8).collect();
+      trie.insert(word.iter().copied(), i);
+      words.push(word);
+    }
+    for _ in 0..50 {
+      let len = rand(7);
+      let query: Vec<u8> = (0..len).map(|_| rand(3) as u8).collect();
+      let k = rand(3) as usize;
+      let mut found: Vec<(Vec<u8>, usize)> = trie.search_fuzzy(query.iter().copied(), k)
+        .into_iter().map(|(key, _, dist)| (key, dist)).collect();
+      found.sort();
+      let mut expected: Vec<(Vec<u8>, usize)> = words.iter()
+        .map(|w| (w.clone(), levenshtein(w, &query)))
+        .filter(|&(_, d)| d <= k)
+        .collect();
+      expected.sort();
+      expected.dedup();
+      assert_eq!(expected, found);
+    }
+  }
+
+  #[test]
+  fn test_count_with_prefix() {
+    let mut trie: Trie<char, u32> = Trie::new();
+    for w in ["tea", "team", "ten", "to", ""].iter() {
+      trie.insert(w.chars(), 1);
+    }
+    trie.insert("tea".chars(), 2); // overwriting does not count twice
+    assert_eq!(5, trie.count_with_prefix("".chars()));
+    assert_eq!(4, trie.count_with_prefix("t".chars()));
+    assert_eq!(3, trie.count_with_prefix("te".chars()));
+    assert_eq!(2, trie.count_with_prefix("tea".chars()));
+    assert_eq!(0, trie.count_with_prefix("x".chars()));
+    trie.remove("team".chars());
+    trie.remove("team".chars());
+    assert_eq!(1, trie.count_with_prefix("tea".chars()));
+    assert_eq!(3, trie.count_with_prefix("t".chars()));
+    let mut cursor = trie.cursor_mut().child(&'t').unwrap();
+    cursor.set_data(5);
+    assert_eq!(4, trie.count_with_prefix("t".chars()));
+    assert_eq!(5, trie.count_with_prefix("".chars()));
+    trie.remove("ten".chars());
+    trie.insert("tx".chars(), 1); // reuses a freed slot
+    assert_eq!(2, trie.count_with_prefix("te".chars()) + trie.count_with_prefix("tx".chars()));
+    trie.clear();
+    assert_eq!(0, trie.count_with_prefix("".chars()));
+  }
+
+  #[test]
+  fn test_merge() {
+    let mut a: Trie<char, u32> = Trie::new();
+    let mut b: Trie<char, u32> = Trie::new();
+    for (w, v) in [("tea", 1), ("ten", 2), ("to", 3)].iter() {
+      a.insert(w.chars(), *v);
+    }
+    for (w, v) in [("tea", 10), ("team", 20), ("i", 30), ("", 40)].iter() {
+      b.insert(w.chars(), *v);
+    }
+    a.merge(b, |ours, theirs| ours + theirs);
+    let merged: Vec<(String, u32)> = a.iter().map(|(k, v)| (k.into_iter().collect(), v)).collect();
+    let expected = [("", 40), ("i", 30), ("tea", 11), ("team", 20), ("ten", 2), ("to", 3)];
+    assert_eq!(expected.iter().map(|&(k, v)| (k.to_string(), v)).collect::<Vec<_>>(), merged);
+    assert_eq!(6, a.count_with_prefix("".chars()));
+    assert_eq!(4, a.count_with_prefix("t".chars()));
+    assert_eq!(2, a.count_with_prefix("tea".chars()));
+    // merged nodes take part in pruning like any other
+    assert_eq!(Some(20), a.remove("team".chars()));
+    assert_eq!(1, a.count_with_prefix("tea".chars()));
+  }
+
+  #[test]
+  fn test_send_sync_and_reuse() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Trie<char, String>>();
+
+    let mut trie: Trie<u8, u32> = Trie::new();
+    trie.insert([1, 2, 3].iter().copied(), 7);
+    let allocated = trie.nodes.len();
+    assert_eq!(Some(7), trie.remove([1, 2, 3].iter().copied()));
+    assert_eq!(3, trie.free.len());
+    trie.insert([4, 5].iter().copied(), 8);
+    assert_eq!(allocated, trie.nodes.len()); // freed slots were reused
+    assert_eq!(Some(8), trie.get([4, 5].iter().copied()));
+
+    let mut cursor = trie.cursor_mut().child(&4).unwrap();
+    cursor.set_data(9);
+    assert_eq!(Some(9), trie.get([4].iter().copied()));
+    assert_eq!(8, trie.cursor().child(&4).unwrap().child(&5).unwrap().get_data());
+  }
+
+  #[test]
+  fn test_from_sequences() {
+    let docs = [(0, "banana"), (1, "band"), (2, "can"), (1, "bandana")];
+    let trie = Trie::from_sequences(docs.iter().map(|&(id, s)| (id, s.chars())));
+    let list = |prefix: &str| trie.get(prefix.chars()).unwrap_or_default();
+    assert_eq!(vec![0, 1, 2], list(""));
+    assert_eq!(vec![0, 1], list("ban"));
+    assert_eq!(vec![1], list("band"));
+    assert_eq!(vec![2], list("c"));
+    assert!(list("x").is_empty());
+    assert_eq!(trie.nodes.len(), trie.count_with_prefix(std::iter::empty()));
+    assert_eq!(4, trie.count_with_prefix("band".chars())); // band, banda, bandan, bandana
+
+    // every suffix of every document: substring queries list the documents containing them
+    let docs = ["abab", "baba", "aab"];
+    let suffixes = docs.iter().enumerate()
+      .flat_map(|(id, d)| (0..d.len()).map(move |i| (id, d[i..].chars())));
+    let trie = Trie::from_sequences(suffixes);
+    for pattern in ["ab", "ba", "aa", "bab", "b", "abb"] {
+      let expected: Vec<usize> = (0..docs.len()).filter(|&id| docs[id].contains(pattern)).collect();
+      assert_eq!(expected, trie.get(pattern.chars()).unwrap_or_default());
+    }
+  }
+
+  fn glob(pattern: &[Wildcard<char>], word: &[char]) -> bool {
+    match pattern.split_first() {
+      None => word.is_empty(),
+      Some((Wildcard::AnyRun, rest)) => (0..=word.len()).any(|i| glob(rest, &word[i..])),
+      Some((p, rest)) => match word.split_first() {
+        Some((c, tail)) => (*p == Wildcard::Any || *p == Wildcard::Key(*c)) && glob(rest, tail),
+        None => false,
+      },
+    }
+  }
+
+  #[test]
+  fn test_get_wildcard() {
+    let mut trie: Trie<char, usize> = Trie::new();
+    let words = ["tad", "ted", "toad", "td", "t", "", "tedd", "bed", "ted*"];
+    for (i, w) in words.iter().enumerate() {
+      trie.insert(w.chars(), i);
+    }
+    let search = |pattern: &str| -> Vec<String> {
+      let mut found: Vec<String> = trie.get_wildcard(Wildcard::parse(pattern).into_iter())
+        .into_iter()
+        .map(|(k, v)| {
+          assert_eq!(words[v].chars().collect::<Vec<_>>(), k);
+          k.into_iter().collect()
+        })
+        .collect();
+      found.sort();
+      found
+    };
+    assert_eq!(vec!["tad", "ted"], search("t?d"));
+    assert_eq!(vec!["tad", "td", "ted", "tedd", "toad"], search("t**d"));
+    assert_eq!(vec!["ted*"], search("ted*"));
+    assert_eq!(vec!["bed", "ted"], search("?ed"));
+    assert_eq!(words.len(), search("**").len());
+    assert_eq!(vec!["", "t"], search("**").into_iter().filter(|w| w.len() < 2).collect::<Vec<_>>());
+    assert!(search("??????").is_empty());
+    for pattern in ["**d", "?**?", "t?**", "**e**", "****", "?"] {
+      let parsed = Wildcard::parse(pattern);
+      let mut expected: Vec<String> = words.iter()
+        .filter(|w| glob(&parsed, &w.chars().collect::<Vec<_>>()))
+        .map(|w| w.to_string())
+        .collect();
+      expected.sort();
+      assert_eq!(expected, search(pattern));
+    }
+  }
+
+  #[test]
+  fn test_cursor_parent_links() {
+    let mut trie: Trie<char, u32> = Trie::new();
+    trie.insert("tea".chars(), 1);
+    trie.insert("ten".chars(), 2);
+    let root = trie.cursor();
+    assert!(root.to_parent().is_none());
+    assert_eq!(0, root.depth());
+    assert!(root.path_so_far().is_empty());
+    let tea = root.child(&'t').unwrap().child(&'e').unwrap().child(&'a').unwrap();
+    assert_eq!(3, tea.depth());
+    assert_eq!(vec!['t', 'e', 'a'], tea.path_so_far());
+    let te = tea.to_parent().unwrap();
+    assert_eq!(vec!['t', 'e'], te.path_so_far());
+    assert_eq!(2, te.child(&'n').unwrap().get_data());
+    assert!(te.to_parent().unwrap().to_parent().unwrap().to_parent().is_none());
+
+    // slots reused after a removal pick up their new key
+    trie.remove("tea".chars());
+    trie.insert("tex".chars(), 3);
+    let tex = trie.cursor().child(&'t').unwrap().child(&'e').unwrap().child(&'x').unwrap();
+    assert_eq!(vec!['t', 'e', 'x'], tex.path_so_far());
+    let mut other: Trie<char, u32> = Trie::new();
+    other.insert("zz".chars(), 4);
+    trie.merge(other, |a, _| a);
+    let zz = trie.cursor().child(&'z').unwrap().child(&'z').unwrap();
+    assert_eq!(vec!['z', 'z'], zz.path_so_far());
+  }
+
+  #[test]
+  fn test_entry_and_references() {
+    // neither Clone nor Default
+    #[derive(Debug, PartialEq)]
+    struct Postings(Vec<usize>);
+
+    let mut trie: Trie<char, Postings> = Trie::new();
+    for (doc, text) in ["to be or not to be", "to do", "be"].iter().enumerate() {
+      for word in text.split(' ') {
+        trie.entry(word.chars()).or_insert_with(|| Postings(vec![])).0.push(doc);
+      }
+    }
+    assert_eq!(Some(&Postings(vec![0, 0, 1])), trie.get_ref("to".chars()));
+    assert_eq!(Some(&Postings(vec![0, 0, 2])), trie.get_ref("be".chars()));
+    assert_eq!(None, trie.get_ref("t".chars()));
+    assert_eq!(None, trie.get_ref("tox".chars()));
+    trie.get_mut("do".chars()).unwrap().0.clear();
+    assert_eq!(Some(0), trie.with_data_mut("do".chars(), |p| p.0.len()));
+    assert_eq!(None, trie.with_data_mut("d".chars(), |p| p.0.len()));
+    assert_eq!(5, trie.count_with_prefix(std::iter::empty())); // to be or not do
+
+    let mut counts: Trie<char, usize> = Trie::new();
+    for word in "a ab a abc ab a".split(' ') {
+      *counts.entry(word.chars()).or_default() += 1;
+    }
+    assert_eq!(Some(3), counts.get("a".chars()));
+    assert_eq!(Some(2), counts.get("ab".chars()));
+    counts.entry("ab".chars()).and_modify(|c| *c *= 10).or_insert(0);
+    counts.entry("abcd".chars()).and_modify(|c| *c *= 10).or_insert(7);
+    assert_eq!(Some(20), counts.get("ab".chars()));
+    assert_eq!(Some(7), counts.get("abcd".chars()));
+    assert_eq!(3, counts.count_with_prefix("ab".chars()));
+  }
+
+  #[test]
+  fn test_serialize_round_trip() {
+    let mut trie: Trie<char, i64> = Trie::new();
+    for (i, w) in ["to", "tea", "ted", "ten", "", "inn", "東京"].iter().enumerate() {
+      trie.insert(w.chars(), i as i64 * 1000 - 3000);
+    }
+    trie.remove("ted".chars());
+    let mut bytes = vec![];
+    trie.serialize_to(&mut bytes).unwrap();
+    let loaded: Trie<char, i64> = Trie::deserialize_from(&mut bytes.as_slice()).unwrap();
+    assert_eq!(trie.iter().collect::<Vec<_>>(), loaded.iter().collect::<Vec<_>>());
+    assert_eq!(3, loaded.count_with_prefix("t".chars()));
+    assert_eq!(6, loaded.count_with_prefix(std::iter::empty()));
+    let mut again = vec![];
+    loaded.serialize_to(&mut again).unwrap();
+    assert_eq!(bytes, again);
+
+    let mut postings: Trie<u8, Vec<String>> = Trie::new();
+    postings.insert(b"ab".iter().copied(), vec!["x".to_string(), "yz".to_string()]);
+    postings.insert(b"a".iter().copied(), vec![]);
+    let mut bytes = vec![];
+    postings.serialize_to(&mut bytes).unwrap();
+    let loaded: Trie<u8, Vec<String>> = Trie::deserialize_from(&mut bytes.as_slice()).unwrap();
+    assert_eq!(Some(&vec!["x".to_string(), "yz".to_string()]), loaded.get_ref(b"ab".iter().copied()));
+    assert_eq!(Some(&vec![]), loaded.get_ref(b"a".iter().copied()));
+    assert_eq!(None, loaded.get_ref(b"".iter().copied()));
+
+    for cut in 0..bytes.len() {
+      assert!(Trie::<u8, Vec<String>>::deserialize_from(&mut &bytes[..cut]).is_err());
+    }
+    assert!(Trie::<u8, u8>::deserialize_from(&mut &b"TRIE"[..]).is_err());
+  }
+
+  #[test]
+  fn test_varint_bounds() {
+    let mut bytes = vec![];
+    write_varint(&mut bytes, u64::MAX).unwrap();
+    assert_eq!(10, bytes.len());
+    assert_eq!(u64::MAX, read_varint(&mut bytes.as_slice()).unwrap());
+    // bits past the 64th, in the tenth byte or an eleventh one, are an error rather than lost
+    bytes[9] = 0x02;
+    assert!(read_varint(&mut bytes.as_slice()).is_err());
+    bytes[9] = 0x81;
+    bytes.push(0x00);
+    assert!(read_varint(&mut bytes.as_slice()).is_err());
+  }
 }
\ No newline at end of file
@@ -0,0 +1,271 @@
+// Range minimum queries in about 2.5 bits per element, without keeping the array (after
+// Fischer & Heun, in the balanced-parentheses form of Ferrada & Navarro). Where a sparse table
+// takes a word per element per level, this suits huge static arrays that live on disk or are
+// implied by another structure, such as the LCP array of a suffix array.
+//
+// The array is encoded by a stack scan: each element pops the larger elements before it, one ')'
+// each, and pushes itself with a '('. Under a leading '(' for a virtual root, the excess of
+// '(' over ')' is the stack depth, and the minimum of a[i..=j] is the element whose '(' follows
+// the rightmost minimum of the excess between the positions just before i's and j's '('.
+// Excess minima come from a block summary with a segment tree over the blocks and byte tables
+// inside them.
+
+const BLOCK: usize = 512; // bits
+
+// per byte of parentheses, least significant bit first: the total excess, the minimum running
+// excess and the rightmost position it is reached at
+struct ByteTables {
+  total: [i8; 256],
+  min: [i8; 256],
+  argmin: [u8; 256],
+}
+
+impl ByteTables {
+  fn new() -> ByteTables {
+    let mut t = ByteTables { total: [0; 256], min: [0; 256], argmin: [0; 256] };
+    for v in 0..256 {
+      let (mut e, mut min, mut argmin) = (0, i8::MAX, 0);
+      for k in 0..8 {
+        e += if v >> k & 1 == 1 { 1 } else { -1 };
+        if e <= min {
+          min = e;
+          argmin = k as u8;
+        }
+      }
+      t.total[v] = e;
+      t.min[v] = min;
+      t.argmin[v] = argmin;
+    }
+    t
+  }
+}
+
+pub struct SuccinctRmq {
+  n: usize,
+  bits: Vec<u64>, // 1 for '('
+  before: Vec<i32>, // excess before each block
+  block_min: Vec<i32>, // lowest excess inside each block
+  tree: Vec<u32>, // segment tree of blocks, each node the block of the rightmost lowest minimum
+  tables: ByteTables,
+}
+
+impl SuccinctRmq {
+  pub fn new<T: Ord>(a: &[T]) -> SuccinctRmq {
+    let len = 2 * a.len() + 2; // parentheses
+    let mut bits = vec![0u64; len.div_ceil(64)];
+    let mut at = 1;
+    bits[0] = 1;
+    let mut stack: Vec<&T> = vec![];
+    for x in a {
+      while stack.last().is_some_and(|&top| top > x) {
+        stack.pop();
+        at += 1;
+      }
+      stack.push(x);
+      bits[at / 64] |= 1 << (at % 64);
+      at += 1;
+    }
+    let blocks = len.div_ceil(BLOCK);
+    let mut rmq = SuccinctRmq {
+      n: a.len(),
+      bits,
+      before: Vec::with_capacity(blocks),
+      block_min: Vec::with_capacity(blocks),
+      tree: vec![0; 2 * blocks],
+      tables: ByteTables::new(),
+    };
+    let mut e = 0;
+    for b in 0..blocks {
+      rmq.before.push(e);
+      let end = ((b + 1) * BLOCK).min(len);
+      let (min, _) = rmq.scan(b * BLOCK, end, e);
+      rmq.block_min.push(min);
+      e += 2 * rmq.ones(b * BLOCK, end) as i32 - (end - b * BLOCK) as i32;
+    }
+    for b in 0..blocks {
+      rmq.tree[blocks + b] = b as u32;
+    }
+    for v in (1..blocks).rev() {
+      rmq.tree[v] = rmq.lower(rmq.tree[2 * v], rmq.tree[2 * v + 1]);
+    }
+    rmq
+  }
+
+  pub fn len(&self) -> usize {
+    self.n
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.n == 0
+  }
+
+  // space taken, tables aside
+  pub fn size_in_bits(&self) -> usize {
+    64 * self.bits.len() + 32 * (self.before.len() + self.block_min.len() + self.tree.len())
+  }
+
+  fn bit(&self, p: usize) -> bool {
+    self.bits[p / 64] >> (p % 64) & 1 == 1
+  }
+
+  // number of '(' in lo..hi
+  fn ones(&self, lo: usize, hi: usize) -> usize {
+    let mut count = 0;
+    let mut p = lo;
+    while p < hi {
+      let take = (64 - p % 64).min(hi - p);
+      let word = self.bits[p / 64] >> (p % 64);
+      count += if take == 64 { word } else { word & ((1 << take) - 1) }.count_ones() as usize;
+      p += take;
+    }
+    count
+  }
+
+  // excess over the parentheses before position p
+  fn excess_before(&self, p: usize) -> i32 {
+    let start = p / BLOCK * BLOCK;
+    self.before[p / BLOCK] + 2 * self.ones(start, p) as i32 - (p - start) as i32
+  }
+
+  // the block of the lower minimum, the later one on a tie
+  fn lower(&self, a: u32, b: u32) -> u32 {
+    let (ma, mb) = (self.block_min[a as usize], self.block_min[b as usize]);
+    if ma < mb || (ma == mb && a > b) { a } else { b }
+  }
+
+  // the lowest excess after a position in lo..hi and the rightmost position reaching it, given
+  // the excess before lo
+  fn scan(&self, lo: usize, hi: usize, mut e: i32) -> (i32, usize) {
+    let (mut min, mut argmin) = (i32::MAX, lo);
+    let mut p = lo;
+    while p < hi {
+      if p.is_multiple_of(8) && p + 8 <= hi {
+        let byte = (self.bits[p / 64] >> (p % 64) & 0xff) as usize;
+        if e + self.tables.min[byte] as i32 <= min {
+          min = e + self.tables.min[byte] as i32;
+          argmin = p + self.tables.argmin[byte] as usize;
+        }
+        e += self.tables.total[byte] as i32;
+        p += 8;
+      } else {
+        e += if self.bit(p) { 1 } else { -1 };
+        if e <= min {
+          min = e;
+          argmin = p;
+        }
+        p += 1;
+      }
+    }
+    (min, argmin)
+  }
+
+  // position of the k-th '(', counting the root as the 0th
+  fn select_open(&self, k: usize) -> usize {
+    // '(' before block b: (excess + position) / 2
+    let opens_before = |b: usize| (self.before[b] as usize + b * BLOCK) / 2;
+    let (mut b, mut hi) = (0, self.before.len());
+    while hi - b > 1 {
+      let mid = (b + hi) / 2;
+      if opens_before(mid) <= k { b = mid } else { hi = mid }
+    }
+    let mut rest = k - opens_before(b);
+    let mut w = b * BLOCK / 64;
+    while self.bits[w].count_ones() as usize <= rest {
+      rest -= self.bits[w].count_ones() as usize;
+      w += 1;
+    }
+    let mut word = self.bits[w];
+    for _ in 0..rest {
+      word &= word - 1;
+    }
+    64 * w + word.trailing_zeros() as usize
+  }
+
+  // rightmost position of the lowest excess in lo..=hi
+  fn range_min(&self, lo: usize, hi: usize) -> usize {
+    let (bl, bh) = (lo / BLOCK, hi / BLOCK);
+    if bl == bh {
+      return self.scan(lo, hi + 1, self.excess_before(lo)).1;
+    }
+    let mut best = self.scan(lo, (bl + 1) * BLOCK, self.excess_before(lo));
+    if bl + 1 < bh {
+      // the blocks strictly between, bottom-up over the segment tree
+      let blocks = self.before.len();
+      let (mut l, mut r) = (bl + 1 + blocks, bh + blocks);
+      let mut found: Option<u32> = None;
+      while l < r {
+        if l % 2 == 1 {
+          found = Some(found.map_or(self.tree[l], |f| self.lower(f, self.tree[l])));
+          l += 1;
+        }
+        if r % 2 == 1 {
+          r -= 1;
+          found = Some(found.map_or(self.tree[r], |f| self.lower(f, self.tree[r])));
+        }
+        l /= 2;
+        r /= 2;
+      }
+      let b = found.unwrap() as usize;
+      if self.block_min[b] <= best.0 {
+        best = self.scan(b * BLOCK, (b + 1) * BLOCK, self.before[b]);
+      }
+    }
+    let last = self.scan(bh * BLOCK, hi + 1, self.before[bh]);
+    if last.0 <= best.0 {
+      best = last;
+    }
+    best.1
+  }
+
+  // index of the minimum of a[range], the leftmost of equal ones
+  pub fn argmin(&self, range: std::ops::Range<usize>) -> usize {
+    assert!(range.start < range.end && range.end <= self.n);
+    let lo = self.select_open(range.start + 1) - 1;
+    let hi = self.select_open(range.end) - 1;
+    let p = self.range_min(lo, hi);
+    // the '(' right after p, numbered without the root: of the parentheses up to p,
+    // (excess + count) / 2 are '(', the root among them
+    let count = p + 1;
+    (self.excess_before(count) as usize + count) / 2 - 1
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_small() {
+    let a = [3, 1, 2, 1, 5, 0];
+    let rmq = SuccinctRmq::new(&a);
+    assert_eq!(1, rmq.argmin(0..3));
+    assert_eq!(1, rmq.argmin(1..4));
+    assert_eq!(3, rmq.argmin(2..5));
+    assert_eq!(5, rmq.argmin(0..6));
+    assert_eq!(0, rmq.argmin(0..1));
+    assert_eq!(4, rmq.argmin(4..5));
+  }
+
+  #[test]
+  fn test_random_against_naive() {
+    let mut rand = rand_gen(163);
+    for _ in 0..50 {
+      let n = 1 + rand(2000) as usize;
+      let spread = 1 + rand(1000);
+      let range = 1 + rand(spread);
+      let a: Vec<u64> = (0..n).map(|_| rand(range)).collect();
+      let rmq = SuccinctRmq::new(&a);
+      for _ in 0..300 {
+        let (i, j) = (rand(n as u64) as usize, rand(n as u64) as usize);
+        let (i, j) = (i.min(j), i.max(j) + 1);
+        let expected = (i..j).min_by_key(|&k| (a[k], k)).unwrap();
+        assert_eq!(expected, rmq.argmin(i..j));
+      }
+    }
+    let a: Vec<u32> = (0..100_000).map(|i| (i * 7919 % 100_003) as u32).collect();
+    let rmq = SuccinctRmq::new(&a);
+    assert!(rmq.size_in_bits() < 3 * a.len());
+    assert_eq!(a.iter().enumerate().min_by_key(|&(_, x)| x).unwrap().0, rmq.argmin(0..a.len()));
+  }
+}
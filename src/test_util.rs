@@ -0,0 +1,18 @@
+// Shared helpers for the tests.
+
+// xorshift64, the whole state in one word: reproducible from the seed, fast, and good enough to
+// drive randomized tests. The seed must not be 0.
+pub(crate) fn xorshift(mut seed: u64) -> impl FnMut() -> u64 {
+  move || {
+    seed ^= seed << 13;
+    seed ^= seed >> 7;
+    seed ^= seed << 17;
+    seed
+  }
+}
+
+// rand(m) draws from 0..m
+pub(crate) fn rand_gen(seed: u64) -> impl FnMut(u64) -> u64 {
+  let mut next = xorshift(seed);
+  move |m: u64| next() % m
+}
@@ -0,0 +1,140 @@
+use std::ops::Range;
+
+// Drivers for two-pointer scans over positions 0..n. A window l..r is kept in state by add(r)
+// when it grows on the right and remove(l) when it shrinks on the left, and ok tells whether the
+// current window satisfies the predicate. Each position is added and removed at most once, so a
+// scan is O(n) calls of each. The state is passed to every closure, rather than captured by them,
+// so they can all change it.
+
+// the shortest non-empty window satisfying ok, the leftmost of those; ok must stay true when a
+// window grows, like a sum of positive values reaching a target, or containing every colour
+pub fn smallest_window<S>(n: usize, state: &mut S, mut add: impl FnMut(&mut S, usize), mut remove: impl FnMut(&mut S, usize), mut ok: impl FnMut(&S) -> bool) -> Option<Range<usize>> {
+  let mut best: Option<Range<usize>> = None;
+  let mut l = 0;
+  for r in 0..n {
+    add(state, r);
+    while l <= r && ok(state) {
+      if best.as_ref().is_none_or(|b| r + 1 - l < b.len()) {
+        best = Some(l..r + 1);
+      }
+      remove(state, l);
+      l += 1;
+    }
+  }
+  best
+}
+
+// the longest window satisfying ok, the leftmost of those; ok must stay true when a window
+// shrinks, down to the empty window, like a sum of positive values within a budget, or at most
+// k distinct values
+pub fn longest_window<S>(n: usize, state: &mut S, add: impl FnMut(&mut S, usize), remove: impl FnMut(&mut S, usize), ok: impl FnMut(&S) -> bool) -> Range<usize> {
+  let mut best = 0..0;
+  scan_shrinking(n, state, add, remove, ok, |window| {
+    if window.len() > best.len() {
+      best = window;
+    }
+  });
+  best
+}
+
+// the number of non-empty windows satisfying ok, which must stay true when a window shrinks
+pub fn count_windows<S>(n: usize, state: &mut S, add: impl FnMut(&mut S, usize), remove: impl FnMut(&mut S, usize), ok: impl FnMut(&S) -> bool) -> u64 {
+  let mut count = 0;
+  // every window ending at r and starting in the longest one
+  scan_shrinking(n, state, add, remove, ok, |window| count += window.len() as u64);
+  count
+}
+
+// the longest window satisfying ok ending at each position, in order
+fn scan_shrinking<S>(n: usize, state: &mut S, mut add: impl FnMut(&mut S, usize), mut remove: impl FnMut(&mut S, usize), mut ok: impl FnMut(&S) -> bool, mut each: impl FnMut(Range<usize>)) {
+  let mut l = 0;
+  for r in 0..n {
+    add(state, r);
+    while !ok(state) {
+      remove(state, l);
+      l += 1;
+    }
+    each(l..r + 1);
+  }
+}
+
+// the number of pairs i < j with cond(i, j), where cond must stay true when i or j moves down:
+// over sorted values, a[i] + a[j] <= target, or a[i] * a[j] < limit for positive ones. O(n) calls
+pub fn count_pairs(n: usize, mut cond: impl FnMut(usize, usize) -> bool) -> u64 {
+  let mut count = 0;
+  let (mut i, mut j) = (0, n.saturating_sub(1));
+  while i < j {
+    if cond(i, j) {
+      // and so with every j' in i + 1..=j
+      count += (j - i) as u64;
+      i += 1;
+    } else {
+      j -= 1;
+    }
+  }
+  count
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_windows() {
+    let a = [2u64, 3, 1, 2, 4, 3];
+    let mut sum = 0;
+    let shortest = smallest_window(a.len(), &mut sum, |s, r| *s += a[r], |s, l| *s -= a[l], |s| *s >= 7);
+    assert_eq!(Some(4..6), shortest);
+    let mut sum = 0;
+    assert_eq!(None, smallest_window(a.len(), &mut sum, |s, r| *s += a[r], |s, l| *s -= a[l], |s| *s >= 100));
+    // longest run with at most two distinct colours
+    let colours = [1, 2, 1, 3, 3, 3, 1, 3];
+    let mut counts = ([0usize; 4], 0);
+    let add = |s: &mut ([usize; 4], usize), r: usize| {
+      s.0[colours[r]] += 1;
+      if s.0[colours[r]] == 1 {
+        s.1 += 1;
+      }
+    };
+    let remove = |s: &mut ([usize; 4], usize), l: usize| {
+      s.0[colours[l]] -= 1;
+      if s.0[colours[l]] == 0 {
+        s.1 -= 1;
+      }
+    };
+    assert_eq!(2..8, longest_window(colours.len(), &mut counts, add, remove, |s| s.1 <= 2));
+    let mut sum = 0;
+    // [2], [3], [1], [2], [4], [3], [2, 3], [3, 1], [1, 2]
+    assert_eq!(9, count_windows(a.len(), &mut sum, |s, r| *s += a[r], |s, l| *s -= a[l], |s| *s <= 5));
+    assert_eq!(0..0, longest_window(0, &mut (), |_, _| {}, |_, _| {}, |_| true));
+    let sorted = [1, 2, 4, 5, 8];
+    assert_eq!(4, count_pairs(sorted.len(), |i, j| sorted[i] + sorted[j] <= 6));
+    assert_eq!(0, count_pairs(0, |_, _| true));
+  }
+
+  #[test]
+  fn test_random_against_brute_force() {
+    let mut rand = rand_gen(383);
+    for _ in 0..300 {
+      let n = rand(30) as usize;
+      let a: Vec<u64> = (0..n).map(|_| 1 + rand(10)).collect();
+      let (target, budget) = (1 + rand(60), rand(40));
+      let sum = |w: &Range<usize>| a[w.clone()].iter().sum::<u64>();
+      let windows: Vec<Range<usize>> = (0..=n).flat_map(|l| (l..=n).map(move |r| l..r)).collect();
+      let mut s = 0;
+      let expected = windows.iter().filter(|w| !w.is_empty() && sum(w) >= target).min_by_key(|w| (w.len(), w.start)).cloned();
+      assert_eq!(expected, smallest_window(n, &mut s, |s, r| *s += a[r], |s, l| *s -= a[l], |s| *s >= target));
+      let mut s = 0;
+      let expected = windows.iter().filter(|w| sum(w) <= budget).min_by_key(|w| (n - w.len(), w.start)).cloned().unwrap();
+      assert_eq!(expected, longest_window(n, &mut s, |s, r| *s += a[r], |s, l| *s -= a[l], |s| *s <= budget));
+      let mut s = 0;
+      let expected = windows.iter().filter(|w| !w.is_empty() && sum(w) <= budget).count() as u64;
+      assert_eq!(expected, count_windows(n, &mut s, |s, r| *s += a[r], |s, l| *s -= a[l], |s| *s <= budget));
+      let mut sorted = a.clone();
+      sorted.sort_unstable();
+      let expected = (0..n).flat_map(|i| (i + 1..n).map(move |j| (i, j))).filter(|&(i, j)| sorted[i] + sorted[j] <= target / 3).count() as u64;
+      assert_eq!(expected, count_pairs(n, |i, j| sorted[i] + sorted[j] <= target / 3));
+    }
+  }
+}
@@ -0,0 +1,205 @@
+use crate::versioned_array::VersionedArray;
+
+// Disjoint sets over 0..n with union by size and path compression: any sequence of m operations
+// in O(m alpha(n)).
+pub struct UnionFind {
+  parent: Vec<usize>,
+  size: Vec<usize>, // meaningful at roots
+  components: usize,
+}
+
+impl UnionFind {
+  // n singletons
+  pub fn new(n: usize) -> UnionFind {
+    UnionFind { parent: (0..n).collect(), size: vec![1; n], components: n }
+  }
+
+  pub fn len(&self) -> usize {
+    self.parent.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.parent.is_empty()
+  }
+
+  pub fn components(&self) -> usize {
+    self.components
+  }
+
+  // the root of x's set, pointing every other node on the way at its grandparent
+  pub fn find(&mut self, x: usize) -> usize {
+    let mut x = x;
+    while self.parent[x] != x {
+      self.parent[x] = self.parent[self.parent[x]];
+      x = self.parent[x];
+    }
+    x
+  }
+
+  // false if a and b were already in one set
+  pub fn union(&mut self, a: usize, b: usize) -> bool {
+    let (mut a, mut b) = (self.find(a), self.find(b));
+    if a == b {
+      return false;
+    }
+    if self.size[a] < self.size[b] {
+      std::mem::swap(&mut a, &mut b);
+    }
+    self.parent[b] = a;
+    self.size[a] += self.size[b];
+    self.components -= 1;
+    true
+  }
+
+  pub fn same(&mut self, a: usize, b: usize) -> bool {
+    self.find(a) == self.find(b)
+  }
+
+  // the number of elements in x's set
+  pub fn size(&mut self, x: usize) -> usize {
+    let root = self.find(x);
+    self.size[root]
+  }
+}
+
+// Union-find that can undo unions, newest first, for offline algorithms that explore and
+// backtrack, like dynamic connectivity over a segment tree of time. Union by size without path
+// compression, so a find is O(log n) and every union changes just two slots, kept in a
+// versioned array that rolls back in time proportional to what is undone.
+pub struct RollbackUnionFind {
+  nodes: VersionedArray<(usize, usize)>, // (parent, size of the set if a root)
+}
+
+impl RollbackUnionFind {
+  pub fn new(n: usize) -> RollbackUnionFind {
+    RollbackUnionFind { nodes: VersionedArray::new((0..n).map(|x| (x, 1)).collect()) }
+  }
+
+  pub fn len(&self) -> usize {
+    self.nodes.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.nodes.is_empty()
+  }
+
+  pub fn components(&self) -> usize {
+    // each union so far wrote two versions
+    self.len() - self.nodes.version() / 2
+  }
+
+  pub fn find(&self, x: usize) -> usize {
+    let mut x = x;
+    while self.nodes.get(x).0 != x {
+      x = self.nodes.get(x).0;
+    }
+    x
+  }
+
+  pub fn union(&mut self, a: usize, b: usize) -> bool {
+    let (mut a, mut b) = (self.find(a), self.find(b));
+    if a == b {
+      return false;
+    }
+    let (mut size_a, mut size_b) = (self.nodes.get(a).1, self.nodes.get(b).1);
+    if size_a < size_b {
+      std::mem::swap(&mut a, &mut b);
+      std::mem::swap(&mut size_a, &mut size_b);
+    }
+    self.nodes.set(b, (a, size_b));
+    self.nodes.set(a, (a, size_a + size_b));
+    true
+  }
+
+  pub fn same(&self, a: usize, b: usize) -> bool {
+    self.find(a) == self.find(b)
+  }
+
+  pub fn size(&self, x: usize) -> usize {
+    self.nodes.get(self.find(x)).1
+  }
+
+  // a point to roll back to, the unions so far
+  pub fn snapshot(&self) -> usize {
+    self.nodes.version() / 2
+  }
+
+  // undoes every union since snapshot was taken
+  pub fn rollback(&mut self, snapshot: usize) {
+    self.nodes.rollback(2 * snapshot);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_sets() {
+    let mut sets = UnionFind::new(6);
+    assert!(sets.union(0, 1) && sets.union(2, 3) && sets.union(1, 3));
+    assert!(!sets.union(0, 2));
+    assert!(sets.same(0, 3) && !sets.same(0, 4));
+    assert_eq!((4, 1, 3), (sets.size(2), sets.size(5), sets.components()));
+    let mut undo = RollbackUnionFind::new(6);
+    undo.union(0, 1);
+    let before = undo.snapshot();
+    assert!(undo.union(1, 2) && undo.union(4, 5) && !undo.union(0, 2));
+    assert_eq!((3, 3), (undo.size(0), undo.components()));
+    undo.rollback(before);
+    assert!(!undo.same(0, 2) && !undo.same(4, 5) && undo.same(0, 1));
+    assert_eq!((2, 5), (undo.size(1), undo.components()));
+    undo.rollback(0);
+    assert_eq!(6, undo.components());
+    assert!(UnionFind::new(0).is_empty() && RollbackUnionFind::new(0).is_empty());
+  }
+
+  #[test]
+  fn test_random_against_labels() {
+    let mut rand = rand_gen(389);
+    for _ in 0..50 {
+      let n = 1 + rand(40) as usize;
+      // labels relabelled on every union, and their history for the rollbacks
+      let mut labels: Vec<usize> = (0..n).collect();
+      let mut history: Vec<Vec<usize>> = vec![];
+      let mut sets = UnionFind::new(n);
+      let mut undo = RollbackUnionFind::new(n);
+      for _ in 0..200 {
+        let (a, b) = (rand(n as u64) as usize, rand(n as u64) as usize);
+        match rand(4) {
+          0 if !history.is_empty() => {
+            let k = rand(history.len() as u64) as usize;
+            undo.rollback(k);
+            history.truncate(k + 1);
+            labels = history.pop().unwrap();
+            // the plain one can only start over and replay
+            sets = UnionFind::new(n);
+            for (x, &l) in labels.iter().enumerate() {
+              sets.union(x, l);
+            }
+          },
+          _ => {
+            let merged = labels[a] != labels[b];
+            assert_eq!(merged, sets.union(a, b));
+            let before = undo.snapshot();
+            assert_eq!(merged, undo.union(a, b));
+            if merged {
+              assert_eq!(before, history.len());
+              history.push(labels.clone());
+              let (from, to) = (labels[b], labels[a]);
+              labels.iter_mut().filter(|l| **l == from).for_each(|l| *l = to);
+            }
+          },
+        }
+        let (x, y) = (rand(n as u64) as usize, rand(n as u64) as usize);
+        let size = labels.iter().filter(|&&l| l == labels[x]).count();
+        let mut distinct = labels.clone();
+        distinct.sort_unstable();
+        distinct.dedup();
+        assert_eq!((labels[x] == labels[y], size, distinct.len()), (sets.same(x, y), sets.size(x), sets.components()));
+        assert_eq!((labels[x] == labels[y], size, distinct.len()), (undo.same(x, y), undo.size(x), undo.components()));
+      }
+    }
+  }
+}
@@ -0,0 +1,117 @@
+// Partially persistent array with fat elements: every slot keeps its history as (version, value)
+// pairs in increasing version order. Reading the newest version is the last pair, O(1); reading
+// version v binary searches the slot's history, O(log h). Each set creates the next version.
+// Updates only apply to the newest version, but rollback discards the newest versions in time
+// proportional to the number undone, which is what union-find with rollback builds on.
+pub struct VersionedArray<T> {
+  history: Vec<Vec<(usize, T)>>,
+  changed: Vec<usize>, // changed[v - 1] is the slot version v wrote
+}
+
+impl<T> VersionedArray<T> {
+  // version 0 holds the given values
+  pub fn new(values: Vec<T>) -> VersionedArray<T> {
+    VersionedArray {
+      history: values.into_iter().map(|v| vec![(0, v)]).collect(),
+      changed: vec![],
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.history.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.history.is_empty()
+  }
+
+  // the newest version
+  pub fn version(&self) -> usize {
+    self.changed.len()
+  }
+
+  pub fn get(&self, i: usize) -> &T {
+    &self.history[i].last().unwrap().1
+  }
+
+  pub fn get_at(&self, version: usize, i: usize) -> &T {
+    assert!(version <= self.version());
+    let h = &self.history[i];
+    let at = h.partition_point(|&(v, _)| v <= version);
+    &h[at - 1].1
+  }
+
+  // writes value at i and returns the version this creates
+  pub fn set(&mut self, i: usize, value: T) -> usize {
+    self.changed.push(i);
+    let version = self.version();
+    self.history[i].push((version, value));
+    version
+  }
+
+  // goes back to version, forgetting every later one
+  pub fn rollback(&mut self, version: usize) {
+    assert!(version <= self.version());
+    while self.version() > version {
+      let i = self.changed.pop().unwrap();
+      self.history[i].pop();
+    }
+  }
+}
+
+impl<T: Clone> VersionedArray<T> {
+  // the whole array as of version, O(n log h)
+  pub fn snapshot(&self, version: usize) -> Vec<T> {
+    (0..self.len()).map(|i| self.get_at(version, i).clone()).collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_against_snapshots() {
+    let mut rand = rand_gen(53);
+    for _ in 0..30 {
+      let n = 1 + rand(10) as usize;
+      let initial: Vec<u64> = (0..n as u64).collect();
+      let mut array = VersionedArray::new(initial.clone());
+      let mut snapshots = vec![initial];
+      for _ in 0..200 {
+        if rand(10) == 0 {
+          let version = rand(array.version() as u64 + 1) as usize;
+          array.rollback(version);
+          snapshots.truncate(version + 1);
+        } else {
+          let (i, value) = (rand(n as u64) as usize, rand(1000));
+          let mut next = snapshots.last().unwrap().clone();
+          next[i] = value;
+          snapshots.push(next);
+          assert_eq!(snapshots.len() - 1, array.set(i, value));
+        }
+        assert_eq!(snapshots.len() - 1, array.version());
+        let newest = snapshots.last().unwrap();
+        assert!((0..n).all(|i| array.get(i) == &newest[i]));
+        let version = rand(snapshots.len() as u64) as usize;
+        assert_eq!(snapshots[version], array.snapshot(version));
+      }
+    }
+  }
+
+  #[test]
+  fn test_versions() {
+    let mut array = VersionedArray::new(vec!['a', 'b', 'c']);
+    assert_eq!(1, array.set(1, 'x'));
+    assert_eq!(2, array.set(1, 'y'));
+    assert_eq!(3, array.set(0, 'z'));
+    assert_eq!(&'b', array.get_at(0, 1));
+    assert_eq!(&'x', array.get_at(1, 1));
+    assert_eq!(&'a', array.get_at(2, 0));
+    assert_eq!(vec!['z', 'y', 'c'], array.snapshot(3));
+    array.rollback(1);
+    assert_eq!(vec!['a', 'x', 'c'], array.snapshot(array.version()));
+    assert_eq!(&'x', array.get(1));
+  }
+}
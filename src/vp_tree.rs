@@ -0,0 +1,208 @@
+use std::cmp::Ordering;
+use std::ops::Add;
+
+use crate::binary_heap::BinaryHeap;
+
+type Farthest<D> = BinaryHeap<(D, usize), fn(&(D, usize), &(D, usize)) -> Ordering>;
+
+fn by_distance<D: PartialOrd>(a: &(D, usize), b: &(D, usize)) -> Ordering {
+  a.0.partial_cmp(&b.0).unwrap().then(a.1.cmp(&b.1))
+}
+
+// Vantage-point tree (Yianilos): nearest neighbours under any metric, given only the distance
+// function. Each node takes one item as vantage point and splits the rest at the median distance
+// from it, closer ones inside. A query at distance d from the vantage point looking no further
+// than tau skips the inside when d > median + tau and the outside when d + tau < median, by the
+// triangle inequality, so distances only need to be ordered and added, like a Levenshtein
+// distance or a Hamming one. The function must be a metric for the answers to be exact.
+//
+// The nodes live in one array in preorder: the node at position lo covering order[lo..hi] has
+// its vantage point at lo, the inside in lo + 1..split[lo] and the outside in split[lo]..hi.
+pub struct VpTree<T, D, F> {
+  items: Vec<T>,
+  distance: F,
+  order: Vec<usize>,
+  median: Vec<Option<D>>, // None for leaves
+  split: Vec<usize>,
+}
+
+impl<T, D: PartialOrd + Copy + Add<Output = D>, F: Fn(&T, &T) -> D> VpTree<T, D, F> {
+  // O(n log n) distance evaluations
+  pub fn new(items: Vec<T>, distance: F) -> VpTree<T, D, F> {
+    let n = items.len();
+    let mut tree = VpTree { items, distance, order: (0..n).collect(), median: vec![None; n], split: vec![0; n] };
+    tree.build(0, n);
+    tree
+  }
+
+  fn build(&mut self, lo: usize, hi: usize) {
+    if hi - lo <= 1 {
+      return;
+    }
+    self.order.swap(lo, lo + (hi - lo) / 2);
+    let vp = &self.items[self.order[lo]];
+    let mut rest: Vec<(D, usize)> = self.order[lo + 1..hi].iter().map(|&i| ((self.distance)(vp, &self.items[i]), i)).collect();
+    let k = (rest.len() - 1) / 2;
+    rest.select_nth_unstable_by(k, |a, b| a.0.partial_cmp(&b.0).unwrap());
+    for (slot, &(_, i)) in self.order[lo + 1..hi].iter_mut().zip(&rest) {
+      *slot = i;
+    }
+    self.median[lo] = Some(rest[k].0);
+    self.split[lo] = lo + k + 2;
+    self.build(lo + 1, lo + k + 2);
+    self.build(lo + k + 2, hi);
+  }
+
+  pub fn len(&self) -> usize {
+    self.items.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.items.is_empty()
+  }
+
+  // the items in the order given, which the indices returned refer to
+  pub fn items(&self) -> &[T] {
+    &self.items
+  }
+
+  // the k items nearest to query as (index, distance), nearest first, ties by index
+  pub fn nearest(&self, query: &T, k: usize) -> Vec<(usize, D)> {
+    if k == 0 {
+      return vec![];
+    }
+    let mut best: Farthest<D> = BinaryHeap::with_comparator(by_distance::<D>);
+    self.nearest_in(query, k, 0, self.len(), &mut best);
+    let mut found = vec![];
+    while let Some((d, i)) = best.pop() {
+      found.push((i, d));
+    }
+    found.reverse();
+    found
+  }
+
+  fn nearest_in(&self, query: &T, k: usize, lo: usize, hi: usize, best: &mut Farthest<D>) {
+    if lo >= hi {
+      return;
+    }
+    let i = self.order[lo];
+    let d = (self.distance)(query, &self.items[i]);
+    best.push((d, i));
+    if best.len() > k {
+      best.pop();
+    }
+    let median = match self.median[lo] {
+      Some(median) => median,
+      None => return,
+    };
+    // the side the query falls in first, then the other if the k-th nearest so far is far
+    // enough to reach across
+    let split = self.split[lo];
+    let sides = if d < median { [true, false] } else { [false, true] };
+    for inside in sides {
+      let tau = if best.len() < k { None } else { best.peek().map(|&(tau, _)| tau) };
+      if inside && tau.is_none_or(|tau| d <= median + tau) {
+        self.nearest_in(query, k, lo + 1, split, best);
+      } else if !inside && tau.is_none_or(|tau| d + tau >= median) {
+        self.nearest_in(query, k, split, hi, best);
+      }
+    }
+  }
+
+  // the items within radius of query, boundary included, as (index, distance), nearest first,
+  // ties by index
+  pub fn within(&self, query: &T, radius: D) -> Vec<(usize, D)> {
+    let mut found = vec![];
+    let mut stack = vec![(0, self.len())];
+    while let Some((lo, hi)) = stack.pop() {
+      if lo >= hi {
+        continue;
+      }
+      let i = self.order[lo];
+      let d = (self.distance)(query, &self.items[i]);
+      if d <= radius {
+        found.push((d, i));
+      }
+      if let Some(median) = self.median[lo] {
+        if d <= median + radius {
+          stack.push((lo + 1, self.split[lo]));
+        }
+        if d + radius >= median {
+          stack.push((self.split[lo], hi));
+        }
+      }
+    }
+    found.sort_by(by_distance);
+    found.into_iter().map(|(d, i)| (i, d)).collect()
+  }
+
+  // the two closest distinct items and their distance, by a two-nearest query from each item;
+  // None with fewer than two
+  pub fn closest_pair(&self) -> Option<(usize, usize, D)> {
+    let mut best: Option<(usize, usize, D)> = None;
+    for (i, item) in self.items.iter().enumerate() {
+      // the item itself, or a duplicate of it, is among the two nearest
+      for (j, d) in self.nearest(item, 2) {
+        if j != i && best.is_none_or(|(_, _, b)| d < b) {
+          best = Some((i.min(j), i.max(j), d));
+        }
+      }
+    }
+    best
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+  use crate::string::edit_distance::levenshtein;
+
+  fn brute<T, D: PartialOrd + Copy>(items: &[T], query: &T, distance: impl Fn(&T, &T) -> D) -> Vec<(usize, D)> {
+    let mut all: Vec<(D, usize)> = items.iter().enumerate().map(|(i, x)| (distance(query, x), i)).collect();
+    all.sort_by(by_distance);
+    all.into_iter().map(|(d, i)| (i, d)).collect()
+  }
+
+  #[test]
+  fn test_words() {
+    let words: Vec<Vec<u8>> = ["book", "books", "cake", "boo", "boon", "cook", "cape", "cart"].iter().map(|w| w.as_bytes().to_vec()).collect();
+    let tree = VpTree::new(words, |a: &Vec<u8>, b: &Vec<u8>| levenshtein(a, b));
+    assert_eq!(8, tree.len());
+    assert_eq!(vec![(0, 1), (3, 1), (4, 1), (1, 2), (5, 2)], tree.within(&b"bool".to_vec(), 2));
+    assert_eq!(vec![(2, 0), (6, 1), (7, 2)], tree.nearest(&b"cake".to_vec(), 3));
+    assert_eq!(Some((0, 1, 1)), tree.closest_pair());
+    assert!(tree.nearest(&b"x".to_vec(), 0).is_empty());
+    let empty = VpTree::new(Vec::<u64>::new(), |a: &u64, b: &u64| (a ^ b).count_ones());
+    assert!(empty.nearest(&0, 3).is_empty() && empty.within(&0, 5).is_empty() && empty.closest_pair().is_none());
+  }
+
+  #[test]
+  fn test_random_against_brute_force() {
+    let mut rand = rand_gen(311);
+    for round in 0..60 {
+      let n = if round % 10 == 0 { 1000 } else { rand(100) as usize };
+      // Hamming distance over 20-bit words, which ties a lot
+      let words: Vec<u64> = (0..n).map(|_| rand(1 << 20)).collect();
+      let hamming = |a: &u64, b: &u64| (a ^ b).count_ones();
+      let tree = VpTree::new(words.clone(), hamming);
+      // Euclidean distance over points in the plane
+      let points: Vec<(f64, f64)> = (0..n).map(|_| (rand(1000) as f64, rand(1000) as f64)).collect();
+      let euclid = |a: &(f64, f64), b: &(f64, f64)| (a.0 - b.0).hypot(a.1 - b.1);
+      let plane = VpTree::new(points.clone(), euclid);
+      for _ in 0..20 {
+        let (query, k, r) = (rand(1 << 20), rand(12) as usize, rand(8) as u32);
+        let expected = brute(&words, &query, hamming);
+        assert_eq!(expected[..k.min(n)].to_vec(), tree.nearest(&query, k));
+        assert_eq!(expected.iter().copied().filter(|&(_, d)| d <= r).collect::<Vec<_>>(), tree.within(&query, r));
+        let query = (rand(1000) as f64, rand(1000) as f64);
+        let expected = brute(&points, &query, euclid);
+        assert_eq!(expected[..k.min(n)].to_vec(), plane.nearest(&query, k));
+        let r = rand(200) as f64;
+        assert_eq!(expected.iter().copied().filter(|&(_, d)| d <= r).collect::<Vec<_>>(), plane.within(&query, r));
+      }
+      let pair = (0..n).flat_map(|i| (i + 1..n).map(move |j| (i, j))).map(|(i, j)| (i, j, hamming(&words[i], &words[j]))).min_by_key(|p| p.2);
+      assert_eq!(pair.map(|p| p.2), tree.closest_pair().map(|p| p.2));
+    }
+  }
+}
@@ -0,0 +1,245 @@
+use std::ops::Range;
+
+// bits with rank in O(1) from counts before every word, select by binary search over them
+struct BitVector {
+  words: Vec<u64>,
+  before: Vec<u32>, // ones before each word, and in all of them at the end
+}
+
+impl BitVector {
+  fn new(bits: impl ExactSizeIterator<Item = bool>) -> BitVector {
+    // a word for position n too, however many bits
+    let mut words = vec![0u64; bits.len() / 64 + 1];
+    for (i, bit) in bits.enumerate() {
+      words[i / 64] |= (bit as u64) << (i % 64);
+    }
+    let mut before = vec![0];
+    for w in &words {
+      before.push(before.last().unwrap() + w.count_ones());
+    }
+    BitVector { words, before }
+  }
+
+  fn get(&self, i: usize) -> bool {
+    self.words[i / 64] >> (i % 64) & 1 == 1
+  }
+
+  // ones in the first i bits
+  fn rank1(&self, i: usize) -> usize {
+    (self.before[i / 64] + (self.words[i / 64] & ((1 << (i % 64)) - 1)).count_ones()) as usize
+  }
+
+  fn rank0(&self, i: usize) -> usize {
+    i - self.rank1(i)
+  }
+
+  // position of the k-th bit equal to bit, counting from 0; it must exist
+  fn select(&self, bit: bool, k: usize) -> usize {
+    let count = |w: usize| if bit { self.before[w] as usize } else { 64 * w - self.before[w] as usize };
+    // the last word with at most k such bits before it
+    let (mut lo, mut hi) = (0, self.words.len());
+    while hi - lo > 1 {
+      let mid = (lo + hi) / 2;
+      if count(mid) <= k { lo = mid } else { hi = mid }
+    }
+    let mut word = if bit { self.words[lo] } else { !self.words[lo] };
+    for _ in 0..k - count(lo) {
+      word &= word - 1;
+    }
+    64 * lo + word.trailing_zeros() as usize
+  }
+}
+
+// Wavelet tree over a sequence of integers, in the levelwise form of the wavelet matrix (Claude,
+// Navarro & Ordonez): level l holds bit l from the top of every value, with the values stably
+// sorted by their higher bits. zeros[l] values have a 0 there; a stable partition on it gives
+// the order of the next level. A node of the tree is a range of a level, and following a range
+// down is two ranks per level, so rank, select, quantile and range counts take O(log sigma), in
+// n bits per level plus rank counts.
+pub struct WaveletTree {
+  n: usize,
+  levels: Vec<BitVector>, // from the highest bit down
+  zeros: Vec<usize>,
+}
+
+impl WaveletTree {
+  pub fn new<T: Copy + Into<u64>>(a: &[T]) -> WaveletTree {
+    let mut values: Vec<u64> = a.iter().map(|&x| x.into()).collect();
+    let max = values.iter().copied().max().unwrap_or(0);
+    let height = 64 - max.leading_zeros() as usize;
+    let mut levels = vec![];
+    let mut zeros = vec![];
+    for shift in (0..height).rev() {
+      levels.push(BitVector::new(values.iter().map(|x| x >> shift & 1 == 1)));
+      let (low, high): (Vec<u64>, Vec<u64>) = values.iter().partition(|&&x| x >> shift & 1 == 0);
+      zeros.push(low.len());
+      values = low;
+      values.extend(high);
+    }
+    WaveletTree { n: a.len(), levels, zeros }
+  }
+
+  pub fn len(&self) -> usize {
+    self.n
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.n == 0
+  }
+
+  fn bit(&self, x: u64, level: usize) -> bool {
+    x >> (self.levels.len() - 1 - level) & 1 == 1
+  }
+
+  fn fits(&self, x: u64) -> bool {
+    self.levels.len() == 64 || x >> self.levels.len() == 0
+  }
+
+  // a position of a level to where it goes on the next, along the given bit
+  fn down(&self, level: usize, i: usize, bit: bool) -> usize {
+    if bit { self.zeros[level] + self.levels[level].rank1(i) } else { self.levels[level].rank0(i) }
+  }
+
+  pub fn get(&self, mut i: usize) -> u64 {
+    assert!(i < self.n);
+    let mut x = 0;
+    for level in 0..self.levels.len() {
+      let bit = self.levels[level].get(i);
+      x = x << 1 | bit as u64;
+      i = self.down(level, i, bit);
+    }
+    x
+  }
+
+  // occurrences of symbol in the first prefix values
+  pub fn rank(&self, symbol: u64, prefix: usize) -> usize {
+    assert!(prefix <= self.n);
+    if !self.fits(symbol) {
+      return 0;
+    }
+    let (mut lo, mut hi) = (0, prefix);
+    for level in 0..self.levels.len() {
+      let bit = self.bit(symbol, level);
+      lo = self.down(level, lo, bit);
+      hi = self.down(level, hi, bit);
+    }
+    hi - lo
+  }
+
+  // position of the k-th occurrence of symbol, counting from 0
+  pub fn select(&self, symbol: u64, k: usize) -> Option<usize> {
+    if k >= self.rank(symbol, self.n) {
+      return None;
+    }
+    // the symbol's values sit together on the last level, from where 0 goes
+    let mut i = (0..self.levels.len()).fold(0, |i, level| self.down(level, i, self.bit(symbol, level))) + k;
+    for level in (0..self.levels.len()).rev() {
+      let bit = self.bit(symbol, level);
+      i = self.levels[level].select(bit, if bit { i - self.zeros[level] } else { i });
+    }
+    Some(i)
+  }
+
+  // the k-th smallest value of a[range], counting from 0
+  pub fn quantile(&self, range: Range<usize>, mut k: usize) -> u64 {
+    assert!(range.start <= range.end && range.end <= self.n && k < range.len());
+    let (mut lo, mut hi) = (range.start, range.end);
+    let mut x = 0;
+    for level in 0..self.levels.len() {
+      let zeros = self.levels[level].rank0(hi) - self.levels[level].rank0(lo);
+      let bit = k >= zeros;
+      if bit {
+        k -= zeros;
+      }
+      x = x << 1 | bit as u64;
+      lo = self.down(level, lo, bit);
+      hi = self.down(level, hi, bit);
+    }
+    x
+  }
+
+  // values of a[range] less than bound
+  fn count_less(&self, range: Range<usize>, bound: u64) -> usize {
+    if !self.fits(bound) {
+      return range.len();
+    }
+    let (mut lo, mut hi) = (range.start, range.end);
+    let mut count = 0;
+    for level in 0..self.levels.len() {
+      let bit = self.bit(bound, level);
+      if bit {
+        // those with a 0 here are smaller
+        count += self.levels[level].rank0(hi) - self.levels[level].rank0(lo);
+      }
+      lo = self.down(level, lo, bit);
+      hi = self.down(level, hi, bit);
+    }
+    count
+  }
+
+  // values of a[range] within values
+  pub fn range_count(&self, range: Range<usize>, values: Range<u64>) -> usize {
+    assert!(range.start <= range.end && range.end <= self.n);
+    if values.start >= values.end {
+      return 0;
+    }
+    self.count_less(range.clone(), values.end) - self.count_less(range, values.start)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::rand_gen;
+
+  #[test]
+  fn test_small() {
+    let a = [5u32, 4, 5, 5, 2, 1, 5, 6, 1, 3, 5, 0];
+    let wt = WaveletTree::new(&a);
+    assert_eq!(12, wt.len());
+    assert_eq!((0..12).map(|i| a[i] as u64).collect::<Vec<_>>(), (0..12).map(|i| wt.get(i)).collect::<Vec<_>>());
+    assert_eq!(3, wt.rank(5, 6));
+    assert_eq!(0, wt.rank(7, 12));
+    assert_eq!(0, wt.rank(100, 12));
+    assert_eq!(Some(6), wt.select(5, 3));
+    assert_eq!(None, wt.select(5, 5));
+    assert_eq!(Some(11), wt.select(0, 0));
+    assert_eq!(1, wt.quantile(4..9, 0));
+    assert_eq!(5, wt.quantile(4..9, 3));
+    assert_eq!(4, wt.range_count(0..12, 1..4));
+    assert_eq!(0, wt.range_count(0..12, 7..100));
+    let empty = WaveletTree::new::<u8>(&[]);
+    assert!(empty.is_empty() && empty.select(0, 0).is_none());
+    let zeros = WaveletTree::new(&[0u8; 5]);
+    assert_eq!((5, Some(3), 0), (zeros.rank(0, 5), zeros.select(0, 3), zeros.quantile(1..4, 2)));
+  }
+
+  #[test]
+  fn test_random_against_naive() {
+    let mut rand = rand_gen(241);
+    for round in 0..100 {
+      let sigma = if round % 10 == 0 { u64::MAX } else { 1 + rand(40) };
+      let n = rand(300) as usize;
+      let a: Vec<u64> = (0..n).map(|_| rand(sigma)).collect();
+      let wt = WaveletTree::new(&a);
+      for _ in 0..50 {
+        let symbol = if n > 0 && rand(2) == 0 { a[rand(n as u64) as usize] } else { rand(sigma) };
+        let prefix = rand(n as u64 + 1) as usize;
+        assert_eq!(a[..prefix].iter().filter(|&&x| x == symbol).count(), wt.rank(symbol, prefix));
+        let k = rand(5) as usize;
+        assert_eq!((0..n).filter(|&i| a[i] == symbol).nth(k), wt.select(symbol, k));
+        let l = rand(n as u64 + 1) as usize;
+        let r = l + rand((n - l) as u64 + 1) as usize;
+        let mut sorted = a[l..r].to_vec();
+        sorted.sort_unstable();
+        if l < r {
+          let k = rand((r - l) as u64) as usize;
+          assert_eq!(sorted[k], wt.quantile(l..r, k));
+        }
+        let (x, y) = (rand(sigma), rand(sigma));
+        let values = x.min(y)..x.max(y);
+        assert_eq!(sorted.iter().filter(|v| values.contains(v)).count(), wt.range_count(l..r, values));
+      }
+    }
+  }
+}